@@ -35,6 +35,8 @@ fn rocksdb_include_dir() -> String {
 fn bindgen_rocksdb() {
     let bindings = bindgen::Builder::default()
         .header(rocksdb_include_dir() + "/rocksdb/c.h")
+        .header("ext/ext.h")
+        .clang_arg(format!("-I{}", rocksdb_include_dir()))
         .derive_debug(false)
         .blocklist_type("max_align_t") // https://github.com/rust-lang-nursery/rust-bindgen/issues/550
         .ctypes_prefix("libc")
@@ -48,14 +50,10 @@ fn bindgen_rocksdb() {
         .expect("unable to write rocksdb bindings");
 }
 
-fn build_rocksdb() {
-    let target = env::var("TARGET").unwrap();
-
-    let mut config = cc::Build::new();
-    config.include("rocksdb/include/");
-    config.include("rocksdb/");
-    config.include("rocksdb/third-party/gtest-1.8.1/fused-src/");
-
+/// Adds the defines and target flags RocksDB is compiled with.  Everything
+/// that includes RocksDB's headers must use the same ones, since they change
+/// the layout of its classes.
+fn add_rocksdb_defines(config: &mut cc::Build, target: &str) {
     if cfg!(feature = "snappy") {
         config.define("SNAPPY", Some("1"));
         config.include("snappy/");
@@ -93,17 +91,8 @@ fn build_rocksdb() {
         config.define("USE_RTTI", Some("1"));
     }
 
-    config.include(".");
     config.define("NDEBUG", Some("1"));
 
-    let mut lib_sources = include_str!("rocksdb_lib_sources.txt")
-        .trim()
-        .split('\n')
-        .map(str::trim)
-        // We have a pre-generated a version of build_version.cc in the local directory
-        .filter(|file| !matches!(*file, "util/build_version.cc"))
-        .collect::<Vec<&'static str>>();
-
     if target.contains("x86_64") {
         // This is needed to enable hardware CRC32C. Technically, SSE 4.2 is
         // only available since Intel Nehalem (about 2010) and AMD Bulldozer
@@ -168,8 +157,6 @@ fn build_rocksdb() {
         config.define("ROCKSDB_PLATFORM_POSIX", None);
         config.define("ROCKSDB_LIB_IO_POSIX", None);
     } else if target.contains("windows") {
-        link("rpcrt4", false);
-        link("shlwapi", false);
         config.define("DWIN32", None);
         config.define("OS_WIN", None);
         config.define("_MBCS", None);
@@ -177,13 +164,56 @@ fn build_rocksdb() {
         config.define("NOMINMAX", None);
         config.define("ROCKSDB_WINDOWS_UTF8_FILENAMES", None);
 
-        if &target == "x86_64-pc-windows-gnu" {
+        if target == "x86_64-pc-windows-gnu" {
             // Tell MinGW to create localtime_r wrapper of localtime_s function.
             config.define("_POSIX_C_SOURCE", Some("1"));
             // Tell MinGW to use at least Windows Vista headers instead of the ones of Windows XP.
             // (This is minimum supported version of rocksdb)
             config.define("_WIN32_WINNT", Some("_WIN32_WINNT_VISTA"));
         }
+    }
+
+    config.define("ROCKSDB_SUPPORT_THREAD_LOCAL", None);
+
+    if cfg!(feature = "jemalloc") {
+        config.define("WITH_JEMALLOC", "ON");
+    }
+
+    if cfg!(feature = "io-uring") && target.contains("linux") {
+        config.define("ROCKSDB_IOURING_PRESENT", Some("1"));
+    }
+
+    if target.contains("msvc") {
+        config.flag("-EHsc");
+        config.flag("-std:c++17");
+    } else {
+        config.flag(&cxx_standard());
+        // matches the flags in CMakeLists.txt from rocksdb
+        config.define("HAVE_UINT128_EXTENSION", Some("1"));
+    }
+}
+
+fn build_rocksdb() {
+    let target = env::var("TARGET").unwrap();
+
+    let mut config = cc::Build::new();
+    config.include("rocksdb/include/");
+    config.include("rocksdb/");
+    config.include("rocksdb/third-party/gtest-1.8.1/fused-src/");
+    add_rocksdb_defines(&mut config, &target);
+    config.include(".");
+
+    let mut lib_sources = include_str!("rocksdb_lib_sources.txt")
+        .trim()
+        .split('\n')
+        .map(str::trim)
+        // We have a pre-generated a version of build_version.cc in the local directory
+        .filter(|file| !matches!(*file, "util/build_version.cc"))
+        .collect::<Vec<&'static str>>();
+
+    if target.contains("windows") {
+        link("rpcrt4", false);
+        link("shlwapi", false);
 
         // Remove POSIX-specific sources
         lib_sources = lib_sources
@@ -216,26 +246,13 @@ fn build_rocksdb() {
         }
     }
 
-    config.define("ROCKSDB_SUPPORT_THREAD_LOCAL", None);
-
-    if cfg!(feature = "jemalloc") {
-        config.define("WITH_JEMALLOC", "ON");
-    }
-
     #[cfg(feature = "io-uring")]
     if target.contains("linux") {
         pkg_config::probe_library("liburing")
             .expect("The io-uring feature was requested but the library is not available");
-        config.define("ROCKSDB_IOURING_PRESENT", Some("1"));
     }
 
-    if target.contains("msvc") {
-        config.flag("-EHsc");
-        config.flag("-std:c++17");
-    } else {
-        config.flag(&cxx_standard());
-        // matches the flags in CMakeLists.txt from rocksdb
-        config.define("HAVE_UINT128_EXTENSION", Some("1"));
+    if !target.contains("msvc") {
         config.flag("-Wsign-compare");
         config.flag("-Wshadow");
         config.flag("-Wno-unused-parameter");
@@ -258,6 +275,28 @@ fn build_rocksdb() {
     config.compile("librocksdb.a");
 }
 
+fn build_ext() {
+    let target = env::var("TARGET").unwrap();
+
+    let mut config = cc::Build::new();
+    config.include(rocksdb_include_dir());
+    // Custom table readers implement RocksDB's internal interfaces.
+    config.include("rocksdb/");
+    config.include("ext/");
+    add_rocksdb_defines(&mut config, &target);
+
+    if !target.contains("msvc") {
+        config.flag("-Wno-unused-parameter");
+    }
+
+    for file in glob::glob("ext/*.cc").unwrap() {
+        config.file(file.unwrap());
+    }
+
+    config.cpp(true);
+    config.compile("librocksdb_ext.a");
+}
+
 fn build_snappy() {
     let target = env::var("TARGET").unwrap();
     let endianness = env::var("CARGO_CFG_TARGET_ENDIAN").unwrap();
@@ -310,6 +349,15 @@ fn try_to_find_and_link_lib(lib_name: &str) -> bool {
     false
 }
 
+/// Returns the version of the bundled RocksDB, from the build metadata of
+/// this crate's version.
+fn rocksdb_version() -> String {
+    let version = env::var("CARGO_PKG_VERSION").unwrap();
+    version
+        .split_once('+')
+        .map_or(version.clone(), |(_, rocksdb)| rocksdb.to_owned())
+}
+
 fn cxx_standard() -> String {
     env::var("ROCKSDB_CXX_STD").map_or("-std=c++17".to_owned(), |cxx_std| {
         if !cxx_std.starts_with("-std=") {
@@ -349,8 +397,22 @@ fn main() {
     if !try_to_find_and_link_lib("ROCKSDB") {
         println!("cargo:rerun-if-changed=rocksdb/");
         fail_on_empty_directory("rocksdb");
+
+        // The extensions are linked before RocksDB itself, so that static
+        // linkers resolve their references to it.
+        println!("cargo:rerun-if-changed=ext/");
+        build_ext();
         build_rocksdb();
     } else {
+        // The extensions include RocksDB's internal headers and the private
+        // structs of its C API (see ext/c_types.h), so they can only be
+        // built together with the bundled RocksDB.
+        println!(
+            "cargo:warning=ROCKSDB_LIB_DIR is set, so the rocksdb_ext_* functions are \
+             not built: they depend on the internals of the bundled RocksDB {}",
+            rocksdb_version()
+        );
+
         let target = env::var("TARGET").unwrap();
         // according to https://github.com/alexcrichton/cc-rs/blob/master/src/lib.rs#L2189
         if target.contains("apple") || target.contains("freebsd") || target.contains("openbsd") {
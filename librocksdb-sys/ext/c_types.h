@@ -0,0 +1,80 @@
+// Definitions of the C API wrapper structs from rocksdb/db/c.cc.
+//
+// The C API keeps these structs private to c.cc.  The extensions in this
+// directory need to reach the wrapped C++ objects, so the definitions are
+// repeated here verbatim.  They must be kept in sync with the bundled
+// RocksDB version.
+
+#pragma once
+
+#include "rocksdb/compaction_filter.h"
+#include "rocksdb/db.h"
+#include "rocksdb/env.h"
+#include "rocksdb/iterator.h"
+#include "rocksdb/options.h"
+#include "rocksdb/slice.h"
+#include "rocksdb/utilities/transaction_db.h"
+#include "rocksdb/version.h"
+#include "rocksdb/write_batch.h"
+
+static_assert(ROCKSDB_MAJOR == 7 && ROCKSDB_MINOR == 4 && ROCKSDB_PATCH == 4,
+              "the structs below are copied from RocksDB 7.4.4, check them "
+              "against db/c.cc before upgrading");
+
+struct rocksdb_env_t {
+  ROCKSDB_NAMESPACE::Env* rep;
+  bool is_default;
+};
+
+struct rocksdb_options_t {
+  ROCKSDB_NAMESPACE::Options rep;
+};
+
+struct rocksdb_ingestexternalfileoptions_t {
+  ROCKSDB_NAMESPACE::IngestExternalFileOptions rep;
+};
+
+struct rocksdb_iterator_t {
+  ROCKSDB_NAMESPACE::Iterator* rep;
+};
+
+struct rocksdb_readoptions_t {
+  ROCKSDB_NAMESPACE::ReadOptions rep;
+  // stack variables to set pointers to in ReadOptions
+  ROCKSDB_NAMESPACE::Slice upper_bound;
+  ROCKSDB_NAMESPACE::Slice lower_bound;
+  ROCKSDB_NAMESPACE::Slice timestamp;
+  ROCKSDB_NAMESPACE::Slice iter_start_ts;
+};
+
+struct rocksdb_t {
+  ROCKSDB_NAMESPACE::DB* rep;
+};
+
+struct rocksdb_column_family_handle_t {
+  ROCKSDB_NAMESPACE::ColumnFamilyHandle* rep;
+};
+
+struct rocksdb_compactionfiltercontext_t {
+  ROCKSDB_NAMESPACE::CompactionFilter::Context rep;
+};
+
+struct rocksdb_transactiondb_options_t {
+  ROCKSDB_NAMESPACE::TransactionDBOptions rep;
+};
+
+struct rocksdb_transaction_options_t {
+  ROCKSDB_NAMESPACE::TransactionOptions rep;
+};
+
+struct rocksdb_transactiondb_t {
+  ROCKSDB_NAMESPACE::TransactionDB* rep;
+};
+
+struct rocksdb_transaction_t {
+  ROCKSDB_NAMESPACE::Transaction* rep;
+};
+
+struct rocksdb_writebatch_t {
+  ROCKSDB_NAMESPACE::WriteBatch rep;
+};
@@ -0,0 +1,109 @@
+// CompactionFilter and CompactionFilterFactory implemented through C
+// callbacks, exposing FilterV2().
+
+#include <memory>
+#include <string>
+
+#include "c_types.h"
+#include "ext.h"
+#include "rocksdb/compaction_filter.h"
+#include "util.h"
+
+using ROCKSDB_NAMESPACE::CompactionFilter;
+using ROCKSDB_NAMESPACE::CompactionFilterFactory;
+using ROCKSDB_NAMESPACE::Slice;
+using ROCKSDB_NAMESPACE::TableFileCreationReason;
+
+static_assert(CompactionFilter::ValueType::kBlobIndex ==
+                  rocksdb_ext_compactionfilter_blob_index,
+              "update ValueType in src/compaction_filter.rs");
+
+void rocksdb_ext_string_assign(rocksdb_ext_string_t* str, const char* data,
+                               size_t len) {
+  str->rep->assign(data, len);
+}
+
+uint32_t rocksdb_ext_compactionfiltercontext_column_family_id(
+    rocksdb_compactionfiltercontext_t* context) {
+  return context->rep.column_family_id;
+}
+
+int rocksdb_ext_compactionfiltercontext_reason(
+    rocksdb_compactionfiltercontext_t* context) {
+  return static_cast<int>(context->rep.reason);
+}
+
+namespace {
+
+using FilterCallbacks = rocksdb_ext_compactionfilter_callbacks_t;
+using FactoryCallbacks = rocksdb_ext_compactionfilterfactory_callbacks_t;
+
+class CallbackCompactionFilter : public CompactionFilter {
+ public:
+  CallbackCompactionFilter(void* state, const FilterCallbacks& cb)
+      : state_(state), cb_(cb) {}
+
+  ~CallbackCompactionFilter() override { cb_.destructor(state_); }
+
+  Decision FilterV2(int level, const Slice& key, ValueType value_type,
+                    const Slice& existing_value, std::string* new_value,
+                    std::string* skip_until) const override {
+    rocksdb_ext_string_t new_value_str{new_value};
+    rocksdb_ext_string_t skip_until_str{skip_until};
+    int decision =
+        cb_.filter(state_, level, key.data(), key.size(),
+                   static_cast<int>(value_type), existing_value.data(),
+                   existing_value.size(), &new_value_str, &skip_until_str);
+    switch (decision) {
+      case rocksdb_ext_compactionfilter_remove:
+        return Decision::kRemove;
+      case rocksdb_ext_compactionfilter_change_value:
+        return Decision::kChangeValue;
+      case rocksdb_ext_compactionfilter_remove_and_skip_until:
+        return Decision::kRemoveAndSkipUntil;
+      default:
+        return Decision::kKeep;
+    }
+  }
+
+  const char* Name() const override { return cb_.name(state_); }
+
+ private:
+  void* state_;
+  FilterCallbacks cb_;
+};
+
+class CallbackCompactionFilterFactory : public CompactionFilterFactory {
+ public:
+  CallbackCompactionFilterFactory(void* state, const FactoryCallbacks& cb)
+      : state_(state), cb_(cb) {}
+
+  ~CallbackCompactionFilterFactory() override { cb_.destructor(state_); }
+
+  std::unique_ptr<CompactionFilter> CreateCompactionFilter(
+      const CompactionFilter::Context& context) override {
+    rocksdb_compactionfiltercontext_t ccontext{context};
+    void* filter = cb_.create_filter(state_, &ccontext);
+    return std::unique_ptr<CompactionFilter>(
+        new CallbackCompactionFilter(filter, cb_.filter));
+  }
+
+  const char* Name() const override { return cb_.name(state_); }
+
+ private:
+  void* state_;
+  FactoryCallbacks cb_;
+};
+
+}  // namespace
+
+void rocksdb_ext_options_set_compaction_filter(
+    rocksdb_options_t* opt, void* state, const FilterCallbacks* callbacks) {
+  opt->rep.compaction_filter = new CallbackCompactionFilter(state, *callbacks);
+}
+
+void rocksdb_ext_options_set_compaction_filter_factory(
+    rocksdb_options_t* opt, void* state, const FactoryCallbacks* callbacks) {
+  opt->rep.compaction_filter_factory =
+      std::make_shared<CallbackCompactionFilterFactory>(state, *callbacks);
+}
@@ -0,0 +1,81 @@
+// Comparator implemented through C callbacks, including the key shortening
+// and timestamp hooks the C API comparator does not forward.
+
+#include <string>
+
+#include "c_types.h"
+#include "ext.h"
+#include "rocksdb/comparator.h"
+#include "util.h"
+
+using ROCKSDB_NAMESPACE::Comparator;
+using ROCKSDB_NAMESPACE::Slice;
+
+struct rocksdb_ext_comparator_t : public Comparator {
+  rocksdb_ext_comparator_t(void* state,
+                           const rocksdb_ext_comparator_callbacks_t& cb,
+                           size_t timestamp_size)
+      : Comparator(timestamp_size), state_(state), cb_(cb) {}
+
+  ~rocksdb_ext_comparator_t() override { cb_.destructor(state_); }
+
+  const char* Name() const override { return cb_.name(state_); }
+
+  int Compare(const Slice& a, const Slice& b) const override {
+    return cb_.compare(state_, a.data(), a.size(), b.data(), b.size());
+  }
+
+  void FindShortestSeparator(std::string* start,
+                             const Slice& limit) const override {
+    std::string result;
+    rocksdb_ext_string_t result_str{&result};
+    if (cb_.find_shortest_separator(state_, start->data(), start->size(),
+                                    limit.data(), limit.size(),
+                                    &result_str)) {
+      start->swap(result);
+    }
+  }
+
+  void FindShortSuccessor(std::string* key) const override {
+    std::string result;
+    rocksdb_ext_string_t result_str{&result};
+    if (cb_.find_short_successor(state_, key->data(), key->size(),
+                                 &result_str)) {
+      key->swap(result);
+    }
+  }
+
+  int CompareTimestamp(const Slice& ts1, const Slice& ts2) const override {
+    return cb_.compare_timestamp(state_, ts1.data(), ts1.size(), ts2.data(),
+                                 ts2.size());
+  }
+
+  using Comparator::CompareWithoutTimestamp;
+  int CompareWithoutTimestamp(const Slice& a, bool a_has_ts, const Slice& b,
+                              bool b_has_ts) const override {
+    const size_t ts_sz = timestamp_size();
+    Slice lhs = a_has_ts ? Slice(a.data(), a.size() - ts_sz) : a;
+    Slice rhs = b_has_ts ? Slice(b.data(), b.size() - ts_sz) : b;
+    return cb_.compare_without_timestamp(state_, lhs.data(), lhs.size(),
+                                         rhs.data(), rhs.size());
+  }
+
+ private:
+  void* state_;
+  rocksdb_ext_comparator_callbacks_t cb_;
+};
+
+rocksdb_ext_comparator_t* rocksdb_ext_comparator_create(
+    void* state, const rocksdb_ext_comparator_callbacks_t* callbacks,
+    size_t timestamp_size) {
+  return new rocksdb_ext_comparator_t(state, *callbacks, timestamp_size);
+}
+
+void rocksdb_ext_comparator_destroy(rocksdb_ext_comparator_t* cmp) {
+  delete cmp;
+}
+
+void rocksdb_ext_options_set_comparator(rocksdb_options_t* opt,
+                                        const rocksdb_ext_comparator_t* cmp) {
+  opt->rep.comparator = cmp;
+}
@@ -0,0 +1,582 @@
+// Env and FileSystem implemented through C callbacks.
+
+#include <cstdarg>
+#include <cstdio>
+#include <ctime>
+#include <memory>
+#include <mutex>
+#include <string>
+#include <vector>
+
+#include "c_types.h"
+#include "ext.h"
+#include "rocksdb/env.h"
+#include "rocksdb/file_system.h"
+#include "rocksdb/system_clock.h"
+#include "util.h"
+
+using ROCKSDB_NAMESPACE::Env;
+using ROCKSDB_NAMESPACE::EnvWrapper;
+using ROCKSDB_NAMESPACE::FileLock;
+using ROCKSDB_NAMESPACE::FileOptions;
+using ROCKSDB_NAMESPACE::FileSystem;
+using ROCKSDB_NAMESPACE::FSDirectory;
+using ROCKSDB_NAMESPACE::FSRandomAccessFile;
+using ROCKSDB_NAMESPACE::FSSequentialFile;
+using ROCKSDB_NAMESPACE::FSWritableFile;
+using ROCKSDB_NAMESPACE::IODebugContext;
+using ROCKSDB_NAMESPACE::IOOptions;
+using ROCKSDB_NAMESPACE::IOStatus;
+using ROCKSDB_NAMESPACE::Logger;
+using ROCKSDB_NAMESPACE::NewCompositeEnv;
+using ROCKSDB_NAMESPACE::Slice;
+using ROCKSDB_NAMESPACE::Status;
+using ROCKSDB_NAMESPACE::SystemClock;
+using rocksdb_ext::ToIOStatus;
+
+struct rocksdb_ext_string_vector_t {
+  std::vector<std::string> rep;
+};
+
+void rocksdb_ext_string_vector_push(rocksdb_ext_string_vector_t* vec,
+                                    const char* name, size_t name_len) {
+  vec->rep.emplace_back(name, name_len);
+}
+
+namespace {
+
+// Owns the callback state.  Shared by the file system and every file it
+// opened so that the state outlives all of them.
+struct FsState {
+  void* state;
+  rocksdb_ext_filesystem_callbacks_t cb;
+
+  ~FsState() { cb.destructor(state); }
+};
+
+class CallbackSequentialFile : public FSSequentialFile {
+ public:
+  CallbackSequentialFile(std::shared_ptr<FsState> fs, void* file)
+      : fs_(std::move(fs)), file_(file) {}
+  ~CallbackSequentialFile() override { fs_->cb.sequential_destroy(file_); }
+
+  IOStatus Read(size_t n, const IOOptions& /*options*/, Slice* result,
+                char* scratch, IODebugContext* /*dbg*/) override {
+    size_t read = 0;
+    char* errmsg = nullptr;
+    int code = fs_->cb.sequential_read(file_, n, scratch, &read, &errmsg);
+    *result = Slice(scratch, code == rocksdb_ext_io_ok ? read : 0);
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus Skip(uint64_t n) override {
+    char* errmsg = nullptr;
+    int code = fs_->cb.sequential_skip(file_, n, &errmsg);
+    return ToIOStatus(code, errmsg);
+  }
+
+ private:
+  std::shared_ptr<FsState> fs_;
+  void* file_;
+};
+
+class CallbackRandomAccessFile : public FSRandomAccessFile {
+ public:
+  CallbackRandomAccessFile(std::shared_ptr<FsState> fs, void* file)
+      : fs_(std::move(fs)), file_(file) {}
+  ~CallbackRandomAccessFile() override {
+    fs_->cb.random_access_destroy(file_);
+  }
+
+  IOStatus Read(uint64_t offset, size_t n, const IOOptions& /*options*/,
+                Slice* result, char* scratch,
+                IODebugContext* /*dbg*/) const override {
+    size_t read = 0;
+    char* errmsg = nullptr;
+    int code = fs_->cb.random_access_read(file_, offset, n, scratch, &read,
+                                          &errmsg);
+    *result = Slice(scratch, code == rocksdb_ext_io_ok ? read : 0);
+    return ToIOStatus(code, errmsg);
+  }
+
+ private:
+  std::shared_ptr<FsState> fs_;
+  void* file_;
+};
+
+class CallbackWritableFile : public FSWritableFile {
+ public:
+  CallbackWritableFile(std::shared_ptr<FsState> fs, void* file)
+      : fs_(std::move(fs)), file_(file) {}
+  ~CallbackWritableFile() override { fs_->cb.writable_destroy(file_); }
+
+  using FSWritableFile::Append;
+  IOStatus Append(const Slice& data, const IOOptions& /*options*/,
+                  IODebugContext* /*dbg*/) override {
+    char* errmsg = nullptr;
+    int code =
+        fs_->cb.writable_append(file_, data.data(), data.size(), &errmsg);
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus Truncate(uint64_t size, const IOOptions& /*options*/,
+                    IODebugContext* /*dbg*/) override {
+    char* errmsg = nullptr;
+    int code = fs_->cb.writable_truncate(file_, size, &errmsg);
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus Close(const IOOptions& /*options*/,
+                 IODebugContext* /*dbg*/) override {
+    char* errmsg = nullptr;
+    int code = fs_->cb.writable_close(file_, &errmsg);
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus Flush(const IOOptions& /*options*/,
+                 IODebugContext* /*dbg*/) override {
+    char* errmsg = nullptr;
+    int code = fs_->cb.writable_flush(file_, &errmsg);
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus Sync(const IOOptions& /*options*/,
+                IODebugContext* /*dbg*/) override {
+    char* errmsg = nullptr;
+    int code = fs_->cb.writable_sync(file_, &errmsg);
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus Fsync(const IOOptions& /*options*/,
+                 IODebugContext* /*dbg*/) override {
+    char* errmsg = nullptr;
+    int code = fs_->cb.writable_fsync(file_, &errmsg);
+    return ToIOStatus(code, errmsg);
+  }
+
+  uint64_t GetFileSize(const IOOptions& /*options*/,
+                       IODebugContext* /*dbg*/) override {
+    return fs_->cb.writable_file_size(file_);
+  }
+
+ private:
+  std::shared_ptr<FsState> fs_;
+  void* file_;
+};
+
+class CallbackDirectory : public FSDirectory {
+ public:
+  CallbackDirectory(std::shared_ptr<FsState> fs, void* dir)
+      : fs_(std::move(fs)), dir_(dir) {}
+  ~CallbackDirectory() override { fs_->cb.directory_destroy(dir_); }
+
+  IOStatus Fsync(const IOOptions& /*options*/,
+                 IODebugContext* /*dbg*/) override {
+    char* errmsg = nullptr;
+    int code = fs_->cb.directory_fsync(dir_, &errmsg);
+    return ToIOStatus(code, errmsg);
+  }
+
+ private:
+  std::shared_ptr<FsState> fs_;
+  void* dir_;
+};
+
+class CallbackFileLock : public FileLock {
+ public:
+  explicit CallbackFileLock(std::string fname) : fname_(std::move(fname)) {}
+
+  const std::string& fname() const { return fname_; }
+
+ private:
+  std::string fname_;
+};
+
+// Writes info log lines to a file of the callback file system.
+class CallbackLogger : public Logger {
+ public:
+  CallbackLogger(std::unique_ptr<FSWritableFile>&& file,
+                 std::shared_ptr<SystemClock> clock)
+      : file_(std::move(file)), clock_(std::move(clock)) {}
+
+  ~CallbackLogger() override {
+    if (!closed_) {
+      closed_ = true;
+      CloseHelper().PermitUncheckedError();
+    }
+  }
+
+  using Logger::Logv;
+  void Logv(const char* format, va_list ap) override {
+    const uint64_t now = clock_->NowMicros();
+    const time_t seconds = static_cast<time_t>(now / 1000000);
+    struct tm t;
+#ifdef _WIN32
+    localtime_s(&t, &seconds);
+#else
+    localtime_r(&seconds, &t);
+#endif
+    char prefix[64];
+    snprintf(prefix, sizeof(prefix), "%04d/%02d/%02d-%02d:%02d:%02d.%06d ",
+             t.tm_year + 1900, t.tm_mon + 1, t.tm_mday, t.tm_hour, t.tm_min,
+             t.tm_sec, static_cast<int>(now % 1000000));
+
+    va_list backup_ap;
+    va_copy(backup_ap, ap);
+    int len = vsnprintf(nullptr, 0, format, backup_ap);
+    va_end(backup_ap);
+    if (len < 0) {
+      return;
+    }
+    std::string line(prefix);
+    size_t offset = line.size();
+    line.resize(offset + static_cast<size_t>(len) + 1);
+    vsnprintf(&line[offset], static_cast<size_t>(len) + 1, format, ap);
+    line.resize(offset + static_cast<size_t>(len));
+    if (line.empty() || line.back() != '\n') {
+      line.push_back('\n');
+    }
+
+    std::lock_guard<std::mutex> lock(mu_);
+    file_->Append(line, IOOptions(), nullptr).PermitUncheckedError();
+    file_->Flush(IOOptions(), nullptr).PermitUncheckedError();
+    size_ += line.size();
+  }
+
+  size_t GetLogFileSize() const override { return size_; }
+
+ protected:
+  Status CloseImpl() override { return CloseHelper(); }
+
+ private:
+  Status CloseHelper() {
+    std::lock_guard<std::mutex> lock(mu_);
+    return file_->Close(IOOptions(), nullptr);
+  }
+
+  std::mutex mu_;
+  std::unique_ptr<FSWritableFile> file_;
+  std::shared_ptr<SystemClock> clock_;
+  size_t size_ = 0;
+};
+
+class CallbackFileSystem : public FileSystem {
+ public:
+  CallbackFileSystem(void* state, const rocksdb_ext_filesystem_callbacks_t& cb)
+      : fs_(new FsState{state, cb}) {}
+
+  static const char* kClassName() { return "CallbackFileSystem"; }
+  const char* Name() const override { return fs_->cb.name(fs_->state); }
+
+  IOStatus NewSequentialFile(const std::string& fname,
+                             const FileOptions& /*file_opts*/,
+                             std::unique_ptr<FSSequentialFile>* result,
+                             IODebugContext* /*dbg*/) override {
+    void* file = nullptr;
+    char* errmsg = nullptr;
+    int code = fs_->cb.new_sequential_file(fs_->state, fname.data(),
+                                           fname.size(), &file, &errmsg);
+    if (code == rocksdb_ext_io_ok) {
+      result->reset(new CallbackSequentialFile(fs_, file));
+    }
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus NewRandomAccessFile(const std::string& fname,
+                               const FileOptions& /*file_opts*/,
+                               std::unique_ptr<FSRandomAccessFile>* result,
+                               IODebugContext* /*dbg*/) override {
+    void* file = nullptr;
+    char* errmsg = nullptr;
+    int code = fs_->cb.new_random_access_file(fs_->state, fname.data(),
+                                              fname.size(), &file, &errmsg);
+    if (code == rocksdb_ext_io_ok) {
+      result->reset(new CallbackRandomAccessFile(fs_, file));
+    }
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus NewWritableFile(const std::string& fname,
+                           const FileOptions& /*file_opts*/,
+                           std::unique_ptr<FSWritableFile>* result,
+                           IODebugContext* /*dbg*/) override {
+    void* file = nullptr;
+    char* errmsg = nullptr;
+    int code = fs_->cb.new_writable_file(fs_->state, fname.data(),
+                                         fname.size(), &file, &errmsg);
+    if (code == rocksdb_ext_io_ok) {
+      result->reset(new CallbackWritableFile(fs_, file));
+    }
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus ReopenWritableFile(const std::string& fname,
+                              const FileOptions& /*file_opts*/,
+                              std::unique_ptr<FSWritableFile>* result,
+                              IODebugContext* /*dbg*/) override {
+    void* file = nullptr;
+    char* errmsg = nullptr;
+    int code = fs_->cb.reopen_writable_file(fs_->state, fname.data(),
+                                            fname.size(), &file, &errmsg);
+    if (code == rocksdb_ext_io_ok) {
+      result->reset(new CallbackWritableFile(fs_, file));
+    }
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus NewDirectory(const std::string& name, const IOOptions& /*io_opts*/,
+                        std::unique_ptr<FSDirectory>* result,
+                        IODebugContext* /*dbg*/) override {
+    void* dir = nullptr;
+    char* errmsg = nullptr;
+    int code = fs_->cb.new_directory(fs_->state, name.data(), name.size(),
+                                     &dir, &errmsg);
+    if (code == rocksdb_ext_io_ok) {
+      result->reset(new CallbackDirectory(fs_, dir));
+    }
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus FileExists(const std::string& fname, const IOOptions& /*options*/,
+                      IODebugContext* /*dbg*/) override {
+    char* errmsg = nullptr;
+    int code =
+        fs_->cb.file_exists(fs_->state, fname.data(), fname.size(), &errmsg);
+    return ToIOStatus(code, errmsg, false);
+  }
+
+  IOStatus GetChildren(const std::string& dir, const IOOptions& /*options*/,
+                       std::vector<std::string>* result,
+                       IODebugContext* /*dbg*/) override {
+    rocksdb_ext_string_vector_t children;
+    char* errmsg = nullptr;
+    int code = fs_->cb.get_children(fs_->state, dir.data(), dir.size(),
+                                    &children, &errmsg);
+    if (code == rocksdb_ext_io_ok) {
+      *result = std::move(children.rep);
+    }
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus DeleteFile(const std::string& fname, const IOOptions& /*options*/,
+                      IODebugContext* /*dbg*/) override {
+    char* errmsg = nullptr;
+    int code =
+        fs_->cb.delete_file(fs_->state, fname.data(), fname.size(), &errmsg);
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus CreateDir(const std::string& dirname, const IOOptions& /*options*/,
+                     IODebugContext* /*dbg*/) override {
+    char* errmsg = nullptr;
+    int code = fs_->cb.create_dir(fs_->state, dirname.data(), dirname.size(),
+                                  &errmsg);
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus CreateDirIfMissing(const std::string& dirname,
+                              const IOOptions& /*options*/,
+                              IODebugContext* /*dbg*/) override {
+    char* errmsg = nullptr;
+    int code = fs_->cb.create_dir_if_missing(fs_->state, dirname.data(),
+                                             dirname.size(), &errmsg);
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus DeleteDir(const std::string& dirname, const IOOptions& /*options*/,
+                     IODebugContext* /*dbg*/) override {
+    char* errmsg = nullptr;
+    int code = fs_->cb.delete_dir(fs_->state, dirname.data(), dirname.size(),
+                                  &errmsg);
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus GetFileSize(const std::string& fname, const IOOptions& /*options*/,
+                       uint64_t* file_size, IODebugContext* /*dbg*/) override {
+    char* errmsg = nullptr;
+    int code = fs_->cb.get_file_size(fs_->state, fname.data(), fname.size(),
+                                     file_size, &errmsg);
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus GetFileModificationTime(const std::string& fname,
+                                   const IOOptions& /*options*/,
+                                   uint64_t* file_mtime,
+                                   IODebugContext* /*dbg*/) override {
+    char* errmsg = nullptr;
+    int code = fs_->cb.get_file_modification_time(
+        fs_->state, fname.data(), fname.size(), file_mtime, &errmsg);
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus RenameFile(const std::string& src, const std::string& target,
+                      const IOOptions& /*options*/,
+                      IODebugContext* /*dbg*/) override {
+    char* errmsg = nullptr;
+    int code = fs_->cb.rename_file(fs_->state, src.data(), src.size(),
+                                   target.data(), target.size(), &errmsg);
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus LinkFile(const std::string& src, const std::string& target,
+                    const IOOptions& /*options*/,
+                    IODebugContext* /*dbg*/) override {
+    char* errmsg = nullptr;
+    int code = fs_->cb.link_file(fs_->state, src.data(), src.size(),
+                                 target.data(), target.size(), &errmsg);
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus LockFile(const std::string& fname, const IOOptions& /*options*/,
+                    FileLock** lock, IODebugContext* /*dbg*/) override {
+    *lock = nullptr;
+    char* errmsg = nullptr;
+    int code =
+        fs_->cb.lock_file(fs_->state, fname.data(), fname.size(), &errmsg);
+    if (code == rocksdb_ext_io_ok) {
+      *lock = new CallbackFileLock(fname);
+    }
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus UnlockFile(FileLock* lock, const IOOptions& /*options*/,
+                      IODebugContext* /*dbg*/) override {
+    auto* file_lock = static_cast<CallbackFileLock*>(lock);
+    char* errmsg = nullptr;
+    int code = fs_->cb.unlock_file(fs_->state, file_lock->fname().data(),
+                                   file_lock->fname().size(), &errmsg);
+    delete file_lock;
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus GetTestDirectory(const IOOptions& /*options*/,
+                            std::string* /*path*/,
+                            IODebugContext* /*dbg*/) override {
+    return IOStatus::NotSupported("GetTestDirectory");
+  }
+
+  IOStatus NewLogger(const std::string& fname, const IOOptions& /*io_opts*/,
+                     std::shared_ptr<Logger>* result,
+                     IODebugContext* dbg) override {
+    std::unique_ptr<FSWritableFile> file;
+    IOStatus s = NewWritableFile(fname, FileOptions(), &file, dbg);
+    if (s.ok()) {
+      result->reset(new CallbackLogger(std::move(file), clock_));
+    }
+    return s;
+  }
+
+  IOStatus GetAbsolutePath(const std::string& db_path,
+                           const IOOptions& /*options*/,
+                           std::string* output_path,
+                           IODebugContext* /*dbg*/) override {
+    rocksdb_ext_string_vector_t path;
+    char* errmsg = nullptr;
+    int code = fs_->cb.get_absolute_path(fs_->state, db_path.data(),
+                                         db_path.size(), &path, &errmsg);
+    if (code == rocksdb_ext_io_ok) {
+      if (path.rep.size() != 1) {
+        return IOStatus::IOError("GetAbsolutePath returned no path");
+      }
+      *output_path = std::move(path.rep[0]);
+    }
+    return ToIOStatus(code, errmsg);
+  }
+
+  IOStatus IsDirectory(const std::string& path, const IOOptions& /*options*/,
+                       bool* is_dir, IODebugContext* /*dbg*/) override {
+    unsigned char dir = 0;
+    char* errmsg = nullptr;
+    int code = fs_->cb.is_directory(fs_->state, path.data(), path.size(),
+                                    &dir, &errmsg);
+    if (code == rocksdb_ext_io_ok && is_dir != nullptr) {
+      *is_dir = dir != 0;
+    }
+    return ToIOStatus(code, errmsg);
+  }
+
+  void SetClock(std::shared_ptr<SystemClock> clock) {
+    clock_ = std::move(clock);
+  }
+
+ private:
+  std::shared_ptr<FsState> fs_;
+  std::shared_ptr<SystemClock> clock_ = SystemClock::Default();
+};
+
+class CallbackClock : public SystemClock {
+ public:
+  CallbackClock(void* state, const rocksdb_ext_clock_callbacks_t& cb)
+      : state_(state), cb_(cb) {}
+  ~CallbackClock() override { cb_.destructor(state_); }
+
+  static const char* kClassName() { return "CallbackClock"; }
+  const char* Name() const override { return kClassName(); }
+
+  uint64_t NowMicros() override { return cb_.now_micros(state_); }
+  uint64_t NowNanos() override { return cb_.now_nanos(state_); }
+
+  void SleepForMicroseconds(int micros) override {
+    cb_.sleep_for_microseconds(state_, micros);
+  }
+
+  Status GetCurrentTime(int64_t* unix_time) override {
+    char* errmsg = nullptr;
+    int code = cb_.get_current_time(state_, unix_time, &errmsg);
+    return ToIOStatus(code, errmsg);
+  }
+
+  std::string TimeToString(uint64_t time) override {
+    return SystemClock::Default()->TimeToString(time);
+  }
+
+ private:
+  void* state_;
+  rocksdb_ext_clock_callbacks_t cb_;
+};
+
+// Composite Env using the callback file system and clock.  Threads are
+// provided by the default Env.
+class CallbackEnv : public EnvWrapper {
+ public:
+  CallbackEnv(std::unique_ptr<Env>&& base, std::shared_ptr<SystemClock> clock)
+      : EnvWrapper(std::move(base)) {
+    file_system_ = target()->GetFileSystem();
+    system_clock_ = clock ? std::move(clock) : target()->GetSystemClock();
+  }
+
+  static const char* kClassName() { return "CallbackEnv"; }
+  const char* Name() const override { return kClassName(); }
+
+  uint64_t NowMicros() override { return system_clock_->NowMicros(); }
+  uint64_t NowNanos() override { return system_clock_->NowNanos(); }
+
+  void SleepForMicroseconds(int micros) override {
+    system_clock_->SleepForMicroseconds(micros);
+  }
+
+  Status GetCurrentTime(int64_t* unix_time) override {
+    return system_clock_->GetCurrentTime(unix_time);
+  }
+
+  std::string TimeToString(uint64_t time) override {
+    return system_clock_->TimeToString(time);
+  }
+};
+
+}  // namespace
+
+rocksdb_env_t* rocksdb_ext_create_env(
+    void* fs_state, const rocksdb_ext_filesystem_callbacks_t* fs,
+    void* clock_state, const rocksdb_ext_clock_callbacks_t* clock) {
+  auto file_system = std::make_shared<CallbackFileSystem>(fs_state, *fs);
+  std::shared_ptr<SystemClock> system_clock;
+  if (clock != nullptr) {
+    system_clock = std::make_shared<CallbackClock>(clock_state, *clock);
+    file_system->SetClock(system_clock);
+  }
+  rocksdb_env_t* result = new rocksdb_env_t;
+  result->rep = new CallbackEnv(NewCompositeEnv(file_system), system_clock);
+  result->is_default = false;
+  return result;
+}
@@ -0,0 +1,194 @@
+// EventListener implemented through C callbacks.
+
+#include <chrono>
+#include <map>
+#include <memory>
+#include <mutex>
+#include <string>
+#include <tuple>
+#include <vector>
+
+#include "c_types.h"
+#include "ext.h"
+#include "rocksdb/db.h"
+#include "rocksdb/listener.h"
+
+using ROCKSDB_NAMESPACE::BackgroundErrorReason;
+using ROCKSDB_NAMESPACE::CompactionJobInfo;
+using ROCKSDB_NAMESPACE::CompactionReason;
+using ROCKSDB_NAMESPACE::DB;
+using ROCKSDB_NAMESPACE::EventListener;
+using ROCKSDB_NAMESPACE::FlushJobInfo;
+using ROCKSDB_NAMESPACE::FlushReason;
+using ROCKSDB_NAMESPACE::Status;
+using ROCKSDB_NAMESPACE::TableFileCreationInfo;
+using ROCKSDB_NAMESPACE::TableFileCreationReason;
+using ROCKSDB_NAMESPACE::WriteStallCondition;
+using ROCKSDB_NAMESPACE::WriteStallInfo;
+
+// The reason and condition enums in src/event_listener.rs mirror the
+// RocksDB enums by value.
+static_assert(static_cast<int>(FlushReason::kWalFull) == 13,
+              "update FlushReason in src/event_listener.rs");
+static_assert(static_cast<int>(CompactionReason::kNumOfReasons) == 18,
+              "update CompactionReason in src/event_listener.rs");
+static_assert(static_cast<int>(WriteStallCondition::kStopped) == 2,
+              "update WriteStallCondition in src/event_listener.rs");
+static_assert(static_cast<int>(BackgroundErrorReason::kManifestWriteNoWAL) ==
+                  6,
+              "update BackgroundErrorReason in src/event_listener.rs");
+static_assert(static_cast<int>(TableFileCreationReason::kMisc) == 3,
+              "update TableFileCreationReason in src/event_listener.rs");
+
+namespace {
+
+// Keeps the strings of a file list alive while the callback runs.
+struct FileList {
+  explicit FileList(const std::vector<std::string>& files) {
+    for (const auto& f : files) {
+      names.push_back(f.data());
+      lens.push_back(f.size());
+    }
+  }
+
+  std::vector<const char*> names;
+  std::vector<size_t> lens;
+};
+
+class CallbackEventListener : public EventListener {
+ public:
+  CallbackEventListener(void* state,
+                        const rocksdb_ext_eventlistener_callbacks_t& cb)
+      : state_(state), cb_(cb) {}
+
+  ~CallbackEventListener() override { cb_.destructor(state_); }
+
+  const char* Name() const override { return "CallbackEventListener"; }
+
+  void OnFlushBegin(DB* db, const FlushJobInfo& info) override {
+    std::lock_guard<std::mutex> lock(mu_);
+    flush_start_[KeyOf(db, info)] = std::chrono::steady_clock::now();
+  }
+
+  void OnFlushCompleted(DB* db, const FlushJobInfo& info) override {
+    uint64_t elapsed_micros = 0;
+    {
+      std::lock_guard<std::mutex> lock(mu_);
+      auto it = flush_start_.find(KeyOf(db, info));
+      if (it != flush_start_.end()) {
+        elapsed_micros = std::chrono::duration_cast<std::chrono::microseconds>(
+                             std::chrono::steady_clock::now() - it->second)
+                             .count();
+        flush_start_.erase(it);
+      }
+    }
+
+    uint64_t file_size = 0;
+    if (!db->GetEnv()->GetFileSize(info.file_path, &file_size).ok()) {
+      file_size = 0;
+    }
+
+    rocksdb_ext_flush_job_info_t c_info;
+    c_info.cf_name = info.cf_name.data();
+    c_info.cf_name_len = info.cf_name.size();
+    c_info.file_path = info.file_path.data();
+    c_info.file_path_len = info.file_path.size();
+    c_info.file_number = info.file_number;
+    c_info.file_size = file_size;
+    c_info.thread_id = info.thread_id;
+    c_info.job_id = info.job_id;
+    c_info.triggered_writes_slowdown = info.triggered_writes_slowdown;
+    c_info.triggered_writes_stop = info.triggered_writes_stop;
+    c_info.smallest_seqno = info.smallest_seqno;
+    c_info.largest_seqno = info.largest_seqno;
+    c_info.num_entries = info.table_properties.num_entries;
+    c_info.raw_key_size = info.table_properties.raw_key_size;
+    c_info.raw_value_size = info.table_properties.raw_value_size;
+    c_info.reason = static_cast<int>(info.flush_reason);
+    c_info.elapsed_micros = elapsed_micros;
+    cb_.on_flush_completed(state_, &c_info);
+  }
+
+  void OnCompactionCompleted(DB* /*db*/,
+                             const CompactionJobInfo& info) override {
+    std::string status = info.status.ToString();
+    FileList inputs(info.input_files);
+    FileList outputs(info.output_files);
+
+    rocksdb_ext_compaction_job_info_t c_info;
+    c_info.cf_name = info.cf_name.data();
+    c_info.cf_name_len = info.cf_name.size();
+    c_info.status = info.status.ok() ? nullptr : status.c_str();
+    c_info.thread_id = info.thread_id;
+    c_info.job_id = info.job_id;
+    c_info.base_input_level = info.base_input_level;
+    c_info.output_level = info.output_level;
+    c_info.input_files = inputs.names.data();
+    c_info.input_file_lens = inputs.lens.data();
+    c_info.num_input_files = inputs.names.size();
+    c_info.output_files = outputs.names.data();
+    c_info.output_file_lens = outputs.lens.data();
+    c_info.num_output_files = outputs.names.size();
+    c_info.reason = static_cast<int>(info.compaction_reason);
+    c_info.elapsed_micros = info.stats.elapsed_micros;
+    c_info.total_input_bytes = info.stats.total_input_bytes;
+    c_info.total_output_bytes = info.stats.total_output_bytes;
+    c_info.num_input_records = info.stats.num_input_records;
+    c_info.num_output_records = info.stats.num_output_records;
+    cb_.on_compaction_completed(state_, &c_info);
+  }
+
+  void OnStallConditionsChanged(const WriteStallInfo& info) override {
+    rocksdb_ext_write_stall_info_t c_info;
+    c_info.cf_name = info.cf_name.data();
+    c_info.cf_name_len = info.cf_name.size();
+    c_info.cur = static_cast<int>(info.condition.cur);
+    c_info.prev = static_cast<int>(info.condition.prev);
+    cb_.on_stall_conditions_changed(state_, &c_info);
+  }
+
+  void OnBackgroundError(BackgroundErrorReason reason,
+                         Status* bg_error) override {
+    cb_.on_background_error(state_, static_cast<int>(reason),
+                            bg_error->ToString().c_str());
+  }
+
+  void OnTableFileCreated(const TableFileCreationInfo& info) override {
+    std::string status = info.status.ToString();
+
+    rocksdb_ext_table_file_creation_info_t c_info;
+    c_info.cf_name = info.cf_name.data();
+    c_info.cf_name_len = info.cf_name.size();
+    c_info.file_path = info.file_path.data();
+    c_info.file_path_len = info.file_path.size();
+    c_info.job_id = info.job_id;
+    c_info.reason = static_cast<int>(info.reason);
+    c_info.file_size = info.file_size;
+    c_info.num_entries = info.table_properties.num_entries;
+    c_info.status = info.status.ok() ? nullptr : status.c_str();
+    cb_.on_table_file_created(state_, &c_info);
+  }
+
+ private:
+  // Flush job ids are only unique within a database; a listener may be
+  // shared by several.
+  using FlushKey = std::tuple<DB*, uint32_t, int>;
+
+  static FlushKey KeyOf(DB* db, const FlushJobInfo& info) {
+    return FlushKey(db, info.cf_id, info.job_id);
+  }
+
+  void* state_;
+  rocksdb_ext_eventlistener_callbacks_t cb_;
+  std::mutex mu_;
+  std::map<FlushKey, std::chrono::steady_clock::time_point> flush_start_;
+};
+
+}  // namespace
+
+void rocksdb_ext_options_add_eventlistener(
+    rocksdb_options_t* opt, void* state,
+    const rocksdb_ext_eventlistener_callbacks_t* callbacks) {
+  opt->rep.listeners.push_back(
+      std::make_shared<CallbackEventListener>(state, *callbacks));
+}
@@ -0,0 +1,785 @@
+/* Extensions to the RocksDB C API used by the `rocksdb` crate.
+
+   The upstream C API does not expose every extension point RocksDB
+   offers.  The functions declared here are implemented in this directory
+   on top of the C++ API and follow the conventions of rocksdb/c.h:
+   objects are opaque, callbacks take an opaque `state` pointer and
+   errors are reported through a `char** errptr` that must be freed
+   with free(). */
+
+#ifndef ROCKSDB_RUST_EXT_H
+#define ROCKSDB_RUST_EXT_H
+
+#include "rocksdb/c.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* File system */
+
+/* Status codes returned by the file system callbacks. */
+enum {
+  rocksdb_ext_io_ok = 0,
+  rocksdb_ext_io_not_found = 1,
+  rocksdb_ext_io_not_supported = 2,
+  rocksdb_ext_io_invalid_argument = 3,
+  rocksdb_ext_io_no_space = 4,
+  rocksdb_ext_io_busy = 5,
+  rocksdb_ext_io_corruption = 6,
+  rocksdb_ext_io_error = 7,
+};
+
+typedef struct rocksdb_ext_string_vector_t rocksdb_ext_string_vector_t;
+
+/* Appends a copy of `name` to `vec`.  Used by `get_children`. */
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_string_vector_push(
+    rocksdb_ext_string_vector_t* vec, const char* name, size_t name_len);
+
+/* Callbacks implementing a RocksDB FileSystem.  Every callback returning
+   an int returns one of the rocksdb_ext_io_* codes and may store a
+   malloc()-ed message in `errmsg`.  Paths are not NUL-terminated.
+
+   File handles created by the `new_*` callbacks are opaque pointers that
+   are passed back to the matching file callbacks and released with the
+   matching `*_destroy` callback. */
+typedef struct {
+  void (*destructor)(void* state);
+  const char* (*name)(void* state);
+
+  int (*new_sequential_file)(void* state, const char* fname,
+                             size_t fname_len, void** file, char** errmsg);
+  int (*new_random_access_file)(void* state, const char* fname,
+                                size_t fname_len, void** file, char** errmsg);
+  int (*new_writable_file)(void* state, const char* fname, size_t fname_len,
+                           void** file, char** errmsg);
+  int (*reopen_writable_file)(void* state, const char* fname,
+                              size_t fname_len, void** file, char** errmsg);
+  int (*new_directory)(void* state, const char* name, size_t name_len,
+                       void** dir, char** errmsg);
+  int (*file_exists)(void* state, const char* fname, size_t fname_len,
+                     char** errmsg);
+  int (*get_children)(void* state, const char* dir, size_t dir_len,
+                      rocksdb_ext_string_vector_t* result, char** errmsg);
+  int (*delete_file)(void* state, const char* fname, size_t fname_len,
+                     char** errmsg);
+  int (*create_dir)(void* state, const char* name, size_t name_len,
+                    char** errmsg);
+  int (*create_dir_if_missing)(void* state, const char* name,
+                               size_t name_len, char** errmsg);
+  int (*delete_dir)(void* state, const char* name, size_t name_len,
+                    char** errmsg);
+  int (*get_file_size)(void* state, const char* fname, size_t fname_len,
+                       uint64_t* size, char** errmsg);
+  int (*get_file_modification_time)(void* state, const char* fname,
+                                    size_t fname_len, uint64_t* mtime,
+                                    char** errmsg);
+  int (*rename_file)(void* state, const char* src, size_t src_len,
+                     const char* target, size_t target_len, char** errmsg);
+  int (*link_file)(void* state, const char* src, size_t src_len,
+                   const char* target, size_t target_len, char** errmsg);
+  int (*lock_file)(void* state, const char* fname, size_t fname_len,
+                   char** errmsg);
+  int (*unlock_file)(void* state, const char* fname, size_t fname_len,
+                     char** errmsg);
+  int (*is_directory)(void* state, const char* path, size_t path_len,
+                      unsigned char* is_dir, char** errmsg);
+  int (*get_absolute_path)(void* state, const char* path, size_t path_len,
+                           rocksdb_ext_string_vector_t* result,
+                           char** errmsg);
+
+  int (*sequential_read)(void* file, size_t n, char* scratch, size_t* read,
+                         char** errmsg);
+  int (*sequential_skip)(void* file, uint64_t n, char** errmsg);
+  void (*sequential_destroy)(void* file);
+
+  int (*random_access_read)(void* file, uint64_t offset, size_t n,
+                            char* scratch, size_t* read, char** errmsg);
+  void (*random_access_destroy)(void* file);
+
+  int (*writable_append)(void* file, const char* data, size_t n,
+                         char** errmsg);
+  int (*writable_truncate)(void* file, uint64_t size, char** errmsg);
+  int (*writable_flush)(void* file, char** errmsg);
+  int (*writable_sync)(void* file, char** errmsg);
+  int (*writable_fsync)(void* file, char** errmsg);
+  int (*writable_close)(void* file, char** errmsg);
+  uint64_t (*writable_file_size)(void* file);
+  void (*writable_destroy)(void* file);
+
+  int (*directory_fsync)(void* dir, char** errmsg);
+  void (*directory_destroy)(void* dir);
+} rocksdb_ext_filesystem_callbacks_t;
+
+/* Callbacks implementing a RocksDB SystemClock. */
+typedef struct {
+  void (*destructor)(void* state);
+  uint64_t (*now_micros)(void* state);
+  uint64_t (*now_nanos)(void* state);
+  void (*sleep_for_microseconds)(void* state, int micros);
+  int (*get_current_time)(void* state, int64_t* unix_time, char** errmsg);
+} rocksdb_ext_clock_callbacks_t;
+
+/* Creates an Env whose file system (and optionally clock) is implemented
+   by the given callbacks.  The callbacks are copied; `fs_state` and
+   `clock_state` are released through their destructors once RocksDB no
+   longer references them.  `clock` may be NULL to use the default clock.
+   Background threads are shared with the default Env. */
+extern ROCKSDB_LIBRARY_API rocksdb_env_t* rocksdb_ext_create_env(
+    void* fs_state, const rocksdb_ext_filesystem_callbacks_t* fs,
+    void* clock_state, const rocksdb_ext_clock_callbacks_t* clock);
+
+/* Statistics */
+
+typedef struct rocksdb_ext_statistics_t rocksdb_ext_statistics_t;
+
+/* Mirrors rocksdb::HistogramData. */
+typedef struct {
+  double median;
+  double percentile95;
+  double percentile99;
+  double average;
+  double standard_deviation;
+  double max;
+  uint64_t count;
+  uint64_t sum;
+  double min;
+} rocksdb_ext_histogram_data_t;
+
+/* Returns a new reference to the statistics object of `opt`, or NULL if
+   statistics are not enabled.  Release it with
+   rocksdb_ext_statistics_destroy(). */
+extern ROCKSDB_LIBRARY_API rocksdb_ext_statistics_t*
+rocksdb_ext_options_get_statistics(const rocksdb_options_t* opt);
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_statistics_destroy(
+    rocksdb_ext_statistics_t* stats);
+extern ROCKSDB_LIBRARY_API uint64_t rocksdb_ext_statistics_get_ticker_count(
+    const rocksdb_ext_statistics_t* stats, uint32_t ticker);
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_statistics_get_histogram_data(
+    const rocksdb_ext_statistics_t* stats, uint32_t histogram,
+    rocksdb_ext_histogram_data_t* data);
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_statistics_reset(
+    rocksdb_ext_statistics_t* stats, char** errptr);
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_statistics_set_stats_level(
+    rocksdb_ext_statistics_t* stats, int level);
+extern ROCKSDB_LIBRARY_API int rocksdb_ext_statistics_get_stats_level(
+    const rocksdb_ext_statistics_t* stats);
+
+/* Event listener */
+
+/* Strings are not NUL-terminated unless noted otherwise and only valid
+   for the duration of the callback.  Enum values are passed as the
+   integer value of the corresponding RocksDB enum. */
+
+typedef struct {
+  const char* cf_name;
+  size_t cf_name_len;
+  const char* file_path;
+  size_t file_path_len;
+  uint64_t file_number;
+  uint64_t file_size;
+  uint64_t thread_id;
+  int job_id;
+  unsigned char triggered_writes_slowdown;
+  unsigned char triggered_writes_stop;
+  uint64_t smallest_seqno;
+  uint64_t largest_seqno;
+  uint64_t num_entries;
+  uint64_t raw_key_size;
+  uint64_t raw_value_size;
+  int reason;
+  uint64_t elapsed_micros;
+} rocksdb_ext_flush_job_info_t;
+
+typedef struct {
+  const char* cf_name;
+  size_t cf_name_len;
+  /* NUL-terminated error message, or NULL if the compaction succeeded. */
+  const char* status;
+  uint64_t thread_id;
+  int job_id;
+  int base_input_level;
+  int output_level;
+  const char* const* input_files;
+  const size_t* input_file_lens;
+  size_t num_input_files;
+  const char* const* output_files;
+  const size_t* output_file_lens;
+  size_t num_output_files;
+  int reason;
+  uint64_t elapsed_micros;
+  uint64_t total_input_bytes;
+  uint64_t total_output_bytes;
+  uint64_t num_input_records;
+  uint64_t num_output_records;
+} rocksdb_ext_compaction_job_info_t;
+
+typedef struct {
+  const char* cf_name;
+  size_t cf_name_len;
+  int cur;
+  int prev;
+} rocksdb_ext_write_stall_info_t;
+
+typedef struct {
+  const char* cf_name;
+  size_t cf_name_len;
+  const char* file_path;
+  size_t file_path_len;
+  int job_id;
+  int reason;
+  uint64_t file_size;
+  uint64_t num_entries;
+  /* NUL-terminated error message, or NULL if the file was created. */
+  const char* status;
+} rocksdb_ext_table_file_creation_info_t;
+
+/* Callbacks of a RocksDB EventListener.  They are invoked from RocksDB's
+   background threads, possibly concurrently. */
+typedef struct {
+  void (*destructor)(void* state);
+  void (*on_flush_completed)(void* state,
+                             const rocksdb_ext_flush_job_info_t* info);
+  void (*on_compaction_completed)(
+      void* state, const rocksdb_ext_compaction_job_info_t* info);
+  void (*on_stall_conditions_changed)(
+      void* state, const rocksdb_ext_write_stall_info_t* info);
+  /* `status` is the NUL-terminated error message. */
+  void (*on_background_error)(void* state, int reason, const char* status);
+  void (*on_table_file_created)(
+      void* state, const rocksdb_ext_table_file_creation_info_t* info);
+} rocksdb_ext_eventlistener_callbacks_t;
+
+/* Adds an event listener to `opt`.  The callbacks are copied; `state` is
+   released through the destructor once no options or database reference
+   the listener any more. */
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_options_add_eventlistener(
+    rocksdb_options_t* opt, void* state,
+    const rocksdb_ext_eventlistener_callbacks_t* callbacks);
+
+/* Ingestion */
+
+extern ROCKSDB_LIBRARY_API void
+rocksdb_ext_ingestexternalfileoptions_set_write_global_seqno(
+    rocksdb_ingestexternalfileoptions_t* opt, unsigned char v);
+
+/* Like rocksdb_ingest_external_file(_cf), but fail with an invalid argument
+   error if files of a custom table format are ingested with
+   write_global_seqno set, instead of appending the sequence number to
+   them. */
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_ingest_external_file(
+    rocksdb_t* db, const char* const* file_list, size_t list_len,
+    const rocksdb_ingestexternalfileoptions_t* opt, char** errptr);
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_ingest_external_file_cf(
+    rocksdb_t* db, rocksdb_column_family_handle_t* handle,
+    const char* const* file_list, size_t list_len,
+    const rocksdb_ingestexternalfileoptions_t* opt, char** errptr);
+
+/* Transactions */
+
+/* Write policies of a transaction database, matching
+   rocksdb::TxnDBWritePolicy. */
+enum {
+  rocksdb_ext_txndb_write_committed = 0,
+  rocksdb_ext_txndb_write_prepared = 1,
+  rocksdb_ext_txndb_write_unprepared = 2,
+};
+
+extern ROCKSDB_LIBRARY_API void
+rocksdb_ext_transactiondb_options_set_write_policy(
+    rocksdb_transactiondb_options_t* opt, int policy);
+extern ROCKSDB_LIBRARY_API void
+rocksdb_ext_transactiondb_options_set_default_write_batch_flush_threshold(
+    rocksdb_transactiondb_options_t* opt, int64_t threshold);
+extern ROCKSDB_LIBRARY_API void
+rocksdb_ext_transaction_options_set_write_batch_flush_threshold(
+    rocksdb_transaction_options_t* opt, int64_t threshold);
+
+/* Calls `add` with every key locked in `db`.  The key is not
+   NUL-terminated and, like `txn_ids`, only valid during the call. */
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_transactiondb_get_lock_status_data(
+    rocksdb_transactiondb_t* db, void* state,
+    void (*add)(void* state, uint32_t cf_id, const char* key, size_t key_len,
+                const uint64_t* txn_ids, size_t num_txn_ids,
+                unsigned char exclusive));
+/* Calls `add_path` with every recent deadlock, newest first, followed by
+   `add_info` with every transaction of its cycle.  Keys are not
+   NUL-terminated and only valid during the call. */
+extern ROCKSDB_LIBRARY_API void
+rocksdb_ext_transactiondb_get_deadlock_info_buffer(
+    rocksdb_transactiondb_t* db, void* state,
+    void (*add_path)(void* state, unsigned char limit_exceeded,
+                     int64_t deadlock_time),
+    void (*add_info)(void* state, uint64_t txn_id, uint32_t cf_id,
+                     unsigned char exclusive, const char* waiting_key,
+                     size_t waiting_key_len));
+extern ROCKSDB_LIBRARY_API void
+rocksdb_ext_transactiondb_set_deadlock_info_buffer_size(
+    rocksdb_transactiondb_t* db, uint32_t target_size);
+
+extern ROCKSDB_LIBRARY_API uint64_t
+rocksdb_ext_transaction_get_id(rocksdb_transaction_t* txn);
+/* Calls `set` once with the key `txn` is waiting to lock and the
+   transactions holding it, which are empty if it is not waiting.  The
+   arguments are only valid during the call.  May be called from another
+   thread than the one using `txn`, as long as `txn` is not destroyed. */
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_transaction_get_waiting_txns(
+    rocksdb_transaction_t* txn, void* state,
+    void (*set)(void* state, uint32_t cf_id, const char* key, size_t key_len,
+                const uint64_t* txn_ids, size_t num_txn_ids));
+
+/* A range lock manager, which lets transactions lock ranges of keys.  Not
+   available on Windows. */
+typedef struct rocksdb_ext_rangelockmanager_t rocksdb_ext_rangelockmanager_t;
+
+extern ROCKSDB_LIBRARY_API rocksdb_ext_rangelockmanager_t*
+rocksdb_ext_rangelockmanager_create(void);
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_rangelockmanager_destroy(
+    rocksdb_ext_rangelockmanager_t* mgr);
+/* Returns 0, or an error code if the locks already use more memory. */
+extern ROCKSDB_LIBRARY_API int
+rocksdb_ext_rangelockmanager_set_max_lock_memory(
+    rocksdb_ext_rangelockmanager_t* mgr, size_t max_lock_memory);
+/* Makes the databases opened with `opt` use `mgr`, which is shared by them
+   and stays alive as long as they do. */
+extern ROCKSDB_LIBRARY_API void
+rocksdb_ext_transactiondb_options_set_lock_manager(
+    rocksdb_transactiondb_options_t* opt, rocksdb_ext_rangelockmanager_t* mgr);
+/* Locks the keys from `start` to `end`, both included, in `cf`, or in the
+   default column family if it is NULL. */
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_transaction_get_range_lock(
+    rocksdb_transaction_t* txn, rocksdb_column_family_handle_t* cf,
+    const char* start, size_t start_len, const char* end, size_t end_len,
+    char** errptr);
+
+/* Table factory */
+
+/* A table file opened by RocksDB, handed to a custom table reader. */
+typedef struct rocksdb_ext_table_file_t rocksdb_ext_table_file_t;
+
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_table_file_destroy(
+    rocksdb_ext_table_file_t* file);
+/* Returns the path of the file.  The string is not NUL-terminated and lives
+   as long as `file`. */
+extern ROCKSDB_LIBRARY_API const char* rocksdb_ext_table_file_name(
+    const rocksdb_ext_table_file_t* file, size_t* name_len);
+extern ROCKSDB_LIBRARY_API uint64_t
+rocksdb_ext_table_file_size(const rocksdb_ext_table_file_t* file);
+/* Reads up to `n` bytes at `offset` into `buf` and returns the number of
+   bytes read, which is smaller than `n` only at the end of the file. */
+extern ROCKSDB_LIBRARY_API size_t rocksdb_ext_table_file_read(
+    const rocksdb_ext_table_file_t* file, uint64_t offset, size_t n,
+    char* buf, char** errptr);
+
+/* Callbacks of a table factory reading tables of a custom format.  Keys
+   passed to and returned from the iterator callbacks are user keys.  Keys
+   and values returned by the iterator stay valid until it is moved or
+   destroyed. */
+typedef struct {
+  void (*destructor)(void* state);
+  /* Opens `file` and returns the reader state, taking ownership of the file
+     even on error. */
+  void* (*open)(void* state, rocksdb_ext_table_file_t* file, char** errptr);
+  void (*reader_destructor)(void* reader);
+  /* Returns the state of a new iterator, which never outlives the reader. */
+  void* (*reader_new_iterator)(void* reader);
+  void (*iterator_destructor)(void* iter);
+  unsigned char (*iterator_valid)(void* iter);
+  void (*iterator_seek_to_first)(void* iter);
+  void (*iterator_seek_to_last)(void* iter);
+  void (*iterator_seek)(void* iter, const char* key, size_t key_len);
+  void (*iterator_seek_for_prev)(void* iter, const char* key, size_t key_len);
+  void (*iterator_next)(void* iter);
+  void (*iterator_prev)(void* iter);
+  const char* (*iterator_key)(void* iter, size_t* key_len);
+  const char* (*iterator_value)(void* iter, size_t* value_len);
+  void (*iterator_status)(void* iter, char** errptr);
+} rocksdb_ext_table_factory_callbacks_t;
+
+/* Sets a table factory that opens tables of a custom format through the
+   callbacks.  Tables written by RocksDB itself, and read back, use the
+   block-based table factory previously set on `opt`, or a default one. */
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_options_set_table_factory(
+    rocksdb_options_t* opt, const char* name, void* state,
+    const rocksdb_ext_table_factory_callbacks_t* callbacks);
+
+/* Table properties */
+
+/* The properties of a table, kept alive independently of the table. */
+typedef struct rocksdb_ext_table_properties_t rocksdb_ext_table_properties_t;
+
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_table_properties_destroy(
+    rocksdb_ext_table_properties_t* props);
+extern ROCKSDB_LIBRARY_API uint64_t rocksdb_ext_table_properties_get_data_size(
+    const rocksdb_ext_table_properties_t* props);
+extern ROCKSDB_LIBRARY_API uint64_t rocksdb_ext_table_properties_get_index_size(
+    const rocksdb_ext_table_properties_t* props);
+extern ROCKSDB_LIBRARY_API uint64_t
+rocksdb_ext_table_properties_get_filter_size(
+    const rocksdb_ext_table_properties_t* props);
+extern ROCKSDB_LIBRARY_API uint64_t
+rocksdb_ext_table_properties_get_raw_key_size(
+    const rocksdb_ext_table_properties_t* props);
+extern ROCKSDB_LIBRARY_API uint64_t
+rocksdb_ext_table_properties_get_raw_value_size(
+    const rocksdb_ext_table_properties_t* props);
+extern ROCKSDB_LIBRARY_API uint64_t
+rocksdb_ext_table_properties_get_num_data_blocks(
+    const rocksdb_ext_table_properties_t* props);
+extern ROCKSDB_LIBRARY_API uint64_t
+rocksdb_ext_table_properties_get_num_entries(
+    const rocksdb_ext_table_properties_t* props);
+extern ROCKSDB_LIBRARY_API uint64_t
+rocksdb_ext_table_properties_get_num_deletions(
+    const rocksdb_ext_table_properties_t* props);
+extern ROCKSDB_LIBRARY_API uint64_t
+rocksdb_ext_table_properties_get_num_merge_operands(
+    const rocksdb_ext_table_properties_t* props);
+extern ROCKSDB_LIBRARY_API uint64_t
+rocksdb_ext_table_properties_get_num_range_deletions(
+    const rocksdb_ext_table_properties_t* props);
+extern ROCKSDB_LIBRARY_API uint64_t
+rocksdb_ext_table_properties_get_format_version(
+    const rocksdb_ext_table_properties_t* props);
+extern ROCKSDB_LIBRARY_API uint64_t
+rocksdb_ext_table_properties_get_fixed_key_len(
+    const rocksdb_ext_table_properties_t* props);
+extern ROCKSDB_LIBRARY_API uint64_t
+rocksdb_ext_table_properties_get_column_family_id(
+    const rocksdb_ext_table_properties_t* props);
+extern ROCKSDB_LIBRARY_API uint64_t
+rocksdb_ext_table_properties_get_creation_time(
+    const rocksdb_ext_table_properties_t* props);
+extern ROCKSDB_LIBRARY_API uint64_t
+rocksdb_ext_table_properties_get_oldest_key_time(
+    const rocksdb_ext_table_properties_t* props);
+extern ROCKSDB_LIBRARY_API uint64_t
+rocksdb_ext_table_properties_get_file_creation_time(
+    const rocksdb_ext_table_properties_t* props);
+/* The string getters return strings that are not NUL-terminated and live
+   as long as `props`. */
+extern ROCKSDB_LIBRARY_API const char*
+rocksdb_ext_table_properties_get_column_family_name(
+    const rocksdb_ext_table_properties_t* props, size_t* len);
+extern ROCKSDB_LIBRARY_API const char*
+rocksdb_ext_table_properties_get_comparator_name(
+    const rocksdb_ext_table_properties_t* props, size_t* len);
+extern ROCKSDB_LIBRARY_API const char*
+rocksdb_ext_table_properties_get_merge_operator_name(
+    const rocksdb_ext_table_properties_t* props, size_t* len);
+extern ROCKSDB_LIBRARY_API const char*
+rocksdb_ext_table_properties_get_prefix_extractor_name(
+    const rocksdb_ext_table_properties_t* props, size_t* len);
+extern ROCKSDB_LIBRARY_API const char*
+rocksdb_ext_table_properties_get_filter_policy_name(
+    const rocksdb_ext_table_properties_t* props, size_t* len);
+extern ROCKSDB_LIBRARY_API const char*
+rocksdb_ext_table_properties_get_compression_name(
+    const rocksdb_ext_table_properties_t* props, size_t* len);
+extern ROCKSDB_LIBRARY_API const char*
+rocksdb_ext_table_properties_get_compression_options(
+    const rocksdb_ext_table_properties_t* props, size_t* len);
+/* Calls `add` with every user-collected property of `props`.  The strings
+   are not NUL-terminated and only valid during the call. */
+extern ROCKSDB_LIBRARY_API void
+rocksdb_ext_table_properties_get_user_collected_properties(
+    const rocksdb_ext_table_properties_t* props, void* state,
+    void (*add)(void* state, const char* key, size_t key_len,
+                const char* value, size_t value_len));
+
+/* The properties of a set of tables, by file path. */
+typedef struct rocksdb_ext_table_properties_collection_t
+    rocksdb_ext_table_properties_collection_t;
+
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_table_properties_collection_destroy(
+    rocksdb_ext_table_properties_collection_t* coll);
+extern ROCKSDB_LIBRARY_API size_t rocksdb_ext_table_properties_collection_count(
+    const rocksdb_ext_table_properties_collection_t* coll);
+/* Returns the path of the `index`-th table.  The string is not NUL-terminated
+   and lives as long as `coll`. */
+extern ROCKSDB_LIBRARY_API const char*
+rocksdb_ext_table_properties_collection_name(
+    const rocksdb_ext_table_properties_collection_t* coll, size_t index,
+    size_t* len);
+/* Returns the properties of the `index`-th table, which stay valid after
+   `coll` is destroyed. */
+extern ROCKSDB_LIBRARY_API rocksdb_ext_table_properties_t*
+rocksdb_ext_table_properties_collection_get(
+    const rocksdb_ext_table_properties_collection_t* coll, size_t index);
+
+/* `column_family` may be NULL for the default column family. */
+extern ROCKSDB_LIBRARY_API rocksdb_ext_table_properties_collection_t*
+rocksdb_ext_get_properties_of_all_tables_cf(
+    rocksdb_t* db, rocksdb_column_family_handle_t* column_family,
+    char** errptr);
+/* Like rocksdb_ext_get_properties_of_all_tables_cf(), but only returns the
+   tables overlapping any of the `num_ranges` ranges
+   [start_keys[i], limit_keys[i]). */
+extern ROCKSDB_LIBRARY_API rocksdb_ext_table_properties_collection_t*
+rocksdb_ext_get_properties_of_tables_in_range_cf(
+    rocksdb_t* db, rocksdb_column_family_handle_t* column_family,
+    size_t num_ranges, const char* const* start_keys,
+    const size_t* start_key_lens, const char* const* limit_keys,
+    const size_t* limit_key_lens, char** errptr);
+
+/* Table properties collector */
+
+/* Mirrors rocksdb::EntryType. */
+enum {
+  rocksdb_ext_entry_put = 0,
+  rocksdb_ext_entry_delete = 1,
+  rocksdb_ext_entry_single_delete = 2,
+  rocksdb_ext_entry_merge = 3,
+  rocksdb_ext_entry_range_deletion = 4,
+  rocksdb_ext_entry_blob_index = 5,
+  rocksdb_ext_entry_delete_with_timestamp = 6,
+  rocksdb_ext_entry_other = 7,
+};
+
+typedef struct rocksdb_ext_user_collected_properties_t
+    rocksdb_ext_user_collected_properties_t;
+
+/* Adds a property.  Used by `collector_finish`. */
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_user_collected_properties_add(
+    rocksdb_ext_user_collected_properties_t* props, const char* key,
+    size_t key_len, const char* value, size_t value_len);
+
+/* Callbacks of a table properties collector factory.  The factory creates a
+   collector for every table RocksDB writes, which is then only used by a
+   single thread.  `level_at_creation` is -1 if unknown. */
+typedef struct {
+  void (*destructor)(void* state);
+  const char* (*name)(void* state);
+  void* (*create_collector)(void* state, uint32_t column_family_id,
+                            int level_at_creation);
+  void (*collector_destructor)(void* collector);
+  const char* (*collector_name)(void* collector);
+  void (*collector_add_user_key)(void* collector, const char* key,
+                                 size_t key_len, const char* value,
+                                 size_t value_len, int type, uint64_t seq,
+                                 uint64_t file_size);
+  void (*collector_finish)(void* collector,
+                           rocksdb_ext_user_collected_properties_t* props);
+  unsigned char (*collector_need_compact)(void* collector);
+} rocksdb_ext_table_properties_collector_factory_callbacks_t;
+
+/* Adds a table properties collector factory to `opt`.  The callbacks are
+   copied. */
+extern ROCKSDB_LIBRARY_API void
+rocksdb_ext_options_add_table_properties_collector_factory(
+    rocksdb_options_t* opt, void* state,
+    const rocksdb_ext_table_properties_collector_factory_callbacks_t*
+        callbacks);
+
+/* Compaction filter */
+
+/* Mirrors rocksdb::CompactionFilter::ValueType. */
+enum {
+  rocksdb_ext_compactionfilter_value = 0,
+  rocksdb_ext_compactionfilter_merge_operand = 1,
+  rocksdb_ext_compactionfilter_blob_index = 2,
+};
+
+/* Decisions returned by the `filter` callback. */
+enum {
+  rocksdb_ext_compactionfilter_keep = 0,
+  rocksdb_ext_compactionfilter_remove = 1,
+  rocksdb_ext_compactionfilter_change_value = 2,
+  rocksdb_ext_compactionfilter_remove_and_skip_until = 3,
+};
+
+typedef struct rocksdb_ext_string_t rocksdb_ext_string_t;
+
+/* Replaces the contents of `str` with a copy of `data`. */
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_string_assign(
+    rocksdb_ext_string_t* str, const char* data, size_t len);
+
+/* Callbacks of a compaction filter.  `filter` returns one of the
+   rocksdb_ext_compactionfilter_* decisions and stores the new value or the
+   key to skip until in `new_value` or `skip_until`. */
+typedef struct {
+  void (*destructor)(void* state);
+  const char* (*name)(void* state);
+  int (*filter)(void* state, int level, const char* key, size_t key_len,
+                int value_type, const char* value, size_t value_len,
+                rocksdb_ext_string_t* new_value,
+                rocksdb_ext_string_t* skip_until);
+} rocksdb_ext_compactionfilter_callbacks_t;
+
+/* Fields of a compaction filter context not covered by the C API.  Reasons
+   are rocksdb::TableFileCreationReason values. */
+extern ROCKSDB_LIBRARY_API uint32_t
+rocksdb_ext_compactionfiltercontext_column_family_id(
+    rocksdb_compactionfiltercontext_t* context);
+extern ROCKSDB_LIBRARY_API int rocksdb_ext_compactionfiltercontext_reason(
+    rocksdb_compactionfiltercontext_t* context);
+
+/* Callbacks of a compaction filter factory.  The filters it creates use the
+   `filter` callbacks with the state returned by `create_filter`. */
+typedef struct {
+  void (*destructor)(void* state);
+  const char* (*name)(void* state);
+  void* (*create_filter)(void* state,
+                         rocksdb_compactionfiltercontext_t* context);
+  rocksdb_ext_compactionfilter_callbacks_t filter;
+} rocksdb_ext_compactionfilterfactory_callbacks_t;
+
+/* Sets the compaction filter of `opt`.  The filter is never destroyed, as
+   with rocksdb_options_set_compaction_filter(). */
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_options_set_compaction_filter(
+    rocksdb_options_t* opt, void* state,
+    const rocksdb_ext_compactionfilter_callbacks_t* callbacks);
+extern ROCKSDB_LIBRARY_API void
+rocksdb_ext_options_set_compaction_filter_factory(
+    rocksdb_options_t* opt, void* state,
+    const rocksdb_ext_compactionfilterfactory_callbacks_t* callbacks);
+
+/* Comparator */
+
+typedef struct rocksdb_ext_comparator_t rocksdb_ext_comparator_t;
+
+/* Callbacks of a comparator.  `find_shortest_separator` and
+   `find_short_successor` return 0 to keep the key, or store a replacement
+   in `result` and return 1.  `compare_without_timestamp` is passed keys
+   whose timestamps were removed. */
+typedef struct {
+  void (*destructor)(void* state);
+  const char* (*name)(void* state);
+  int (*compare)(void* state, const char* a, size_t a_len, const char* b,
+                 size_t b_len);
+  unsigned char (*find_shortest_separator)(void* state, const char* start,
+                                           size_t start_len,
+                                           const char* limit,
+                                           size_t limit_len,
+                                           rocksdb_ext_string_t* result);
+  unsigned char (*find_short_successor)(void* state, const char* key,
+                                        size_t key_len,
+                                        rocksdb_ext_string_t* result);
+  int (*compare_timestamp)(void* state, const char* a, size_t a_len,
+                           const char* b, size_t b_len);
+  int (*compare_without_timestamp)(void* state, const char* a, size_t a_len,
+                                   const char* b, size_t b_len);
+} rocksdb_ext_comparator_callbacks_t;
+
+/* Creates a comparator for keys with timestamps of `timestamp_size` bytes,
+   or without timestamps if it is 0. */
+extern ROCKSDB_LIBRARY_API rocksdb_ext_comparator_t*
+rocksdb_ext_comparator_create(
+    void* state, const rocksdb_ext_comparator_callbacks_t* callbacks,
+    size_t timestamp_size);
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_comparator_destroy(
+    rocksdb_ext_comparator_t* cmp);
+
+/* Sets the comparator of `opt`, which must outlive `opt`, its copies and
+   the databases opened with them. */
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_options_set_comparator(
+    rocksdb_options_t* opt, const rocksdb_ext_comparator_t* cmp);
+
+/* Merge operator */
+
+/* Callbacks of a merge operator.  Operands are passed as `num_operands`
+   pointers and lengths, oldest first, except for `should_merge`, which is
+   passed them newest first.  `existing_value` is NULL if the key has no
+   value.  `full_merge` and `partial_merge_multi` store the result in
+   `new_value` and return 1, or return 0 on failure. */
+typedef struct {
+  void (*destructor)(void* state);
+  const char* (*name)(void* state);
+  unsigned char (*full_merge)(void* state, const char* key, size_t key_len,
+                              const char* existing_value,
+                              size_t existing_value_len,
+                              const char* const* operands,
+                              const size_t* operand_lens, int num_operands,
+                              rocksdb_ext_string_t* new_value);
+  unsigned char (*partial_merge_multi)(void* state, const char* key,
+                                       size_t key_len,
+                                       const char* const* operands,
+                                       const size_t* operand_lens,
+                                       int num_operands,
+                                       rocksdb_ext_string_t* new_value);
+  unsigned char (*should_merge)(void* state, const char* const* operands,
+                                const size_t* operand_lens, int num_operands);
+  unsigned char (*allow_single_operand)(void* state);
+} rocksdb_ext_mergeoperator_callbacks_t;
+
+/* Sets the merge operator of `opt`, which is shared by its copies and
+   destroyed with the last of them. */
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_options_set_merge_operator(
+    rocksdb_options_t* opt, void* state,
+    const rocksdb_ext_mergeoperator_callbacks_t* callbacks);
+
+/* SST file reader */
+
+typedef struct rocksdb_ext_sstfilereader_t rocksdb_ext_sstfilereader_t;
+
+extern ROCKSDB_LIBRARY_API rocksdb_ext_sstfilereader_t*
+rocksdb_ext_sstfilereader_create(const rocksdb_options_t* opt);
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_sstfilereader_destroy(
+    rocksdb_ext_sstfilereader_t* reader);
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_sstfilereader_open(
+    rocksdb_ext_sstfilereader_t* reader, const char* path, char** errptr);
+/* Returns an iterator over the file, which must not outlive `reader`.
+   Requires the reader to be open. */
+extern ROCKSDB_LIBRARY_API rocksdb_iterator_t*
+rocksdb_ext_sstfilereader_create_iterator(
+    rocksdb_ext_sstfilereader_t* reader, const rocksdb_readoptions_t* options);
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_sstfilereader_verify_checksum(
+    rocksdb_ext_sstfilereader_t* reader, char** errptr);
+extern ROCKSDB_LIBRARY_API rocksdb_ext_table_properties_t*
+rocksdb_ext_sstfilereader_get_table_properties(
+    const rocksdb_ext_sstfilereader_t* reader);
+
+/* Write batches */
+
+extern ROCKSDB_LIBRARY_API uint32_t
+rocksdb_ext_column_family_handle_get_id(rocksdb_column_family_handle_t* cf);
+
+/* Puts and deletes with a timestamp in the default column family, which the
+   C API only has for other column families. */
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_writebatch_put_with_ts(
+    rocksdb_writebatch_t* batch, const char* key, size_t key_len,
+    const char* ts, size_t ts_len, const char* value, size_t value_len);
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_writebatch_delete_with_ts(
+    rocksdb_writebatch_t* batch, const char* key, size_t key_len,
+    const char* ts, size_t ts_len);
+
+/* Callbacks receiving the records of a write batch, with the ID of their
+   column family, its log data and the markers of two-phase commit.  Keys,
+   values, blobs and transaction names are not NUL-terminated and only valid
+   during the call. */
+typedef struct rocksdb_ext_writebatch_handler_t {
+  void (*put)(void* state, uint32_t cf_id, const char* key, size_t key_len,
+              const char* value, size_t value_len);
+  void (*delete_)(void* state, uint32_t cf_id, const char* key,
+                  size_t key_len);
+  void (*single_delete)(void* state, uint32_t cf_id, const char* key,
+                        size_t key_len);
+  void (*delete_range)(void* state, uint32_t cf_id, const char* begin_key,
+                       size_t begin_key_len, const char* end_key,
+                       size_t end_key_len);
+  void (*merge)(void* state, uint32_t cf_id, const char* key, size_t key_len,
+                const char* value, size_t value_len);
+  void (*log_data)(void* state, const char* blob, size_t blob_len);
+  void (*mark_begin_prepare)(void* state, unsigned char unprepared);
+  void (*mark_end_prepare)(void* state, const char* xid, size_t xid_len);
+  void (*mark_commit)(void* state, const char* xid, size_t xid_len);
+  void (*mark_rollback)(void* state, const char* xid, size_t xid_len);
+  void (*mark_noop)(void* state, unsigned char empty_batch);
+} rocksdb_ext_writebatch_handler_t;
+
+extern ROCKSDB_LIBRARY_API void rocksdb_ext_writebatch_iterate_cf(
+    const rocksdb_writebatch_t* batch, void* state,
+    const rocksdb_ext_writebatch_handler_t* handler, char** errptr);
+
+/* WAL */
+
+/* Returns the sequence number of the first batch in the WAL files of `db`,
+   including archived ones, or 0 if they hold no batch. */
+extern ROCKSDB_LIBRARY_API uint64_t
+rocksdb_ext_get_oldest_wal_sequence(rocksdb_t* db, char** errptr);
+
+#ifdef __cplusplus
+} /* end extern "C" */
+#endif
+
+#endif /* ROCKSDB_RUST_EXT_H */
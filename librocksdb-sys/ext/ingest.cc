@@ -0,0 +1,50 @@
+// Ingestion options not exposed by the RocksDB C API, and ingestion that
+// checks files of custom table formats first.
+
+#include <string>
+#include <vector>
+
+#include "c_types.h"
+#include "ext.h"
+#include "table_factory.h"
+#include "util.h"
+
+using ROCKSDB_NAMESPACE::ColumnFamilyHandle;
+using ROCKSDB_NAMESPACE::Status;
+using rocksdb_ext::SaveError;
+
+void rocksdb_ext_ingestexternalfileoptions_set_write_global_seqno(
+    rocksdb_ingestexternalfileoptions_t* opt, unsigned char v) {
+  opt->rep.write_global_seqno = v;
+}
+
+namespace {
+
+void IngestExternalFile(rocksdb_t* db, ColumnFamilyHandle* cf,
+                        const char* const* file_list, size_t list_len,
+                        const rocksdb_ingestexternalfileoptions_t* opt,
+                        char** errptr) {
+  std::vector<std::string> files(file_list, file_list + list_len);
+  Status s =
+      rocksdb_ext::CheckIngestedTableFormats(db->rep, cf, files, opt->rep);
+  if (s.ok()) {
+    s = db->rep->IngestExternalFile(cf, files, opt->rep);
+  }
+  SaveError(errptr, s);
+}
+
+}  // namespace
+
+void rocksdb_ext_ingest_external_file(
+    rocksdb_t* db, const char* const* file_list, size_t list_len,
+    const rocksdb_ingestexternalfileoptions_t* opt, char** errptr) {
+  IngestExternalFile(db, db->rep->DefaultColumnFamily(), file_list, list_len,
+                     opt, errptr);
+}
+
+void rocksdb_ext_ingest_external_file_cf(
+    rocksdb_t* db, rocksdb_column_family_handle_t* handle,
+    const char* const* file_list, size_t list_len,
+    const rocksdb_ingestexternalfileoptions_t* opt, char** errptr) {
+  IngestExternalFile(db, handle->rep, file_list, list_len, opt, errptr);
+}
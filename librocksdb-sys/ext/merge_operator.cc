@@ -0,0 +1,94 @@
+// MergeOperator implemented through C callbacks, exposing
+// PartialMergeMulti(), ShouldMerge() and AllowSingleOperand(), which the C
+// API merge operator does not forward.
+
+#include <deque>
+#include <memory>
+#include <string>
+#include <vector>
+
+#include "c_types.h"
+#include "ext.h"
+#include "rocksdb/merge_operator.h"
+#include "util.h"
+
+using ROCKSDB_NAMESPACE::Logger;
+using ROCKSDB_NAMESPACE::MergeOperator;
+using ROCKSDB_NAMESPACE::Slice;
+
+namespace {
+
+// Splits `operands` into the pointer and length arrays the callbacks take.
+template <typename Operands>
+void SplitOperands(const Operands& operands, std::vector<const char*>* data,
+                   std::vector<size_t>* lens) {
+  data->reserve(operands.size());
+  lens->reserve(operands.size());
+  for (const Slice& operand : operands) {
+    data->push_back(operand.data());
+    lens->push_back(operand.size());
+  }
+}
+
+class CallbackMergeOperator : public MergeOperator {
+ public:
+  CallbackMergeOperator(void* state,
+                        const rocksdb_ext_mergeoperator_callbacks_t& cb)
+      : state_(state), cb_(cb) {}
+
+  ~CallbackMergeOperator() override { cb_.destructor(state_); }
+
+  bool FullMergeV2(const MergeOperationInput& merge_in,
+                   MergeOperationOutput* merge_out) const override {
+    std::vector<const char*> data;
+    std::vector<size_t> lens;
+    SplitOperands(merge_in.operand_list, &data, &lens);
+    const Slice* existing = merge_in.existing_value;
+    rocksdb_ext_string_t new_value{&merge_out->new_value};
+    return cb_.full_merge(state_, merge_in.key.data(), merge_in.key.size(),
+                          existing ? existing->data() : nullptr,
+                          existing ? existing->size() : 0, data.data(),
+                          lens.data(), static_cast<int>(data.size()),
+                          &new_value);
+  }
+
+  bool PartialMergeMulti(const Slice& key,
+                         const std::deque<Slice>& operand_list,
+                         std::string* new_value,
+                         Logger* /*logger*/) const override {
+    std::vector<const char*> data;
+    std::vector<size_t> lens;
+    SplitOperands(operand_list, &data, &lens);
+    rocksdb_ext_string_t new_value_str{new_value};
+    return cb_.partial_merge_multi(state_, key.data(), key.size(), data.data(),
+                                   lens.data(), static_cast<int>(data.size()),
+                                   &new_value_str);
+  }
+
+  bool ShouldMerge(const std::vector<Slice>& operands) const override {
+    std::vector<const char*> data;
+    std::vector<size_t> lens;
+    SplitOperands(operands, &data, &lens);
+    return cb_.should_merge(state_, data.data(), lens.data(),
+                            static_cast<int>(data.size()));
+  }
+
+  bool AllowSingleOperand() const override {
+    return cb_.allow_single_operand(state_);
+  }
+
+  const char* Name() const override { return cb_.name(state_); }
+
+ private:
+  void* state_;
+  rocksdb_ext_mergeoperator_callbacks_t cb_;
+};
+
+}  // namespace
+
+void rocksdb_ext_options_set_merge_operator(
+    rocksdb_options_t* opt, void* state,
+    const rocksdb_ext_mergeoperator_callbacks_t* callbacks) {
+  opt->rep.merge_operator =
+      std::make_shared<CallbackMergeOperator>(state, *callbacks);
+}
@@ -0,0 +1,44 @@
+// Reading SST files outside of a database.
+
+#include "rocksdb/sst_file_reader.h"
+
+#include "c_types.h"
+#include "ext.h"
+#include "table_properties.h"
+#include "util.h"
+
+using ROCKSDB_NAMESPACE::SstFileReader;
+using rocksdb_ext::SaveError;
+
+struct rocksdb_ext_sstfilereader_t {
+  SstFileReader rep;
+};
+
+rocksdb_ext_sstfilereader_t* rocksdb_ext_sstfilereader_create(
+    const rocksdb_options_t* opt) {
+  return new rocksdb_ext_sstfilereader_t{SstFileReader(opt->rep)};
+}
+
+void rocksdb_ext_sstfilereader_destroy(rocksdb_ext_sstfilereader_t* reader) {
+  delete reader;
+}
+
+void rocksdb_ext_sstfilereader_open(rocksdb_ext_sstfilereader_t* reader,
+                                    const char* path, char** errptr) {
+  SaveError(errptr, reader->rep.Open(path));
+}
+
+rocksdb_iterator_t* rocksdb_ext_sstfilereader_create_iterator(
+    rocksdb_ext_sstfilereader_t* reader, const rocksdb_readoptions_t* options) {
+  return new rocksdb_iterator_t{reader->rep.NewIterator(options->rep)};
+}
+
+void rocksdb_ext_sstfilereader_verify_checksum(
+    rocksdb_ext_sstfilereader_t* reader, char** errptr) {
+  SaveError(errptr, reader->rep.VerifyChecksum());
+}
+
+rocksdb_ext_table_properties_t* rocksdb_ext_sstfilereader_get_table_properties(
+    const rocksdb_ext_sstfilereader_t* reader) {
+  return new rocksdb_ext_table_properties_t{reader->rep.GetTableProperties()};
+}
@@ -0,0 +1,73 @@
+// Typed access to the statistics object of an Options.
+
+#include <memory>
+
+#include "c_types.h"
+#include "ext.h"
+#include "rocksdb/statistics.h"
+#include "util.h"
+
+using ROCKSDB_NAMESPACE::HistogramData;
+using ROCKSDB_NAMESPACE::Statistics;
+using ROCKSDB_NAMESPACE::StatsLevel;
+using rocksdb_ext::SaveError;
+
+// The `Ticker`, `Histogram` and `StatsLevel` enums in src/statistics.rs
+// mirror the RocksDB enums by position.
+static_assert(ROCKSDB_NAMESPACE::TICKER_ENUM_MAX == 185,
+              "update Ticker in src/statistics.rs");
+static_assert(ROCKSDB_NAMESPACE::HISTOGRAM_ENUM_MAX == 57,
+              "update Histogram in src/statistics.rs");
+static_assert(StatsLevel::kAll == 5, "update StatsLevel in src/statistics.rs");
+
+struct rocksdb_ext_statistics_t {
+  std::shared_ptr<Statistics> rep;
+};
+
+rocksdb_ext_statistics_t* rocksdb_ext_options_get_statistics(
+    const rocksdb_options_t* opt) {
+  if (opt->rep.statistics == nullptr) {
+    return nullptr;
+  }
+  return new rocksdb_ext_statistics_t{opt->rep.statistics};
+}
+
+void rocksdb_ext_statistics_destroy(rocksdb_ext_statistics_t* stats) {
+  delete stats;
+}
+
+uint64_t rocksdb_ext_statistics_get_ticker_count(
+    const rocksdb_ext_statistics_t* stats, uint32_t ticker) {
+  return stats->rep->getTickerCount(ticker);
+}
+
+void rocksdb_ext_statistics_get_histogram_data(
+    const rocksdb_ext_statistics_t* stats, uint32_t histogram,
+    rocksdb_ext_histogram_data_t* data) {
+  HistogramData hist;
+  stats->rep->histogramData(histogram, &hist);
+  data->median = hist.median;
+  data->percentile95 = hist.percentile95;
+  data->percentile99 = hist.percentile99;
+  data->average = hist.average;
+  data->standard_deviation = hist.standard_deviation;
+  data->max = hist.max;
+  data->count = hist.count;
+  data->sum = hist.sum;
+  data->min = hist.min;
+}
+
+void rocksdb_ext_statistics_reset(rocksdb_ext_statistics_t* stats,
+                                  char** errptr) {
+  SaveError(errptr, stats->rep->Reset());
+}
+
+void rocksdb_ext_statistics_set_stats_level(rocksdb_ext_statistics_t* stats,
+                                            int level) {
+  stats->rep->set_stats_level(static_cast<StatsLevel>(level));
+}
+
+int rocksdb_ext_statistics_get_stats_level(
+    const rocksdb_ext_statistics_t* stats) {
+  return static_cast<int>(stats->rep->get_stats_level());
+}
@@ -0,0 +1,487 @@
+// TableFactory reading tables of a custom format through C callbacks.
+//
+// RocksDB only exposes the table reader interface through its internal
+// headers, so this file requires the RocksDB source tree.
+
+#include <cstring>
+#include <memory>
+#include <new>
+#include <string>
+
+#include "c_types.h"
+#include "db/dbformat.h"
+#include "db/pinned_iterators_manager.h"
+#include "ext.h"
+#include "file/random_access_file_reader.h"
+#include "memory/arena.h"
+#include "rocksdb/db.h"
+#include "rocksdb/file_system.h"
+#include "rocksdb/table.h"
+#include "rocksdb/table_properties.h"
+#include "table/block_based/block_based_table_builder.h"
+#include "table/get_context.h"
+#include "table/internal_iterator.h"
+#include "table/sst_file_writer_collectors.h"
+#include "table/table_builder.h"
+#include "table/table_reader.h"
+#include "table_factory.h"
+#include "util.h"
+#include "util/coding.h"
+
+using ROCKSDB_NAMESPACE::AlignedBuf;
+using ROCKSDB_NAMESPACE::Arena;
+using ROCKSDB_NAMESPACE::ColumnFamilyHandle;
+using ROCKSDB_NAMESPACE::ColumnFamilyOptions;
+using ROCKSDB_NAMESPACE::Comparator;
+using ROCKSDB_NAMESPACE::Customizable;
+using ROCKSDB_NAMESPACE::DB;
+using ROCKSDB_NAMESPACE::DBOptions;
+using ROCKSDB_NAMESPACE::Env;
+using ROCKSDB_NAMESPACE::ExternalSstFilePropertyNames;
+using ROCKSDB_NAMESPACE::FileOptions;
+using ROCKSDB_NAMESPACE::FileSystem;
+using ROCKSDB_NAMESPACE::FSRandomAccessFile;
+using ROCKSDB_NAMESPACE::GetContext;
+using ROCKSDB_NAMESPACE::IngestExternalFileOptions;
+using ROCKSDB_NAMESPACE::InternalIterator;
+using ROCKSDB_NAMESPACE::IOOptions;
+using ROCKSDB_NAMESPACE::IOStatus;
+using ROCKSDB_NAMESPACE::kBlockBasedTableMagicNumber;
+using ROCKSDB_NAMESPACE::kLegacyBlockBasedTableMagicNumber;
+using ROCKSDB_NAMESPACE::kTypeValue;
+using ROCKSDB_NAMESPACE::ParsedInternalKey;
+using ROCKSDB_NAMESPACE::PinnedIteratorsManager;
+using ROCKSDB_NAMESPACE::RandomAccessFileReader;
+using ROCKSDB_NAMESPACE::ReadOptions;
+using ROCKSDB_NAMESPACE::SequenceNumber;
+using ROCKSDB_NAMESPACE::Slice;
+using ROCKSDB_NAMESPACE::SliceTransform;
+using ROCKSDB_NAMESPACE::Status;
+using ROCKSDB_NAMESPACE::TableBuilder;
+using ROCKSDB_NAMESPACE::TableBuilderOptions;
+using ROCKSDB_NAMESPACE::TableFactory;
+using ROCKSDB_NAMESPACE::TableProperties;
+using ROCKSDB_NAMESPACE::TableReader;
+using ROCKSDB_NAMESPACE::TableReaderCaller;
+using ROCKSDB_NAMESPACE::TableReaderOptions;
+using ROCKSDB_NAMESPACE::WritableFileWriter;
+using rocksdb_ext::SaveError;
+using rocksdb_ext::TakeMessage;
+
+struct rocksdb_ext_table_file_t {
+  std::unique_ptr<RandomAccessFileReader> rep;
+  uint64_t size;
+};
+
+void rocksdb_ext_table_file_destroy(rocksdb_ext_table_file_t* file) {
+  delete file;
+}
+
+const char* rocksdb_ext_table_file_name(const rocksdb_ext_table_file_t* file,
+                                        size_t* name_len) {
+  const std::string& name = file->rep->file_name();
+  *name_len = name.size();
+  return name.data();
+}
+
+uint64_t rocksdb_ext_table_file_size(const rocksdb_ext_table_file_t* file) {
+  return file->size;
+}
+
+size_t rocksdb_ext_table_file_read(const rocksdb_ext_table_file_t* file,
+                                   uint64_t offset, size_t n, char* buf,
+                                   char** errptr) {
+  Slice result;
+  IOStatus s = file->rep->Read(IOOptions(), offset, n, &result, buf,
+                               static_cast<AlignedBuf*>(nullptr),
+                               Env::IO_TOTAL);
+  if (SaveError(errptr, s)) {
+    return 0;
+  }
+  // Memory-mapped files return a slice of the mapping.
+  if (result.data() != buf) {
+    memcpy(buf, result.data(), result.size());
+  }
+  return result.size();
+}
+
+namespace {
+
+using Callbacks = rocksdb_ext_table_factory_callbacks_t;
+
+// Presents the entries of a custom table as internal keys.  All entries of
+// an external file share one sequence number: 0 until it is ingested, the
+// sequence number assigned by the ingestion afterwards.
+class CallbackTableIterator : public InternalIterator {
+ public:
+  CallbackTableIterator(const Callbacks& cb, void* iter, const Comparator* ucmp,
+                        SequenceNumber seqno)
+      : cb_(cb),
+        iter_(iter),
+        ucmp_(ucmp),
+        seqno_(seqno),
+        footer_(ROCKSDB_NAMESPACE::PackSequenceAndType(seqno, kTypeValue)) {}
+
+  ~CallbackTableIterator() override { cb_.iterator_destructor(iter_); }
+
+  bool Valid() const override { return valid_; }
+
+  void SeekToFirst() override {
+    cb_.iterator_seek_to_first(iter_);
+    Update();
+  }
+
+  void SeekToLast() override {
+    cb_.iterator_seek_to_last(iter_);
+    Update();
+  }
+
+  void Seek(const Slice& target) override {
+    Slice user_key = ROCKSDB_NAMESPACE::ExtractUserKey(target);
+    cb_.iterator_seek(iter_, user_key.data(), user_key.size());
+    Update();
+    // The entry of the target's user key sorts before the target if it is
+    // newer.
+    if (valid_ &&
+        footer_ > ROCKSDB_NAMESPACE::ExtractInternalKeyFooter(target) &&
+        ucmp_->Equal(UserKey(), user_key)) {
+      Next();
+    }
+  }
+
+  void SeekForPrev(const Slice& target) override {
+    Slice user_key = ROCKSDB_NAMESPACE::ExtractUserKey(target);
+    cb_.iterator_seek_for_prev(iter_, user_key.data(), user_key.size());
+    Update();
+    if (valid_ &&
+        footer_ < ROCKSDB_NAMESPACE::ExtractInternalKeyFooter(target) &&
+        ucmp_->Equal(UserKey(), user_key)) {
+      Prev();
+    }
+  }
+
+  void Next() override {
+    cb_.iterator_next(iter_);
+    Update();
+  }
+
+  void Prev() override {
+    cb_.iterator_prev(iter_);
+    Update();
+  }
+
+  Slice key() const override { return key_; }
+
+  Slice value() const override { return value_; }
+
+  void SetPinnedItersMgr(PinnedIteratorsManager* pinned_iters_mgr) override {
+    pinned_iters_mgr_ = pinned_iters_mgr;
+  }
+
+  // Values are copied while pinning is enabled: reverse iteration needs them
+  // to outlive the current position.
+  bool IsValuePinned() const override {
+    return pinned_iters_mgr_ != nullptr && pinned_iters_mgr_->PinningEnabled();
+  }
+
+  Status status() const override {
+    char* err = nullptr;
+    cb_.iterator_status(iter_, &err);
+    if (err != nullptr) {
+      return Status::Corruption(TakeMessage(err));
+    }
+    return Status::OK();
+  }
+
+ private:
+  Slice UserKey() const { return Slice(key_.data(), key_.size() - 8); }
+
+  void Update() {
+    valid_ = cb_.iterator_valid(iter_);
+    if (valid_) {
+      size_t len = 0;
+      const char* data = cb_.iterator_key(iter_, &len);
+      key_.assign(data, len);
+      ROCKSDB_NAMESPACE::AppendInternalKeyFooter(&key_, seqno_, kTypeValue);
+      data = cb_.iterator_value(iter_, &len);
+      value_ = Slice(data, len);
+      if (IsValuePinned()) {
+        auto* pinned = new std::string(data, len);
+        pinned_iters_mgr_->PinPtr(pinned, &ReleasePinnedValue);
+        value_ = Slice(*pinned);
+      }
+    }
+  }
+
+  static void ReleasePinnedValue(void* value) {
+    delete static_cast<std::string*>(value);
+  }
+
+  const Callbacks& cb_;
+  void* iter_;
+  const Comparator* ucmp_;
+  SequenceNumber seqno_;
+  uint64_t footer_;
+  bool valid_ = false;
+  std::string key_;
+  Slice value_;
+  PinnedIteratorsManager* pinned_iters_mgr_ = nullptr;
+};
+
+class CallbackTableReader : public TableReader {
+ public:
+  CallbackTableReader(const Callbacks& cb, void* reader,
+                      const TableReaderOptions& options)
+      : cb_(cb),
+        reader_(reader),
+        ucmp_(options.internal_comparator.user_comparator()),
+        seqno_(options.largest_seqno) {}
+
+  // Scans the table for the properties RocksDB relies on, such as its
+  // number of entries and raw sizes.
+  //
+  // The file is reported as an external file of the current format, so
+  // that it can be ingested.  It has no global sequence number field, so
+  // the field points past the end of the file.  RocksDB would append the
+  // assigned sequence number there, which CheckIngestedTableFormats()
+  // prevents.
+  Status ReadProperties(uint64_t file_size) {
+    auto props = std::make_shared<TableProperties>();
+    CallbackTableIterator iter(cb_, cb_.reader_new_iterator(reader_), ucmp_,
+                               seqno_);
+    for (iter.SeekToFirst(); iter.Valid(); iter.Next()) {
+      props->num_entries++;
+      props->raw_key_size += iter.key().size();
+      props->raw_value_size += iter.value().size();
+    }
+    Status s = iter.status();
+    if (!s.ok()) {
+      return s;
+    }
+    props->data_size = file_size;
+    props->comparator_name = ucmp_->Name();
+    std::string version;
+    ROCKSDB_NAMESPACE::PutFixed32(&version, 2);
+    props->user_collected_properties[ExternalSstFilePropertyNames::kVersion] =
+        version;
+    std::string global_seqno;
+    ROCKSDB_NAMESPACE::PutFixed64(&global_seqno, 0);
+    props->user_collected_properties
+        [ExternalSstFilePropertyNames::kGlobalSeqno] = global_seqno;
+    props->external_sst_file_global_seqno_offset = file_size;
+    props_ = props;
+    return Status::OK();
+  }
+
+  ~CallbackTableReader() override { cb_.reader_destructor(reader_); }
+
+  InternalIterator* NewIterator(const ReadOptions& /*read_options*/,
+                                const SliceTransform* /*prefix_extractor*/,
+                                Arena* arena, bool /*skip_filters*/,
+                                TableReaderCaller /*caller*/,
+                                size_t /*compaction_readahead_size*/,
+                                bool /*allow_unprepared_value*/) override {
+    void* iter = cb_.reader_new_iterator(reader_);
+    if (arena == nullptr) {
+      return new CallbackTableIterator(cb_, iter, ucmp_, seqno_);
+    }
+    void* mem = arena->AllocateAligned(sizeof(CallbackTableIterator));
+    return new (mem) CallbackTableIterator(cb_, iter, ucmp_, seqno_);
+  }
+
+  uint64_t ApproximateOffsetOf(const Slice& /*key*/,
+                               TableReaderCaller /*caller*/) override {
+    return 0;
+  }
+
+  uint64_t ApproximateSize(const Slice& /*start*/, const Slice& /*end*/,
+                           TableReaderCaller /*caller*/) override {
+    return 0;
+  }
+
+  void SetupForCompaction() override {}
+
+  std::shared_ptr<const TableProperties> GetTableProperties() const override {
+    return props_;
+  }
+
+  size_t ApproximateMemoryUsage() const override { return 0; }
+
+  Status Get(const ReadOptions& /*read_options*/, const Slice& key,
+             GetContext* get_context, const SliceTransform* /*prefix_extractor*/,
+             bool /*skip_filters*/) override {
+    CallbackTableIterator iter(cb_, cb_.reader_new_iterator(reader_), ucmp_,
+                               seqno_);
+    for (iter.Seek(key); iter.Valid(); iter.Next()) {
+      ParsedInternalKey parsed_key;
+      Status s = ROCKSDB_NAMESPACE::ParseInternalKey(iter.key(), &parsed_key,
+                                                     false);
+      if (!s.ok()) {
+        return s;
+      }
+      bool matched = false;
+      if (!get_context->SaveValue(parsed_key, iter.value(), &matched)) {
+        break;
+      }
+    }
+    return iter.status();
+  }
+
+ private:
+  // Copied from the factory: table readers may outlive it.
+  Callbacks cb_;
+  void* reader_;
+  const Comparator* ucmp_;
+  SequenceNumber seqno_;
+  std::shared_ptr<const TableProperties> props_;
+};
+
+// Block-based tables end with one of two magic numbers.
+Status IsBlockBased(RandomAccessFileReader* file, uint64_t file_size,
+                    bool* result) {
+  *result = false;
+  if (file_size < 8) {
+    return Status::OK();
+  }
+  char buf[8];
+  Slice magic;
+  IOStatus s = file->Read(IOOptions(), file_size - 8, 8, &magic, buf,
+                          static_cast<AlignedBuf*>(nullptr), Env::IO_TOTAL);
+  if (!s.ok()) {
+    return s;
+  }
+  if (magic.size() == 8) {
+    uint64_t number = ROCKSDB_NAMESPACE::DecodeFixed64(magic.data());
+    *result = number == kBlockBasedTableMagicNumber ||
+              number == kLegacyBlockBasedTableMagicNumber;
+  }
+  return Status::OK();
+}
+
+class CallbackTableFactory : public TableFactory {
+ public:
+  CallbackTableFactory(const char* name, void* state, const Callbacks& cb,
+                       std::shared_ptr<TableFactory> block_based)
+      : name_(name), state_(state), cb_(cb), block_based_(block_based) {}
+
+  ~CallbackTableFactory() override { cb_.destructor(state_); }
+
+  // Identifies these factories, whatever name they were given.
+  static const char* kClassName() { return "rocksdb_ext.CallbackTable"; }
+
+  const char* Name() const override { return name_.c_str(); }
+
+  bool IsInstanceOf(const std::string& name) const override {
+    return name == kClassName() || TableFactory::IsInstanceOf(name);
+  }
+
+  using TableFactory::NewTableReader;
+
+  Status NewTableReader(const ReadOptions& ro,
+                        const TableReaderOptions& table_reader_options,
+                        std::unique_ptr<RandomAccessFileReader>&& file,
+                        uint64_t file_size,
+                        std::unique_ptr<TableReader>* table_reader,
+                        bool prefetch_index_and_filter_in_cache) const override {
+    bool is_block_based = false;
+    Status s = IsBlockBased(file.get(), file_size, &is_block_based);
+    if (!s.ok()) {
+      return s;
+    }
+    if (is_block_based) {
+      return block_based_->NewTableReader(
+          ro, table_reader_options, std::move(file), file_size, table_reader,
+          prefetch_index_and_filter_in_cache);
+    }
+
+    char* err = nullptr;
+    void* reader = cb_.open(
+        state_, new rocksdb_ext_table_file_t{std::move(file), file_size}, &err);
+    if (err != nullptr) {
+      return Status::Corruption(TakeMessage(err));
+    }
+    std::unique_ptr<CallbackTableReader> callback_reader(
+        new CallbackTableReader(cb_, reader, table_reader_options));
+    s = callback_reader->ReadProperties(file_size);
+    if (s.ok()) {
+      *table_reader = std::move(callback_reader);
+    }
+    return s;
+  }
+
+  TableBuilder* NewTableBuilder(const TableBuilderOptions& options,
+                                WritableFileWriter* file) const override {
+    return block_based_->NewTableBuilder(options, file);
+  }
+
+  Status ValidateOptions(const DBOptions& db_opts,
+                         const ColumnFamilyOptions& cf_opts) const override {
+    return block_based_->ValidateOptions(db_opts, cf_opts);
+  }
+
+  bool IsDeleteRangeSupported() const override {
+    return block_based_->IsDeleteRangeSupported();
+  }
+
+ protected:
+  // Makes the options of the block-based factory, such as its block cache,
+  // reachable through this one.
+  const Customizable* Inner() const override { return block_based_.get(); }
+
+ private:
+  std::string name_;
+  void* state_;
+  Callbacks cb_;
+  std::shared_ptr<TableFactory> block_based_;
+};
+
+}  // namespace
+
+Status rocksdb_ext::CheckIngestedTableFormats(
+    DB* db, ColumnFamilyHandle* cf, const std::vector<std::string>& files,
+    const IngestExternalFileOptions& options) {
+  if (!options.write_global_seqno) {
+    return Status::OK();
+  }
+  std::shared_ptr<TableFactory> factory = db->GetOptions(cf).table_factory;
+  if (factory == nullptr ||
+      !factory->IsInstanceOf(CallbackTableFactory::kClassName())) {
+    return Status::OK();
+  }
+  FileSystem* fs = db->GetFileSystem();
+  for (const std::string& path : files) {
+    uint64_t file_size = 0;
+    IOStatus io_s = fs->GetFileSize(path, IOOptions(), &file_size, nullptr);
+    std::unique_ptr<FSRandomAccessFile> file;
+    if (io_s.ok()) {
+      io_s = fs->NewRandomAccessFile(path, FileOptions(), &file, nullptr);
+    }
+    if (!io_s.ok()) {
+      return io_s;
+    }
+    RandomAccessFileReader reader(std::move(file), path);
+    bool is_block_based = false;
+    Status s = IsBlockBased(&reader, file_size, &is_block_based);
+    if (!s.ok()) {
+      return s;
+    }
+    if (!is_block_based) {
+      return Status::InvalidArgument(
+          path, "files of a custom table format must be ingested with "
+                "write_global_seqno disabled");
+    }
+  }
+  return Status::OK();
+}
+
+void rocksdb_ext_options_set_table_factory(
+    rocksdb_options_t* opt, const char* name, void* state,
+    const rocksdb_ext_table_factory_callbacks_t* callbacks) {
+  std::shared_ptr<TableFactory> block_based = opt->rep.table_factory;
+  if (block_based == nullptr ||
+      !block_based->IsInstanceOf(TableFactory::kBlockBasedTableName())) {
+    block_based.reset(ROCKSDB_NAMESPACE::NewBlockBasedTableFactory());
+  }
+  opt->rep.table_factory = std::make_shared<CallbackTableFactory>(
+      name, state, *callbacks, block_based);
+}
@@ -0,0 +1,22 @@
+// Checks of the custom table factory, shared with ingestion.
+
+#pragma once
+
+#include <string>
+#include <vector>
+
+#include "rocksdb/db.h"
+#include "rocksdb/options.h"
+#include "rocksdb/status.h"
+
+namespace rocksdb_ext {
+
+// Fails if `files` include tables of a custom format and `options` set
+// write_global_seqno.  Such files have no field for the sequence number,
+// and RocksDB would append it to the file instead.
+ROCKSDB_NAMESPACE::Status CheckIngestedTableFormats(
+    ROCKSDB_NAMESPACE::DB* db, ROCKSDB_NAMESPACE::ColumnFamilyHandle* cf,
+    const std::vector<std::string>& files,
+    const ROCKSDB_NAMESPACE::IngestExternalFileOptions& options);
+
+}  // namespace rocksdb_ext
@@ -0,0 +1,151 @@
+// Access to the properties of tables.
+
+#include <string>
+#include <utility>
+#include <vector>
+
+#include "c_types.h"
+#include "ext.h"
+#include "rocksdb/db.h"
+#include "table_properties.h"
+#include "util.h"
+
+using ROCKSDB_NAMESPACE::ColumnFamilyHandle;
+using ROCKSDB_NAMESPACE::Range;
+using ROCKSDB_NAMESPACE::Slice;
+using ROCKSDB_NAMESPACE::TableProperties;
+using ROCKSDB_NAMESPACE::TablePropertiesCollection;
+using rocksdb_ext::SaveError;
+
+void rocksdb_ext_table_properties_destroy(
+    rocksdb_ext_table_properties_t* props) {
+  delete props;
+}
+
+#define UINT64_PROPERTY(name)                                                 \
+  uint64_t rocksdb_ext_table_properties_get_##name(                           \
+      const rocksdb_ext_table_properties_t* props) {                          \
+    return props->rep->name;                                                  \
+  }
+
+#define STRING_PROPERTY(name)                                                 \
+  const char* rocksdb_ext_table_properties_get_##name(                        \
+      const rocksdb_ext_table_properties_t* props, size_t* len) {             \
+    *len = props->rep->name.size();                                           \
+    return props->rep->name.data();                                           \
+  }
+
+UINT64_PROPERTY(data_size)
+UINT64_PROPERTY(index_size)
+UINT64_PROPERTY(filter_size)
+UINT64_PROPERTY(raw_key_size)
+UINT64_PROPERTY(raw_value_size)
+UINT64_PROPERTY(num_data_blocks)
+UINT64_PROPERTY(num_entries)
+UINT64_PROPERTY(num_deletions)
+UINT64_PROPERTY(num_merge_operands)
+UINT64_PROPERTY(num_range_deletions)
+UINT64_PROPERTY(format_version)
+UINT64_PROPERTY(fixed_key_len)
+UINT64_PROPERTY(column_family_id)
+UINT64_PROPERTY(creation_time)
+UINT64_PROPERTY(oldest_key_time)
+UINT64_PROPERTY(file_creation_time)
+
+STRING_PROPERTY(column_family_name)
+STRING_PROPERTY(comparator_name)
+STRING_PROPERTY(merge_operator_name)
+STRING_PROPERTY(prefix_extractor_name)
+STRING_PROPERTY(filter_policy_name)
+STRING_PROPERTY(compression_name)
+STRING_PROPERTY(compression_options)
+
+#undef UINT64_PROPERTY
+#undef STRING_PROPERTY
+
+void rocksdb_ext_table_properties_get_user_collected_properties(
+    const rocksdb_ext_table_properties_t* props, void* state,
+    void (*add)(void* state, const char* key, size_t key_len,
+                const char* value, size_t value_len)) {
+  for (const auto& prop : props->rep->user_collected_properties) {
+    add(state, prop.first.data(), prop.first.size(), prop.second.data(),
+        prop.second.size());
+  }
+}
+
+struct rocksdb_ext_table_properties_collection_t {
+  std::vector<std::pair<std::string, std::shared_ptr<const TableProperties>>>
+      rep;
+};
+
+void rocksdb_ext_table_properties_collection_destroy(
+    rocksdb_ext_table_properties_collection_t* coll) {
+  delete coll;
+}
+
+size_t rocksdb_ext_table_properties_collection_count(
+    const rocksdb_ext_table_properties_collection_t* coll) {
+  return coll->rep.size();
+}
+
+const char* rocksdb_ext_table_properties_collection_name(
+    const rocksdb_ext_table_properties_collection_t* coll, size_t index,
+    size_t* len) {
+  const std::string& name = coll->rep[index].first;
+  *len = name.size();
+  return name.data();
+}
+
+rocksdb_ext_table_properties_t* rocksdb_ext_table_properties_collection_get(
+    const rocksdb_ext_table_properties_collection_t* coll, size_t index) {
+  return new rocksdb_ext_table_properties_t{coll->rep[index].second};
+}
+
+static rocksdb_ext_table_properties_collection_t* NewCollection(
+    TablePropertiesCollection&& props) {
+  auto coll = new rocksdb_ext_table_properties_collection_t;
+  coll->rep.reserve(props.size());
+  for (auto& entry : props) {
+    coll->rep.emplace_back(entry.first, std::move(entry.second));
+  }
+  return coll;
+}
+
+static ColumnFamilyHandle* ColumnFamily(
+    rocksdb_t* db, rocksdb_column_family_handle_t* column_family) {
+  return column_family != nullptr ? column_family->rep
+                                  : db->rep->DefaultColumnFamily();
+}
+
+rocksdb_ext_table_properties_collection_t*
+rocksdb_ext_get_properties_of_all_tables_cf(
+    rocksdb_t* db, rocksdb_column_family_handle_t* column_family,
+    char** errptr) {
+  TablePropertiesCollection props;
+  if (SaveError(errptr, db->rep->GetPropertiesOfAllTables(
+                            ColumnFamily(db, column_family), &props))) {
+    return nullptr;
+  }
+  return NewCollection(std::move(props));
+}
+
+rocksdb_ext_table_properties_collection_t*
+rocksdb_ext_get_properties_of_tables_in_range_cf(
+    rocksdb_t* db, rocksdb_column_family_handle_t* column_family,
+    size_t num_ranges, const char* const* start_keys,
+    const size_t* start_key_lens, const char* const* limit_keys,
+    const size_t* limit_key_lens, char** errptr) {
+  std::vector<Range> ranges;
+  ranges.reserve(num_ranges);
+  for (size_t i = 0; i < num_ranges; i++) {
+    ranges.emplace_back(Slice(start_keys[i], start_key_lens[i]),
+                        Slice(limit_keys[i], limit_key_lens[i]));
+  }
+  TablePropertiesCollection props;
+  if (SaveError(errptr, db->rep->GetPropertiesOfTablesInRange(
+                            ColumnFamily(db, column_family), ranges.data(),
+                            ranges.size(), &props))) {
+    return nullptr;
+  }
+  return NewCollection(std::move(props));
+}
@@ -0,0 +1,13 @@
+// The wrapper struct of table properties, shared by the extensions that
+// hand out properties.
+
+#pragma once
+
+#include <memory>
+
+#include "ext.h"
+#include "rocksdb/table_properties.h"
+
+struct rocksdb_ext_table_properties_t {
+  std::shared_ptr<const ROCKSDB_NAMESPACE::TableProperties> rep;
+};
@@ -0,0 +1,108 @@
+// TablePropertiesCollector implemented through C callbacks.
+
+#include <memory>
+#include <string>
+
+#include "c_types.h"
+#include "ext.h"
+#include "rocksdb/table_properties.h"
+
+using ROCKSDB_NAMESPACE::EntryType;
+using ROCKSDB_NAMESPACE::SequenceNumber;
+using ROCKSDB_NAMESPACE::Slice;
+using ROCKSDB_NAMESPACE::Status;
+using ROCKSDB_NAMESPACE::TablePropertiesCollector;
+using ROCKSDB_NAMESPACE::TablePropertiesCollectorFactory;
+using ROCKSDB_NAMESPACE::UserCollectedProperties;
+
+// The `EntryType` enum in src/table_properties_collector.rs mirrors the
+// RocksDB enum by value.
+static_assert(EntryType::kEntryOther == rocksdb_ext_entry_other,
+              "update EntryType in src/table_properties_collector.rs");
+
+struct rocksdb_ext_user_collected_properties_t {
+  UserCollectedProperties* rep;
+};
+
+void rocksdb_ext_user_collected_properties_add(
+    rocksdb_ext_user_collected_properties_t* props, const char* key,
+    size_t key_len, const char* value, size_t value_len) {
+  (*props->rep)[std::string(key, key_len)] = std::string(value, value_len);
+}
+
+namespace {
+
+using Callbacks = rocksdb_ext_table_properties_collector_factory_callbacks_t;
+
+class CallbackTablePropertiesCollector : public TablePropertiesCollector {
+ public:
+  CallbackTablePropertiesCollector(void* state, const Callbacks& cb)
+      : state_(state), cb_(cb) {}
+
+  ~CallbackTablePropertiesCollector() override {
+    cb_.collector_destructor(state_);
+  }
+
+  Status AddUserKey(const Slice& key, const Slice& value, EntryType type,
+                    SequenceNumber seq, uint64_t file_size) override {
+    cb_.collector_add_user_key(state_, key.data(), key.size(), value.data(),
+                               value.size(), static_cast<int>(type), seq,
+                               file_size);
+    return Status::OK();
+  }
+
+  Status Finish(UserCollectedProperties* properties) override {
+    rocksdb_ext_user_collected_properties_t props{properties};
+    cb_.collector_finish(state_, &props);
+    readable_ = *properties;
+    return Status::OK();
+  }
+
+  UserCollectedProperties GetReadableProperties() const override {
+    return readable_;
+  }
+
+  const char* Name() const override { return cb_.collector_name(state_); }
+
+  bool NeedCompact() const override {
+    return cb_.collector_need_compact(state_);
+  }
+
+ private:
+  void* state_;
+  Callbacks cb_;
+  UserCollectedProperties readable_;
+};
+
+class CallbackTablePropertiesCollectorFactory
+    : public TablePropertiesCollectorFactory {
+ public:
+  CallbackTablePropertiesCollectorFactory(void* state, const Callbacks& cb)
+      : state_(state), cb_(cb) {}
+
+  ~CallbackTablePropertiesCollectorFactory() override {
+    cb_.destructor(state_);
+  }
+
+  TablePropertiesCollector* CreateTablePropertiesCollector(
+      TablePropertiesCollectorFactory::Context context) override {
+    void* collector = cb_.create_collector(state_, context.column_family_id,
+                                           context.level_at_creation);
+    return new CallbackTablePropertiesCollector(collector, cb_);
+  }
+
+  const char* Name() const override { return cb_.name(state_); }
+
+ private:
+  void* state_;
+  Callbacks cb_;
+};
+
+}  // namespace
+
+void rocksdb_ext_options_add_table_properties_collector_factory(
+    rocksdb_options_t* opt, void* state, const Callbacks* callbacks) {
+  opt->rep.table_properties_collector_factories.push_back(
+      std::make_shared<CallbackTablePropertiesCollectorFactory>(state,
+                                                                *callbacks));
+}
@@ -0,0 +1,127 @@
+// Transaction options, lock introspection and range locking not exposed by
+// the RocksDB C API.
+
+#include <memory>
+#include <string>
+#include <vector>
+
+#include "c_types.h"
+#include "ext.h"
+#include "rocksdb/utilities/transaction_db.h"
+#include "util.h"
+
+using ROCKSDB_NAMESPACE::Slice;
+using ROCKSDB_NAMESPACE::TxnDBWritePolicy;
+
+static_assert(TxnDBWritePolicy::WRITE_COMMITTED ==
+                  rocksdb_ext_txndb_write_committed,
+              "update TxnDBWritePolicy in src/transactions/options.rs");
+static_assert(TxnDBWritePolicy::WRITE_PREPARED ==
+                  rocksdb_ext_txndb_write_prepared,
+              "update TxnDBWritePolicy in src/transactions/options.rs");
+static_assert(TxnDBWritePolicy::WRITE_UNPREPARED ==
+                  rocksdb_ext_txndb_write_unprepared,
+              "update TxnDBWritePolicy in src/transactions/options.rs");
+
+void rocksdb_ext_transactiondb_options_set_write_policy(
+    rocksdb_transactiondb_options_t* opt, int policy) {
+  opt->rep.write_policy = static_cast<TxnDBWritePolicy>(policy);
+}
+
+void rocksdb_ext_transactiondb_options_set_default_write_batch_flush_threshold(
+    rocksdb_transactiondb_options_t* opt, int64_t threshold) {
+  opt->rep.default_write_batch_flush_threshold = threshold;
+}
+
+void rocksdb_ext_transaction_options_set_write_batch_flush_threshold(
+    rocksdb_transaction_options_t* opt, int64_t threshold) {
+  opt->rep.write_batch_flush_threshold = threshold;
+}
+
+void rocksdb_ext_transactiondb_get_lock_status_data(
+    rocksdb_transactiondb_t* db, void* state,
+    void (*add)(void* state, uint32_t cf_id, const char* key, size_t key_len,
+                const uint64_t* txn_ids, size_t num_txn_ids,
+                unsigned char exclusive)) {
+  for (const auto& entry : db->rep->GetLockStatusData()) {
+    const ROCKSDB_NAMESPACE::KeyLockInfo& info = entry.second;
+    add(state, entry.first, info.key.data(), info.key.size(), info.ids.data(),
+        info.ids.size(), info.exclusive);
+  }
+}
+
+void rocksdb_ext_transactiondb_get_deadlock_info_buffer(
+    rocksdb_transactiondb_t* db, void* state,
+    void (*add_path)(void* state, unsigned char limit_exceeded,
+                     int64_t deadlock_time),
+    void (*add_info)(void* state, uint64_t txn_id, uint32_t cf_id,
+                     unsigned char exclusive, const char* waiting_key,
+                     size_t waiting_key_len)) {
+  for (const auto& path : db->rep->GetDeadlockInfoBuffer()) {
+    add_path(state, path.limit_exceeded, path.deadlock_time);
+    for (const auto& info : path.path) {
+      add_info(state, info.m_txn_id, info.m_cf_id, info.m_exclusive,
+               info.m_waiting_key.data(), info.m_waiting_key.size());
+    }
+  }
+}
+
+void rocksdb_ext_transactiondb_set_deadlock_info_buffer_size(
+    rocksdb_transactiondb_t* db, uint32_t target_size) {
+  db->rep->SetDeadlockInfoBufferSize(target_size);
+}
+
+uint64_t rocksdb_ext_transaction_get_id(rocksdb_transaction_t* txn) {
+  return txn->rep->GetID();
+}
+
+void rocksdb_ext_transaction_get_waiting_txns(
+    rocksdb_transaction_t* txn, void* state,
+    void (*set)(void* state, uint32_t cf_id, const char* key, size_t key_len,
+                const uint64_t* txn_ids, size_t num_txn_ids)) {
+  uint32_t cf_id = 0;
+  std::string key;
+  std::vector<ROCKSDB_NAMESPACE::TransactionID> ids =
+      txn->rep->GetWaitingTxns(&cf_id, &key);
+  set(state, cf_id, key.data(), key.size(), ids.data(), ids.size());
+}
+
+#ifndef OS_WIN
+
+struct rocksdb_ext_rangelockmanager_t {
+  std::shared_ptr<ROCKSDB_NAMESPACE::RangeLockManagerHandle> rep;
+};
+
+rocksdb_ext_rangelockmanager_t* rocksdb_ext_rangelockmanager_create() {
+  return new rocksdb_ext_rangelockmanager_t{
+      std::shared_ptr<ROCKSDB_NAMESPACE::RangeLockManagerHandle>(
+          ROCKSDB_NAMESPACE::NewRangeLockManager(nullptr))};
+}
+
+void rocksdb_ext_rangelockmanager_destroy(rocksdb_ext_rangelockmanager_t* mgr) {
+  delete mgr;
+}
+
+int rocksdb_ext_rangelockmanager_set_max_lock_memory(
+    rocksdb_ext_rangelockmanager_t* mgr, size_t max_lock_memory) {
+  return mgr->rep->SetMaxLockMemory(max_lock_memory);
+}
+
+void rocksdb_ext_transactiondb_options_set_lock_manager(
+    rocksdb_transactiondb_options_t* opt, rocksdb_ext_rangelockmanager_t* mgr) {
+  opt->rep.lock_mgr_handle = mgr->rep;
+}
+
+void rocksdb_ext_transaction_get_range_lock(rocksdb_transaction_t* txn,
+                                            rocksdb_column_family_handle_t* cf,
+                                            const char* start,
+                                            size_t start_len, const char* end,
+                                            size_t end_len, char** errptr) {
+  rocksdb_ext::SaveError(
+      errptr, txn->rep->GetRangeLock(
+                  cf ? cf->rep : nullptr,
+                  ROCKSDB_NAMESPACE::Endpoint(Slice(start, start_len)),
+                  ROCKSDB_NAMESPACE::Endpoint(Slice(end, end_len))));
+}
+
+#endif  // OS_WIN
@@ -0,0 +1,73 @@
+// Helpers shared by the C API extensions.
+
+#pragma once
+
+#include <cassert>
+#include <cstdlib>
+#include <cstring>
+#include <string>
+
+#include "ext.h"
+#include "rocksdb/io_status.h"
+#include "rocksdb/status.h"
+
+// A string owned by RocksDB, filled by a callback.
+struct rocksdb_ext_string_t {
+  std::string* rep;
+};
+
+namespace rocksdb_ext {
+
+// Mirrors SaveError() from rocksdb/db/c.cc.
+inline bool SaveError(char** errptr, const ROCKSDB_NAMESPACE::Status& s) {
+  assert(errptr != nullptr);
+  if (s.ok()) {
+    return false;
+  } else if (*errptr == nullptr) {
+    *errptr = strdup(s.ToString().c_str());
+  } else {
+    free(*errptr);
+    *errptr = strdup(s.ToString().c_str());
+  }
+  return true;
+}
+
+// Copies a malloc()-ed message produced by a callback and frees it.
+inline std::string TakeMessage(char* errmsg) {
+  std::string msg;
+  if (errmsg != nullptr) {
+    msg = errmsg;
+    free(errmsg);
+  }
+  return msg;
+}
+
+// Converts a rocksdb_ext_io_* code returned by a callback into an IOStatus.
+// RocksDB distinguishes a missing file reported by FileExists() (NotFound)
+// from a failure to open a missing file (IOError with PathNotFound subcode).
+inline ROCKSDB_NAMESPACE::IOStatus ToIOStatus(int code, char* errmsg,
+                                              bool not_found_is_path = true) {
+  using ROCKSDB_NAMESPACE::IOStatus;
+  std::string msg = TakeMessage(errmsg);
+  switch (code) {
+    case rocksdb_ext_io_ok:
+      return IOStatus::OK();
+    case rocksdb_ext_io_not_found:
+      return not_found_is_path ? IOStatus::PathNotFound(msg)
+                               : IOStatus::NotFound(msg);
+    case rocksdb_ext_io_not_supported:
+      return IOStatus::NotSupported(msg);
+    case rocksdb_ext_io_invalid_argument:
+      return IOStatus::InvalidArgument(msg);
+    case rocksdb_ext_io_no_space:
+      return IOStatus::NoSpace(msg);
+    case rocksdb_ext_io_busy:
+      return IOStatus::Busy(msg);
+    case rocksdb_ext_io_corruption:
+      return IOStatus::Corruption(msg);
+    default:
+      return IOStatus::IOError(msg);
+  }
+}
+
+}  // namespace rocksdb_ext
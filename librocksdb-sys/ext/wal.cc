@@ -0,0 +1,24 @@
+// WAL metadata not exposed by the RocksDB C API.
+
+#include "c_types.h"
+#include "ext.h"
+#include "rocksdb/db.h"
+#include "rocksdb/transaction_log.h"
+#include "util.h"
+
+using rocksdb_ext::SaveError;
+
+uint64_t rocksdb_ext_get_oldest_wal_sequence(rocksdb_t* db, char** errptr) {
+  ROCKSDB_NAMESPACE::VectorLogPtr files;
+  if (SaveError(errptr, db->rep->GetSortedWalFiles(files))) {
+    return 0;
+  }
+  // Files are sorted by log number, so the first one holding a batch has
+  // the oldest.
+  for (const auto& file : files) {
+    if (file->StartSequence() != 0) {
+      return file->StartSequence();
+    }
+  }
+  return 0;
+}
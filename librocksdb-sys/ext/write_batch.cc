@@ -0,0 +1,124 @@
+// Iteration of write batches with column family IDs, merges, range
+// deletions, log data and two-phase commit markers, which
+// rocksdb_writebatch_iterate() does not report.
+
+#include "c_types.h"
+#include "db/write_batch_internal.h"
+#include "ext.h"
+#include "rocksdb/write_batch.h"
+#include "util.h"
+
+using ROCKSDB_NAMESPACE::Slice;
+using ROCKSDB_NAMESPACE::SliceParts;
+using ROCKSDB_NAMESPACE::Status;
+using ROCKSDB_NAMESPACE::WriteBatch;
+using ROCKSDB_NAMESPACE::WriteBatchInternal;
+
+// WriteBatch only takes timestamps along with a column family handle, which
+// a batch built without a database lacks.  The default column family always
+// has ID 0, so the records are written as WriteBatch would write them.
+static const uint32_t kDefaultColumnFamilyId = 0;
+
+uint32_t rocksdb_ext_column_family_handle_get_id(
+    rocksdb_column_family_handle_t* cf) {
+  return cf->rep->GetID();
+}
+
+void rocksdb_ext_writebatch_put_with_ts(rocksdb_writebatch_t* batch,
+                                        const char* key, size_t key_len,
+                                        const char* ts, size_t ts_len,
+                                        const char* value, size_t value_len) {
+  Slice key_with_ts[2] = {Slice(key, key_len), Slice(ts, ts_len)};
+  Slice value_slice(value, value_len);
+  WriteBatchInternal::Put(&batch->rep, kDefaultColumnFamilyId,
+                          SliceParts(key_with_ts, 2),
+                          SliceParts(&value_slice, 1));
+}
+
+void rocksdb_ext_writebatch_delete_with_ts(rocksdb_writebatch_t* batch,
+                                           const char* key, size_t key_len,
+                                           const char* ts, size_t ts_len) {
+  Slice key_with_ts[2] = {Slice(key, key_len), Slice(ts, ts_len)};
+  WriteBatchInternal::Delete(&batch->rep, kDefaultColumnFamilyId,
+                             SliceParts(key_with_ts, 2));
+}
+
+namespace {
+
+class CallbackHandler : public WriteBatch::Handler {
+ public:
+  CallbackHandler(void* state, const rocksdb_ext_writebatch_handler_t* handler)
+      : state_(state), handler_(*handler) {}
+
+  Status PutCF(uint32_t cf_id, const Slice& key, const Slice& value) override {
+    handler_.put(state_, cf_id, key.data(), key.size(), value.data(),
+                 value.size());
+    return Status::OK();
+  }
+
+  Status DeleteCF(uint32_t cf_id, const Slice& key) override {
+    handler_.delete_(state_, cf_id, key.data(), key.size());
+    return Status::OK();
+  }
+
+  Status SingleDeleteCF(uint32_t cf_id, const Slice& key) override {
+    handler_.single_delete(state_, cf_id, key.data(), key.size());
+    return Status::OK();
+  }
+
+  Status DeleteRangeCF(uint32_t cf_id, const Slice& begin_key,
+                       const Slice& end_key) override {
+    handler_.delete_range(state_, cf_id, begin_key.data(), begin_key.size(),
+                          end_key.data(), end_key.size());
+    return Status::OK();
+  }
+
+  Status MergeCF(uint32_t cf_id, const Slice& key,
+                 const Slice& value) override {
+    handler_.merge(state_, cf_id, key.data(), key.size(), value.data(),
+                   value.size());
+    return Status::OK();
+  }
+
+  void LogData(const Slice& blob) override {
+    handler_.log_data(state_, blob.data(), blob.size());
+  }
+
+  Status MarkBeginPrepare(bool unprepared) override {
+    handler_.mark_begin_prepare(state_, unprepared);
+    return Status::OK();
+  }
+
+  Status MarkEndPrepare(const Slice& xid) override {
+    handler_.mark_end_prepare(state_, xid.data(), xid.size());
+    return Status::OK();
+  }
+
+  Status MarkCommit(const Slice& xid) override {
+    handler_.mark_commit(state_, xid.data(), xid.size());
+    return Status::OK();
+  }
+
+  Status MarkRollback(const Slice& xid) override {
+    handler_.mark_rollback(state_, xid.data(), xid.size());
+    return Status::OK();
+  }
+
+  Status MarkNoop(bool empty_batch) override {
+    handler_.mark_noop(state_, empty_batch);
+    return Status::OK();
+  }
+
+ private:
+  void* state_;
+  rocksdb_ext_writebatch_handler_t handler_;
+};
+
+}  // namespace
+
+void rocksdb_ext_writebatch_iterate_cf(
+    const rocksdb_writebatch_t* batch, void* state,
+    const rocksdb_ext_writebatch_handler_t* handler, char** errptr) {
+  CallbackHandler callback_handler(state, handler);
+  rocksdb_ext::SaveError(errptr, batch->rep.Iterate(&callback_handler));
+}
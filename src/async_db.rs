@@ -0,0 +1,406 @@
+// Copyright 2022 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{mpsc, Arc, Mutex};
+use std::task::{Context, Poll};
+use std::thread;
+
+use futures_channel::oneshot;
+use futures_core::Stream;
+
+use crate::db_iterator::{DBIteratorWithThreadMode, KVBytes};
+use crate::{Direction, Error, IteratorMode, WriteBatch, DB};
+
+/// Number of entries a [`DBStream`] reads from RocksDB at once.
+const STREAM_BATCH_SIZE: usize = 128;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A fixed set of threads running blocking database calls.
+struct ThreadPool {
+    sender: Mutex<mpsc::Sender<Job>>,
+}
+
+impl ThreadPool {
+    fn new(threads: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for i in 0..threads.max(1) {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name(format!("rocksdb-async-{i}"))
+                .spawn(move || loop {
+                    let job = receiver.lock().unwrap().recv();
+                    match job {
+                        Ok(job) => job(),
+                        // The pool was dropped and all queued jobs ran.
+                        Err(_) => break,
+                    }
+                })
+                .expect("failed to spawn rocksdb-async thread");
+        }
+        Self {
+            sender: Mutex::new(sender),
+        }
+    }
+
+    fn spawn<T, F>(&self, f: F) -> DBFuture<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let job = Box::new(move || {
+            let _ = tx.send(panic::catch_unwind(AssertUnwindSafe(f)));
+        });
+        // Workers only exit once the sender is dropped, so sending can't fail.
+        let _ = self.sender.lock().unwrap().send(job);
+        DBFuture { rx }
+    }
+}
+
+/// The result of a call on [`AsyncDB`].
+///
+/// The call runs on the thread pool of the `AsyncDB` whether or not the
+/// future is polled; dropping the future discards the result.  A panic in
+/// the call is resumed when the future is polled.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct DBFuture<T> {
+    rx: oneshot::Receiver<thread::Result<T>>,
+}
+
+impl<T> Future for DBFuture<T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        match Pin::new(&mut self.rx).poll(cx) {
+            Poll::Ready(Ok(Ok(value))) => Poll::Ready(value),
+            Poll::Ready(Ok(Err(payload))) => panic::resume_unwind(payload),
+            Poll::Ready(Err(oneshot::Canceled)) => panic!("rocksdb-async job was dropped"),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// A [`DB`] whose calls run on a dedicated thread pool and return futures.
+///
+/// `AsyncDB` does not depend on a particular async runtime.  It is cheap to
+/// clone; clones share the database and the thread pool.  The database is
+/// closed once every clone, stream and running call is gone.
+///
+/// ```
+/// use futures::{executor::block_on, StreamExt};
+/// use rocksdb::{AsyncDB, IteratorMode, Options, DB};
+///
+/// let path = "_rust_rocksdb_async_db_doc";
+/// {
+///     let db = AsyncDB::new(DB::open_default(path).unwrap());
+///     block_on(async {
+///         db.put(b"k1", b"v1").await.unwrap();
+///         assert_eq!(db.get(b"k1").await.unwrap().unwrap(), b"v1");
+///
+///         let entries: Vec<_> = db.iterator(IteratorMode::Start).collect().await;
+///         assert_eq!(entries.len(), 1);
+///     });
+/// }
+/// let _ = DB::destroy(&Options::default(), path);
+/// ```
+#[derive(Clone)]
+pub struct AsyncDB {
+    db: Arc<DB>,
+    pool: Arc<ThreadPool>,
+}
+
+impl AsyncDB {
+    /// Wraps `db`, running its calls on one thread per available CPU.
+    pub fn new(db: DB) -> Self {
+        let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        Self::with_threads(db, threads)
+    }
+
+    /// Wraps `db`, running its calls on `threads` threads.
+    pub fn with_threads(db: DB, threads: usize) -> Self {
+        Self {
+            db: Arc::new(db),
+            pool: Arc::new(ThreadPool::new(threads)),
+        }
+    }
+
+    /// Returns the wrapped database for calls that don't block.
+    pub fn db(&self) -> &DB {
+        &self.db
+    }
+
+    /// Runs `f` with the database on the thread pool.
+    pub fn run<T, F>(&self, f: F) -> DBFuture<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&DB) -> T + Send + 'static,
+    {
+        let db = self.db.clone();
+        self.pool.spawn(move || f(&db))
+    }
+
+    pub fn get<K: AsRef<[u8]>>(&self, key: K) -> DBFuture<Result<Option<Vec<u8>>, Error>> {
+        let key = key.as_ref().to_vec();
+        self.run(move |db| db.get(key))
+    }
+
+    pub fn get_cf<K: AsRef<[u8]>>(
+        &self,
+        cf_name: &str,
+        key: K,
+    ) -> DBFuture<Result<Option<Vec<u8>>, Error>> {
+        let cf_name = cf_name.to_owned();
+        let key = key.as_ref().to_vec();
+        self.run(move |db| {
+            let cf = cf_handle(db, &cf_name)?;
+            db.get_cf(&cf, key)
+        })
+    }
+
+    pub fn multi_get<K, I>(&self, keys: I) -> DBFuture<Vec<Result<Option<Vec<u8>>, Error>>>
+    where
+        K: AsRef<[u8]>,
+        I: IntoIterator<Item = K>,
+    {
+        let keys: Vec<Vec<u8>> = keys.into_iter().map(|k| k.as_ref().to_vec()).collect();
+        self.run(move |db| db.multi_get(keys))
+    }
+
+    pub fn put<K, V>(&self, key: K, value: V) -> DBFuture<Result<(), Error>>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let key = key.as_ref().to_vec();
+        let value = value.as_ref().to_vec();
+        self.run(move |db| db.put(key, value))
+    }
+
+    pub fn put_cf<K, V>(&self, cf_name: &str, key: K, value: V) -> DBFuture<Result<(), Error>>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let cf_name = cf_name.to_owned();
+        let key = key.as_ref().to_vec();
+        let value = value.as_ref().to_vec();
+        self.run(move |db| {
+            let cf = cf_handle(db, &cf_name)?;
+            db.put_cf(&cf, key, value)
+        })
+    }
+
+    pub fn delete<K: AsRef<[u8]>>(&self, key: K) -> DBFuture<Result<(), Error>> {
+        let key = key.as_ref().to_vec();
+        self.run(move |db| db.delete(key))
+    }
+
+    pub fn delete_cf<K: AsRef<[u8]>>(&self, cf_name: &str, key: K) -> DBFuture<Result<(), Error>> {
+        let cf_name = cf_name.to_owned();
+        let key = key.as_ref().to_vec();
+        self.run(move |db| {
+            let cf = cf_handle(db, &cf_name)?;
+            db.delete_cf(&cf, key)
+        })
+    }
+
+    pub fn write(&self, batch: WriteBatch) -> DBFuture<Result<(), Error>> {
+        self.run(move |db| db.write(batch))
+    }
+
+    pub fn flush(&self) -> DBFuture<Result<(), Error>> {
+        self.run(DB::flush)
+    }
+
+    pub fn flush_cf(&self, cf_name: &str) -> DBFuture<Result<(), Error>> {
+        let cf_name = cf_name.to_owned();
+        self.run(move |db| {
+            let cf = cf_handle(db, &cf_name)?;
+            db.flush_cf(&cf)
+        })
+    }
+
+    pub fn compact_range<S: AsRef<[u8]>, E: AsRef<[u8]>>(
+        &self,
+        start: Option<S>,
+        end: Option<E>,
+    ) -> DBFuture<()> {
+        let start = start.map(|s| s.as_ref().to_vec());
+        let end = end.map(|e| e.as_ref().to_vec());
+        self.run(move |db| db.compact_range(start, end))
+    }
+
+    pub fn compact_range_cf<S: AsRef<[u8]>, E: AsRef<[u8]>>(
+        &self,
+        cf_name: &str,
+        start: Option<S>,
+        end: Option<E>,
+    ) -> DBFuture<Result<(), Error>> {
+        let cf_name = cf_name.to_owned();
+        let start = start.map(|s| s.as_ref().to_vec());
+        let end = end.map(|e| e.as_ref().to_vec());
+        self.run(move |db| {
+            let cf = cf_handle(db, &cf_name)?;
+            db.compact_range_cf(&cf, start, end);
+            Ok(())
+        })
+    }
+
+    /// Returns a stream over the entries of the database.
+    ///
+    /// Entries are read in batches on the thread pool.  The stream ends
+    /// after the first error.
+    pub fn iterator(&self, mode: IteratorMode) -> DBStream {
+        DBStream::new(self, None, mode)
+    }
+
+    /// Returns a stream over the entries of a column family.
+    pub fn iterator_cf(&self, cf_name: &str, mode: IteratorMode) -> DBStream {
+        DBStream::new(self, Some(cf_name.to_owned()), mode)
+    }
+}
+
+#[cfg(not(feature = "multi-threaded-cf"))]
+fn cf_handle<'a>(db: &'a DB, name: &str) -> Result<&'a crate::ColumnFamily, Error> {
+    db.cf_handle(name)
+        .ok_or_else(|| Error::new(format!("Invalid column family: {name}")))
+}
+
+#[cfg(feature = "multi-threaded-cf")]
+fn cf_handle<'a>(db: &'a DB, name: &str) -> Result<Arc<crate::BoundColumnFamily<'a>>, Error> {
+    db.cf_handle(name)
+        .ok_or_else(|| Error::new(format!("Invalid column family: {name}")))
+}
+
+/// An iterator owning a reference to its database.
+struct OwnedIterator {
+    // Declared before `_db` so that it is dropped first.
+    iter: DBIteratorWithThreadMode<'static, DB>,
+    _db: Arc<DB>,
+}
+
+impl OwnedIterator {
+    fn new(db: Arc<DB>, cf_name: Option<&str>, mode: IteratorMode) -> Result<Self, Error> {
+        // SAFETY: the iterator never outlives `_db`, which keeps the database
+        // alive at a stable address.
+        let db_ref: &'static DB = unsafe { &*Arc::as_ptr(&db) };
+        let iter = match cf_name {
+            Some(name) => db_ref.iterator_cf(&cf_handle(db_ref, name)?, mode),
+            None => db_ref.iterator(mode),
+        };
+        Ok(Self { iter, _db: db })
+    }
+
+    /// Reads the next batch of entries.  Returns `None` as the iterator
+    /// once it is exhausted.
+    fn next_batch(mut self) -> (Option<Self>, Vec<Result<KVBytes, Error>>) {
+        let mut batch = Vec::with_capacity(STREAM_BATCH_SIZE);
+        for item in self.iter.by_ref().take(STREAM_BATCH_SIZE) {
+            let failed = item.is_err();
+            batch.push(item);
+            if failed {
+                return (None, batch);
+            }
+        }
+        if batch.len() < STREAM_BATCH_SIZE {
+            (None, batch)
+        } else {
+            (Some(self), batch)
+        }
+    }
+}
+
+type Batch = (Option<OwnedIterator>, Vec<Result<KVBytes, Error>>);
+
+enum StreamState {
+    /// Waiting to be polled; holds the call reading the next batch.
+    Idle(Box<dyn FnOnce() -> Batch + Send>),
+    Reading(DBFuture<Batch>),
+    Done,
+}
+
+/// A stream over the entries of an [`AsyncDB`], created by
+/// [`AsyncDB::iterator`] and [`AsyncDB::iterator_cf`].
+///
+/// A batch is only read once the previous one is consumed, so dropping a
+/// stream that is not being polled releases its iterator immediately.
+pub struct DBStream {
+    pool: Arc<ThreadPool>,
+    buffer: VecDeque<Result<KVBytes, Error>>,
+    state: StreamState,
+}
+
+impl DBStream {
+    fn new(db: &AsyncDB, cf_name: Option<String>, mode: IteratorMode) -> Self {
+        let db_ref = db.db.clone();
+        let (start, direction) = match mode {
+            IteratorMode::Start => (None, None),
+            IteratorMode::End => (None, Some(Direction::Reverse)),
+            IteratorMode::From(key, direction) => (Some(key.to_vec()), Some(direction)),
+        };
+        let open = move || {
+            let mode = match (&start, direction) {
+                (Some(key), Some(direction)) => IteratorMode::From(key, direction),
+                (_, Some(_)) => IteratorMode::End,
+                _ => IteratorMode::Start,
+            };
+            match OwnedIterator::new(db_ref, cf_name.as_deref(), mode) {
+                Ok(iter) => iter.next_batch(),
+                Err(e) => (None, vec![Err(e)]),
+            }
+        };
+        Self {
+            pool: db.pool.clone(),
+            buffer: VecDeque::new(),
+            state: StreamState::Idle(Box::new(open)),
+        }
+    }
+}
+
+impl Stream for DBStream {
+    type Item = Result<KVBytes, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(item) = self.buffer.pop_front() {
+                return Poll::Ready(Some(item));
+            }
+            match std::mem::replace(&mut self.state, StreamState::Done) {
+                StreamState::Idle(read) => {
+                    self.state = StreamState::Reading(self.pool.spawn(read));
+                }
+                StreamState::Reading(mut reading) => match Pin::new(&mut reading).poll(cx) {
+                    Poll::Ready((iter, batch)) => {
+                        if let Some(iter) = iter {
+                            self.state = StreamState::Idle(Box::new(move || iter.next_batch()));
+                        }
+                        self.buffer.extend(batch);
+                    }
+                    Poll::Pending => {
+                        self.state = StreamState::Reading(reading);
+                        return Poll::Pending;
+                    }
+                },
+                StreamState::Done => return Poll::Ready(None),
+            }
+        }
+    }
+}
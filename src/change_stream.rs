@@ -0,0 +1,308 @@
+// Copyright 2020 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeSet, VecDeque};
+
+use crate::{ffi, AsColumnFamilyRef, DBWALIterator, Error, WriteBatch, WriteBatchIteratorCf};
+
+/// A write operation read from the WAL by a [`ChangeStream`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeOp {
+    Put {
+        key: Vec<u8>,
+        value: Vec<u8>,
+    },
+    Delete {
+        key: Vec<u8>,
+    },
+    SingleDelete {
+        key: Vec<u8>,
+    },
+    Merge {
+        key: Vec<u8>,
+        operand: Vec<u8>,
+    },
+    /// Deletes the keys from `begin_key` included to `end_key` excluded.
+    DeleteRange {
+        begin_key: Vec<u8>,
+        end_key: Vec<u8>,
+    },
+}
+
+/// A write operation and where it was applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// The sequence number of the operation.
+    pub sequence: u64,
+    /// The ID of the column family, see [`AsColumnFamilyRef::id`].
+    pub cf_id: u32,
+    pub op: ChangeOp,
+}
+
+/// The write operations applied to a database, in the order of their
+/// sequence numbers.
+///
+/// Returned by [`DBCommon::change_stream`](crate::DBCommon::change_stream),
+/// which reads the WAL with
+/// [`get_updates_since`](crate::DBCommon::get_updates_since).  The stream
+/// ends when it has read the whole WAL.  To read the changes written later
+/// on, or after a restart, create a new stream resuming from
+/// [`sequence`](Self::sequence).
+///
+/// The WAL only holds the recent changes: WAL files are deleted once their
+/// changes are flushed, unless kept by
+/// [`Options::set_wal_ttl_seconds`](crate::Options::set_wal_ttl_seconds) or
+/// [`Options::set_wal_size_limit_mb`](crate::Options::set_wal_size_limit_mb).
+/// If changes to resume from are no longer in the WAL, the stream returns a
+/// `NotFound` error instead of skipping them.
+///
+/// Writes without the WAL, as with
+/// [`WriteOptions::disable_wal`](crate::WriteOptions::disable_wal) or
+/// [`DB::write_without_wal`](crate::DBWithThreadMode::write_without_wal),
+/// use sequence numbers but are missing from the WAL.  A stream reaching
+/// them fails with an `Incomplete` or `Corruption` error, so they should not
+/// be used on a database read by change streams.
+///
+/// Sequence numbers are those of databases with the default write policy,
+/// where every operation of a batch has a sequence number of its own.
+/// Databases written by a [`TransactionDB`](crate::TransactionDB) using
+/// two-phase commit are not supported: their WAL holds prepared operations
+/// apart from their commit, so a stream reaching them fails with a
+/// `NotSupported` error.
+///
+/// ```
+/// use rocksdb::{ChangeOp, Options, DB};
+///
+/// let path = "_path_for_rocksdb_storage_change_stream";
+/// {
+///     let db = DB::open_default(path).unwrap();
+///     let start = db.latest_sequence_number();
+///     db.put(b"k1", b"v1").unwrap();
+///     db.delete(b"k1").unwrap();
+///
+///     let mut stream = db.change_stream(start).unwrap();
+///     let ops: Vec<ChangeOp> = stream.by_ref().map(|change| change.unwrap().op).collect();
+///     assert_eq!(
+///         ops,
+///         [
+///             ChangeOp::Put { key: b"k1".to_vec(), value: b"v1".to_vec() },
+///             ChangeOp::Delete { key: b"k1".to_vec() },
+///         ]
+///     );
+///     // Persist this to resume after a restart.
+///     assert_eq!(stream.sequence(), db.latest_sequence_number());
+/// }
+/// let _ = DB::destroy(&Options::default(), path);
+/// ```
+pub struct ChangeStream {
+    iter: DBWALIterator,
+    /// The sequence number of the oldest batch in the WAL when the stream
+    /// was created.
+    oldest_sequence: Option<u64>,
+    started: bool,
+    done: bool,
+    sequence: u64,
+    cf_ids: Option<BTreeSet<u32>>,
+    pending: VecDeque<Change>,
+}
+
+impl ChangeStream {
+    /// `iter` must be positioned at the batch containing `resume_after`, and
+    /// `oldest_sequence` be that of the first batch in the WAL, if any.
+    pub(crate) fn new(
+        iter: DBWALIterator,
+        oldest_sequence: Option<u64>,
+        resume_after: u64,
+    ) -> Self {
+        Self {
+            iter,
+            oldest_sequence,
+            started: false,
+            done: false,
+            sequence: resume_after,
+            cf_ids: None,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Only returns the changes of `cf`, and of the other column families
+    /// passed to this method.
+    pub fn filter_cf(mut self, cf: &impl AsColumnFamilyRef) -> Self {
+        self.cf_ids
+            .get_or_insert_with(BTreeSet::new)
+            .insert(cf.id());
+        self
+    }
+
+    /// Returns the sequence number of the last change read, including
+    /// changes skipped by [`filter_cf`](Self::filter_cf).  A stream created
+    /// with this sequence number resumes after that change.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Reads the next batch into `pending`.  Returns `false` at the end of
+    /// the WAL.
+    fn read_batch(&mut self) -> Result<bool, Error> {
+        if self.started {
+            unsafe {
+                ffi::rocksdb_wal_iter_next(self.iter.inner);
+            }
+        }
+        self.started = true;
+        if !self.iter.valid() {
+            self.iter.status()?;
+            return Ok(false);
+        }
+        let mut batch_sequence: u64 = 0;
+        let inner =
+            unsafe { ffi::rocksdb_wal_iter_get_batch(self.iter.inner, &mut batch_sequence) };
+        let batch = WriteBatch { inner };
+        if batch_sequence > self.sequence + 1 {
+            let (first, last) = (self.sequence + 1, batch_sequence - 1);
+            let written_without_wal = self.oldest_sequence.map_or(false, |oldest| first >= oldest);
+            return Err(Error::new(if written_without_wal {
+                format!(
+                    "Result incomplete: changes from sequence number {} to {} were written \
+                     without the WAL",
+                    first, last
+                )
+            } else {
+                format!(
+                    "NotFound: changes from sequence number {} to {} are no longer in the WAL",
+                    first, last
+                )
+            }));
+        }
+        self.pending = decode_batch(&batch, batch_sequence)?.into();
+        Ok(true)
+    }
+}
+
+impl Iterator for ChangeStream {
+    type Item = Result<Change, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while let Some(change) = self.pending.pop_front() {
+                // The first batch may start before the stream.
+                if change.sequence <= self.sequence {
+                    continue;
+                }
+                self.sequence = change.sequence;
+                if self
+                    .cf_ids
+                    .as_ref()
+                    .map_or(true, |cf_ids| cf_ids.contains(&change.cf_id))
+                {
+                    return Some(Ok(change));
+                }
+            }
+            if self.done {
+                return None;
+            }
+            match self.read_batch() {
+                Ok(true) => {}
+                Ok(false) => {
+                    self.done = true;
+                    return None;
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+struct Decoder {
+    sequence: u64,
+    changes: Vec<Change>,
+    /// Whether the batch holds markers of two-phase commit.
+    two_phase: bool,
+}
+
+impl Decoder {
+    fn push(&mut self, cf_id: u32, op: ChangeOp) {
+        let sequence = self.sequence + self.changes.len() as u64;
+        self.changes.push(Change {
+            sequence,
+            cf_id,
+            op,
+        });
+    }
+}
+
+impl WriteBatchIteratorCf for Decoder {
+    fn put_cf(&mut self, cf_id: u32, key: &[u8], value: &[u8]) {
+        let (key, value) = (key.to_vec(), value.to_vec());
+        self.push(cf_id, ChangeOp::Put { key, value });
+    }
+
+    fn delete_cf(&mut self, cf_id: u32, key: &[u8]) {
+        let key = key.to_vec();
+        self.push(cf_id, ChangeOp::Delete { key });
+    }
+
+    fn single_delete_cf(&mut self, cf_id: u32, key: &[u8]) {
+        let key = key.to_vec();
+        self.push(cf_id, ChangeOp::SingleDelete { key });
+    }
+
+    fn merge_cf(&mut self, cf_id: u32, key: &[u8], operand: &[u8]) {
+        let (key, operand) = (key.to_vec(), operand.to_vec());
+        self.push(cf_id, ChangeOp::Merge { key, operand });
+    }
+
+    fn delete_range_cf(&mut self, cf_id: u32, begin_key: &[u8], end_key: &[u8]) {
+        let (begin_key, end_key) = (begin_key.to_vec(), end_key.to_vec());
+        self.push(cf_id, ChangeOp::DeleteRange { begin_key, end_key });
+    }
+
+    fn mark_begin_prepare(&mut self, _unprepared: bool) {
+        self.two_phase = true;
+    }
+
+    fn mark_end_prepare(&mut self, _xid: &[u8]) {
+        self.two_phase = true;
+    }
+
+    fn mark_commit(&mut self, _xid: &[u8]) {
+        self.two_phase = true;
+    }
+
+    fn mark_rollback(&mut self, _xid: &[u8]) {
+        self.two_phase = true;
+    }
+}
+
+/// Decodes the operations of `batch`, whose first operation has the
+/// sequence number `sequence`.
+fn decode_batch(batch: &WriteBatch, sequence: u64) -> Result<Vec<Change>, Error> {
+    let mut decoder = Decoder {
+        sequence,
+        changes: Vec::with_capacity(batch.len()),
+        two_phase: false,
+    };
+    batch.iterate_cf(&mut decoder)?;
+    if decoder.two_phase {
+        return Err(Error::new(format!(
+            "Not implemented: the batch at sequence number {} is part of a two-phase commit, \
+             which change streams do not support",
+            sequence
+        )));
+    }
+    Ok(decoder.changes)
+}
@@ -115,6 +115,12 @@ pub type ColumnFamilyRef<'a> = Arc<BoundColumnFamily<'a>>;
 /// (`&ColumnFamily` and `BoundColumnFamily`)
 pub trait AsColumnFamilyRef {
     fn inner(&self) -> *mut ffi::rocksdb_column_family_handle_t;
+
+    /// Returns the ID of the column family, by which lock status, table
+    /// properties and [`Change`](crate::Change)s refer to it.
+    fn id(&self) -> u32 {
+        unsafe { ffi::rocksdb_ext_column_family_handle_get_id(self.inner()) }
+    }
 }
 
 impl AsColumnFamilyRef for ColumnFamily {
@@ -17,6 +17,8 @@ use libc::{c_char, c_int, c_uchar, c_void, size_t};
 use std::ffi::{CStr, CString};
 use std::slice;
 
+use crate::ffi;
+
 /// Decision about how to handle compacting an object
 ///
 /// This is returned by a compaction filter callback. Depending
@@ -31,6 +33,57 @@ pub enum Decision {
     Change(&'static [u8]),
 }
 
+/// Decision about how to handle compacting an entry, returned by
+/// [`CompactionFilter::filter_v2`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecisionV2 {
+    /// Keep the entry
+    Keep,
+    /// Remove the value or merge operand
+    Remove,
+    /// Change the value or merge operand
+    Change(Vec<u8>),
+    /// Remove the entry and every entry with a key before the given key,
+    /// without passing them to the filter.  A key at or before the key of
+    /// the entry is the same as [`DecisionV2::Keep`].
+    ///
+    /// The entries are removed even if they are part of a snapshot.  Older
+    /// versions of the skipped keys may become visible again, if they were
+    /// not compacted together with the newer ones.
+    RemoveAndSkipUntil(Vec<u8>),
+}
+
+impl From<Decision> for DecisionV2 {
+    fn from(decision: Decision) -> Self {
+        match decision {
+            Decision::Keep => DecisionV2::Keep,
+            Decision::Remove => DecisionV2::Remove,
+            Decision::Change(value) => DecisionV2::Change(value.to_vec()),
+        }
+    }
+}
+
+/// The type of an entry passed to [`CompactionFilter::filter_v2`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ValueType {
+    /// A value written with `put`
+    Value,
+    /// An operand written with `merge`
+    MergeOperand,
+    /// A reference to a value stored in a blob file
+    BlobIndex,
+}
+
+impl ValueType {
+    fn from_raw(value: c_int) -> Self {
+        match value {
+            0 => ValueType::Value,
+            1 => ValueType::MergeOperand,
+            _ => ValueType::BlobIndex,
+        }
+    }
+}
+
 /// CompactionFilter allows an application to modify/delete a key-value at
 /// the time of compaction.
 pub trait CompactionFilter {
@@ -57,7 +110,35 @@ pub trait CompactionFilter {
     /// be used by a single thread that is doing the compaction run, and this
     /// call does not need to be thread-safe.  However, multiple filters may be
     /// in existence and operating concurrently.
-    fn filter(&mut self, level: u32, key: &[u8], value: &[u8]) -> Decision;
+    ///
+    /// This method is only called by the default implementation of
+    /// [`filter_v2`](Self::filter_v2), and keeps every entry by default.
+    fn filter(&mut self, _level: u32, _key: &[u8], _value: &[u8]) -> Decision {
+        Decision::Keep
+    }
+
+    /// An extended version of [`filter`](Self::filter), called for values as
+    /// well as merge operands.  It can change values to owned buffers and
+    /// remove ranges of keys.  Filters implementing it don't need to
+    /// implement `filter`.
+    ///
+    /// It is not recommended to filter out or change merge operands in a
+    /// `TransactionDB`, which may then miss write conflicts.
+    ///
+    /// The default implementation passes values to `filter` and keeps merge
+    /// operands and blob indexes.
+    fn filter_v2(
+        &mut self,
+        level: u32,
+        key: &[u8],
+        value_type: ValueType,
+        value: &[u8],
+    ) -> DecisionV2 {
+        match value_type {
+            ValueType::Value => self.filter(level, key, value).into(),
+            ValueType::MergeOperand | ValueType::BlobIndex => DecisionV2::Keep,
+        }
+    }
 
     /// Returns a name that identifies this compaction filter.
     /// The name will be printed to LOG file on start up for diagnosis.
@@ -75,6 +156,20 @@ pub trait CompactionFilter {
 pub trait CompactionFilterFn: FnMut(u32, &[u8], &[u8]) -> Decision {}
 impl<F> CompactionFilterFn for F where F: FnMut(u32, &[u8], &[u8]) -> Decision + Send + 'static {}
 
+/// Function to filter compaction with, which sees merge operands and can
+/// return owned values.
+///
+/// See [`CompactionFilter::filter_v2`] and
+/// [Options::set_compaction_filter_v2][set_compaction_filter_v2] for more
+/// details.
+///
+/// [set_compaction_filter_v2]: ../struct.Options.html#method.set_compaction_filter_v2
+pub trait CompactionFilterV2Fn: FnMut(u32, &[u8], ValueType, &[u8]) -> DecisionV2 {}
+impl<F> CompactionFilterV2Fn for F where
+    F: FnMut(u32, &[u8], ValueType, &[u8]) -> DecisionV2 + Send + 'static
+{
+}
+
 pub struct CompactionFilterCallback<F>
 where
     F: CompactionFilterFn,
@@ -96,6 +191,42 @@ where
     }
 }
 
+pub(crate) struct CompactionFilterV2Callback<F>
+where
+    F: CompactionFilterV2Fn,
+{
+    pub name: CString,
+    pub filter_fn: F,
+}
+
+impl<F> CompactionFilter for CompactionFilterV2Callback<F>
+where
+    F: CompactionFilterV2Fn,
+{
+    fn name(&self) -> &CStr {
+        self.name.as_c_str()
+    }
+
+    fn filter_v2(
+        &mut self,
+        level: u32,
+        key: &[u8],
+        value_type: ValueType,
+        value: &[u8],
+    ) -> DecisionV2 {
+        (self.filter_fn)(level, key, value_type, value)
+    }
+}
+
+pub(crate) fn create_callbacks<F: CompactionFilter>(
+) -> ffi::rocksdb_ext_compactionfilter_callbacks_t {
+    ffi::rocksdb_ext_compactionfilter_callbacks_t {
+        destructor: Some(destructor_callback::<F>),
+        name: Some(name_callback::<F>),
+        filter: Some(filter_v2_callback::<F>),
+    }
+}
+
 pub unsafe extern "C" fn destructor_callback<F>(raw_cb: *mut c_void)
 where
     F: CompactionFilter,
@@ -143,6 +274,34 @@ where
     }
 }
 
+unsafe extern "C" fn filter_v2_callback<F: CompactionFilter>(
+    raw_cb: *mut c_void,
+    level: c_int,
+    key: *const c_char,
+    key_len: size_t,
+    value_type: c_int,
+    value: *const c_char,
+    value_len: size_t,
+    new_value: *mut ffi::rocksdb_ext_string_t,
+    skip_until: *mut ffi::rocksdb_ext_string_t,
+) -> c_int {
+    let cb = &mut *(raw_cb as *mut F);
+    let key = slice::from_raw_parts(key as *const u8, key_len);
+    let value = slice::from_raw_parts(value as *const u8, value_len);
+    match cb.filter_v2(level as u32, key, ValueType::from_raw(value_type), value) {
+        DecisionV2::Keep => ffi::rocksdb_ext_compactionfilter_keep as c_int,
+        DecisionV2::Remove => ffi::rocksdb_ext_compactionfilter_remove as c_int,
+        DecisionV2::Change(value) => {
+            ffi::rocksdb_ext_string_assign(new_value, value.as_ptr() as *const c_char, value.len());
+            ffi::rocksdb_ext_compactionfilter_change_value as c_int
+        }
+        DecisionV2::RemoveAndSkipUntil(key) => {
+            ffi::rocksdb_ext_string_assign(skip_until, key.as_ptr() as *const c_char, key.len());
+            ffi::rocksdb_ext_compactionfilter_remove_and_skip_until as c_int
+        }
+    }
+}
+
 #[cfg(test)]
 #[allow(unused_variables)]
 fn test_filter(level: u32, key: &[u8], value: &[u8]) -> Decision {
@@ -4,6 +4,7 @@ use libc::{self, c_char, c_void};
 
 use crate::{
     compaction_filter::{self, CompactionFilter},
+    event_listener::TableFileCreationReason,
     ffi,
 };
 
@@ -42,12 +43,25 @@ where
 }
 
 /// Context information of a compaction run
+///
+/// RocksDB 7.4 does not pass the column family name, the output level, the
+/// smallest and largest user keys or the compaction reason to compaction
+/// filter factories.  To tell column families apart, compare
+/// [`column_family_id`](Self::column_family_id) with
+/// [`AsColumnFamilyRef::id`](crate::AsColumnFamilyRef::id).
+#[derive(Debug, Copy, Clone)]
+#[non_exhaustive]
 pub struct CompactionFilterContext {
     /// Does this compaction run include all data files
     pub is_full_compaction: bool,
     /// Is this compaction requested by the client (true),
     /// or is it occurring as an automatic compaction process
     pub is_manual_compaction: bool,
+    /// The ID of the column family being compacted
+    pub column_family_id: u32,
+    /// Why the filtered table files are created.  Only compactions are
+    /// filtered, so this is [`TableFileCreationReason::Compaction`].
+    pub reason: TableFileCreationReason,
 }
 
 impl CompactionFilterContext {
@@ -55,10 +69,15 @@ impl CompactionFilterContext {
         let is_full_compaction = ffi::rocksdb_compactionfiltercontext_is_full_compaction(ptr) != 0;
         let is_manual_compaction =
             ffi::rocksdb_compactionfiltercontext_is_manual_compaction(ptr) != 0;
+        let column_family_id = ffi::rocksdb_ext_compactionfiltercontext_column_family_id(ptr);
+        let reason =
+            TableFileCreationReason::from_raw(ffi::rocksdb_ext_compactionfiltercontext_reason(ptr));
 
         Self {
             is_full_compaction,
             is_manual_compaction,
+            column_family_id,
+            reason,
         }
     }
 }
@@ -84,6 +103,25 @@ where
     )
 }
 
+pub(crate) fn create_callbacks<F: CompactionFilterFactory>(
+) -> ffi::rocksdb_ext_compactionfilterfactory_callbacks_t {
+    ffi::rocksdb_ext_compactionfilterfactory_callbacks_t {
+        destructor: Some(destructor_callback::<F>),
+        name: Some(name_callback::<F>),
+        create_filter: Some(create_filter_callback::<F>),
+        filter: compaction_filter::create_callbacks::<F::Filter>(),
+    }
+}
+
+unsafe extern "C" fn create_filter_callback<F: CompactionFilterFactory>(
+    raw_self: *mut c_void,
+    context: *mut ffi::rocksdb_compactionfiltercontext_t,
+) -> *mut c_void {
+    let self_ = &mut *(raw_self as *mut F);
+    let filter = self_.create(CompactionFilterContext::from_raw(context));
+    Box::into_raw(Box::new(filter)).cast::<c_void>()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
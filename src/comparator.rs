@@ -13,13 +13,254 @@
 // limitations under the License.
 //
 
-use libc::{c_char, c_int, c_void, size_t};
+use libc::{c_char, c_int, c_uchar, c_void, size_t};
 use std::cmp::Ordering;
-use std::ffi::CString;
+use std::convert::TryInto;
+use std::ffi::{CStr, CString};
 use std::slice;
 
+use crate::ffi;
+
 pub type CompareFn = fn(&[u8], &[u8]) -> Ordering;
 
+/// Defines the order of keys in a column family.
+///
+/// Unlike [`CompareFn`], a comparator can carry state such as a collation
+/// table, and can shorten the keys RocksDB stores in index blocks.
+///
+/// See [`Options::set_comparator_impl`](crate::Options::set_comparator_impl).
+pub trait Comparator: Send + Sync {
+    /// Returns a name that identifies the order.  Opening a database with a
+    /// comparator of a different name fails, so the name must change
+    /// whenever the order does.
+    fn name(&self) -> &CStr;
+
+    /// Compares two keys.  With timestamps, versions of the same key are
+    /// ordered from newer to older.
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering;
+
+    /// Returns a key that is at least `start` and less than `limit`, if it
+    /// is shorter than `start`.  Index blocks store such separators instead
+    /// of the last key of each block.
+    ///
+    /// The default implementation returns `None`, keeping `start`.
+    fn find_shortest_separator(&self, _start: &[u8], _limit: &[u8]) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Returns a key that is at least `key`, if it is shorter than `key`.
+    ///
+    /// The default implementation returns `None`, keeping `key`.
+    fn find_short_successor(&self, _key: &[u8]) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Returns the size of the timestamp at the end of every key, or 0 if
+    /// keys have no timestamps.  See
+    /// [`Options::set_comparator_with_ts`](crate::Options::set_comparator_with_ts).
+    fn timestamp_size(&self) -> usize {
+        0
+    }
+
+    /// Compares two timestamps, from older to newer.  Only called if
+    /// [`timestamp_size`](Self::timestamp_size) is not 0.
+    fn compare_timestamp(&self, _a: &[u8], _b: &[u8]) -> Ordering {
+        Ordering::Equal
+    }
+
+    /// Compares two keys without their timestamps.  The default
+    /// implementation calls [`compare`](Self::compare), and must be replaced
+    /// if [`timestamp_size`](Self::timestamp_size) is not 0.
+    fn compare_without_timestamp(&self, a: &[u8], b: &[u8]) -> Ordering {
+        self.compare(a, b)
+    }
+}
+
+/// Orders keys byte-wise in reverse, like RocksDB's
+/// `ReverseBytewiseComparator`, with which it is interchangeable.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ReverseBytewiseComparator;
+
+impl Comparator for ReverseBytewiseComparator {
+    fn name(&self) -> &CStr {
+        CStr::from_bytes_with_nul(b"rocksdb.ReverseBytewiseComparator\0").unwrap()
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        b.cmp(a)
+    }
+
+    fn find_shortest_separator(&self, start: &[u8], limit: &[u8]) -> Option<Vec<u8>> {
+        // Cut `start` right after the first byte that is greater than in
+        // `limit`, unless one key is a prefix of the other.
+        let diff_index = start.iter().zip(limit).take_while(|(a, b)| a == b).count();
+        if diff_index < start.len().min(limit.len())
+            && start[diff_index] > limit[diff_index]
+            && diff_index < start.len() - 1
+        {
+            Some(start[..=diff_index].to_vec())
+        } else {
+            None
+        }
+    }
+}
+
+/// Orders keys byte-wise, followed by an 8 byte timestamp encoded by
+/// `u64::to_le_bytes`.  Matches RocksDB's `BytewiseComparatorWithU64Ts` and
+/// [`Options::set_comparator_with_u64_ts`](crate::Options::set_comparator_with_u64_ts),
+/// for use where a [`Comparator`] is needed.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct BytewiseComparatorWithU64Ts;
+
+impl Comparator for BytewiseComparatorWithU64Ts {
+    fn name(&self) -> &CStr {
+        CStr::from_bytes_with_nul(b"leveldb.BytewiseComparator.u64ts\0").unwrap()
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        compare_with_ts(a, b, self.timestamp_size(), <[u8]>::cmp, compare_u64_ts)
+    }
+
+    fn timestamp_size(&self) -> usize {
+        std::mem::size_of::<u64>()
+    }
+
+    fn compare_timestamp(&self, a: &[u8], b: &[u8]) -> Ordering {
+        compare_u64_ts(a, b)
+    }
+
+    fn compare_without_timestamp(&self, a: &[u8], b: &[u8]) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+/// Owns a comparator set on options, which must outlive every database
+/// using it.
+pub(crate) struct ComparatorWrapper {
+    pub(crate) inner: *mut ffi::rocksdb_ext_comparator_t,
+}
+
+unsafe impl Send for ComparatorWrapper {}
+unsafe impl Sync for ComparatorWrapper {}
+
+impl ComparatorWrapper {
+    pub(crate) fn new(comparator: Box<dyn Comparator>) -> Self {
+        let timestamp_size = comparator.timestamp_size();
+        let callbacks = ffi::rocksdb_ext_comparator_callbacks_t {
+            destructor: Some(impl_destructor_callback),
+            name: Some(impl_name_callback),
+            compare: Some(impl_compare_callback),
+            find_shortest_separator: Some(impl_find_shortest_separator_callback),
+            find_short_successor: Some(impl_find_short_successor_callback),
+            compare_timestamp: Some(impl_compare_timestamp_callback),
+            compare_without_timestamp: Some(impl_compare_without_timestamp_callback),
+        };
+        let inner = unsafe {
+            ffi::rocksdb_ext_comparator_create(
+                Box::into_raw(Box::new(comparator)).cast::<c_void>(),
+                std::ptr::addr_of!(callbacks),
+                timestamp_size,
+            )
+        };
+        Self { inner }
+    }
+}
+
+impl Drop for ComparatorWrapper {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::rocksdb_ext_comparator_destroy(self.inner);
+        }
+    }
+}
+
+unsafe fn comparator_from_state<'a>(state: *mut c_void) -> &'a dyn Comparator {
+    &**(state as *const Box<dyn Comparator>)
+}
+
+unsafe extern "C" fn impl_destructor_callback(state: *mut c_void) {
+    drop(Box::from_raw(state as *mut Box<dyn Comparator>));
+}
+
+unsafe extern "C" fn impl_name_callback(state: *mut c_void) -> *const c_char {
+    comparator_from_state(state).name().as_ptr()
+}
+
+unsafe extern "C" fn impl_compare_callback(
+    state: *mut c_void,
+    a: *const c_char,
+    a_len: size_t,
+    b: *const c_char,
+    b_len: size_t,
+) -> c_int {
+    let a = slice::from_raw_parts(a as *const u8, a_len);
+    let b = slice::from_raw_parts(b as *const u8, b_len);
+    ordering_to_int(comparator_from_state(state).compare(a, b))
+}
+
+unsafe extern "C" fn impl_find_shortest_separator_callback(
+    state: *mut c_void,
+    start: *const c_char,
+    start_len: size_t,
+    limit: *const c_char,
+    limit_len: size_t,
+    result: *mut ffi::rocksdb_ext_string_t,
+) -> c_uchar {
+    let start = slice::from_raw_parts(start as *const u8, start_len);
+    let limit = slice::from_raw_parts(limit as *const u8, limit_len);
+    assign_result(
+        comparator_from_state(state).find_shortest_separator(start, limit),
+        result,
+    )
+}
+
+unsafe extern "C" fn impl_find_short_successor_callback(
+    state: *mut c_void,
+    key: *const c_char,
+    key_len: size_t,
+    result: *mut ffi::rocksdb_ext_string_t,
+) -> c_uchar {
+    let key = slice::from_raw_parts(key as *const u8, key_len);
+    assign_result(
+        comparator_from_state(state).find_short_successor(key),
+        result,
+    )
+}
+
+unsafe fn assign_result(key: Option<Vec<u8>>, result: *mut ffi::rocksdb_ext_string_t) -> c_uchar {
+    match key {
+        Some(key) => {
+            ffi::rocksdb_ext_string_assign(result, key.as_ptr() as *const c_char, key.len());
+            1
+        }
+        None => 0,
+    }
+}
+
+unsafe extern "C" fn impl_compare_timestamp_callback(
+    state: *mut c_void,
+    a: *const c_char,
+    a_len: size_t,
+    b: *const c_char,
+    b_len: size_t,
+) -> c_int {
+    let a = slice::from_raw_parts(a as *const u8, a_len);
+    let b = slice::from_raw_parts(b as *const u8, b_len);
+    ordering_to_int(comparator_from_state(state).compare_timestamp(a, b))
+}
+
+unsafe extern "C" fn impl_compare_without_timestamp_callback(
+    state: *mut c_void,
+    a: *const c_char,
+    a_len: size_t,
+    b: *const c_char,
+    b_len: size_t,
+) -> c_int {
+    let a = slice::from_raw_parts(a as *const u8, a_len);
+    let b = slice::from_raw_parts(b as *const u8, b_len);
+    ordering_to_int(comparator_from_state(state).compare_without_timestamp(a, b))
+}
+
 pub struct ComparatorCallback {
     pub name: CString,
     pub f: CompareFn,
@@ -51,3 +292,129 @@ pub unsafe extern "C" fn compare_callback(
         Ordering::Greater => 1,
     }
 }
+
+pub struct ComparatorWithTsCallback {
+    pub name: CString,
+    pub timestamp_size: usize,
+    pub f: CompareFn,
+    pub ts_f: CompareFn,
+}
+
+/// Splits a key of a column family with user-defined timestamps into the
+/// key without its timestamp and the timestamp of `timestamp_size` bytes.
+/// Returns `None` if the key is too short to hold a timestamp.
+///
+/// Write batches store the timestamp at the end of the key, so
+/// [`WriteBatchIterator`](crate::WriteBatchIterator) and
+/// [`WriteBatchIteratorCf`](crate::WriteBatchIteratorCf) receive both at
+/// once.
+///
+/// ```
+/// use rocksdb::split_timestamp;
+///
+/// let key = [b"key".as_ref(), &7u64.to_le_bytes()].concat();
+/// assert_eq!(
+///     split_timestamp(&key, 8),
+///     Some((b"key".as_ref(), 7u64.to_le_bytes().as_ref()))
+/// );
+/// assert_eq!(split_timestamp(b"key", 8), None);
+/// ```
+pub fn split_timestamp(key: &[u8], timestamp_size: usize) -> Option<(&[u8], &[u8])> {
+    key.len()
+        .checked_sub(timestamp_size)
+        .map(|len| key.split_at(len))
+}
+
+/// Orders keys without their timestamps by `f` first, then newer timestamps
+/// before older ones by `ts_f`, as RocksDB expects.
+///
+/// Comparators run in callbacks from RocksDB, where a panic aborts the
+/// process, so keys too short for a timestamp are compared byte-wise.
+pub(crate) fn compare_with_ts(
+    a: &[u8],
+    b: &[u8],
+    timestamp_size: usize,
+    f: CompareFn,
+    ts_f: CompareFn,
+) -> Ordering {
+    match (
+        split_timestamp(a, timestamp_size),
+        split_timestamp(b, timestamp_size),
+    ) {
+        (Some((a, a_ts)), Some((b, b_ts))) => f(a, b).then_with(|| ts_f(b_ts, a_ts)),
+        _ => a.cmp(b),
+    }
+}
+
+pub unsafe extern "C" fn destructor_with_ts_callback(raw_cb: *mut c_void) {
+    drop(Box::from_raw(raw_cb as *mut ComparatorWithTsCallback));
+}
+
+pub unsafe extern "C" fn name_with_ts_callback(raw_cb: *mut c_void) -> *const c_char {
+    let cb = &*(raw_cb as *const ComparatorWithTsCallback);
+    cb.name.as_ptr()
+}
+
+pub unsafe extern "C" fn compare_with_ts_callback(
+    raw_cb: *mut c_void,
+    a_raw: *const c_char,
+    a_len: size_t,
+    b_raw: *const c_char,
+    b_len: size_t,
+) -> c_int {
+    let cb = &*(raw_cb as *const ComparatorWithTsCallback);
+    let a = slice::from_raw_parts(a_raw as *const u8, a_len);
+    let b = slice::from_raw_parts(b_raw as *const u8, b_len);
+    ordering_to_int(compare_with_ts(a, b, cb.timestamp_size, cb.f, cb.ts_f))
+}
+
+pub unsafe extern "C" fn compare_ts_callback(
+    raw_cb: *mut c_void,
+    a_ts: *const c_char,
+    a_ts_len: size_t,
+    b_ts: *const c_char,
+    b_ts_len: size_t,
+) -> c_int {
+    let cb = &*(raw_cb as *const ComparatorWithTsCallback);
+    let a_ts = slice::from_raw_parts(a_ts as *const u8, a_ts_len);
+    let b_ts = slice::from_raw_parts(b_ts as *const u8, b_ts_len);
+    ordering_to_int((cb.ts_f)(a_ts, b_ts))
+}
+
+pub unsafe extern "C" fn compare_without_ts_callback(
+    raw_cb: *mut c_void,
+    a_raw: *const c_char,
+    a_len: size_t,
+    a_has_ts: c_uchar,
+    b_raw: *const c_char,
+    b_len: size_t,
+    b_has_ts: c_uchar,
+) -> c_int {
+    let cb = &*(raw_cb as *const ComparatorWithTsCallback);
+    let mut a = slice::from_raw_parts(a_raw as *const u8, a_len);
+    let mut b = slice::from_raw_parts(b_raw as *const u8, b_len);
+    if a_has_ts != 0 {
+        a = split_timestamp(a, cb.timestamp_size).map_or(a, |(a, _)| a);
+    }
+    if b_has_ts != 0 {
+        b = split_timestamp(b, cb.timestamp_size).map_or(b, |(b, _)| b);
+    }
+    ordering_to_int((cb.f)(a, b))
+}
+
+/// Compares 8 byte little-endian timestamps, as written by `u64::to_le_bytes`.
+/// Timestamps of another size are compared byte-wise.
+pub(crate) fn compare_u64_ts(a: &[u8], b: &[u8]) -> Ordering {
+    match (a.try_into(), b.try_into()) {
+        (Ok(a), Ok(b)) => u64::from_le_bytes(a).cmp(&u64::from_le_bytes(b)),
+        _ => a.cmp(b),
+    }
+}
+
+fn ordering_to_int(ordering: Ordering) -> c_int {
+    match ordering {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+    }
+}
@@ -20,10 +20,11 @@ use crate::{
     db_options::OptionsMustOutliveDB,
     ffi,
     ffi_util::{from_cstr, opt_bytes_to_ptr, raw_data, to_cpath, CStrLike},
-    ColumnFamily, ColumnFamilyDescriptor, CompactOptions, DBIteratorWithThreadMode,
+    table_properties::{self, TablePropertiesCollection},
+    ChangeStream, ColumnFamily, ColumnFamilyDescriptor, CompactOptions, DBIteratorWithThreadMode,
     DBPinnableSlice, DBRawIteratorWithThreadMode, DBWALIterator, Direction, Error, FlushOptions,
     IngestExternalFileOptions, IteratorMode, Options, ReadOptions, SnapshotWithThreadMode,
-    WriteBatch, WriteOptions, DEFAULT_COLUMN_FAMILY_NAME,
+    WriteBatch, WriteBatchWithIndex, WriteOptions, DEFAULT_COLUMN_FAMILY_NAME,
 };
 
 use libc::{self, c_char, c_int, c_uchar, c_void, size_t};
@@ -32,6 +33,7 @@ use std::ffi::{CStr, CString};
 use std::fmt;
 use std::fs;
 use std::iter;
+use std::ops::Range;
 use std::path::Path;
 use std::path::PathBuf;
 use std::ptr;
@@ -601,11 +603,13 @@ impl<T: ThreadMode> DBWithThreadMode<T> {
 
         let cpath = to_cpath(&path)?;
 
-        if let Err(e) = fs::create_dir_all(&path) {
-            return Err(Error::new(format!(
-                "Failed to create RocksDB directory: `{:?}`.",
-                e
-            )));
+        if opts.uses_local_file_system() {
+            if let Err(e) = fs::create_dir_all(&path) {
+                return Err(Error::new(format!(
+                    "Failed to create RocksDB directory: `{:?}`.",
+                    e
+                )));
+            }
         }
 
         let db: *mut ffi::rocksdb_t;
@@ -821,6 +825,26 @@ impl<T: ThreadMode> DBWithThreadMode<T> {
         wo.disable_wal(true);
         self.write_opt(batch, &wo)
     }
+
+    /// Writes the operations of a [`WriteBatchWithIndex`] atomically.
+    pub fn write_with_index_opt(
+        &self,
+        batch: &WriteBatchWithIndex,
+        writeopts: &WriteOptions,
+    ) -> Result<(), Error> {
+        unsafe {
+            ffi_try!(ffi::rocksdb_write_writebatch_wi(
+                self.inner.inner(),
+                writeopts.inner,
+                batch.inner
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn write_with_index(&self, batch: &WriteBatchWithIndex) -> Result<(), Error> {
+        self.write_with_index_opt(batch, &WriteOptions::default())
+    }
 }
 
 /// Common methods of `DBWithThreadMode` and `OptimisticTransactionDB`.
@@ -1550,6 +1574,122 @@ impl<T: ThreadMode, D: DBInner> DBCommon<T, D> {
         }
     }
 
+    /// Writes `value` for `key` at timestamp `ts`, in a column family with
+    /// user-defined timestamps.  See
+    /// [`Options::set_comparator_with_ts`](crate::Options::set_comparator_with_ts).
+    pub fn put_with_ts_opt<K, S, V>(
+        &self,
+        key: K,
+        ts: S,
+        value: V,
+        writeopts: &WriteOptions,
+    ) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+        S: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let key = key.as_ref();
+        let ts = ts.as_ref();
+        let value = value.as_ref();
+
+        unsafe {
+            ffi_try!(ffi::rocksdb_put_with_ts(
+                self.inner.inner(),
+                writeopts.inner,
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                ts.as_ptr() as *const c_char,
+                ts.len() as size_t,
+                value.as_ptr() as *const c_char,
+                value.len() as size_t,
+            ));
+            Ok(())
+        }
+    }
+
+    pub fn put_cf_with_ts_opt<K, S, V>(
+        &self,
+        cf: &impl AsColumnFamilyRef,
+        key: K,
+        ts: S,
+        value: V,
+        writeopts: &WriteOptions,
+    ) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+        S: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let key = key.as_ref();
+        let ts = ts.as_ref();
+        let value = value.as_ref();
+
+        unsafe {
+            ffi_try!(ffi::rocksdb_put_cf_with_ts(
+                self.inner.inner(),
+                writeopts.inner,
+                cf.inner(),
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                ts.as_ptr() as *const c_char,
+                ts.len() as size_t,
+                value.as_ptr() as *const c_char,
+                value.len() as size_t,
+            ));
+            Ok(())
+        }
+    }
+
+    /// Deletes `key` at timestamp `ts`, in a column family with
+    /// user-defined timestamps.  Reads as of an older timestamp still see
+    /// the previous value.
+    pub fn delete_with_ts_opt<K: AsRef<[u8]>, S: AsRef<[u8]>>(
+        &self,
+        key: K,
+        ts: S,
+        writeopts: &WriteOptions,
+    ) -> Result<(), Error> {
+        let key = key.as_ref();
+        let ts = ts.as_ref();
+
+        unsafe {
+            ffi_try!(ffi::rocksdb_delete_with_ts(
+                self.inner.inner(),
+                writeopts.inner,
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                ts.as_ptr() as *const c_char,
+                ts.len() as size_t,
+            ));
+            Ok(())
+        }
+    }
+
+    pub fn delete_cf_with_ts_opt<K: AsRef<[u8]>, S: AsRef<[u8]>>(
+        &self,
+        cf: &impl AsColumnFamilyRef,
+        key: K,
+        ts: S,
+        writeopts: &WriteOptions,
+    ) -> Result<(), Error> {
+        let key = key.as_ref();
+        let ts = ts.as_ref();
+
+        unsafe {
+            ffi_try!(ffi::rocksdb_delete_cf_with_ts(
+                self.inner.inner(),
+                writeopts.inner,
+                cf.inner(),
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                ts.as_ptr() as *const c_char,
+                ts.len() as size_t,
+            ));
+            Ok(())
+        }
+    }
+
     pub fn put<K, V>(&self, key: K, value: V) -> Result<(), Error>
     where
         K: AsRef<[u8]>,
@@ -1594,6 +1734,87 @@ impl<T: ThreadMode, D: DBInner> DBCommon<T, D> {
         self.delete_cf_opt(cf, key.as_ref(), &WriteOptions::default())
     }
 
+    pub fn put_with_ts<K, S, V>(&self, key: K, ts: S, value: V) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+        S: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        self.put_with_ts_opt(key, ts, value, &WriteOptions::default())
+    }
+
+    pub fn put_cf_with_ts<K, S, V>(
+        &self,
+        cf: &impl AsColumnFamilyRef,
+        key: K,
+        ts: S,
+        value: V,
+    ) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+        S: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        self.put_cf_with_ts_opt(cf, key, ts, value, &WriteOptions::default())
+    }
+
+    pub fn delete_with_ts<K: AsRef<[u8]>, S: AsRef<[u8]>>(
+        &self,
+        key: K,
+        ts: S,
+    ) -> Result<(), Error> {
+        self.delete_with_ts_opt(key, ts, &WriteOptions::default())
+    }
+
+    pub fn delete_cf_with_ts<K: AsRef<[u8]>, S: AsRef<[u8]>>(
+        &self,
+        cf: &impl AsColumnFamilyRef,
+        key: K,
+        ts: S,
+    ) -> Result<(), Error> {
+        self.delete_cf_with_ts_opt(cf, key, ts, &WriteOptions::default())
+    }
+
+    /// Raises the timestamp below which compactions may drop the versions
+    /// of keys that are hidden by newer versions.  Reads as of an older
+    /// timestamp may then miss versions that were dropped.
+    ///
+    /// The new timestamp must not be older than the current one, see
+    /// [`get_full_history_ts_low`](Self::get_full_history_ts_low).
+    pub fn increase_full_history_ts_low<S: AsRef<[u8]>>(
+        &self,
+        cf: &impl AsColumnFamilyRef,
+        ts: S,
+    ) -> Result<(), Error> {
+        let ts = ts.as_ref();
+        unsafe {
+            ffi_try!(ffi::rocksdb_increase_full_history_ts_low(
+                self.inner.inner(),
+                cf.inner(),
+                ts.as_ptr() as *const c_char,
+                ts.len() as size_t,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the timestamp set by
+    /// [`increase_full_history_ts_low`](Self::increase_full_history_ts_low),
+    /// which is empty if it was never set.
+    pub fn get_full_history_ts_low(&self, cf: &impl AsColumnFamilyRef) -> Result<Vec<u8>, Error> {
+        unsafe {
+            let mut ts_len: size_t = 0;
+            let ts = ffi_try!(ffi::rocksdb_get_full_history_ts_low(
+                self.inner.inner(),
+                cf.inner(),
+                ptr::addr_of_mut!(ts_len),
+            ));
+            let result = raw_data(ts, ts_len).unwrap_or_default();
+            ffi::rocksdb_free(ts as *mut c_void);
+            Ok(result)
+        }
+    }
+
     /// Runs a manual compaction on the Range of keys given. This is not likely to be needed for typical usage.
     pub fn compact_range<S: AsRef<[u8]>, E: AsRef<[u8]>>(&self, start: Option<S>, end: Option<E>) {
         unsafe {
@@ -1853,6 +2074,19 @@ impl<T: ThreadMode, D: DBInner> DBCommon<T, D> {
         }
     }
 
+    /// Returns the write operations applied after the sequence number
+    /// `resume_after`, decoded from the WAL.  See [`ChangeStream`].
+    pub fn change_stream(&self, resume_after: u64) -> Result<ChangeStream, Error> {
+        let iter = self.get_updates_since(resume_after)?;
+        let oldest_sequence =
+            unsafe { ffi_try!(ffi::rocksdb_ext_get_oldest_wal_sequence(self.inner.inner())) };
+        Ok(ChangeStream::new(
+            iter,
+            Some(oldest_sequence).filter(|&sequence| sequence != 0),
+            resume_after,
+        ))
+    }
+
     /// Tries to catch up with the primary by reading as much as possible from the
     /// log files.
     pub fn try_catch_up_with_primary(&self) -> Result<(), Error> {
@@ -1919,7 +2153,7 @@ impl<T: ThreadMode, D: DBInner> DBCommon<T, D> {
         cpaths: &[*const c_char],
     ) -> Result<(), Error> {
         unsafe {
-            ffi_try!(ffi::rocksdb_ingest_external_file(
+            ffi_try!(ffi::rocksdb_ext_ingest_external_file(
                 self.inner.inner(),
                 cpaths.as_ptr(),
                 paths_v.len(),
@@ -1937,7 +2171,7 @@ impl<T: ThreadMode, D: DBInner> DBCommon<T, D> {
         cpaths: &[*const c_char],
     ) -> Result<(), Error> {
         unsafe {
-            ffi_try!(ffi::rocksdb_ingest_external_file_cf(
+            ffi_try!(ffi::rocksdb_ext_ingest_external_file_cf(
                 self.inner.inner(),
                 cf.inner(),
                 cpaths.as_ptr(),
@@ -1997,6 +2231,86 @@ impl<T: ThreadMode, D: DBInner> DBCommon<T, D> {
         }
     }
 
+    /// Returns the properties of all table files of the default column
+    /// family, by file path.
+    pub fn get_properties_of_all_tables(&self) -> Result<TablePropertiesCollection, Error> {
+        self.get_properties_of_all_tables_raw(ptr::null_mut())
+    }
+
+    /// Returns the properties of all table files of a column family, by file
+    /// path.
+    pub fn get_properties_of_all_tables_cf(
+        &self,
+        cf: &impl AsColumnFamilyRef,
+    ) -> Result<TablePropertiesCollection, Error> {
+        self.get_properties_of_all_tables_raw(cf.inner())
+    }
+
+    fn get_properties_of_all_tables_raw(
+        &self,
+        cf: *mut ffi::rocksdb_column_family_handle_t,
+    ) -> Result<TablePropertiesCollection, Error> {
+        unsafe {
+            let coll = ffi_try!(ffi::rocksdb_ext_get_properties_of_all_tables_cf(
+                self.inner.inner(),
+                cf
+            ));
+            Ok(table_properties::collection_from_raw(coll))
+        }
+    }
+
+    /// Returns the properties of the table files of the default column
+    /// family that overlap any of `ranges`, by file path.
+    pub fn get_properties_of_tables_in_range<K: AsRef<[u8]>>(
+        &self,
+        ranges: &[Range<K>],
+    ) -> Result<TablePropertiesCollection, Error> {
+        self.get_properties_of_tables_in_range_raw(ptr::null_mut(), ranges)
+    }
+
+    /// Returns the properties of the table files of a column family that
+    /// overlap any of `ranges`, by file path.
+    pub fn get_properties_of_tables_in_range_cf<K: AsRef<[u8]>>(
+        &self,
+        cf: &impl AsColumnFamilyRef,
+        ranges: &[Range<K>],
+    ) -> Result<TablePropertiesCollection, Error> {
+        self.get_properties_of_tables_in_range_raw(cf.inner(), ranges)
+    }
+
+    fn get_properties_of_tables_in_range_raw<K: AsRef<[u8]>>(
+        &self,
+        cf: *mut ffi::rocksdb_column_family_handle_t,
+        ranges: &[Range<K>],
+    ) -> Result<TablePropertiesCollection, Error> {
+        let (start_keys, start_key_lens): (Vec<_>, Vec<_>) = ranges
+            .iter()
+            .map(|range| {
+                let key = range.start.as_ref();
+                (key.as_ptr() as *const c_char, key.len() as size_t)
+            })
+            .unzip();
+        let (limit_keys, limit_key_lens): (Vec<_>, Vec<_>) = ranges
+            .iter()
+            .map(|range| {
+                let key = range.end.as_ref();
+                (key.as_ptr() as *const c_char, key.len() as size_t)
+            })
+            .unzip();
+        unsafe {
+            let coll = ffi_try!(ffi::rocksdb_ext_get_properties_of_tables_in_range_cf(
+                self.inner.inner(),
+                cf,
+                ranges.len(),
+                start_keys.as_ptr(),
+                start_key_lens.as_ptr(),
+                limit_keys.as_ptr(),
+                limit_key_lens.as_ptr()
+            ));
+            Ok(table_properties::collection_from_raw(coll))
+        }
+    }
+
     /// Delete sst files whose keys are entirely in the given range.
     ///
     /// Could leave some keys in the range which are in files which are not
@@ -17,13 +17,14 @@ use crate::{
     ffi, Error, ReadOptions, WriteBatch,
 };
 use libc::{c_char, c_uchar, size_t};
-use std::{marker::PhantomData, slice};
+use std::{marker::PhantomData, ptr, slice};
 
 /// A type alias to keep compatibility. See [`DBRawIteratorWithThreadMode`] for details
 pub type DBRawIterator<'a> = DBRawIteratorWithThreadMode<'a, DB>;
 
 /// An iterator over a database or column family, with specifiable
-/// ranges and direction.
+/// ranges and direction.  [`SstFileReader`](crate::SstFileReader) returns
+/// the same iterator over the contents of an SST file.
 ///
 /// This iterator is different to the standard ``DBIteratorWithThreadMode`` as it aims Into
 /// replicate the underlying iterator API within RocksDB itself. This should
@@ -70,7 +71,7 @@ pub type DBRawIterator<'a> = DBRawIteratorWithThreadMode<'a, DB>;
 /// }
 /// let _ = DB::destroy(&Options::default(), path);
 /// ```
-pub struct DBRawIteratorWithThreadMode<'a, D: DBAccess> {
+pub struct DBRawIteratorWithThreadMode<'a, D> {
     inner: std::ptr::NonNull<ffi::rocksdb_iterator_t>,
 
     /// When iterate_lower_bound or iterate_upper_bound are set, the inner
@@ -100,8 +101,10 @@ impl<'a, D: DBAccess> DBRawIteratorWithThreadMode<'a, D> {
         let inner = unsafe { db.create_iterator_cf(cf_handle, &readopts) };
         Self::from_inner(inner, readopts)
     }
+}
 
-    fn from_inner(inner: *mut ffi::rocksdb_iterator_t, readopts: ReadOptions) -> Self {
+impl<D> DBRawIteratorWithThreadMode<'_, D> {
+    pub(crate) fn from_inner(inner: *mut ffi::rocksdb_iterator_t, readopts: ReadOptions) -> Self {
         // This unwrap will never fail since rocksdb_create_iterator and
         // rocksdb_create_iterator_cf functions always return non-null. They
         // use new and deference the result so any nulls would end up with SIGSEGV
@@ -114,6 +117,15 @@ impl<'a, D: DBAccess> DBRawIteratorWithThreadMode<'a, D> {
         }
     }
 
+    /// Releases the inner iterator and the read options it refers to, which
+    /// must outlive it.
+    pub(crate) fn into_raw_parts(self) -> (*mut ffi::rocksdb_iterator_t, ReadOptions) {
+        let this = std::mem::ManuallyDrop::new(self);
+        // `this` is never dropped, so the read options are moved out of it.
+        let readopts = unsafe { std::ptr::read(&this._readopts) };
+        (this.inner.as_ptr(), readopts)
+    }
+
     /// Returns `true` if the iterator is valid. An iterator is invalidated when
     /// it reaches the end of its defined range, or when it encounters an error.
     ///
@@ -328,6 +340,23 @@ impl<'a, D: DBAccess> DBRawIteratorWithThreadMode<'a, D> {
         }
     }
 
+    /// Returns a slice of the timestamp of the current entry, which is empty
+    /// unless the comparator of the column family has timestamps.  See
+    /// [`Options::set_comparator_with_ts`](crate::Options::set_comparator_with_ts).
+    pub fn timestamp(&self) -> Option<&[u8]> {
+        if self.valid() {
+            // Safety Note: see `key_impl`.
+            unsafe {
+                let mut ts_len: size_t = 0;
+                let ts_ptr =
+                    ffi::rocksdb_iter_timestamp(self.inner.as_ptr(), ptr::addr_of_mut!(ts_len));
+                Some(slice::from_raw_parts(ts_ptr as *const c_uchar, ts_len))
+            }
+        } else {
+            None
+        }
+    }
+
     /// Returns a slice of the current key; assumes the iterator is valid.
     fn key_impl(&self) -> &[u8] {
         // Safety Note: This is safe as all methods that may invalidate the buffer returned
@@ -353,7 +382,7 @@ impl<'a, D: DBAccess> DBRawIteratorWithThreadMode<'a, D> {
     }
 }
 
-impl<'a, D: DBAccess> Drop for DBRawIteratorWithThreadMode<'a, D> {
+impl<D> Drop for DBRawIteratorWithThreadMode<'_, D> {
     fn drop(&mut self) {
         unsafe {
             ffi::rocksdb_iter_destroy(self.inner.as_ptr());
@@ -361,8 +390,8 @@ impl<'a, D: DBAccess> Drop for DBRawIteratorWithThreadMode<'a, D> {
     }
 }
 
-unsafe impl<'a, D: DBAccess> Send for DBRawIteratorWithThreadMode<'a, D> {}
-unsafe impl<'a, D: DBAccess> Sync for DBRawIteratorWithThreadMode<'a, D> {}
+unsafe impl<D> Send for DBRawIteratorWithThreadMode<'_, D> {}
+unsafe impl<D> Sync for DBRawIteratorWithThreadMode<'_, D> {}
 
 /// A type alias to keep compatibility. See [`DBIteratorWithThreadMode`] for details
 pub type DBIterator<'a> = DBIteratorWithThreadMode<'a, DB>;
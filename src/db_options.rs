@@ -12,23 +12,35 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::ffi::CStr;
+use std::ffi::{CStr, CString};
 use std::path::Path;
 use std::sync::Arc;
 
 use libc::{self, c_char, c_double, c_int, c_uchar, c_uint, c_void, size_t};
 
 use crate::{
-    compaction_filter::{self, CompactionFilterCallback, CompactionFilterFn},
+    compaction_filter::{
+        self, CompactionFilter, CompactionFilterCallback, CompactionFilterFn,
+        CompactionFilterV2Callback, CompactionFilterV2Fn,
+    },
     compaction_filter_factory::{self, CompactionFilterFactory},
-    comparator::{self, ComparatorCallback, CompareFn},
+    comparator::{
+        self, Comparator, ComparatorCallback, ComparatorWithTsCallback, ComparatorWrapper,
+        CompareFn,
+    },
     db::DBAccess,
+    event_listener::{self, EventListener},
     ffi,
     ffi_util::{to_cpath, CStrLike},
+    file_system::{create_env, Clock, FileSystem, SystemClock},
     merge_operator::{
-        self, full_merge_callback, partial_merge_callback, MergeFn, MergeOperatorCallback,
+        self, full_merge_callback, partial_merge_callback, MergeFn, MergeOperator,
+        MergeOperatorCallback,
     },
     slice_transform::SliceTransform,
+    statistics::Statistics,
+    table_factory::{self, TableFactory},
+    table_properties_collector::{self, TablePropertiesCollectorFactory},
     Error, SnapshotWithThreadMode,
 };
 
@@ -95,6 +107,8 @@ pub struct Env(Arc<EnvWrapper>);
 
 pub(crate) struct EnvWrapper {
     inner: *mut ffi::rocksdb_env_t,
+    /// Whether files are stored by a Rust [`FileSystem`].
+    custom_file_system: bool,
 }
 
 impl Drop for EnvWrapper {
@@ -112,7 +126,10 @@ impl Env {
         if env.is_null() {
             Err(Error::new("Could not create mem env".to_owned()))
         } else {
-            Ok(Self(Arc::new(EnvWrapper { inner: env })))
+            Ok(Self(Arc::new(EnvWrapper {
+                inner: env,
+                custom_file_system: false,
+            })))
         }
     }
 
@@ -123,7 +140,38 @@ impl Env {
         if env.is_null() {
             Err(Error::new("Could not create mem env".to_owned()))
         } else {
-            Ok(Self(Arc::new(EnvWrapper { inner: env })))
+            Ok(Self(Arc::new(EnvWrapper {
+                inner: env,
+                custom_file_system: false,
+            })))
+        }
+    }
+
+    /// Returns a new environment whose file operations are implemented by `fs`.
+    /// Threads and time related tasks are delegated to the default env.
+    ///
+    /// See [`file_system`](crate::file_system) for details.
+    pub fn from_file_system<F: FileSystem>(fs: F) -> Result<Self, Error> {
+        Self::from_raw_env(create_env::<F, SystemClock>(fs, None))
+    }
+
+    /// Returns a new environment whose file operations are implemented by `fs`
+    /// and which reads the time from `clock`.
+    pub fn from_file_system_and_clock<F: FileSystem, C: Clock>(
+        fs: F,
+        clock: C,
+    ) -> Result<Self, Error> {
+        Self::from_raw_env(create_env(fs, Some(clock)))
+    }
+
+    fn from_raw_env(env: *mut ffi::rocksdb_env_t) -> Result<Self, Error> {
+        if env.is_null() {
+            Err(Error::new("Could not create env".to_owned()))
+        } else {
+            Ok(Self(Arc::new(EnvWrapper {
+                inner: env,
+                custom_file_system: true,
+            })))
         }
     }
 
@@ -206,6 +254,7 @@ pub(crate) struct OptionsMustOutliveDB {
     env: Option<Env>,
     row_cache: Option<Cache>,
     block_based: Option<BlockBasedOptionsMustOutliveDB>,
+    comparator: Option<Arc<ComparatorWrapper>>,
 }
 
 impl OptionsMustOutliveDB {
@@ -217,6 +266,7 @@ impl OptionsMustOutliveDB {
                 .block_based
                 .as_ref()
                 .map(BlockBasedOptionsMustOutliveDB::clone),
+            comparator: self.comparator.clone(),
         }
     }
 }
@@ -338,6 +388,8 @@ pub struct ReadOptions {
     pub(crate) inner: *mut ffi::rocksdb_readoptions_t,
     iterate_upper_bound: Option<Vec<u8>>,
     iterate_lower_bound: Option<Vec<u8>>,
+    timestamp: Option<Vec<u8>>,
+    iter_start_ts: Option<Vec<u8>>,
 }
 
 /// Configuration of cuckoo-based storage.
@@ -1061,6 +1113,15 @@ impl Options {
         self.outlive.env = Some(env.clone());
     }
 
+    /// Returns whether database files are stored on the local file system,
+    /// i.e. whether no Rust [`FileSystem`] is used through [`Options::set_env`].
+    pub(crate) fn uses_local_file_system(&self) -> bool {
+        match &self.outlive.env {
+            Some(env) => !env.0.custom_file_system,
+            None => true,
+        }
+    }
+
     /// Sets the compression algorithm that will be used for compressing blocks.
     ///
     /// Default: `DBCompressionType::Snappy` (`DBCompressionType::None` if
@@ -1323,6 +1384,87 @@ impl Options {
         }
     }
 
+    /// Sets a merge operator with state, which can also merge many operands
+    /// at once, and lets reads merge the newest operands of a key without
+    /// collecting older ones.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::ffi::CStr;
+    /// use rocksdb::{merge_operator::MergeOperator, MergeOperands, Options, DB};
+    ///
+    /// /// Adds up `u64` counters.  Operands are a tag byte, `ADD` or
+    /// /// `RESET`, followed by the number, so reads stop at the newest reset.
+    /// struct Counter;
+    ///
+    /// const ADD: u8 = 0;
+    /// const RESET: u8 = 1;
+    ///
+    /// fn operand(tag: u8, n: u64) -> Vec<u8> {
+    ///     let mut op = vec![tag];
+    ///     op.extend_from_slice(&n.to_le_bytes());
+    ///     op
+    /// }
+    ///
+    /// fn decode(bytes: &[u8]) -> u64 {
+    ///     let mut buf = [0; 8];
+    ///     buf.copy_from_slice(&bytes[bytes.len() - 8..]);
+    ///     u64::from_le_bytes(buf)
+    /// }
+    ///
+    /// impl MergeOperator for Counter {
+    ///     fn name(&self) -> &CStr {
+    ///         CStr::from_bytes_with_nul(b"counter\0").unwrap()
+    ///     }
+    ///
+    ///     fn full_merge(
+    ///         &self,
+    ///         _key: &[u8],
+    ///         existing_value: Option<&[u8]>,
+    ///         operands: &MergeOperands,
+    ///     ) -> Option<Vec<u8>> {
+    ///         let mut sum = existing_value.map_or(0, decode);
+    ///         for op in operands {
+    ///             sum = if op[0] == RESET { decode(op) } else { sum + decode(op) };
+    ///         }
+    ///         Some(sum.to_le_bytes().to_vec())
+    ///     }
+    ///
+    ///     fn should_merge(&self, operands: &MergeOperands) -> bool {
+    ///         operands.iter().any(|op| op[0] == RESET)
+    ///     }
+    /// }
+    ///
+    /// let path = "_path_for_rocksdb_storage_with_merge_operator_impl";
+    /// {
+    ///     let mut opts = Options::default();
+    ///     opts.create_if_missing(true);
+    ///     opts.set_merge_operator_impl(Counter);
+    ///     let db = DB::open(&opts, path).unwrap();
+    ///     db.merge(b"k", operand(ADD, 1)).unwrap();
+    ///     db.merge(b"k", operand(ADD, 2)).unwrap();
+    ///     assert_eq!(db.get(b"k").unwrap().unwrap(), 3_u64.to_le_bytes());
+    ///     db.merge(b"k", operand(RESET, 61)).unwrap();
+    ///     db.merge(b"k", operand(ADD, 1)).unwrap();
+    ///     assert_eq!(db.get(b"k").unwrap().unwrap(), 62_u64.to_le_bytes());
+    /// }
+    /// let _ = DB::destroy(&Options::default(), path);
+    /// ```
+    pub fn set_merge_operator_impl<M>(&mut self, merge_operator: M)
+    where
+        M: MergeOperator + 'static,
+    {
+        let callbacks = merge_operator::create_callbacks::<M>();
+        unsafe {
+            ffi::rocksdb_ext_options_set_merge_operator(
+                self.inner,
+                Box::into_raw(Box::new(merge_operator)).cast::<c_void>(),
+                std::ptr::addr_of!(callbacks),
+            );
+        }
+    }
+
     #[deprecated(
         since = "0.5.0",
         note = "add_merge_operator has been renamed to set_merge_operator"
@@ -1331,6 +1473,58 @@ impl Options {
         self.set_merge_operator_associative(name, merge_fn);
     }
 
+    /// Adds a listener notified of flushes, compactions, write stalls,
+    /// background errors and table file creations in databases opened with
+    /// these options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rocksdb::event_listener::{EventListener, WriteStallCondition, WriteStallInfo};
+    /// use rocksdb::Options;
+    ///
+    /// struct StallLogger;
+    ///
+    /// impl EventListener for StallLogger {
+    ///     fn on_stall_conditions_changed(&self, info: &WriteStallInfo) {
+    ///         if info.current != WriteStallCondition::Normal {
+    ///             eprintln!("writes to {} are {:?}", info.column_family_name, info.current);
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut opts = Options::default();
+    /// opts.add_event_listener(StallLogger);
+    /// ```
+    pub fn add_event_listener<L: EventListener>(&mut self, listener: L) {
+        let callbacks = event_listener::create_callbacks::<L>();
+        unsafe {
+            ffi::rocksdb_ext_options_add_eventlistener(
+                self.inner,
+                Box::into_raw(Box::new(listener)).cast::<c_void>(),
+                std::ptr::addr_of!(callbacks),
+            );
+        }
+    }
+
+    /// Adds a factory of collectors gathering custom properties of every
+    /// table the database writes.
+    ///
+    /// See the [`table_properties_collector`] module for an example.
+    pub fn add_table_properties_collector_factory<F: TablePropertiesCollectorFactory>(
+        &mut self,
+        factory: F,
+    ) {
+        let callbacks = table_properties_collector::create_callbacks::<F>();
+        unsafe {
+            ffi::rocksdb_ext_options_add_table_properties_collector_factory(
+                self.inner,
+                Box::into_raw(Box::new(factory)).cast::<c_void>(),
+                std::ptr::addr_of!(callbacks),
+            );
+        }
+    }
+
     /// Sets a compaction filter used to determine if entries should be kept, changed,
     /// or removed during compaction.
     ///
@@ -1345,19 +1539,38 @@ impl Options {
     where
         F: CompactionFilterFn + Send + 'static,
     {
-        let cb = Box::new(CompactionFilterCallback {
+        self.set_compaction_filter_impl(CompactionFilterCallback {
+            name: name.into_c_string().unwrap(),
+            filter_fn,
+        });
+    }
+
+    /// Sets a compaction filter that also sees merge operands and can change
+    /// values to owned buffers or remove ranges of keys.
+    ///
+    /// See [`CompactionFilter::filter_v2`] and
+    /// [`set_compaction_filter`](Self::set_compaction_filter) for more
+    /// details.
+    ///
+    /// [`CompactionFilter::filter_v2`]: crate::compaction_filter::CompactionFilter::filter_v2
+    pub fn set_compaction_filter_v2<F>(&mut self, name: impl CStrLike, filter_fn: F)
+    where
+        F: CompactionFilterV2Fn + Send + 'static,
+    {
+        self.set_compaction_filter_impl(CompactionFilterV2Callback {
             name: name.into_c_string().unwrap(),
             filter_fn,
         });
+    }
 
+    fn set_compaction_filter_impl<F: CompactionFilter>(&mut self, filter: F) {
+        let callbacks = compaction_filter::create_callbacks::<F>();
         unsafe {
-            let cf = ffi::rocksdb_compactionfilter_create(
-                Box::into_raw(cb).cast::<c_void>(),
-                Some(compaction_filter::destructor_callback::<CompactionFilterCallback<F>>),
-                Some(compaction_filter::filter_callback::<CompactionFilterCallback<F>>),
-                Some(compaction_filter::name_callback::<CompactionFilterCallback<F>>),
+            ffi::rocksdb_ext_options_set_compaction_filter(
+                self.inner,
+                Box::into_raw(Box::new(filter)).cast::<c_void>(),
+                std::ptr::addr_of!(callbacks),
             );
-            ffi::rocksdb_options_set_compaction_filter(self.inner, cf);
         }
     }
 
@@ -1373,17 +1586,13 @@ impl Options {
     where
         F: CompactionFilterFactory + 'static,
     {
-        let factory = Box::new(factory);
-
+        let callbacks = compaction_filter_factory::create_callbacks::<F>();
         unsafe {
-            let cff = ffi::rocksdb_compactionfilterfactory_create(
-                Box::into_raw(factory).cast::<c_void>(),
-                Some(compaction_filter_factory::destructor_callback::<F>),
-                Some(compaction_filter_factory::create_compaction_filter_callback::<F>),
-                Some(compaction_filter_factory::name_callback::<F>),
+            ffi::rocksdb_ext_options_set_compaction_filter_factory(
+                self.inner,
+                Box::into_raw(Box::new(factory)).cast::<c_void>(),
+                std::ptr::addr_of!(callbacks),
             );
-
-            ffi::rocksdb_options_set_compaction_filter_factory(self.inner, cff);
         }
     }
 
@@ -1410,6 +1619,116 @@ impl Options {
         }
     }
 
+    /// Sets a comparator for keys with user-defined timestamps.
+    ///
+    /// Every key of the column family is then written together with a
+    /// timestamp of `timestamp_size` bytes, e.g. with
+    /// [`DB::put_with_ts`](crate::DB::put_with_ts), and reads must set
+    /// [`ReadOptions::set_timestamp`] to the time they read the data as of.
+    ///
+    /// `compare_fn` orders keys without their timestamps, and `compare_ts_fn`
+    /// orders timestamps from older to newer.  Versions of the same key are
+    /// ordered from newer to older.
+    ///
+    /// As with [`set_comparator`](Self::set_comparator), the name and
+    /// ordering must not change once the database was created.
+    pub fn set_comparator_with_ts(
+        &mut self,
+        name: impl CStrLike,
+        timestamp_size: usize,
+        compare_fn: CompareFn,
+        compare_ts_fn: CompareFn,
+    ) {
+        let cb = Box::new(ComparatorWithTsCallback {
+            name: name.into_c_string().unwrap(),
+            timestamp_size,
+            f: compare_fn,
+            ts_f: compare_ts_fn,
+        });
+
+        unsafe {
+            let cmp = ffi::rocksdb_comparator_with_ts_create(
+                Box::into_raw(cb).cast::<c_void>(),
+                Some(comparator::destructor_with_ts_callback),
+                Some(comparator::compare_with_ts_callback),
+                Some(comparator::compare_ts_callback),
+                Some(comparator::compare_without_ts_callback),
+                Some(comparator::name_with_ts_callback),
+                timestamp_size,
+            );
+            ffi::rocksdb_options_set_comparator(self.inner, cmp);
+        }
+    }
+
+    /// Sets a comparator ordering keys byte-wise, with 8 byte timestamps
+    /// encoded by `u64::to_le_bytes`.
+    ///
+    /// See [`set_comparator_with_ts`](Self::set_comparator_with_ts).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rocksdb::{Options, ReadOptions, DB};
+    ///
+    /// let path = "_path_for_rocksdb_storage_with_u64_ts";
+    /// {
+    ///     let mut opts = Options::default();
+    ///     opts.create_if_missing(true);
+    ///     opts.set_comparator_with_u64_ts();
+    ///     let db = DB::open(&opts, path).unwrap();
+    ///
+    ///     db.put_with_ts(b"k", 1u64.to_le_bytes(), b"v1").unwrap();
+    ///     db.put_with_ts(b"k", 2u64.to_le_bytes(), b"v2").unwrap();
+    ///
+    ///     let mut readopts = ReadOptions::default();
+    ///     readopts.set_timestamp(1u64.to_le_bytes());
+    ///     assert_eq!(db.get_opt(b"k", &readopts).unwrap().unwrap(), b"v1");
+    /// }
+    /// let _ = DB::destroy(&Options::default(), path);
+    /// ```
+    pub fn set_comparator_with_u64_ts(&mut self) {
+        self.set_comparator_with_ts(
+            "leveldb.BytewiseComparator.u64ts",
+            std::mem::size_of::<u64>(),
+            <[u8]>::cmp,
+            comparator::compare_u64_ts,
+        );
+    }
+
+    /// Sets a comparator with state, which can also shorten the keys stored
+    /// in index blocks and order keys with timestamps.
+    ///
+    /// As with [`set_comparator`](Self::set_comparator), the name and
+    /// ordering must not change once the database was created.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rocksdb::{Options, ReverseBytewiseComparator, DB};
+    ///
+    /// let path = "_path_for_rocksdb_storage_with_comparator_impl";
+    /// {
+    ///     let mut opts = Options::default();
+    ///     opts.create_if_missing(true);
+    ///     opts.set_comparator_impl(Box::new(ReverseBytewiseComparator));
+    ///     let db = DB::open(&opts, path).unwrap();
+    ///     db.put(b"a", b"1").unwrap();
+    ///     db.put(b"b", b"2").unwrap();
+    ///
+    ///     let mut iter = db.raw_iterator();
+    ///     iter.seek_to_first();
+    ///     assert_eq!(iter.key(), Some(&b"b"[..]));
+    /// }
+    /// let _ = DB::destroy(&Options::default(), path);
+    /// ```
+    pub fn set_comparator_impl(&mut self, comparator: Box<dyn Comparator>) {
+        let comparator = Arc::new(ComparatorWrapper::new(comparator));
+        unsafe {
+            ffi::rocksdb_ext_options_set_comparator(self.inner, comparator.inner);
+        }
+        self.outlive.comparator = Some(comparator);
+    }
+
     pub fn set_prefix_extractor(&mut self, prefix_extractor: SliceTransform) {
         unsafe {
             ffi::rocksdb_options_set_prefix_extractor(self.inner, prefix_extractor.inner);
@@ -2520,6 +2839,27 @@ impl Options {
         }
     }
 
+    /// Sets a factory opening tables of a custom, read-only format.
+    ///
+    /// Tables written by the database keep using the block-based format
+    /// configured with [`set_block_based_table_factory`] before this call, or
+    /// the default one.  See the [`table_factory`] module for how to add
+    /// tables of the custom format.
+    ///
+    /// [`set_block_based_table_factory`]: Self::set_block_based_table_factory
+    pub fn set_table_factory<F: TableFactory>(&mut self, factory: F) {
+        let callbacks = table_factory::create_callbacks::<F>();
+        let name = CString::new(factory.name().to_bytes()).unwrap();
+        unsafe {
+            ffi::rocksdb_ext_options_set_table_factory(
+                self.inner,
+                name.as_ptr(),
+                Box::into_raw(Box::new(factory)).cast::<c_void>(),
+                std::ptr::addr_of!(callbacks),
+            );
+        }
+    }
+
     /// Sets the start level to use compression.
     pub fn set_min_level_to_compress(&mut self, lvl: c_int) {
         unsafe {
@@ -2604,6 +2944,24 @@ impl Options {
         }
     }
 
+    /// Returns a handle to the statistics enabled with
+    /// [`enable_statistics`](Self::enable_statistics), or `None` if they are
+    /// not enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rocksdb::{statistics::Ticker, Options};
+    ///
+    /// let mut opts = Options::default();
+    /// opts.enable_statistics();
+    /// let stats = opts.statistics().unwrap();
+    /// assert_eq!(stats.ticker(Ticker::BlockCacheMiss), 0);
+    /// ```
+    pub fn statistics(&self) -> Option<Statistics> {
+        Statistics::from_options(self.inner)
+    }
+
     /// If not zero, dump `rocksdb.stats` to LOG every `stats_dump_period_sec`.
     ///
     /// Default: `600` (10 mins)
@@ -3340,6 +3698,9 @@ impl ReadOptions {
     /// options.set_iterate_range(rocksdb::PrefixRange("xy".as_bytes()));
     /// ```
     ///
+    /// Keys encoded with [`crate::keycodec`] can be given as
+    /// [`crate::keycodec::Encoded`] tuples, in a range or a prefix range.
+    ///
     /// Note that setting range using this method is separate to using prefix
     /// iterators.  Prefix iterators use prefix extractor configured for
     /// a column family.  Setting bounds via [`crate::PrefixRange`] is more akin
@@ -3386,6 +3747,42 @@ impl ReadOptions {
         }
     }
 
+    /// Sets the timestamp to read the data as of, for column families with
+    /// user-defined timestamps.  Only versions written at or before the
+    /// timestamp are visible.
+    ///
+    /// Reads from such column families fail unless a timestamp is set.
+    pub fn set_timestamp<T: Into<Vec<u8>>>(&mut self, ts: T) {
+        let ts = ts.into();
+        unsafe {
+            ffi::rocksdb_readoptions_set_timestamp(
+                self.inner,
+                ts.as_ptr() as *const c_char,
+                ts.len() as size_t,
+            );
+        }
+        self.timestamp = Some(ts);
+    }
+
+    /// Sets the oldest timestamp an iterator returns versions for.  Together
+    /// with [`set_timestamp`](Self::set_timestamp), the iterator returns
+    /// every version of the keys written between the two timestamps,
+    /// including deletions, instead of only the newest one.
+    ///
+    /// The keys the iterator returns are then internal keys: the key, its
+    /// timestamp, and 8 bytes of sequence number and entry type.
+    pub fn set_iter_start_ts<T: Into<Vec<u8>>>(&mut self, ts: T) {
+        let ts = ts.into();
+        unsafe {
+            ffi::rocksdb_readoptions_set_iter_start_ts(
+                self.inner,
+                ts.as_ptr() as *const c_char,
+                ts.len() as size_t,
+            );
+        }
+        self.iter_start_ts = Some(ts);
+    }
+
     /// Specify if this read request should process data that ALREADY
     /// resides on a particular cache. If the required data is not
     /// found at the specified cache, then Status::Incomplete is returned.
@@ -3517,6 +3914,8 @@ impl Default for ReadOptions {
                 inner: ffi::rocksdb_readoptions_create(),
                 iterate_upper_bound: None,
                 iterate_lower_bound: None,
+                timestamp: None,
+                iter_start_ts: None,
             }
         }
     }
@@ -3575,6 +3974,21 @@ impl IngestExternalFileOptions {
             ffi::rocksdb_ingestexternalfileoptions_set_ingest_behind(self.inner, c_uchar::from(v));
         }
     }
+
+    /// If set to false, the sequence number assigned to an ingested file is
+    /// only kept in the MANIFEST instead of also being written into the file.
+    /// Files of a custom table format must be ingested with this disabled;
+    /// ingesting them with it enabled fails with an invalid argument error.
+    ///
+    /// Default: true
+    pub fn set_write_global_seqno(&mut self, v: bool) {
+        unsafe {
+            ffi::rocksdb_ext_ingestexternalfileoptions_set_write_global_seqno(
+                self.inner,
+                c_uchar::from(v),
+            );
+        }
+    }
 }
 
 impl Default for IngestExternalFileOptions {
@@ -0,0 +1,491 @@
+// Copyright 2022 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Callbacks for events happening inside RocksDB.
+//!
+//! An [`EventListener`] is registered with [`Options::add_event_listener`]
+//! and notified of flushes, compactions, write stalls, background errors
+//! and table file creations.
+//!
+//! ```
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//! use std::sync::Arc;
+//!
+//! use rocksdb::event_listener::{EventListener, FlushJobInfo};
+//! use rocksdb::{Options, DB};
+//!
+//! #[derive(Default)]
+//! struct FlushCounter(AtomicUsize);
+//!
+//! impl EventListener for FlushCounter {
+//!     fn on_flush_completed(&self, info: &FlushJobInfo) {
+//!         println!("flushed {} entries of {}", info.num_entries, info.column_family_name);
+//!         self.0.fetch_add(1, Ordering::SeqCst);
+//!     }
+//! }
+//!
+//! let path = "_rust_rocksdb_event_listener_doc";
+//! {
+//!     let counter = Arc::new(FlushCounter::default());
+//!     let mut opts = Options::default();
+//!     opts.create_if_missing(true);
+//!     opts.add_event_listener(counter.clone());
+//!
+//!     let db = DB::open(&opts, path).unwrap();
+//!     db.put(b"key", b"value").unwrap();
+//!     db.flush().unwrap();
+//! }
+//! let _ = DB::destroy(&Options::default(), path);
+//! ```
+//!
+//! [`Options::add_event_listener`]: crate::Options::add_event_listener
+
+use std::ffi::CStr;
+use std::path::PathBuf;
+use std::slice;
+use std::sync::Arc;
+use std::time::Duration;
+
+use libc::{c_char, c_int, c_void, size_t};
+
+use crate::{ffi, file_system::to_path_buf_lossy, Error};
+
+/// Receives notifications of events happening inside a database.
+///
+/// The callbacks are invoked from RocksDB's background threads, possibly
+/// concurrently, and should return quickly since they block the job that
+/// triggered them.  They must not call back into the database that issued
+/// the event.
+pub trait EventListener: Send + Sync + 'static {
+    /// Called after a flush finished writing its table file.
+    fn on_flush_completed(&self, _info: &FlushJobInfo) {}
+
+    /// Called after a compaction finished, whether successfully or not.
+    fn on_compaction_completed(&self, _info: &CompactionJobInfo) {}
+
+    /// Called when writes to a column family start or stop being delayed or
+    /// stopped.
+    fn on_stall_conditions_changed(&self, _info: &WriteStallInfo) {}
+
+    /// Called when a background job fails and the database stops accepting
+    /// writes.
+    fn on_background_error(&self, _info: &BackgroundErrorInfo) {}
+
+    /// Called after a table file was created, whether successfully or not.
+    fn on_table_file_created(&self, _info: &TableFileCreationInfo) {}
+}
+
+/// Allows keeping a handle to a registered listener.
+impl<L: EventListener> EventListener for Arc<L> {
+    fn on_flush_completed(&self, info: &FlushJobInfo) {
+        (**self).on_flush_completed(info);
+    }
+
+    fn on_compaction_completed(&self, info: &CompactionJobInfo) {
+        (**self).on_compaction_completed(info);
+    }
+
+    fn on_stall_conditions_changed(&self, info: &WriteStallInfo) {
+        (**self).on_stall_conditions_changed(info);
+    }
+
+    fn on_background_error(&self, info: &BackgroundErrorInfo) {
+        (**self).on_background_error(info);
+    }
+
+    fn on_table_file_created(&self, info: &TableFileCreationInfo) {
+        (**self).on_table_file_created(info);
+    }
+}
+
+macro_rules! raw_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $(
+                $(#[$variant_meta:meta])*
+                $variant:ident = $value:literal,
+            )+
+        }
+        default $default:ident
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        #[repr(i32)]
+        pub enum $name {
+            $(
+                $(#[$variant_meta])*
+                $variant = $value,
+            )+
+        }
+
+        impl $name {
+            pub(crate) fn from_raw(value: c_int) -> Self {
+                match value {
+                    $($value => $name::$variant,)+
+                    _ => $name::$default,
+                }
+            }
+        }
+    };
+}
+
+raw_enum! {
+    /// The reason a flush was started.
+    pub enum FlushReason {
+        Others = 0,
+        GetLiveFiles = 1,
+        ShutDown = 2,
+        ExternalFileIngestion = 3,
+        ManualCompaction = 4,
+        WriteBufferManager = 5,
+        WriteBufferFull = 6,
+        Test = 7,
+        DeleteFiles = 8,
+        AutoCompaction = 9,
+        ManualFlush = 10,
+        ErrorRecovery = 11,
+        ErrorRecoveryRetryFlush = 12,
+        WalFull = 13,
+    }
+    default Others
+}
+
+raw_enum! {
+    /// The reason a compaction was started.
+    pub enum CompactionReason {
+        Unknown = 0,
+        /// Level compaction: number of L0 files > level0_file_num_compaction_trigger
+        LevelL0FilesNum = 1,
+        /// Level compaction: total size of level > MaxBytesForLevel()
+        LevelMaxLevelSize = 2,
+        /// Universal compaction: compacting for size amplification
+        UniversalSizeAmplification = 3,
+        /// Universal compaction: compacting for size ratio
+        UniversalSizeRatio = 4,
+        /// Universal compaction: number of sorted runs > level0_file_num_compaction_trigger
+        UniversalSortedRunNum = 5,
+        /// FIFO compaction: total size > max_table_files_size
+        FifoMaxSize = 6,
+        /// FIFO compaction: reduce number of files.
+        FifoReduceNumFiles = 7,
+        /// FIFO compaction: files with creation time < (current_time - interval)
+        FifoTtl = 8,
+        /// Manual compaction
+        ManualCompaction = 9,
+        /// DB::SuggestCompactRange() marked files for compaction
+        FilesMarkedForCompaction = 10,
+        /// Level compaction: Automatic compaction within bottommost level to cleanup duplicate
+        /// versions of same user key, usually due to a released snapshot.
+        BottommostFiles = 11,
+        /// Compaction based on TTL
+        Ttl = 12,
+        /// Flushes are reported as level 0 compactions in internal stats.
+        Flush = 13,
+        /// Compaction caused by external sst file ingestion
+        ExternalSstIngestion = 14,
+        /// Compaction due to SST file being too old
+        PeriodicCompaction = 15,
+        /// Compaction in order to move files to temperature
+        ChangeTemperature = 16,
+        /// Compaction scheduled to force garbage collection of blob files
+        ForcedBlobGc = 17,
+    }
+    default Unknown
+}
+
+raw_enum! {
+    /// The state of the write controller of a column family.
+    pub enum WriteStallCondition {
+        /// Writes are not stalled.
+        Normal = 0,
+        /// Writes are slowed down.
+        Delayed = 1,
+        /// Writes are blocked.
+        Stopped = 2,
+    }
+    default Normal
+}
+
+raw_enum! {
+    /// The kind of background job that failed.
+    pub enum BackgroundErrorReason {
+        Flush = 0,
+        Compaction = 1,
+        WriteCallback = 2,
+        MemTable = 3,
+        ManifestWrite = 4,
+        FlushNoWal = 5,
+        ManifestWriteNoWal = 6,
+    }
+    default Flush
+}
+
+raw_enum! {
+    /// The reason a table file was created.
+    pub enum TableFileCreationReason {
+        Flush = 0,
+        Compaction = 1,
+        Recovery = 2,
+        Misc = 3,
+    }
+    default Misc
+}
+
+/// Information about a completed flush.
+#[derive(Debug, Clone)]
+pub struct FlushJobInfo {
+    /// Name of the flushed column family
+    pub column_family_name: String,
+    /// Path of the newly created table file
+    pub file_path: PathBuf,
+    /// File number of the newly created table file
+    pub file_number: u64,
+    /// Size of the newly created table file
+    pub file_size: u64,
+    /// Id of the thread that completed the flush
+    pub thread_id: u64,
+    /// Id of the job, unique within a database
+    pub job_id: i32,
+    /// Whether writes are slowed down because of too many level 0 files
+    pub triggered_writes_slowdown: bool,
+    /// Whether writes are stopped because of too many level 0 files
+    pub triggered_writes_stop: bool,
+    /// Smallest sequence number in the newly created file
+    pub smallest_seqno: u64,
+    /// Largest sequence number in the newly created file
+    pub largest_seqno: u64,
+    /// Number of entries in the newly created file
+    pub num_entries: u64,
+    /// Total size of the keys written to the file, before compression
+    pub raw_key_size: u64,
+    /// Total size of the values written to the file, before compression
+    pub raw_value_size: u64,
+    /// Why the flush was started
+    pub reason: FlushReason,
+    /// Time the flush took
+    pub duration: Duration,
+}
+
+/// Information about a completed compaction.
+#[derive(Debug, Clone)]
+pub struct CompactionJobInfo {
+    /// Name of the compacted column family
+    pub column_family_name: String,
+    /// Whether the compaction succeeded
+    pub status: Result<(), Error>,
+    /// Id of the thread that completed the compaction
+    pub thread_id: u64,
+    /// Id of the job, unique within a database
+    pub job_id: i32,
+    /// Smallest input level of the compaction
+    pub base_input_level: i32,
+    /// Output level of the compaction
+    pub output_level: i32,
+    /// Paths of the input files
+    pub input_files: Vec<PathBuf>,
+    /// Paths of the output files.  A file moved to another level appears in
+    /// both `input_files` and `output_files`.
+    pub output_files: Vec<PathBuf>,
+    /// Why the compaction was started
+    pub reason: CompactionReason,
+    /// Time the compaction took
+    pub duration: Duration,
+    /// Total size of the input files
+    pub total_input_bytes: u64,
+    /// Total size of the output files
+    pub total_output_bytes: u64,
+    /// Number of records read from the input files
+    pub num_input_records: u64,
+    /// Number of records written to the output files
+    pub num_output_records: u64,
+}
+
+/// Information about a change of write stall condition.
+#[derive(Debug, Clone)]
+pub struct WriteStallInfo {
+    /// Name of the column family
+    pub column_family_name: String,
+    /// The new condition
+    pub current: WriteStallCondition,
+    /// The previous condition
+    pub previous: WriteStallCondition,
+}
+
+/// Information about a background error.
+#[derive(Debug, Clone)]
+pub struct BackgroundErrorInfo {
+    /// The kind of job that failed
+    pub reason: BackgroundErrorReason,
+    /// The error the job failed with
+    pub error: Error,
+}
+
+/// Information about a created table file.
+#[derive(Debug, Clone)]
+pub struct TableFileCreationInfo {
+    /// Name of the column family the file belongs to
+    pub column_family_name: String,
+    /// Path of the file
+    pub file_path: PathBuf,
+    /// Id of the job that created the file
+    pub job_id: i32,
+    /// Why the file was created
+    pub reason: TableFileCreationReason,
+    /// Size of the file
+    pub file_size: u64,
+    /// Number of entries in the file
+    pub num_entries: u64,
+    /// Whether the file was created successfully
+    pub status: Result<(), Error>,
+}
+
+pub(crate) fn create_callbacks<L: EventListener>() -> ffi::rocksdb_ext_eventlistener_callbacks_t {
+    ffi::rocksdb_ext_eventlistener_callbacks_t {
+        destructor: Some(destructor_callback::<L>),
+        on_flush_completed: Some(flush_completed_callback::<L>),
+        on_compaction_completed: Some(compaction_completed_callback::<L>),
+        on_stall_conditions_changed: Some(stall_conditions_changed_callback::<L>),
+        on_background_error: Some(background_error_callback::<L>),
+        on_table_file_created: Some(table_file_created_callback::<L>),
+    }
+}
+
+unsafe fn to_string(ptr: *const c_char, len: size_t) -> String {
+    String::from_utf8_lossy(slice::from_raw_parts(ptr as *const u8, len)).into_owned()
+}
+
+unsafe fn to_status(status: *const c_char) -> Result<(), Error> {
+    if status.is_null() {
+        Ok(())
+    } else {
+        Err(to_error(status))
+    }
+}
+
+unsafe fn to_error(message: *const c_char) -> Error {
+    Error::new(CStr::from_ptr(message).to_string_lossy().into_owned())
+}
+
+unsafe fn to_paths(paths: *const *const c_char, lens: *const size_t, n: size_t) -> Vec<PathBuf> {
+    if n == 0 {
+        return Vec::new();
+    }
+    let paths = slice::from_raw_parts(paths, n);
+    let lens = slice::from_raw_parts(lens, n);
+    paths
+        .iter()
+        .zip(lens)
+        .map(|(&path, &len)| to_path_buf_lossy(path, len))
+        .collect()
+}
+
+unsafe extern "C" fn destructor_callback<L: EventListener>(raw_cb: *mut c_void) {
+    drop(Box::from_raw(raw_cb as *mut L));
+}
+
+unsafe extern "C" fn flush_completed_callback<L: EventListener>(
+    raw_cb: *mut c_void,
+    info: *const ffi::rocksdb_ext_flush_job_info_t,
+) {
+    let cb = &*(raw_cb as *const L);
+    let info = &*info;
+    cb.on_flush_completed(&FlushJobInfo {
+        column_family_name: to_string(info.cf_name, info.cf_name_len),
+        file_path: to_path_buf_lossy(info.file_path, info.file_path_len),
+        file_number: info.file_number,
+        file_size: info.file_size,
+        thread_id: info.thread_id,
+        job_id: info.job_id,
+        triggered_writes_slowdown: info.triggered_writes_slowdown != 0,
+        triggered_writes_stop: info.triggered_writes_stop != 0,
+        smallest_seqno: info.smallest_seqno,
+        largest_seqno: info.largest_seqno,
+        num_entries: info.num_entries,
+        raw_key_size: info.raw_key_size,
+        raw_value_size: info.raw_value_size,
+        reason: FlushReason::from_raw(info.reason),
+        duration: Duration::from_micros(info.elapsed_micros),
+    });
+}
+
+unsafe extern "C" fn compaction_completed_callback<L: EventListener>(
+    raw_cb: *mut c_void,
+    info: *const ffi::rocksdb_ext_compaction_job_info_t,
+) {
+    let cb = &*(raw_cb as *const L);
+    let info = &*info;
+    cb.on_compaction_completed(&CompactionJobInfo {
+        column_family_name: to_string(info.cf_name, info.cf_name_len),
+        status: to_status(info.status),
+        thread_id: info.thread_id,
+        job_id: info.job_id,
+        base_input_level: info.base_input_level,
+        output_level: info.output_level,
+        input_files: to_paths(info.input_files, info.input_file_lens, info.num_input_files),
+        output_files: to_paths(
+            info.output_files,
+            info.output_file_lens,
+            info.num_output_files,
+        ),
+        reason: CompactionReason::from_raw(info.reason),
+        duration: Duration::from_micros(info.elapsed_micros),
+        total_input_bytes: info.total_input_bytes,
+        total_output_bytes: info.total_output_bytes,
+        num_input_records: info.num_input_records,
+        num_output_records: info.num_output_records,
+    });
+}
+
+unsafe extern "C" fn stall_conditions_changed_callback<L: EventListener>(
+    raw_cb: *mut c_void,
+    info: *const ffi::rocksdb_ext_write_stall_info_t,
+) {
+    let cb = &*(raw_cb as *const L);
+    let info = &*info;
+    cb.on_stall_conditions_changed(&WriteStallInfo {
+        column_family_name: to_string(info.cf_name, info.cf_name_len),
+        current: WriteStallCondition::from_raw(info.cur),
+        previous: WriteStallCondition::from_raw(info.prev),
+    });
+}
+
+unsafe extern "C" fn background_error_callback<L: EventListener>(
+    raw_cb: *mut c_void,
+    reason: c_int,
+    status: *const c_char,
+) {
+    let cb = &*(raw_cb as *const L);
+    cb.on_background_error(&BackgroundErrorInfo {
+        reason: BackgroundErrorReason::from_raw(reason),
+        error: to_error(status),
+    });
+}
+
+unsafe extern "C" fn table_file_created_callback<L: EventListener>(
+    raw_cb: *mut c_void,
+    info: *const ffi::rocksdb_ext_table_file_creation_info_t,
+) {
+    let cb = &*(raw_cb as *const L);
+    let info = &*info;
+    cb.on_table_file_created(&TableFileCreationInfo {
+        column_family_name: to_string(info.cf_name, info.cf_name_len),
+        file_path: to_path_buf_lossy(info.file_path, info.file_path_len),
+        job_id: info.job_id,
+        reason: TableFileCreationReason::from_raw(info.reason),
+        file_size: info.file_size,
+        num_entries: info.num_entries,
+        status: to_status(info.status),
+    });
+}
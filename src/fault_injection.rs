@@ -0,0 +1,478 @@
+// Copyright 2022 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An [`Env`] injecting file system faults, for crash-consistency testing.
+//!
+//! [`FaultInjectionEnv`] keeps track of the data written to every file since
+//! its last sync. It can fail syncs, fail any operation on chosen paths and
+//! simulate a power loss by dropping the unsynced data, so that tests can
+//! check what a database recovers, e.g. with each [`DBRecoveryMode`].
+//!
+//! ```
+//! use rocksdb::{fault_injection::FaultInjectionEnv, Options, WriteOptions, DB};
+//!
+//! let path = "_rust_rocksdb_fault_injection_example";
+//! let env = FaultInjectionEnv::new().unwrap();
+//! let mut opts = Options::default();
+//! opts.create_if_missing(true);
+//! opts.set_env(env.env());
+//! {
+//!     let db = DB::open(&opts, path).unwrap();
+//!     let mut sync = WriteOptions::default();
+//!     sync.set_sync(true);
+//!     db.put_opt(b"synced", b"value", &sync).unwrap();
+//!     db.put(b"unsynced", b"value").unwrap();
+//!     env.simulate_power_loss().unwrap();
+//! }
+//! env.set_active(true);
+//! {
+//!     let db = DB::open(&opts, path).unwrap();
+//!     assert!(db.get(b"synced").unwrap().is_some());
+//!     assert!(db.get(b"unsynced").unwrap().is_none());
+//! }
+//! let _ = DB::destroy(&Options::default(), path);
+//! ```
+//!
+//! [`Env`]: crate::Env
+//! [`DBRecoveryMode`]: crate::DBRecoveryMode
+
+use std::collections::HashMap;
+use std::ffi::{CStr, OsString};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    file_system::{
+        Directory, FileSystem, RandomAccessFile, SequentialFile, StdFileSystem, WritableFile,
+    },
+    Env, Error,
+};
+
+type PathFilter = Box<dyn Fn(&Path) -> bool + Send + Sync>;
+
+/// An [`Env`] whose file system can be made to fail.
+///
+/// Files are stored by the wrapped [`FileSystem`], which defaults to
+/// [`StdFileSystem`]. Faults are controlled through this handle while the
+/// [`Env`] returned by [`env`](FaultInjectionEnv::env) is used by databases.
+pub struct FaultInjectionEnv<F: FileSystem = StdFileSystem> {
+    env: Env,
+    inner: Arc<Inner<F>>,
+}
+
+struct Inner<F> {
+    base: F,
+    active: AtomicBool,
+    syncs_until_failure: Mutex<Option<usize>>,
+    failing_paths: Mutex<Option<PathFilter>>,
+    /// Size and synced size of the files written through this env.
+    files: Mutex<HashMap<PathBuf, FileState>>,
+}
+
+#[derive(Clone, Copy, Default)]
+struct FileState {
+    size: u64,
+    synced: u64,
+}
+
+impl FaultInjectionEnv {
+    /// Creates an env storing files with [`StdFileSystem`].
+    pub fn new() -> Result<Self, Error> {
+        Self::with_file_system(StdFileSystem::default())
+    }
+}
+
+impl<F: FileSystem> FaultInjectionEnv<F> {
+    /// Creates an env storing files with `base`.
+    pub fn with_file_system(base: F) -> Result<Self, Error> {
+        let inner = Arc::new(Inner {
+            base,
+            active: AtomicBool::new(true),
+            syncs_until_failure: Mutex::new(None),
+            failing_paths: Mutex::new(None),
+            files: Mutex::new(HashMap::new()),
+        });
+        let env = Env::from_file_system(FaultInjectionFileSystem(inner.clone()))?;
+        Ok(Self { env, inner })
+    }
+
+    /// Returns the env to pass to [`Options::set_env`](crate::Options::set_env).
+    pub fn env(&self) -> &Env {
+        &self.env
+    }
+
+    /// Activates or deactivates the file system. While inactive, every
+    /// operation modifying files fails, as if the machine was down.
+    ///
+    /// Default: active
+    pub fn set_active(&self, active: bool) {
+        self.inner.active.store(active, Ordering::SeqCst);
+    }
+
+    /// Returns whether the file system is active.
+    pub fn is_active(&self) -> bool {
+        self.inner.active.load(Ordering::SeqCst)
+    }
+
+    /// Makes the `n`th next file sync fail, counting from 1. Later syncs
+    /// succeed again.
+    pub fn fail_nth_sync(&self, n: usize) {
+        *self.inner.syncs_until_failure.lock().unwrap() = Some(n.max(1));
+    }
+
+    /// Makes every operation on a path for which `filter` returns true fail
+    /// with an IO error.
+    pub fn fail_paths<P>(&self, filter: P)
+    where
+        P: Fn(&Path) -> bool + Send + Sync + 'static,
+    {
+        *self.inner.failing_paths.lock().unwrap() = Some(Box::new(filter));
+    }
+
+    /// Removes the faults configured with [`fail_nth_sync`] and
+    /// [`fail_paths`].
+    ///
+    /// [`fail_nth_sync`]: FaultInjectionEnv::fail_nth_sync
+    /// [`fail_paths`]: FaultInjectionEnv::fail_paths
+    pub fn clear_faults(&self) {
+        *self.inner.syncs_until_failure.lock().unwrap() = None;
+        *self.inner.failing_paths.lock().unwrap() = None;
+    }
+
+    /// Truncates every file to the size it had when it was last synced.
+    ///
+    /// The database using the env must be closed, or the env deactivated,
+    /// so that no file is written concurrently.
+    pub fn drop_unsynced_writes(&self) -> io::Result<()> {
+        self.truncate_unsynced_writes(0)
+    }
+
+    /// Truncates every file to the size it had when it was last synced, plus
+    /// at most `keep` bytes of the data written since. This simulates writes
+    /// torn by a crash.
+    ///
+    /// The same restrictions as for
+    /// [`drop_unsynced_writes`](FaultInjectionEnv::drop_unsynced_writes) apply.
+    pub fn truncate_unsynced_writes(&self, keep: u64) -> io::Result<()> {
+        let mut files = self.inner.files.lock().unwrap();
+        for (path, state) in files.iter_mut() {
+            let size = state.size.min(state.synced + keep);
+            if size < state.size {
+                self.inner.truncate(path, size)?;
+                state.size = size;
+                state.synced = size;
+            }
+        }
+        Ok(())
+    }
+
+    /// Simulates a power loss: deactivates the file system, then drops the
+    /// unsynced writes. Call [`set_active`](FaultInjectionEnv::set_active)
+    /// once the database is closed to reopen it.
+    pub fn simulate_power_loss(&self) -> io::Result<()> {
+        self.set_active(false);
+        self.drop_unsynced_writes()
+    }
+}
+
+impl<F: FileSystem> Inner<F> {
+    fn check_path(&self, path: &Path) -> io::Result<()> {
+        if let Some(filter) = &*self.failing_paths.lock().unwrap() {
+            if filter(path) {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("injected error for {}", path.display()),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that `path` can be modified.
+    fn check_write(&self, path: &Path) -> io::Result<()> {
+        if !self.active.load(Ordering::SeqCst) {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "file system is inactive",
+            ));
+        }
+        self.check_path(path)
+    }
+
+    fn check_sync(&self, path: &Path) -> io::Result<()> {
+        self.check_write(path)?;
+        let mut syncs_until_failure = self.syncs_until_failure.lock().unwrap();
+        match *syncs_until_failure {
+            Some(1) => {
+                *syncs_until_failure = None;
+                Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("injected sync error for {}", path.display()),
+                ))
+            }
+            Some(n) => {
+                *syncs_until_failure = Some(n - 1);
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn truncate(&self, path: &Path, size: u64) -> io::Result<()> {
+        let mut data = vec![0; size as usize];
+        let mut file = self.base.new_sequential_file(path)?;
+        let mut read = 0;
+        while read < data.len() {
+            match file.read(&mut data[read..])? {
+                0 => break,
+                n => read += n,
+            }
+        }
+        data.truncate(read);
+        let mut file = self.base.new_writable_file(path)?;
+        file.append(&data)?;
+        file.sync()?;
+        file.close()
+    }
+}
+
+struct FaultInjectionFileSystem<F>(Arc<Inner<F>>);
+
+impl<F: FileSystem> FileSystem for FaultInjectionFileSystem<F> {
+    fn name(&self) -> &CStr {
+        CStr::from_bytes_with_nul(b"FaultInjectionFileSystem\0").unwrap()
+    }
+
+    fn new_sequential_file(&self, path: &Path) -> io::Result<Box<dyn SequentialFile>> {
+        self.0.check_path(path)?;
+        self.0.base.new_sequential_file(path)
+    }
+
+    fn new_random_access_file(&self, path: &Path) -> io::Result<Box<dyn RandomAccessFile>> {
+        self.0.check_path(path)?;
+        let file = self.0.base.new_random_access_file(path)?;
+        Ok(Box::new(FaultInjectionRandomAccessFile {
+            inner: self.0.clone(),
+            path: path.to_path_buf(),
+            file,
+        }))
+    }
+
+    fn new_writable_file(&self, path: &Path) -> io::Result<Box<dyn WritableFile>> {
+        self.0.check_write(path)?;
+        let file = self.0.base.new_writable_file(path)?;
+        let mut files = self.0.files.lock().unwrap();
+        files.insert(path.to_path_buf(), FileState::default());
+        Ok(Box::new(FaultInjectionWritableFile {
+            inner: self.0.clone(),
+            path: path.to_path_buf(),
+            file,
+        }))
+    }
+
+    fn reopen_writable_file(&self, path: &Path) -> io::Result<Box<dyn WritableFile>> {
+        self.0.check_write(path)?;
+        let file = self.0.base.reopen_writable_file(path)?;
+        let size = file.file_size();
+        let mut files = self.0.files.lock().unwrap();
+        files
+            .entry(path.to_path_buf())
+            .or_insert(FileState { size, synced: size });
+        Ok(Box::new(FaultInjectionWritableFile {
+            inner: self.0.clone(),
+            path: path.to_path_buf(),
+            file,
+        }))
+    }
+
+    fn new_directory(&self, path: &Path) -> io::Result<Box<dyn Directory>> {
+        self.0.check_path(path)?;
+        let dir = self.0.base.new_directory(path)?;
+        Ok(Box::new(FaultInjectionDirectory {
+            inner: self.0.clone(),
+            path: path.to_path_buf(),
+            dir,
+        }))
+    }
+
+    fn file_exists(&self, path: &Path) -> io::Result<bool> {
+        self.0.check_path(path)?;
+        self.0.base.file_exists(path)
+    }
+
+    fn get_children(&self, dir: &Path) -> io::Result<Vec<OsString>> {
+        self.0.check_path(dir)?;
+        self.0.base.get_children(dir)
+    }
+
+    fn delete_file(&self, path: &Path) -> io::Result<()> {
+        self.0.check_write(path)?;
+        self.0.base.delete_file(path)?;
+        self.0.files.lock().unwrap().remove(path);
+        Ok(())
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        self.0.check_write(path)?;
+        self.0.base.create_dir(path)
+    }
+
+    fn create_dir_if_missing(&self, path: &Path) -> io::Result<()> {
+        self.0.check_write(path)?;
+        self.0.base.create_dir_if_missing(path)
+    }
+
+    fn delete_dir(&self, path: &Path) -> io::Result<()> {
+        self.0.check_write(path)?;
+        self.0.base.delete_dir(path)
+    }
+
+    fn get_file_size(&self, path: &Path) -> io::Result<u64> {
+        self.0.check_path(path)?;
+        self.0.base.get_file_size(path)
+    }
+
+    fn get_file_modification_time(&self, path: &Path) -> io::Result<u64> {
+        self.0.check_path(path)?;
+        self.0.base.get_file_modification_time(path)
+    }
+
+    fn rename_file(&self, src: &Path, target: &Path) -> io::Result<()> {
+        self.0.check_write(src)?;
+        self.0.check_write(target)?;
+        self.0.base.rename_file(src, target)?;
+        let mut files = self.0.files.lock().unwrap();
+        match files.remove(src) {
+            Some(state) => files.insert(target.to_path_buf(), state),
+            None => files.remove(target),
+        };
+        Ok(())
+    }
+
+    fn link_file(&self, src: &Path, target: &Path) -> io::Result<()> {
+        self.0.check_write(src)?;
+        self.0.check_write(target)?;
+        self.0.base.link_file(src, target)
+    }
+
+    fn lock_file(&self, path: &Path) -> io::Result<()> {
+        self.0.check_write(path)?;
+        self.0.base.lock_file(path)
+    }
+
+    fn unlock_file(&self, path: &Path) -> io::Result<()> {
+        // Always release locks, so that a database can be reopened after a
+        // simulated power loss.
+        self.0.base.unlock_file(path)
+    }
+
+    fn is_directory(&self, path: &Path) -> io::Result<bool> {
+        self.0.check_path(path)?;
+        self.0.base.is_directory(path)
+    }
+
+    fn get_absolute_path(&self, path: &Path) -> io::Result<PathBuf> {
+        self.0.base.get_absolute_path(path)
+    }
+}
+
+struct FaultInjectionRandomAccessFile<F> {
+    inner: Arc<Inner<F>>,
+    path: PathBuf,
+    file: Box<dyn RandomAccessFile>,
+}
+
+impl<F: FileSystem> RandomAccessFile for FaultInjectionRandomAccessFile<F> {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.check_path(&self.path)?;
+        self.file.read_at(offset, buf)
+    }
+}
+
+struct FaultInjectionWritableFile<F> {
+    inner: Arc<Inner<F>>,
+    path: PathBuf,
+    file: Box<dyn WritableFile>,
+}
+
+impl<F> FaultInjectionWritableFile<F> {
+    fn update_state(&self, synced: bool) {
+        let mut files = self.inner.files.lock().unwrap();
+        if let Some(state) = files.get_mut(&self.path) {
+            state.size = self.file.file_size();
+            if synced {
+                state.synced = state.size;
+            }
+        }
+    }
+}
+
+impl<F: FileSystem> WritableFile for FaultInjectionWritableFile<F> {
+    fn append(&mut self, data: &[u8]) -> io::Result<()> {
+        self.inner.check_write(&self.path)?;
+        self.file.append(data)?;
+        self.update_state(false);
+        Ok(())
+    }
+
+    fn truncate(&mut self, size: u64) -> io::Result<()> {
+        self.inner.check_write(&self.path)?;
+        self.file.truncate(size)?;
+        self.update_state(false);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.check_write(&self.path)?;
+        self.file.flush()
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.inner.check_sync(&self.path)?;
+        self.file.sync()?;
+        self.update_state(true);
+        Ok(())
+    }
+
+    fn fsync(&mut self) -> io::Result<()> {
+        self.inner.check_sync(&self.path)?;
+        self.file.fsync()?;
+        self.update_state(true);
+        Ok(())
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        self.inner.check_write(&self.path)?;
+        self.file.close()
+    }
+
+    fn file_size(&self) -> u64 {
+        self.file.file_size()
+    }
+}
+
+struct FaultInjectionDirectory<F> {
+    inner: Arc<Inner<F>>,
+    path: PathBuf,
+    dir: Box<dyn Directory>,
+}
+
+impl<F: FileSystem> Directory for FaultInjectionDirectory<F> {
+    fn fsync(&self) -> io::Result<()> {
+        self.inner.check_sync(&self.path)?;
+        self.dir.fsync()
+    }
+}
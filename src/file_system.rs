@@ -0,0 +1,1007 @@
+// Copyright 2022 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! File systems and clocks implemented in Rust.
+//!
+//! RocksDB performs all file and time related operations through its `Env`.
+//! Implementing [`FileSystem`] (and optionally [`Clock`]) allows to route
+//! these operations to a custom storage backend, e.g. an in-memory store
+//! for tests, an encrypted or remote store. The resulting [`Env`] is used
+//! with [`Options::set_env`].
+//!
+//! ```
+//! use rocksdb::{file_system::StdFileSystem, Env, Options, DB};
+//!
+//! let path = "_rust_rocksdb_file_system_example";
+//! {
+//!     let env = Env::from_file_system(StdFileSystem::default()).unwrap();
+//!     let mut opts = Options::default();
+//!     opts.create_if_missing(true);
+//!     opts.set_env(&env);
+//!     let db = DB::open(&opts, path).unwrap();
+//!     db.put(b"key", b"value").unwrap();
+//! }
+//! let _ = DB::destroy(&Options::default(), path);
+//! ```
+//!
+//! [`Env`]: crate::Env
+//! [`Options::set_env`]: crate::Options::set_env
+
+use std::collections::HashSet;
+use std::ffi::{CStr, CString, OsString};
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{ptr, slice, thread};
+
+use libc::{c_char, c_int, c_uchar, c_void, size_t};
+
+use crate::ffi;
+
+/// A file system used by RocksDB to store its files.
+///
+/// Errors are reported as [`io::Error`]s. An error of kind
+/// [`io::ErrorKind::NotFound`] tells RocksDB that the file does not exist,
+/// which is required for instance when a database is created.
+///
+/// Implementations must be thread safe: RocksDB calls them concurrently
+/// from foreground and background threads.
+pub trait FileSystem: Send + Sync + 'static {
+    /// Returns a name that identifies this file system.
+    fn name(&self) -> &CStr;
+
+    /// Opens an existing file for sequential reading.
+    fn new_sequential_file(&self, path: &Path) -> io::Result<Box<dyn SequentialFile>>;
+
+    /// Opens an existing file for random reads.
+    fn new_random_access_file(&self, path: &Path) -> io::Result<Box<dyn RandomAccessFile>>;
+
+    /// Creates a new file for writing, truncating any existing file with the
+    /// same name.
+    fn new_writable_file(&self, path: &Path) -> io::Result<Box<dyn WritableFile>>;
+
+    /// Opens a file for appending, creating it if it does not exist.
+    ///
+    /// Default: not supported.
+    fn reopen_writable_file(&self, _path: &Path) -> io::Result<Box<dyn WritableFile>> {
+        Err(unsupported("reopen_writable_file"))
+    }
+
+    /// Opens an existing directory so that it can be synced.
+    fn new_directory(&self, path: &Path) -> io::Result<Box<dyn Directory>>;
+
+    /// Returns whether a file or a directory exists at `path`.
+    fn file_exists(&self, path: &Path) -> io::Result<bool>;
+
+    /// Returns the names of the entries of the directory `dir`, relative to
+    /// `dir`.
+    fn get_children(&self, dir: &Path) -> io::Result<Vec<OsString>>;
+
+    /// Deletes the file at `path`.
+    fn delete_file(&self, path: &Path) -> io::Result<()>;
+
+    /// Creates the directory `path`. Fails if it already exists.
+    fn create_dir(&self, path: &Path) -> io::Result<()>;
+
+    /// Creates the directory `path` unless it already exists.
+    fn create_dir_if_missing(&self, path: &Path) -> io::Result<()>;
+
+    /// Deletes the empty directory `path`.
+    fn delete_dir(&self, path: &Path) -> io::Result<()>;
+
+    /// Returns the size of the file at `path` in bytes.
+    fn get_file_size(&self, path: &Path) -> io::Result<u64>;
+
+    /// Returns the last modification time of the file at `path`, in seconds
+    /// since the Unix epoch.
+    fn get_file_modification_time(&self, path: &Path) -> io::Result<u64>;
+
+    /// Atomically renames `src` to `target`, replacing `target` if it exists.
+    fn rename_file(&self, src: &Path, target: &Path) -> io::Result<()>;
+
+    /// Creates a hard link of `src` at `target`.
+    ///
+    /// Default: not supported. RocksDB falls back to copying files.
+    fn link_file(&self, _src: &Path, _target: &Path) -> io::Result<()> {
+        Err(unsupported("link_file"))
+    }
+
+    /// Locks the file at `path`, creating it if needed, to prevent
+    /// concurrent use of a database. Fails if the file is already locked.
+    fn lock_file(&self, path: &Path) -> io::Result<()>;
+
+    /// Releases a lock acquired with [`lock_file`](FileSystem::lock_file).
+    fn unlock_file(&self, path: &Path) -> io::Result<()>;
+
+    /// Returns whether `path` is a directory.
+    fn is_directory(&self, path: &Path) -> io::Result<bool>;
+
+    /// Returns the absolute form of `path`.
+    ///
+    /// Default: `path` when it is absolute, otherwise `path` relative to the
+    /// current working directory.
+    fn get_absolute_path(&self, path: &Path) -> io::Result<PathBuf> {
+        if path.is_absolute() {
+            Ok(path.to_path_buf())
+        } else {
+            Ok(std::env::current_dir()?.join(path))
+        }
+    }
+}
+
+/// A file read sequentially, e.g. a write ahead log during recovery.
+pub trait SequentialFile: Send {
+    /// Reads up to `buf.len()` bytes and returns the number of bytes read.
+    /// Returning less than requested signals the end of the file.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>;
+
+    /// Skips `n` bytes.
+    fn skip(&mut self, n: u64) -> io::Result<()>;
+}
+
+/// A file read at arbitrary offsets, e.g. an SST file.
+///
+/// Reads may be issued concurrently from several threads.
+pub trait RandomAccessFile: Send + Sync {
+    /// Reads up to `buf.len()` bytes starting at `offset` and returns the
+    /// number of bytes read. Returning less than requested signals the end
+    /// of the file.
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize>;
+}
+
+/// A file written sequentially.
+pub trait WritableFile: Send {
+    /// Appends `data` to the end of the file.
+    fn append(&mut self, data: &[u8]) -> io::Result<()>;
+
+    /// Truncates the file to `size` bytes.
+    ///
+    /// Default: does nothing.
+    fn truncate(&mut self, _size: u64) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Flushes buffered data to the file system.
+    fn flush(&mut self) -> io::Result<()>;
+
+    /// Makes the data of the file durable.
+    fn sync(&mut self) -> io::Result<()>;
+
+    /// Makes the data and the metadata of the file durable.
+    ///
+    /// Default: calls [`sync`](WritableFile::sync).
+    fn fsync(&mut self) -> io::Result<()> {
+        self.sync()
+    }
+
+    /// Closes the file. No other method is called afterwards.
+    fn close(&mut self) -> io::Result<()>;
+
+    /// Returns the current size of the file in bytes.
+    fn file_size(&self) -> u64;
+}
+
+/// A directory whose entries can be made durable.
+pub trait Directory: Send + Sync {
+    /// Makes the entries of the directory durable.
+    fn fsync(&self) -> io::Result<()>;
+}
+
+/// A clock used by RocksDB for timestamps, statistics and rate limiting.
+pub trait Clock: Send + Sync + 'static {
+    /// Returns the number of microseconds since the Unix epoch.
+    fn now_micros(&self) -> u64;
+
+    /// Returns the number of nanoseconds since some fixed point in time.
+    /// Only useful for computing deltas of time.
+    ///
+    /// Default: `now_micros() * 1000`.
+    fn now_nanos(&self) -> u64 {
+        self.now_micros() * 1000
+    }
+
+    /// Sleeps for the given number of microseconds.
+    ///
+    /// Default: [`thread::sleep`].
+    fn sleep_for_microseconds(&self, micros: i32) {
+        thread::sleep(Duration::from_micros(micros.max(0) as u64));
+    }
+
+    /// Returns the number of seconds since the Unix epoch.
+    ///
+    /// Default: `now_micros() / 1_000_000`.
+    fn current_time(&self) -> io::Result<i64> {
+        Ok((self.now_micros() / 1_000_000) as i64)
+    }
+}
+
+/// A [`Clock`] reading the system time.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_micros(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_micros() as u64)
+    }
+
+    fn now_nanos(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64)
+    }
+}
+
+/// A [`FileSystem`] backed by [`std::fs`].
+///
+/// File locks are only tracked within the current process.
+#[derive(Default)]
+pub struct StdFileSystem {
+    locks: Mutex<HashSet<PathBuf>>,
+}
+
+impl FileSystem for StdFileSystem {
+    fn name(&self) -> &CStr {
+        CStr::from_bytes_with_nul(b"StdFileSystem\0").unwrap()
+    }
+
+    fn new_sequential_file(&self, path: &Path) -> io::Result<Box<dyn SequentialFile>> {
+        Ok(Box::new(StdFile(fs::File::open(path)?)))
+    }
+
+    fn new_random_access_file(&self, path: &Path) -> io::Result<Box<dyn RandomAccessFile>> {
+        Ok(Box::new(StdFile(fs::File::open(path)?)))
+    }
+
+    fn new_writable_file(&self, path: &Path) -> io::Result<Box<dyn WritableFile>> {
+        let file = fs::File::create(path)?;
+        Ok(Box::new(StdWritableFile { file, size: 0 }))
+    }
+
+    fn reopen_writable_file(&self, path: &Path) -> io::Result<Box<dyn WritableFile>> {
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Box::new(StdWritableFile { file, size }))
+    }
+
+    fn new_directory(&self, path: &Path) -> io::Result<Box<dyn Directory>> {
+        if fs::metadata(path)?.is_dir() {
+            Ok(Box::new(StdDirectory(path.to_path_buf())))
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a directory", path.display()),
+            ))
+        }
+    }
+
+    fn file_exists(&self, path: &Path) -> io::Result<bool> {
+        Ok(path.exists())
+    }
+
+    fn get_children(&self, dir: &Path) -> io::Result<Vec<OsString>> {
+        fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.file_name()))
+            .collect()
+    }
+
+    fn delete_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir(path)
+    }
+
+    fn create_dir_if_missing(&self, path: &Path) -> io::Result<()> {
+        match fs::create_dir(path) {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && path.is_dir() => Ok(()),
+            result => result,
+        }
+    }
+
+    fn delete_dir(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir(path)
+    }
+
+    fn get_file_size(&self, path: &Path) -> io::Result<u64> {
+        Ok(fs::metadata(path)?.len())
+    }
+
+    fn get_file_modification_time(&self, path: &Path) -> io::Result<u64> {
+        let modified = fs::metadata(path)?.modified()?;
+        Ok(modified
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()))
+    }
+
+    fn rename_file(&self, src: &Path, target: &Path) -> io::Result<()> {
+        fs::rename(src, target)
+    }
+
+    fn link_file(&self, src: &Path, target: &Path) -> io::Result<()> {
+        fs::hard_link(src, target)
+    }
+
+    fn lock_file(&self, path: &Path) -> io::Result<()> {
+        let mut locks = self.locks.lock().unwrap();
+        if locks.contains(path) {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("lock {}: already held by process", path.display()),
+            ));
+        }
+        fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)?;
+        locks.insert(path.to_path_buf());
+        Ok(())
+    }
+
+    fn unlock_file(&self, path: &Path) -> io::Result<()> {
+        self.locks.lock().unwrap().remove(path);
+        Ok(())
+    }
+
+    fn is_directory(&self, path: &Path) -> io::Result<bool> {
+        Ok(fs::metadata(path)?.is_dir())
+    }
+}
+
+struct StdFile(fs::File);
+
+impl SequentialFile for StdFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut read = 0;
+        while read < buf.len() {
+            match self.0.read(&mut buf[read..]) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(read)
+    }
+
+    fn skip(&mut self, n: u64) -> io::Result<()> {
+        self.0.seek(SeekFrom::Current(n as i64)).map(|_| ())
+    }
+}
+
+impl RandomAccessFile for StdFile {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        let mut read = 0;
+        while read < buf.len() {
+            match read_at(&self.0, offset + read as u64, &mut buf[read..]) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(read)
+    }
+}
+
+#[cfg(unix)]
+fn read_at(file: &fs::File, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+    std::os::unix::fs::FileExt::read_at(file, buf, offset)
+}
+
+#[cfg(windows)]
+fn read_at(file: &fs::File, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+    std::os::windows::fs::FileExt::seek_read(file, buf, offset)
+}
+
+struct StdWritableFile {
+    file: fs::File,
+    size: u64,
+}
+
+impl WritableFile for StdWritableFile {
+    fn append(&mut self, data: &[u8]) -> io::Result<()> {
+        self.file.write_all(data)?;
+        self.size += data.len() as u64;
+        Ok(())
+    }
+
+    fn truncate(&mut self, size: u64) -> io::Result<()> {
+        self.file.set_len(size)?;
+        self.file.seek(SeekFrom::Start(size))?;
+        self.size = size;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.file.sync_data()
+    }
+
+    fn fsync(&mut self) -> io::Result<()> {
+        self.file.sync_all()
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn file_size(&self) -> u64 {
+        self.size
+    }
+}
+
+struct StdDirectory(PathBuf);
+
+impl Directory for StdDirectory {
+    #[cfg(unix)]
+    fn fsync(&self) -> io::Result<()> {
+        fs::File::open(&self.0)?.sync_all()
+    }
+
+    #[cfg(not(unix))]
+    fn fsync(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn unsupported(operation: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{operation} is not supported by this file system"),
+    )
+}
+
+/// Creates a C API `Env` using `fs` and, when given, `clock`.
+pub(crate) fn create_env<F, C>(fs: F, clock: Option<C>) -> *mut ffi::rocksdb_env_t
+where
+    F: FileSystem,
+    C: Clock,
+{
+    let fs_state = Box::into_raw(Box::new(FileSystemState {
+        name: fs.name().to_owned(),
+        fs,
+    }));
+    let fs_callbacks = file_system_callbacks::<F>();
+    let clock_callbacks = clock_callbacks::<C>();
+    match clock {
+        Some(clock) => unsafe {
+            ffi::rocksdb_ext_create_env(
+                fs_state.cast::<c_void>(),
+                ptr::addr_of!(fs_callbacks),
+                Box::into_raw(Box::new(clock)).cast::<c_void>(),
+                ptr::addr_of!(clock_callbacks),
+            )
+        },
+        None => unsafe {
+            ffi::rocksdb_ext_create_env(
+                fs_state.cast::<c_void>(),
+                ptr::addr_of!(fs_callbacks),
+                ptr::null_mut(),
+                ptr::null(),
+            )
+        },
+    }
+}
+
+struct FileSystemState<F> {
+    name: CString,
+    fs: F,
+}
+
+fn file_system_callbacks<F: FileSystem>() -> ffi::rocksdb_ext_filesystem_callbacks_t {
+    ffi::rocksdb_ext_filesystem_callbacks_t {
+        destructor: Some(fs_destructor_callback::<F>),
+        name: Some(fs_name_callback::<F>),
+        new_sequential_file: Some(new_sequential_file_callback::<F>),
+        new_random_access_file: Some(new_random_access_file_callback::<F>),
+        new_writable_file: Some(new_writable_file_callback::<F>),
+        reopen_writable_file: Some(reopen_writable_file_callback::<F>),
+        new_directory: Some(new_directory_callback::<F>),
+        file_exists: Some(file_exists_callback::<F>),
+        get_children: Some(get_children_callback::<F>),
+        delete_file: Some(delete_file_callback::<F>),
+        create_dir: Some(create_dir_callback::<F>),
+        create_dir_if_missing: Some(create_dir_if_missing_callback::<F>),
+        delete_dir: Some(delete_dir_callback::<F>),
+        get_file_size: Some(get_file_size_callback::<F>),
+        get_file_modification_time: Some(get_file_modification_time_callback::<F>),
+        rename_file: Some(rename_file_callback::<F>),
+        link_file: Some(link_file_callback::<F>),
+        lock_file: Some(lock_file_callback::<F>),
+        unlock_file: Some(unlock_file_callback::<F>),
+        is_directory: Some(is_directory_callback::<F>),
+        get_absolute_path: Some(get_absolute_path_callback::<F>),
+        sequential_read: Some(sequential_read_callback),
+        sequential_skip: Some(sequential_skip_callback),
+        sequential_destroy: Some(sequential_destroy_callback),
+        random_access_read: Some(random_access_read_callback),
+        random_access_destroy: Some(random_access_destroy_callback),
+        writable_append: Some(writable_append_callback),
+        writable_truncate: Some(writable_truncate_callback),
+        writable_flush: Some(writable_flush_callback),
+        writable_sync: Some(writable_sync_callback),
+        writable_fsync: Some(writable_fsync_callback),
+        writable_close: Some(writable_close_callback),
+        writable_file_size: Some(writable_file_size_callback),
+        writable_destroy: Some(writable_destroy_callback),
+        directory_fsync: Some(directory_fsync_callback),
+        directory_destroy: Some(directory_destroy_callback),
+    }
+}
+
+fn clock_callbacks<C: Clock>() -> ffi::rocksdb_ext_clock_callbacks_t {
+    ffi::rocksdb_ext_clock_callbacks_t {
+        destructor: Some(clock_destructor_callback::<C>),
+        now_micros: Some(now_micros_callback::<C>),
+        now_nanos: Some(now_nanos_callback::<C>),
+        sleep_for_microseconds: Some(sleep_for_microseconds_callback::<C>),
+        get_current_time: Some(get_current_time_callback::<C>),
+    }
+}
+
+/// Converts an `io::Result` into a status code understood by the C++ side,
+/// passing the value to `on_ok` on success.
+unsafe fn status<T>(
+    result: io::Result<T>,
+    errmsg: *mut *mut c_char,
+    on_ok: impl FnOnce(T),
+) -> c_int {
+    match result {
+        Ok(value) => {
+            on_ok(value);
+            ffi::rocksdb_ext_io_ok as c_int
+        }
+        Err(e) => {
+            let message = CString::new(e.to_string().replace('\0', "")).unwrap_or_default();
+            *errmsg = libc::strdup(message.as_ptr());
+            error_code(&e)
+        }
+    }
+}
+
+fn error_code(e: &io::Error) -> c_int {
+    let code = match e.kind() {
+        io::ErrorKind::NotFound => ffi::rocksdb_ext_io_not_found,
+        io::ErrorKind::Unsupported => ffi::rocksdb_ext_io_not_supported,
+        io::ErrorKind::InvalidInput => ffi::rocksdb_ext_io_invalid_argument,
+        io::ErrorKind::InvalidData => ffi::rocksdb_ext_io_corruption,
+        io::ErrorKind::WouldBlock => ffi::rocksdb_ext_io_busy,
+        _ if e.raw_os_error() == Some(libc::ENOSPC) => ffi::rocksdb_ext_io_no_space,
+        _ => ffi::rocksdb_ext_io_error,
+    };
+    code as c_int
+}
+
+#[cfg(unix)]
+pub(crate) unsafe fn to_path<'a>(ptr: *const c_char, len: size_t) -> io::Result<&'a Path> {
+    use std::os::unix::ffi::OsStrExt;
+    let bytes = slice::from_raw_parts(ptr as *const u8, len);
+    Ok(Path::new(std::ffi::OsStr::from_bytes(bytes)))
+}
+
+#[cfg(not(unix))]
+pub(crate) unsafe fn to_path<'a>(ptr: *const c_char, len: size_t) -> io::Result<&'a Path> {
+    let bytes = slice::from_raw_parts(ptr as *const u8, len);
+    std::str::from_utf8(bytes).map(Path::new).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("path is not valid UTF-8: {}", e),
+        )
+    })
+}
+
+/// Like [`to_path`], but replaces invalid UTF-8 instead of failing, for paths
+/// that are only reported and never opened.
+pub(crate) unsafe fn to_path_buf_lossy(ptr: *const c_char, len: size_t) -> PathBuf {
+    to_path(ptr, len).map_or_else(
+        |_| {
+            let bytes = slice::from_raw_parts(ptr as *const u8, len);
+            PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+        },
+        Path::to_path_buf,
+    )
+}
+
+#[cfg(unix)]
+unsafe fn push_path(vec: *mut ffi::rocksdb_ext_string_vector_t, path: &std::ffi::OsStr) {
+    use std::os::unix::ffi::OsStrExt;
+    let bytes = path.as_bytes();
+    ffi::rocksdb_ext_string_vector_push(vec, bytes.as_ptr() as *const c_char, bytes.len());
+}
+
+#[cfg(not(unix))]
+unsafe fn push_path(vec: *mut ffi::rocksdb_ext_string_vector_t, path: &std::ffi::OsStr) {
+    let path = path.to_string_lossy();
+    ffi::rocksdb_ext_string_vector_push(vec, path.as_ptr() as *const c_char, path.len());
+}
+
+unsafe fn fs<'a, F: FileSystem>(raw_self: *mut c_void) -> &'a F {
+    &(*(raw_self as *const FileSystemState<F>)).fs
+}
+
+unsafe extern "C" fn fs_destructor_callback<F: FileSystem>(raw_self: *mut c_void) {
+    drop(Box::from_raw(raw_self as *mut FileSystemState<F>));
+}
+
+unsafe extern "C" fn fs_name_callback<F: FileSystem>(raw_self: *mut c_void) -> *const c_char {
+    (*(raw_self as *const FileSystemState<F>)).name.as_ptr()
+}
+
+unsafe extern "C" fn new_sequential_file_callback<F: FileSystem>(
+    raw_self: *mut c_void,
+    fname: *const c_char,
+    fname_len: size_t,
+    file: *mut *mut c_void,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let result =
+        to_path(fname, fname_len).and_then(|path| fs::<F>(raw_self).new_sequential_file(path));
+    status(result, errmsg, |f| {
+        *file = Box::into_raw(Box::new(f)).cast::<c_void>();
+    })
+}
+
+unsafe extern "C" fn new_random_access_file_callback<F: FileSystem>(
+    raw_self: *mut c_void,
+    fname: *const c_char,
+    fname_len: size_t,
+    file: *mut *mut c_void,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let result =
+        to_path(fname, fname_len).and_then(|path| fs::<F>(raw_self).new_random_access_file(path));
+    status(result, errmsg, |f| {
+        *file = Box::into_raw(Box::new(f)).cast::<c_void>();
+    })
+}
+
+unsafe extern "C" fn new_writable_file_callback<F: FileSystem>(
+    raw_self: *mut c_void,
+    fname: *const c_char,
+    fname_len: size_t,
+    file: *mut *mut c_void,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let result =
+        to_path(fname, fname_len).and_then(|path| fs::<F>(raw_self).new_writable_file(path));
+    status(result, errmsg, |f| {
+        *file = Box::into_raw(Box::new(f)).cast::<c_void>();
+    })
+}
+
+unsafe extern "C" fn reopen_writable_file_callback<F: FileSystem>(
+    raw_self: *mut c_void,
+    fname: *const c_char,
+    fname_len: size_t,
+    file: *mut *mut c_void,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let result =
+        to_path(fname, fname_len).and_then(|path| fs::<F>(raw_self).reopen_writable_file(path));
+    status(result, errmsg, |f| {
+        *file = Box::into_raw(Box::new(f)).cast::<c_void>();
+    })
+}
+
+unsafe extern "C" fn new_directory_callback<F: FileSystem>(
+    raw_self: *mut c_void,
+    name: *const c_char,
+    name_len: size_t,
+    dir: *mut *mut c_void,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let result = to_path(name, name_len).and_then(|path| fs::<F>(raw_self).new_directory(path));
+    status(result, errmsg, |d| {
+        *dir = Box::into_raw(Box::new(d)).cast::<c_void>();
+    })
+}
+
+unsafe extern "C" fn file_exists_callback<F: FileSystem>(
+    raw_self: *mut c_void,
+    fname: *const c_char,
+    fname_len: size_t,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    match to_path(fname, fname_len).and_then(|path| fs::<F>(raw_self).file_exists(path)) {
+        Ok(false) => ffi::rocksdb_ext_io_not_found as c_int,
+        result => status(result, errmsg, |_| ()),
+    }
+}
+
+unsafe extern "C" fn get_children_callback<F: FileSystem>(
+    raw_self: *mut c_void,
+    dir: *const c_char,
+    dir_len: size_t,
+    result: *mut ffi::rocksdb_ext_string_vector_t,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let children = to_path(dir, dir_len).and_then(|path| fs::<F>(raw_self).get_children(path));
+    status(children, errmsg, |children| {
+        for child in children {
+            push_path(result, &child);
+        }
+    })
+}
+
+unsafe extern "C" fn delete_file_callback<F: FileSystem>(
+    raw_self: *mut c_void,
+    fname: *const c_char,
+    fname_len: size_t,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let result = to_path(fname, fname_len).and_then(|path| fs::<F>(raw_self).delete_file(path));
+    status(result, errmsg, |()| ())
+}
+
+unsafe extern "C" fn create_dir_callback<F: FileSystem>(
+    raw_self: *mut c_void,
+    name: *const c_char,
+    name_len: size_t,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let result = to_path(name, name_len).and_then(|path| fs::<F>(raw_self).create_dir(path));
+    status(result, errmsg, |()| ())
+}
+
+unsafe extern "C" fn create_dir_if_missing_callback<F: FileSystem>(
+    raw_self: *mut c_void,
+    name: *const c_char,
+    name_len: size_t,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let result =
+        to_path(name, name_len).and_then(|path| fs::<F>(raw_self).create_dir_if_missing(path));
+    status(result, errmsg, |()| ())
+}
+
+unsafe extern "C" fn delete_dir_callback<F: FileSystem>(
+    raw_self: *mut c_void,
+    name: *const c_char,
+    name_len: size_t,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let result = to_path(name, name_len).and_then(|path| fs::<F>(raw_self).delete_dir(path));
+    status(result, errmsg, |()| ())
+}
+
+unsafe extern "C" fn get_file_size_callback<F: FileSystem>(
+    raw_self: *mut c_void,
+    fname: *const c_char,
+    fname_len: size_t,
+    size: *mut u64,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let result = to_path(fname, fname_len).and_then(|path| fs::<F>(raw_self).get_file_size(path));
+    status(result, errmsg, |s| *size = s)
+}
+
+unsafe extern "C" fn get_file_modification_time_callback<F: FileSystem>(
+    raw_self: *mut c_void,
+    fname: *const c_char,
+    fname_len: size_t,
+    mtime: *mut u64,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let result = to_path(fname, fname_len)
+        .and_then(|path| fs::<F>(raw_self).get_file_modification_time(path));
+    status(result, errmsg, |t| *mtime = t)
+}
+
+unsafe extern "C" fn rename_file_callback<F: FileSystem>(
+    raw_self: *mut c_void,
+    src: *const c_char,
+    src_len: size_t,
+    target: *const c_char,
+    target_len: size_t,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let result = to_path(src, src_len).and_then(|src| {
+        to_path(target, target_len).and_then(|target| fs::<F>(raw_self).rename_file(src, target))
+    });
+    status(result, errmsg, |()| ())
+}
+
+unsafe extern "C" fn link_file_callback<F: FileSystem>(
+    raw_self: *mut c_void,
+    src: *const c_char,
+    src_len: size_t,
+    target: *const c_char,
+    target_len: size_t,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let result = to_path(src, src_len).and_then(|src| {
+        to_path(target, target_len).and_then(|target| fs::<F>(raw_self).link_file(src, target))
+    });
+    status(result, errmsg, |()| ())
+}
+
+unsafe extern "C" fn lock_file_callback<F: FileSystem>(
+    raw_self: *mut c_void,
+    fname: *const c_char,
+    fname_len: size_t,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let result = to_path(fname, fname_len).and_then(|path| fs::<F>(raw_self).lock_file(path));
+    status(result, errmsg, |()| ())
+}
+
+unsafe extern "C" fn unlock_file_callback<F: FileSystem>(
+    raw_self: *mut c_void,
+    fname: *const c_char,
+    fname_len: size_t,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let result = to_path(fname, fname_len).and_then(|path| fs::<F>(raw_self).unlock_file(path));
+    status(result, errmsg, |()| ())
+}
+
+unsafe extern "C" fn is_directory_callback<F: FileSystem>(
+    raw_self: *mut c_void,
+    path: *const c_char,
+    path_len: size_t,
+    is_dir: *mut c_uchar,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let result = to_path(path, path_len).and_then(|path| fs::<F>(raw_self).is_directory(path));
+    status(result, errmsg, |d| *is_dir = c_uchar::from(d))
+}
+
+unsafe extern "C" fn get_absolute_path_callback<F: FileSystem>(
+    raw_self: *mut c_void,
+    path: *const c_char,
+    path_len: size_t,
+    result: *mut ffi::rocksdb_ext_string_vector_t,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let absolute =
+        to_path(path, path_len).and_then(|path| fs::<F>(raw_self).get_absolute_path(path));
+    status(absolute, errmsg, |p| push_path(result, p.as_os_str()))
+}
+
+unsafe extern "C" fn sequential_read_callback(
+    file: *mut c_void,
+    n: size_t,
+    scratch: *mut c_char,
+    read: *mut size_t,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let file = &mut *(file as *mut Box<dyn SequentialFile>);
+    let buf = slice::from_raw_parts_mut(scratch as *mut u8, n);
+    status(file.read(buf), errmsg, |r| *read = r)
+}
+
+unsafe extern "C" fn sequential_skip_callback(
+    file: *mut c_void,
+    n: u64,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let file = &mut *(file as *mut Box<dyn SequentialFile>);
+    status(file.skip(n), errmsg, |()| ())
+}
+
+unsafe extern "C" fn sequential_destroy_callback(file: *mut c_void) {
+    drop(Box::from_raw(file as *mut Box<dyn SequentialFile>));
+}
+
+unsafe extern "C" fn random_access_read_callback(
+    file: *mut c_void,
+    offset: u64,
+    n: size_t,
+    scratch: *mut c_char,
+    read: *mut size_t,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let file = &*(file as *const Box<dyn RandomAccessFile>);
+    let buf = slice::from_raw_parts_mut(scratch as *mut u8, n);
+    status(file.read_at(offset, buf), errmsg, |r| *read = r)
+}
+
+unsafe extern "C" fn random_access_destroy_callback(file: *mut c_void) {
+    drop(Box::from_raw(file as *mut Box<dyn RandomAccessFile>));
+}
+
+unsafe fn writable<'a>(file: *mut c_void) -> &'a mut Box<dyn WritableFile> {
+    &mut *(file as *mut Box<dyn WritableFile>)
+}
+
+unsafe extern "C" fn writable_append_callback(
+    file: *mut c_void,
+    data: *const c_char,
+    n: size_t,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let data = slice::from_raw_parts(data as *const u8, n);
+    status(writable(file).append(data), errmsg, |()| ())
+}
+
+unsafe extern "C" fn writable_truncate_callback(
+    file: *mut c_void,
+    size: u64,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    status(writable(file).truncate(size), errmsg, |()| ())
+}
+
+unsafe extern "C" fn writable_flush_callback(file: *mut c_void, errmsg: *mut *mut c_char) -> c_int {
+    status(writable(file).flush(), errmsg, |()| ())
+}
+
+unsafe extern "C" fn writable_sync_callback(file: *mut c_void, errmsg: *mut *mut c_char) -> c_int {
+    status(writable(file).sync(), errmsg, |()| ())
+}
+
+unsafe extern "C" fn writable_fsync_callback(file: *mut c_void, errmsg: *mut *mut c_char) -> c_int {
+    status(writable(file).fsync(), errmsg, |()| ())
+}
+
+unsafe extern "C" fn writable_close_callback(file: *mut c_void, errmsg: *mut *mut c_char) -> c_int {
+    status(writable(file).close(), errmsg, |()| ())
+}
+
+unsafe extern "C" fn writable_file_size_callback(file: *mut c_void) -> u64 {
+    writable(file).file_size()
+}
+
+unsafe extern "C" fn writable_destroy_callback(file: *mut c_void) {
+    drop(Box::from_raw(file as *mut Box<dyn WritableFile>));
+}
+
+unsafe extern "C" fn directory_fsync_callback(dir: *mut c_void, errmsg: *mut *mut c_char) -> c_int {
+    let dir = &*(dir as *const Box<dyn Directory>);
+    status(dir.fsync(), errmsg, |()| ())
+}
+
+unsafe extern "C" fn directory_destroy_callback(dir: *mut c_void) {
+    drop(Box::from_raw(dir as *mut Box<dyn Directory>));
+}
+
+unsafe extern "C" fn clock_destructor_callback<C: Clock>(raw_self: *mut c_void) {
+    drop(Box::from_raw(raw_self as *mut C));
+}
+
+unsafe extern "C" fn now_micros_callback<C: Clock>(raw_self: *mut c_void) -> u64 {
+    (*(raw_self as *const C)).now_micros()
+}
+
+unsafe extern "C" fn now_nanos_callback<C: Clock>(raw_self: *mut c_void) -> u64 {
+    (*(raw_self as *const C)).now_nanos()
+}
+
+unsafe extern "C" fn sleep_for_microseconds_callback<C: Clock>(
+    raw_self: *mut c_void,
+    micros: c_int,
+) {
+    (*(raw_self as *const C)).sleep_for_microseconds(micros);
+}
+
+unsafe extern "C" fn get_current_time_callback<C: Clock>(
+    raw_self: *mut c_void,
+    unix_time: *mut i64,
+    errmsg: *mut *mut c_char,
+) -> c_int {
+    let result = (*(raw_self as *const C)).current_time();
+    status(result, errmsg, |t| *unix_time = t)
+}
@@ -0,0 +1,340 @@
+// Copyright 2020 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tables with secondary indexes.
+//!
+//! An [`IndexedTable`] stores its entries in a primary column family of a
+//! [`TransactionDB`], and each of its indexes in another column family.  An
+//! index maps the values returned by its extractor for an entry to the key
+//! of the entry.  Writes to the table update its indexes in the same
+//! transaction, so readers never see an index out of date.
+//!
+//! ```
+//! use rocksdb::{indexed_table::IndexedTable, Options, TransactionDB, TransactionDBOptions, DB};
+//!
+//! let path = "_path_for_rocksdb_storage_indexed_table";
+//! {
+//!     let mut opts = Options::default();
+//!     opts.create_if_missing(true);
+//!     opts.create_missing_column_families(true);
+//!     let db: TransactionDB = TransactionDB::open_cf(
+//!         &opts,
+//!         &TransactionDBOptions::default(),
+//!         path,
+//!         ["users", "users_by_city"],
+//!     )
+//!     .unwrap();
+//!
+//!     // Values are "name,city".
+//!     let city = |_key: &[u8], value: &[u8]| {
+//!         value.split(|&b| b == b',').skip(1).map(<[u8]>::to_vec).collect()
+//!     };
+//!     let users = IndexedTable::new(&db, db.cf_handle("users").unwrap()).with_index(
+//!         "by_city",
+//!         db.cf_handle("users_by_city").unwrap(),
+//!         city,
+//!     );
+//!
+//!     users.put(b"1", b"alice,paris").unwrap();
+//!     users.put(b"2", b"bob,oslo").unwrap();
+//!     users.put(b"1", b"alice,oslo").unwrap();
+//!     assert_eq!(
+//!         users.lookup_by_index("by_city", b"oslo").unwrap(),
+//!         vec![b"1".to_vec(), b"2".to_vec()]
+//!     );
+//!     assert!(users.lookup_by_index("by_city", b"paris").unwrap().is_empty());
+//! }
+//! let _ = DB::destroy(&Options::default(), path);
+//! ```
+//!
+//! [`TransactionDB`]: crate::TransactionDB
+
+use std::collections::BTreeSet;
+
+use crate::{
+    keycodec::{self, Encoded},
+    AsColumnFamilyRef, Error, IteratorMode, PrefixRange, ReadOptions, ThreadMode, Transaction,
+    TransactionDB, TransactionOptions, WriteOptions,
+};
+
+type Extractor = Box<dyn Fn(&[u8], &[u8]) -> Vec<Vec<u8>> + Send + Sync>;
+
+struct Index<CF> {
+    name: String,
+    cf: CF,
+    extractor: Extractor,
+}
+
+/// An entry of an index: the key of an entry of the table, and one of the
+/// values the extractor of the index returned for it.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct IndexEntry {
+    pub index_value: Vec<u8>,
+    pub primary_key: Vec<u8>,
+}
+
+impl IndexEntry {
+    fn encode(&self) -> Vec<u8> {
+        index_key(&self.index_value, &self.primary_key)
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let (index_value, primary_key) = keycodec::decode(bytes)?;
+        Ok(Self {
+            index_value,
+            primary_key,
+        })
+    }
+}
+
+/// The differences between an index and the entries of its table, as found
+/// by [`IndexedTable::verify_index`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexReport {
+    /// Entries the index lacks.
+    pub missing: Vec<IndexEntry>,
+    /// Entries of the index which do not match an entry of the table.
+    pub dangling: Vec<IndexEntry>,
+}
+
+impl IndexReport {
+    pub fn is_consistent(&self) -> bool {
+        self.missing.is_empty() && self.dangling.is_empty()
+    }
+}
+
+fn index_key(index_value: &[u8], primary_key: &[u8]) -> Vec<u8> {
+    keycodec::encode(&(index_value.to_vec(), primary_key.to_vec()))
+}
+
+/// A table whose secondary indexes are kept consistent with it.
+///
+/// Keys of an index column family are the index values followed by the
+/// primary keys, in the encoding of [`keycodec`], so an index value may be
+/// shared by many entries.  Index column families should only be written
+/// through the table.
+pub struct IndexedTable<'db, T: ThreadMode, CF> {
+    db: &'db TransactionDB<T>,
+    primary: CF,
+    indexes: Vec<Index<CF>>,
+}
+
+impl<'db, T: ThreadMode, CF: AsColumnFamilyRef> IndexedTable<'db, T, CF> {
+    /// Creates a table stored in the column family `primary`, without any
+    /// index.
+    pub fn new(db: &'db TransactionDB<T>, primary: CF) -> Self {
+        Self {
+            db,
+            primary,
+            indexes: Vec::new(),
+        }
+    }
+
+    /// Adds the index `name`, stored in the column family `cf`.
+    ///
+    /// `extractor` returns the index values of an entry from its key and
+    /// value, which may be none.  It must be deterministic, since it is
+    /// called again on the old value of an entry to find the index entries
+    /// to remove.  Adding an index to a table with entries requires
+    /// [`rebuild_index`](Self::rebuild_index).
+    pub fn with_index<F>(mut self, name: impl Into<String>, cf: CF, extractor: F) -> Self
+    where
+        F: Fn(&[u8], &[u8]) -> Vec<Vec<u8>> + Send + Sync + 'static,
+    {
+        self.indexes.push(Index {
+            name: name.into(),
+            cf,
+            extractor: Box::new(extractor),
+        });
+        self
+    }
+
+    fn index(&self, name: &str) -> Result<&Index<CF>, Error> {
+        self.indexes
+            .iter()
+            .find(|index| index.name == name)
+            .ok_or_else(|| Error::new(format!("Invalid argument: unknown index {}", name)))
+    }
+
+    pub fn get<K: AsRef<[u8]>>(&self, key: K) -> Result<Option<Vec<u8>>, Error> {
+        self.db.get_cf(&self.primary, key)
+    }
+
+    /// Inserts or replaces an entry and updates the indexes, in a
+    /// transaction of its own.
+    pub fn put<K, V>(&self, key: K, value: V) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let txn = self.db.transaction();
+        self.put_in(&txn, key, value)?;
+        txn.commit()
+    }
+
+    /// Inserts or replaces an entry and updates the indexes in `txn`.
+    pub fn put_in<K, V>(
+        &self,
+        txn: &Transaction<TransactionDB<T>>,
+        key: K,
+        value: V,
+    ) -> Result<(), Error>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let (key, value) = (key.as_ref(), value.as_ref());
+        self.update_indexes(txn, key, Some(value))?;
+        txn.put_cf(&self.primary, key, value)
+    }
+
+    /// Deletes an entry and its index entries, in a transaction of its own.
+    /// Does nothing if the entry does not exist.
+    pub fn delete<K: AsRef<[u8]>>(&self, key: K) -> Result<(), Error> {
+        let txn = self.db.transaction();
+        self.delete_in(&txn, key)?;
+        txn.commit()
+    }
+
+    /// Deletes an entry and its index entries in `txn`.
+    pub fn delete_in<K: AsRef<[u8]>>(
+        &self,
+        txn: &Transaction<TransactionDB<T>>,
+        key: K,
+    ) -> Result<(), Error> {
+        let key = key.as_ref();
+        self.update_indexes(txn, key, None)?;
+        txn.delete_cf(&self.primary, key)
+    }
+
+    /// Replaces the index entries of the old value of `key`, which is locked
+    /// until `txn` ends, by those of `value`.
+    fn update_indexes(
+        &self,
+        txn: &Transaction<TransactionDB<T>>,
+        key: &[u8],
+        value: Option<&[u8]>,
+    ) -> Result<(), Error> {
+        let old_value = txn.get_for_update_cf(&self.primary, key, true)?;
+        for index in &self.indexes {
+            let old_values = match &old_value {
+                Some(old_value) => (index.extractor)(key, old_value),
+                None => Vec::new(),
+            };
+            let new_values = match value {
+                Some(value) => (index.extractor)(key, value),
+                None => Vec::new(),
+            };
+            for old in &old_values {
+                if !new_values.contains(old) {
+                    txn.delete_cf(&index.cf, index_key(old, key))?;
+                }
+            }
+            for new in &new_values {
+                if !old_values.contains(new) {
+                    txn.put_cf(&index.cf, index_key(new, key), b"")?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the keys of the entries which have `index_value` in the index
+    /// `index`, in ascending order.
+    pub fn lookup_by_index<V: AsRef<[u8]>>(
+        &self,
+        index: &str,
+        index_value: V,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let index = self.index(index)?;
+        let mut readopts = ReadOptions::default();
+        readopts.set_iterate_range(PrefixRange(Encoded((index_value.as_ref().to_vec(),))));
+        self.db
+            .iterator_cf_opt(&index.cf, readopts, IteratorMode::Start)
+            .map(|item| Ok(IndexEntry::decode(&item?.0)?.primary_key))
+            .collect()
+    }
+
+    /// Compares the index `index` with the entries of the table, as of a
+    /// snapshot.  Both are read into memory.
+    pub fn verify_index(&self, index: &str) -> Result<IndexReport, Error> {
+        let index = self.index(index)?;
+        let snapshot = self.db.snapshot();
+        self.compare(
+            index,
+            snapshot.iterator_cf(&self.primary, IteratorMode::Start),
+            snapshot.iterator_cf(&index.cf, IteratorMode::Start),
+        )
+    }
+
+    /// Adds the missing entries of the index `index`, and removes its
+    /// dangling entries, in a single transaction.  Returns the differences
+    /// which were repaired.
+    ///
+    /// The table and the index are compared as of the start of the
+    /// transaction.  If the table is written meanwhile such that a repair
+    /// conflicts with the write, the rebuild fails with a `Busy` error and
+    /// can be retried.
+    pub fn rebuild_index(&self, index: &str) -> Result<IndexReport, Error> {
+        let index = self.index(index)?;
+        let mut txn_opts = TransactionOptions::default();
+        txn_opts.set_snapshot(true);
+        let txn = self.db.transaction_opt(&WriteOptions::default(), &txn_opts);
+        let snapshot = txn.snapshot();
+        let report = self.compare(
+            index,
+            snapshot.iterator_cf(&self.primary, IteratorMode::Start),
+            snapshot.iterator_cf(&index.cf, IteratorMode::Start),
+        )?;
+        drop(snapshot);
+        for entry in &report.dangling {
+            txn.delete_cf(&index.cf, entry.encode())?;
+        }
+        for entry in &report.missing {
+            txn.put_cf(&index.cf, entry.encode(), b"")?;
+        }
+        txn.commit()?;
+        Ok(report)
+    }
+
+    fn compare<P, I>(
+        &self,
+        index: &Index<CF>,
+        primary_iter: P,
+        index_iter: I,
+    ) -> Result<IndexReport, Error>
+    where
+        P: Iterator<Item = Result<(Box<[u8]>, Box<[u8]>), Error>>,
+        I: Iterator<Item = Result<(Box<[u8]>, Box<[u8]>), Error>>,
+    {
+        let mut expected = BTreeSet::new();
+        for item in primary_iter {
+            let (key, value) = item?;
+            for index_value in (index.extractor)(&key, &value) {
+                expected.insert(IndexEntry {
+                    index_value,
+                    primary_key: key.to_vec(),
+                });
+            }
+        }
+        let mut actual = BTreeSet::new();
+        for item in index_iter {
+            actual.insert(IndexEntry::decode(&item?.0)?);
+        }
+        Ok(IndexReport {
+            missing: expected.difference(&actual).cloned().collect(),
+            dangling: actual.difference(&expected).cloned().collect(),
+        })
+    }
+}
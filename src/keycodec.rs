@@ -0,0 +1,241 @@
+// Copyright 2020 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Order-preserving encoding of keys.
+//!
+//! [`encode`] turns integers, booleans, strings, byte strings, optional
+//! fields and tuples of those into bytes whose byte-wise order, the order of
+//! the default comparator, matches the order of the values.  [`decode`]
+//! turns the bytes back into the value.
+//!
+//! The encoding of a tuple starts with the encoding of its first fields, so
+//! [`Encoded`] can turn a tuple into a bound or, with [`PrefixRange`], into
+//! the range of keys starting with the tuple:
+//!
+//! ```
+//! use rocksdb::{
+//!     keycodec::{self, Encoded},
+//!     Options, PrefixRange, ReadOptions, DB,
+//! };
+//!
+//! let path = "_path_for_rocksdb_storage_keycodec";
+//! {
+//!     let db = DB::open_default(path).unwrap();
+//!     for (user, seq) in [(1u64, 2i64), (1, -5), (2, 0), (10, 1)] {
+//!         db.put(keycodec::encode(&(user, seq)), b"").unwrap();
+//!     }
+//!
+//!     let mut readopts = ReadOptions::default();
+//!     readopts.set_iterate_range(PrefixRange(Encoded((1u64,))));
+//!     let keys: Vec<(u64, i64)> = db
+//!         .iterator_opt(rocksdb::IteratorMode::Start, readopts)
+//!         .map(|item| keycodec::decode(&item.unwrap().0).unwrap())
+//!         .collect();
+//!     assert_eq!(keys, [(1, -5), (1, 2)]);
+//! }
+//! let _ = DB::destroy(&Options::default(), path);
+//! ```
+//!
+//! [`PrefixRange`]: crate::PrefixRange
+
+use std::convert::TryInto;
+
+use crate::Error;
+
+/// A type with an order-preserving encoding.
+///
+/// Unsigned integers are encoded big-endian, and signed integers likewise
+/// with the sign bit flipped, so that negative numbers come first.  Booleans
+/// are one byte.  Strings and byte strings end with `0x00 0x01`, and their
+/// zero bytes are escaped as `0x00 0xff`, so that a shorter string comes
+/// before the strings it is a prefix of whatever field follows it.  `None`
+/// is `0x00`, and `Some` is `0x01` followed by its value.  Tuples are the
+/// concatenation of their fields.
+pub trait OrderedKey: Sized {
+    /// Appends the encoding of `self` to `out`.
+    fn encode_ordered(&self, out: &mut Vec<u8>);
+
+    /// Decodes a value from the start of `bytes` and advances it past the
+    /// value, or returns `None` if `bytes` does not start with one.
+    fn decode_ordered(bytes: &mut &[u8]) -> Option<Self>;
+}
+
+/// Returns the order-preserving encoding of `key`.
+pub fn encode<K: OrderedKey>(key: &K) -> Vec<u8> {
+    let mut out = Vec::new();
+    key.encode_ordered(&mut out);
+    out
+}
+
+/// Decodes a key encoded by [`encode`].  Fails with a corruption error if
+/// `bytes` is not exactly the encoding of a `K`.
+pub fn decode<K: OrderedKey>(mut bytes: &[u8]) -> Result<K, Error> {
+    match K::decode_ordered(&mut bytes) {
+        Some(key) if bytes.is_empty() => Ok(key),
+        _ => Err(Error::new(
+            "Corruption: invalid order-preserving key encoding".to_owned(),
+        )),
+    }
+}
+
+/// A key converted into bytes by [`encode`], for use as a bound of
+/// [`ReadOptions::set_iterate_range`](crate::ReadOptions::set_iterate_range)
+/// and of [`PrefixRange`](crate::PrefixRange).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Encoded<K>(pub K);
+
+impl<K: OrderedKey> From<Encoded<K>> for Vec<u8> {
+    fn from(key: Encoded<K>) -> Self {
+        encode(&key.0)
+    }
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+    if bytes.len() < len {
+        return None;
+    }
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Some(head)
+}
+
+macro_rules! impl_ordered_key_unsigned {
+    ($($t:ty),*) => {$(
+        impl OrderedKey for $t {
+            fn encode_ordered(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_be_bytes());
+            }
+
+            fn decode_ordered(bytes: &mut &[u8]) -> Option<Self> {
+                let head = take(bytes, std::mem::size_of::<$t>())?;
+                Some(<$t>::from_be_bytes(head.try_into().unwrap()))
+            }
+        }
+    )*};
+}
+
+impl_ordered_key_unsigned!(u8, u16, u32, u64, u128);
+
+macro_rules! impl_ordered_key_signed {
+    ($($t:ty => $u:ty),*) => {$(
+        impl OrderedKey for $t {
+            fn encode_ordered(&self, out: &mut Vec<u8>) {
+                ((*self as $u) ^ (1 << (<$u>::BITS - 1))).encode_ordered(out);
+            }
+
+            fn decode_ordered(bytes: &mut &[u8]) -> Option<Self> {
+                <$u>::decode_ordered(bytes).map(|v| (v ^ (1 << (<$u>::BITS - 1))) as $t)
+            }
+        }
+    )*};
+}
+
+impl_ordered_key_signed!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128);
+
+impl OrderedKey for bool {
+    fn encode_ordered(&self, out: &mut Vec<u8>) {
+        out.push(u8::from(*self));
+    }
+
+    fn decode_ordered(bytes: &mut &[u8]) -> Option<Self> {
+        match u8::decode_ordered(bytes)? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
+fn encode_ordered_bytes(bytes: &[u8], out: &mut Vec<u8>) {
+    for &byte in bytes {
+        out.push(byte);
+        if byte == 0 {
+            out.push(0xff);
+        }
+    }
+    out.extend_from_slice(&[0x00, 0x01]);
+}
+
+impl OrderedKey for Vec<u8> {
+    fn encode_ordered(&self, out: &mut Vec<u8>) {
+        encode_ordered_bytes(self, out);
+    }
+
+    fn decode_ordered(bytes: &mut &[u8]) -> Option<Self> {
+        let mut result = Vec::new();
+        loop {
+            match take(bytes, 1)?[0] {
+                0 => match take(bytes, 1)?[0] {
+                    0x01 => return Some(result),
+                    0xff => result.push(0),
+                    _ => return None,
+                },
+                byte => result.push(byte),
+            }
+        }
+    }
+}
+
+impl OrderedKey for String {
+    fn encode_ordered(&self, out: &mut Vec<u8>) {
+        // Byte-wise order of UTF-8 matches the order of `str`.
+        encode_ordered_bytes(self.as_bytes(), out);
+    }
+
+    fn decode_ordered(bytes: &mut &[u8]) -> Option<Self> {
+        String::from_utf8(Vec::decode_ordered(bytes)?).ok()
+    }
+}
+
+impl<T: OrderedKey> OrderedKey for Option<T> {
+    fn encode_ordered(&self, out: &mut Vec<u8>) {
+        match self {
+            None => out.push(0),
+            Some(value) => {
+                out.push(1);
+                value.encode_ordered(out);
+            }
+        }
+    }
+
+    fn decode_ordered(bytes: &mut &[u8]) -> Option<Self> {
+        match take(bytes, 1)?[0] {
+            0 => Some(None),
+            1 => Some(Some(T::decode_ordered(bytes)?)),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_ordered_key_tuple {
+    ($($name:ident $field:ident),+) => {
+        impl<$($name: OrderedKey),+> OrderedKey for ($($name,)+) {
+            fn encode_ordered(&self, out: &mut Vec<u8>) {
+                let ($($field,)+) = self;
+                $($field.encode_ordered(out);)+
+            }
+
+            fn decode_ordered(bytes: &mut &[u8]) -> Option<Self> {
+                Some(($($name::decode_ordered(bytes)?,)+))
+            }
+        }
+    };
+}
+
+impl_ordered_key_tuple!(A a);
+impl_ordered_key_tuple!(A a, B b);
+impl_ordered_key_tuple!(A a, B b, C c);
+impl_ordered_key_tuple!(A a, B b, C c, D d);
+impl_ordered_key_tuple!(A a, B b, C c, D d, E e);
+impl_ordered_key_tuple!(A a, B b, C c, D d, E e, F f);
@@ -75,7 +75,10 @@
 #[macro_use]
 mod ffi_util;
 
+#[cfg(feature = "async")]
+mod async_db;
 pub mod backup;
+mod change_stream;
 pub mod checkpoint;
 mod column_family;
 pub mod compaction_filter;
@@ -85,22 +88,38 @@ mod db;
 mod db_iterator;
 mod db_options;
 mod db_pinnable_slice;
+pub mod event_listener;
+pub mod fault_injection;
+pub mod file_system;
+pub mod indexed_table;
 mod iter_range;
+pub mod keycodec;
 pub mod merge_operator;
 pub mod perf;
 pub mod properties;
 mod slice_transform;
 mod snapshot;
+mod sst_file_reader;
 mod sst_file_writer;
+pub mod statistics;
+pub mod table_factory;
+pub mod table_properties;
+pub mod table_properties_collector;
 mod transactions;
+pub mod typed_column_family;
 mod write_batch;
+mod write_batch_with_index;
 
 pub use crate::{
+    change_stream::{Change, ChangeOp, ChangeStream},
     column_family::{
         AsColumnFamilyRef, BoundColumnFamily, ColumnFamily, ColumnFamilyDescriptor,
         ColumnFamilyRef, DEFAULT_COLUMN_FAMILY_NAME,
     },
-    compaction_filter::Decision as CompactionDecision,
+    compaction_filter::{Decision as CompactionDecision, DecisionV2 as CompactionDecisionV2},
+    comparator::{
+        split_timestamp, BytewiseComparatorWithU64Ts, Comparator, ReverseBytewiseComparator,
+    },
     db::{
         DBAccess, DBCommon, DBWithThreadMode, LiveFile, MultiThreaded, SingleThreaded, ThreadMode,
         DB,
@@ -123,14 +142,27 @@ pub use crate::{
     perf::{PerfContext, PerfMetric, PerfStatsLevel},
     slice_transform::SliceTransform,
     snapshot::{Snapshot, SnapshotWithThreadMode},
+    sst_file_reader::{SstFileReader, SstFileReaderIterator},
     sst_file_writer::SstFileWriter,
+    statistics::Statistics,
+    table_properties::{TableProperties, TablePropertiesCollection},
     transactions::{
-        OptimisticTransactionDB, OptimisticTransactionOptions, Transaction, TransactionDB,
-        TransactionDBOptions, TransactionOptions,
+        DeadlockInfo, DeadlockPath, KeyLockInfo, OptimisticTransactionDB,
+        OptimisticTransactionOptions, Transaction, TransactionDB, TransactionDBOptions,
+        TransactionOptions, TxnDBWritePolicy, WaitingTxns,
+    },
+    write_batch::{
+        WriteBatch, WriteBatchIterator, WriteBatchIteratorCf, WriteBatchWithTransaction,
     },
-    write_batch::{WriteBatch, WriteBatchIterator, WriteBatchWithTransaction},
+    write_batch_with_index::WriteBatchWithIndex,
 };
 
+#[cfg(not(windows))]
+pub use crate::transactions::RangeLockManager;
+
+#[cfg(feature = "async")]
+pub use crate::async_db::{AsyncDB, DBFuture, DBStream};
+
 use librocksdb_sys as ffi;
 
 use std::error;
@@ -232,8 +264,8 @@ mod test {
         db_options::{CacheWrapper, EnvWrapper},
         BlockBasedOptions, BoundColumnFamily, Cache, ColumnFamily, ColumnFamilyDescriptor,
         DBIterator, DBRawIterator, Env, IngestExternalFileOptions, Options,
-        PlainTableFactoryOptions, ReadOptions, Snapshot, SstFileWriter, WriteBatch, WriteOptions,
-        DB,
+        PlainTableFactoryOptions, ReadOptions, Snapshot, SstFileWriter, Statistics, WriteBatch,
+        WriteOptions, DB,
     };
 
     #[test]
@@ -271,6 +303,7 @@ mod test {
         is_send::<TransactionDBOptions>();
         is_send::<OptimisticTransactionOptions>();
         is_send::<TransactionOptions>();
+        is_send::<Statistics>();
     }
 
     #[test]
@@ -301,5 +334,6 @@ mod test {
         is_sync::<TransactionDBOptions>();
         is_sync::<OptimisticTransactionOptions>();
         is_sync::<TransactionOptions>();
+        is_sync::<Statistics>();
     }
 }
@@ -55,12 +55,16 @@
 //!let _ = DB::destroy(&opts, path);
 //! ```
 
-use libc::{self, c_char, c_int, c_void, size_t};
-use std::ffi::CString;
+use libc::{self, c_char, c_int, c_uchar, c_void, size_t};
+use std::ffi::{CStr, CString};
 use std::mem;
 use std::ptr;
 use std::slice;
 
+use crate::ffi;
+
+pub mod builtin;
+
 pub trait MergeFn:
     Fn(&[u8], Option<&[u8]>, &MergeOperands) -> Option<Vec<u8>> + Send + Sync + 'static
 {
@@ -164,6 +168,157 @@ pub unsafe extern "C" fn partial_merge_callback<F: MergeFn, PF: MergeFn>(
     )
 }
 
+/// A merge operator with state, which can also merge many operands at once
+/// and stop reads from collecting long chains of operands.
+///
+/// See [`Options::set_merge_operator_impl`](crate::Options::set_merge_operator_impl).
+pub trait MergeOperator: Send + Sync {
+    /// Returns a name that identifies this merge operator.
+    fn name(&self) -> &CStr;
+
+    /// Applies `operands`, oldest first, to the existing value of `key`, or
+    /// to no value if the key has none.  Returns `None` on failure, which
+    /// fails the read or compaction.
+    fn full_merge(
+        &self,
+        key: &[u8],
+        existing_value: Option<&[u8]>,
+        operands: &MergeOperands,
+    ) -> Option<Vec<u8>>;
+
+    /// Combines two operands into one, or returns `None` to keep both.
+    ///
+    /// The default implementation returns `None`.
+    fn partial_merge(&self, _key: &[u8], _left: &[u8], _right: &[u8]) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Combines `operands`, oldest first, into one, or returns `None` to
+    /// keep them.  There are at least two operands unless
+    /// [`allow_single_operand`](Self::allow_single_operand) returns true.
+    ///
+    /// The default implementation combines the operands pairwise with
+    /// [`partial_merge`](Self::partial_merge).
+    fn partial_merge_multi(&self, key: &[u8], operands: &MergeOperands) -> Option<Vec<u8>> {
+        let mut iter = operands.iter();
+        let mut result = iter.next()?.to_vec();
+        for operand in iter {
+            result = self.partial_merge(key, &result, operand)?;
+        }
+        Some(result)
+    }
+
+    /// Returns whether a read, having collected `operands`, newest first,
+    /// should stop looking for older ones and merge what it has.  The
+    /// operands are then passed to [`full_merge`](Self::full_merge) with no
+    /// existing value, so this must only return true once the newest
+    /// operands determine the result.
+    ///
+    /// The default implementation returns false.
+    fn should_merge(&self, _operands: &MergeOperands) -> bool {
+        false
+    }
+
+    /// Returns whether [`partial_merge_multi`](Self::partial_merge_multi)
+    /// may be called with a single operand, for instance to compact it.
+    ///
+    /// The default implementation returns false.
+    fn allow_single_operand(&self) -> bool {
+        false
+    }
+}
+
+pub(crate) fn create_callbacks<M: MergeOperator>() -> ffi::rocksdb_ext_mergeoperator_callbacks_t {
+    ffi::rocksdb_ext_mergeoperator_callbacks_t {
+        destructor: Some(impl_destructor_callback::<M>),
+        name: Some(impl_name_callback::<M>),
+        full_merge: Some(impl_full_merge_callback::<M>),
+        partial_merge_multi: Some(impl_partial_merge_multi_callback::<M>),
+        should_merge: Some(impl_should_merge_callback::<M>),
+        allow_single_operand: Some(impl_allow_single_operand_callback::<M>),
+    }
+}
+
+unsafe extern "C" fn impl_destructor_callback<M: MergeOperator>(state: *mut c_void) {
+    drop(Box::from_raw(state as *mut M));
+}
+
+unsafe extern "C" fn impl_name_callback<M: MergeOperator>(state: *mut c_void) -> *const c_char {
+    let self_ = &*(state as *const M);
+    self_.name().as_ptr()
+}
+
+unsafe extern "C" fn impl_full_merge_callback<M: MergeOperator>(
+    state: *mut c_void,
+    key: *const c_char,
+    key_len: size_t,
+    existing_value: *const c_char,
+    existing_value_len: size_t,
+    operands_list: *const *const c_char,
+    operands_list_len: *const size_t,
+    num_operands: c_int,
+    new_value: *mut ffi::rocksdb_ext_string_t,
+) -> c_uchar {
+    let self_ = &*(state as *const M);
+    let key = slice::from_raw_parts(key as *const u8, key_len);
+    let existing_value = if existing_value.is_null() {
+        None
+    } else {
+        Some(slice::from_raw_parts(
+            existing_value as *const u8,
+            existing_value_len,
+        ))
+    };
+    let operands = &MergeOperands::new(operands_list, operands_list_len, num_operands);
+    assign_result(self_.full_merge(key, existing_value, operands), new_value)
+}
+
+unsafe extern "C" fn impl_partial_merge_multi_callback<M: MergeOperator>(
+    state: *mut c_void,
+    key: *const c_char,
+    key_len: size_t,
+    operands_list: *const *const c_char,
+    operands_list_len: *const size_t,
+    num_operands: c_int,
+    new_value: *mut ffi::rocksdb_ext_string_t,
+) -> c_uchar {
+    let self_ = &*(state as *const M);
+    let key = slice::from_raw_parts(key as *const u8, key_len);
+    let operands = &MergeOperands::new(operands_list, operands_list_len, num_operands);
+    assign_result(self_.partial_merge_multi(key, operands), new_value)
+}
+
+unsafe extern "C" fn impl_should_merge_callback<M: MergeOperator>(
+    state: *mut c_void,
+    operands_list: *const *const c_char,
+    operands_list_len: *const size_t,
+    num_operands: c_int,
+) -> c_uchar {
+    let self_ = &*(state as *const M);
+    let operands = &MergeOperands::new(operands_list, operands_list_len, num_operands);
+    c_uchar::from(self_.should_merge(operands))
+}
+
+unsafe extern "C" fn impl_allow_single_operand_callback<M: MergeOperator>(
+    state: *mut c_void,
+) -> c_uchar {
+    let self_ = &*(state as *const M);
+    c_uchar::from(self_.allow_single_operand())
+}
+
+unsafe fn assign_result(
+    value: Option<Vec<u8>>,
+    new_value: *mut ffi::rocksdb_ext_string_t,
+) -> c_uchar {
+    match value {
+        Some(value) => {
+            ffi::rocksdb_ext_string_assign(new_value, value.as_ptr() as *const c_char, value.len());
+            1
+        }
+        None => 0,
+    }
+}
+
 pub struct MergeOperands {
     operands_list: *const *const c_char,
     operands_list_len: *const size_t,
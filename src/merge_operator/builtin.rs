@@ -0,0 +1,218 @@
+// Copyright 2020 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Common merge operators.
+//!
+//! Every operator here is associative, so it can be passed to
+//! [`Options::set_merge_operator_associative`], or as both functions to
+//! [`Options::set_merge_operator`].  A merge fails if an operand or the
+//! existing value is not encoded as the operator expects.
+//!
+//! ```
+//! use rocksdb::{merge_operator::builtin, Options, DB};
+//!
+//! let path = "_rust_path_to_rocksdb_builtin_merge";
+//! let mut opts = Options::default();
+//! opts.create_if_missing(true);
+//! opts.set_merge_operator_associative("u64_add", builtin::u64_add);
+//! {
+//!     let db = DB::open(&opts, path).unwrap();
+//!     db.merge(b"hits", builtin::encode_u64(2)).unwrap();
+//!     db.merge(b"hits", builtin::encode_u64(3)).unwrap();
+//!     let hits = db.get(b"hits").unwrap().unwrap();
+//!     assert_eq!(builtin::decode_u64(&hits), Some(5));
+//! }
+//! let _ = DB::destroy(&opts, path);
+//! ```
+//!
+//! [`Options::set_merge_operator_associative`]: crate::Options::set_merge_operator_associative
+//! [`Options::set_merge_operator`]: crate::Options::set_merge_operator
+
+use std::convert::TryInto;
+
+use super::{MergeFn, MergeOperands};
+
+/// Encodes a value or operand of [`u64_add`], [`u64_max`] and [`u64_min`]:
+/// 8 bytes, little-endian.
+pub fn encode_u64(value: u64) -> [u8; 8] {
+    value.to_le_bytes()
+}
+
+/// Decodes a value encoded by [`encode_u64`], or returns `None` if it is not
+/// 8 bytes long.
+pub fn decode_u64(bytes: &[u8]) -> Option<u64> {
+    bytes.try_into().ok().map(u64::from_le_bytes)
+}
+
+fn fold_u64(
+    existing_val: Option<&[u8]>,
+    operands: &MergeOperands,
+    f: impl Fn(u64, u64) -> u64,
+) -> Option<Vec<u8>> {
+    let mut values = existing_val.into_iter().chain(operands).map(decode_u64);
+    let mut result = values.next()??;
+    for value in values {
+        result = f(result, value?);
+    }
+    Some(encode_u64(result).to_vec())
+}
+
+/// Adds [`encode_u64`] operands to the value, wrapping around on overflow.
+/// A missing value counts as 0.
+pub fn u64_add(
+    _key: &[u8],
+    existing_val: Option<&[u8]>,
+    operands: &MergeOperands,
+) -> Option<Vec<u8>> {
+    fold_u64(existing_val, operands, u64::wrapping_add)
+}
+
+/// Keeps the largest of the value and the [`encode_u64`] operands.
+pub fn u64_max(
+    _key: &[u8],
+    existing_val: Option<&[u8]>,
+    operands: &MergeOperands,
+) -> Option<Vec<u8>> {
+    fold_u64(existing_val, operands, u64::max)
+}
+
+/// Keeps the smallest of the value and the [`encode_u64`] operands.
+pub fn u64_min(
+    _key: &[u8],
+    existing_val: Option<&[u8]>,
+    operands: &MergeOperands,
+) -> Option<Vec<u8>> {
+    fold_u64(existing_val, operands, u64::min)
+}
+
+/// Returns an operator appending operands to the value, separated by
+/// `delimiter`.  Operands are arbitrary bytes, and should not contain the
+/// delimiter if the value is to be split by [`split_appended`].
+///
+/// ```
+/// use rocksdb::{merge_operator::builtin, Options, DB};
+///
+/// let path = "_rust_path_to_rocksdb_builtin_string_append";
+/// let mut opts = Options::default();
+/// opts.create_if_missing(true);
+/// opts.set_merge_operator_associative("string_append", builtin::string_append(","));
+/// {
+///     let db = DB::open(&opts, path).unwrap();
+///     db.put(b"tags", b"a").unwrap();
+///     db.merge(b"tags", b"b").unwrap();
+///     assert_eq!(db.get(b"tags").unwrap().unwrap(), b"a,b");
+/// }
+/// let _ = DB::destroy(&opts, path);
+/// ```
+pub fn string_append(delimiter: impl Into<Vec<u8>>) -> impl MergeFn + Clone {
+    let delimiter = delimiter.into();
+    move |_key: &[u8], existing_val: Option<&[u8]>, operands: &MergeOperands| {
+        let mut parts = existing_val.into_iter().chain(operands);
+        let mut result = parts.next()?.to_vec();
+        for part in parts {
+            result.extend_from_slice(&delimiter);
+            result.extend_from_slice(part);
+        }
+        Some(result)
+    }
+}
+
+/// Splits a value built by [`string_append`] into the appended operands.
+pub fn split_appended<'a>(
+    value: &'a [u8],
+    delimiter: &'a [u8],
+) -> impl Iterator<Item = &'a [u8]> + 'a {
+    assert!(!delimiter.is_empty(), "delimiter must not be empty");
+    let mut rest = Some(value);
+    std::iter::from_fn(move || {
+        let value = rest?;
+        match value
+            .windows(delimiter.len())
+            .position(|window| window == delimiter)
+        {
+            Some(index) => {
+                rest = Some(&value[index + delimiter.len()..]);
+                Some(&value[..index])
+            }
+            None => {
+                rest = None;
+                Some(value)
+            }
+        }
+    })
+}
+
+/// Encodes a value or operand of [`set_union`]: the distinct elements in
+/// ascending byte-wise order, each preceded by its length as a
+/// little-endian `u32`.
+pub fn encode_set<I>(elements: I) -> Vec<u8>
+where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+{
+    let mut elements: Vec<I::Item> = elements.into_iter().collect();
+    elements.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+    elements.dedup_by(|a, b| a.as_ref() == b.as_ref());
+    let mut result = Vec::new();
+    for element in &elements {
+        push_element(&mut result, element.as_ref());
+    }
+    result
+}
+
+/// Decodes a value encoded by [`encode_set`], or returns `None` if it is
+/// truncated.
+pub fn decode_set(mut bytes: &[u8]) -> Option<Vec<&[u8]>> {
+    let mut elements = Vec::new();
+    while !bytes.is_empty() {
+        if bytes.len() < 4 {
+            return None;
+        }
+        let (len, rest) = bytes.split_at(4);
+        let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+        if rest.len() < len {
+            return None;
+        }
+        let (element, rest) = rest.split_at(len);
+        elements.push(element);
+        bytes = rest;
+    }
+    Some(elements)
+}
+
+fn push_element(result: &mut Vec<u8>, element: &[u8]) {
+    let len: u32 = element.len().try_into().expect("set element is too large");
+    result.extend_from_slice(&len.to_le_bytes());
+    result.extend_from_slice(element);
+}
+
+/// Keeps the union of the value and the [`encode_set`] operands.
+pub fn set_union(
+    _key: &[u8],
+    existing_val: Option<&[u8]>,
+    operands: &MergeOperands,
+) -> Option<Vec<u8>> {
+    let mut elements = Vec::new();
+    for set in existing_val.into_iter().chain(operands) {
+        elements.extend(decode_set(set)?);
+    }
+    elements.sort_unstable();
+    elements.dedup();
+    let mut result = Vec::new();
+    for element in elements {
+        push_element(&mut result, element);
+    }
+    Some(result)
+}
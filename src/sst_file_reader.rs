@@ -0,0 +1,126 @@
+// Copyright 2022 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    ffi, ffi_util::to_cpath, table_properties::TableProperties, DBRawIteratorWithThreadMode, Error,
+    Options, ReadOptions,
+};
+
+use std::path::Path;
+
+/// An iterator over the contents of an SST file.
+/// See [`DBRawIteratorWithThreadMode`] for details.
+pub type SstFileReaderIterator<'a> = DBRawIteratorWithThreadMode<'a, SstFileReader>;
+
+/// SstFileReader reads sst files outside of a database, such as the files
+/// created by [`SstFileWriter`](crate::SstFileWriter) before they are
+/// ingested.
+///
+/// ```
+/// use rocksdb::{Options, SstFileReader, SstFileWriter};
+///
+/// let path = "_path_for_sst_file_reader";
+/// let opts = Options::default();
+/// let mut writer = SstFileWriter::create(&opts);
+/// writer.open(path).unwrap();
+/// writer.put(b"k1", b"v1").unwrap();
+/// writer.finish().unwrap();
+///
+/// let reader = SstFileReader::open(&opts, path).unwrap();
+/// reader.verify_checksum().unwrap();
+/// assert_eq!(reader.table_properties().num_entries(), 1);
+///
+/// let mut iter = reader.raw_iterator();
+/// iter.seek_to_first();
+/// assert_eq!(iter.item(), Some((&b"k1"[..], &b"v1"[..])));
+/// # drop(iter);
+/// # std::fs::remove_file(path).unwrap();
+/// ```
+pub struct SstFileReader {
+    inner: *mut ffi::rocksdb_ext_sstfilereader_t,
+}
+
+unsafe impl Send for SstFileReader {}
+unsafe impl Sync for SstFileReader {}
+
+impl SstFileReader {
+    /// Opens the sst file at `path`.  The file is read with the comparator,
+    /// table factory and environment of `opts`, which must match those it
+    /// was written with.
+    pub fn open<P: AsRef<Path>>(opts: &Options, path: P) -> Result<Self, Error> {
+        let cpath = to_cpath(&path)?;
+        let reader = Self {
+            inner: unsafe { ffi::rocksdb_ext_sstfilereader_create(opts.inner) },
+        };
+        unsafe {
+            ffi_try!(ffi::rocksdb_ext_sstfilereader_open(
+                reader.inner,
+                cpath.as_ptr()
+            ));
+        }
+        Ok(reader)
+    }
+
+    /// Opens a raw iterator over the entries of the file.
+    pub fn raw_iterator(&self) -> SstFileReaderIterator<'_> {
+        self.raw_iterator_opt(ReadOptions::default())
+    }
+
+    /// Opens a raw iterator over the entries of the file with the given read
+    /// options.
+    pub fn raw_iterator_opt(&self, readopts: ReadOptions) -> SstFileReaderIterator<'_> {
+        let inner =
+            unsafe { ffi::rocksdb_ext_sstfilereader_create_iterator(self.inner, readopts.inner) };
+        DBRawIteratorWithThreadMode::from_inner(inner, readopts)
+    }
+
+    /// Returns the smallest key of the file, or `None` if it has no entries.
+    pub fn smallest_key(&self) -> Result<Option<Vec<u8>>, Error> {
+        let mut iter = self.raw_iterator();
+        iter.seek_to_first();
+        iter.status()?;
+        Ok(iter.key().map(<[u8]>::to_vec))
+    }
+
+    /// Returns the largest key of the file, or `None` if it has no entries.
+    pub fn largest_key(&self) -> Result<Option<Vec<u8>>, Error> {
+        let mut iter = self.raw_iterator();
+        iter.seek_to_last();
+        iter.status()?;
+        Ok(iter.key().map(<[u8]>::to_vec))
+    }
+
+    /// Reads the whole file and verifies the checksums of its blocks.
+    pub fn verify_checksum(&self) -> Result<(), Error> {
+        unsafe {
+            ffi_try!(ffi::rocksdb_ext_sstfilereader_verify_checksum(self.inner));
+        }
+        Ok(())
+    }
+
+    /// Returns the properties of the file.
+    pub fn table_properties(&self) -> TableProperties {
+        TableProperties::from_raw(unsafe {
+            ffi::rocksdb_ext_sstfilereader_get_table_properties(self.inner)
+        })
+    }
+}
+
+impl Drop for SstFileReader {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::rocksdb_ext_sstfilereader_destroy(self.inner);
+        }
+    }
+}
@@ -0,0 +1,695 @@
+// Copyright 2022 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Typed access to the statistics collected by RocksDB.
+//!
+//! Statistics are enabled with [`Options::enable_statistics`] and read
+//! through the [`Statistics`] handle returned by [`Options::statistics`].
+//!
+//! ```
+//! use rocksdb::{statistics::{Histogram, StatsLevel, Ticker}, Options, DB};
+//!
+//! let path = "_rust_rocksdb_statistics_doc";
+//! {
+//!     let mut opts = Options::default();
+//!     opts.create_if_missing(true);
+//!     opts.enable_statistics();
+//!     let stats = opts.statistics().unwrap();
+//!     stats.set_stats_level(StatsLevel::All);
+//!
+//!     let db = DB::open(&opts, path).unwrap();
+//!     db.put(b"key", b"value").unwrap();
+//!     assert_eq!(stats.ticker(Ticker::NumberKeysWritten), 1);
+//!     assert_eq!(stats.histogram(Histogram::DbWrite).count, 1);
+//! }
+//! let _ = DB::destroy(&Options::default(), path);
+//! ```
+//!
+//! [`Options::enable_statistics`]: crate::Options::enable_statistics
+//! [`Options::statistics`]: crate::Options::statistics
+
+use std::fmt;
+use std::ptr;
+
+use crate::{ffi, Error};
+
+macro_rules! iterable_named_enum {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $(
+                $(#[$variant_meta:meta])*
+                $variant:ident($variant_name:literal),
+            )+
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        #[repr(u32)]
+        $vis enum $name {
+            $(
+                $(#[$variant_meta])*
+                $variant,
+            )+
+        }
+
+        impl $name {
+            /// All values, in the order RocksDB defines them.
+            pub const ALL: &'static [$name] = &[$($name::$variant,)+];
+
+            /// Returns the name RocksDB uses for this value, for example in
+            /// the output of [`Options::get_statistics`].
+            ///
+            /// [`Options::get_statistics`]: crate::Options::get_statistics
+            pub fn name(self) -> &'static str {
+                match self {
+                    $($name::$variant => $variant_name,)+
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+    };
+}
+
+iterable_named_enum! {
+    /// A counter collected by RocksDB.
+    pub enum Ticker {
+        /// Total block cache misses
+        ///
+        /// Equal to the sum of the index, filter and data block misses.
+        BlockCacheMiss("rocksdb.block.cache.miss"),
+        /// Total block cache hit
+        ///
+        /// Equal to the sum of the index, filter and data block hits.
+        BlockCacheHit("rocksdb.block.cache.hit"),
+        /// Number of blocks added to block cache.
+        BlockCacheAdd("rocksdb.block.cache.add"),
+        /// Number of failures when adding blocks to block cache.
+        BlockCacheAddFailures("rocksdb.block.cache.add.failures"),
+        /// Number of times cache miss when accessing index block from block cache.
+        BlockCacheIndexMiss("rocksdb.block.cache.index.miss"),
+        /// Number of times cache hit when accessing index block from block cache.
+        BlockCacheIndexHit("rocksdb.block.cache.index.hit"),
+        /// Number of index blocks added to block cache.
+        BlockCacheIndexAdd("rocksdb.block.cache.index.add"),
+        /// Number of bytes of index blocks inserted into cache
+        BlockCacheIndexBytesInsert("rocksdb.block.cache.index.bytes.insert"),
+        /// Number of bytes of index block erased from cache
+        BlockCacheIndexBytesEvict("rocksdb.block.cache.index.bytes.evict"),
+        /// Number of times cache miss when accessing filter block from block cache.
+        BlockCacheFilterMiss("rocksdb.block.cache.filter.miss"),
+        /// Number of times cache hit when accessing filter block from block cache.
+        BlockCacheFilterHit("rocksdb.block.cache.filter.hit"),
+        /// Number of filter blocks added to block cache.
+        BlockCacheFilterAdd("rocksdb.block.cache.filter.add"),
+        /// Number of bytes of bloom filter blocks inserted into cache
+        BlockCacheFilterBytesInsert("rocksdb.block.cache.filter.bytes.insert"),
+        /// Number of bytes of bloom filter block erased from cache
+        BlockCacheFilterBytesEvict("rocksdb.block.cache.filter.bytes.evict"),
+        /// Number of times cache miss when accessing data block from block cache.
+        BlockCacheDataMiss("rocksdb.block.cache.data.miss"),
+        /// Number of times cache hit when accessing data block from block cache.
+        BlockCacheDataHit("rocksdb.block.cache.data.hit"),
+        /// Number of data blocks added to block cache.
+        BlockCacheDataAdd("rocksdb.block.cache.data.add"),
+        /// Number of bytes of data blocks inserted into cache
+        BlockCacheDataBytesInsert("rocksdb.block.cache.data.bytes.insert"),
+        /// Number of bytes read from cache.
+        BlockCacheBytesRead("rocksdb.block.cache.bytes.read"),
+        /// Number of bytes written into cache.
+        BlockCacheBytesWrite("rocksdb.block.cache.bytes.write"),
+        /// Number of times bloom filter has avoided file reads, i.e., negatives.
+        BloomFilterUseful("rocksdb.bloom.filter.useful"),
+        /// Number of times bloom FullFilter has not avoided the reads.
+        BloomFilterFullPositive("rocksdb.bloom.filter.full.positive"),
+        /// Number of times bloom FullFilter has not avoided the reads and data actually
+        /// exist.
+        BloomFilterFullTruePositive("rocksdb.bloom.filter.full.true.positive"),
+        BloomFilterMicros("rocksdb.bloom.filter.micros"),
+        /// Number of persistent cache hits
+        PersistentCacheHit("rocksdb.persistent.cache.hit"),
+        /// Number of persistent cache misses
+        PersistentCacheMiss("rocksdb.persistent.cache.miss"),
+        /// Number of total simulation block cache hits
+        SimBlockCacheHit("rocksdb.sim.block.cache.hit"),
+        /// Number of total simulation block cache misses
+        SimBlockCacheMiss("rocksdb.sim.block.cache.miss"),
+        /// Number of memtable hits.
+        MemtableHit("rocksdb.memtable.hit"),
+        /// Number of memtable misses.
+        MemtableMiss("rocksdb.memtable.miss"),
+        /// Number of Get() queries served by L0
+        GetHitL0("rocksdb.l0.hit"),
+        /// Number of Get() queries served by L1
+        GetHitL1("rocksdb.l1.hit"),
+        /// Number of Get() queries served by L2 and up
+        GetHitL2AndUp("rocksdb.l2andup.hit"),
+        /// Key was written with a newer value. Also includes keys dropped for
+        /// range del.
+        CompactionKeyDropNewerEntry("rocksdb.compaction.key.drop.new"),
+        /// The key is obsolete.
+        CompactionKeyDropObsolete("rocksdb.compaction.key.drop.obsolete"),
+        /// Key was covered by a range tombstone.
+        CompactionKeyDropRangeDel("rocksdb.compaction.key.drop.range_del"),
+        /// User compaction function has dropped the key.
+        CompactionKeyDropUser("rocksdb.compaction.key.drop.user"),
+        /// All keys in range were deleted.
+        CompactionRangeDelDropObsolete("rocksdb.compaction.range_del.drop.obsolete"),
+        /// Deletions obsoleted before bottom level due to file gap optimization.
+        CompactionOptimizedDelDropObsolete("rocksdb.compaction.optimized.del.drop.obsolete"),
+        /// If a compaction was canceled in sfm to prevent ENOSPC
+        CompactionCancelled("rocksdb.compaction.cancelled"),
+        /// Number of keys written to the database via the Put and Write call's
+        NumberKeysWritten("rocksdb.number.keys.written"),
+        /// Number of Keys read,
+        NumberKeysRead("rocksdb.number.keys.read"),
+        /// Number keys updated, if inplace update is enabled
+        NumberKeysUpdated("rocksdb.number.keys.updated"),
+        /// The number of uncompressed bytes issued by DB::Put(), DB::Delete(),
+        /// DB::Merge(), and DB::Write().
+        BytesWritten("rocksdb.bytes.written"),
+        /// The number of uncompressed bytes read from DB::Get().  It could be
+        /// either from memtables, cache, or table files.
+        /// For the number of logical bytes read from DB::MultiGet(),
+        /// please use `NumberMultigetBytesRead`.
+        BytesRead("rocksdb.bytes.read"),
+        /// The number of calls to seek/next/prev
+        NumberDbSeek("rocksdb.number.db.seek"),
+        NumberDbNext("rocksdb.number.db.next"),
+        NumberDbPrev("rocksdb.number.db.prev"),
+        /// The number of calls to seek/next/prev that returned data
+        NumberDbSeekFound("rocksdb.number.db.seek.found"),
+        NumberDbNextFound("rocksdb.number.db.next.found"),
+        NumberDbPrevFound("rocksdb.number.db.prev.found"),
+        /// The number of uncompressed bytes read from an iterator.
+        /// Includes size of key and value.
+        IterBytesRead("rocksdb.db.iter.bytes.read"),
+        NoFileCloses("rocksdb.no.file.closes"),
+        NoFileOpens("rocksdb.no.file.opens"),
+        NoFileErrors("rocksdb.no.file.errors"),
+        /// DEPRECATED Time system had to wait to do LO-L1 compactions
+        StallL0SlowdownMicros("rocksdb.l0.slowdown.micros"),
+        /// DEPRECATED Time system had to wait to move memtable to L1.
+        StallMemtableCompactionMicros("rocksdb.memtable.compaction.micros"),
+        /// DEPRECATED write throttle because of too many files in L0
+        StallL0NumFilesMicros("rocksdb.l0.num.files.stall.micros"),
+        /// Writer has to wait for compaction or flush to finish.
+        StallMicros("rocksdb.stall.micros"),
+        /// The wait time for db mutex.
+        /// Disabled by default. To enable it set stats level to kAll
+        DbMutexWaitMicros("rocksdb.db.mutex.wait.micros"),
+        RateLimitDelayMillis("rocksdb.rate.limit.delay.millis"),
+        /// DEPRECATED number of iterators currently open
+        NoIterators("rocksdb.num.iterators"),
+        /// Number of MultiGet calls, keys read, and bytes read
+        NumberMultigetCalls("rocksdb.number.multiget.get"),
+        NumberMultigetKeysRead("rocksdb.number.multiget.keys.read"),
+        NumberMultigetBytesRead("rocksdb.number.multiget.bytes.read"),
+        /// Number of deletes records that were not required to be
+        /// written to storage because key does not exist
+        NumberFilteredDeletes("rocksdb.number.deletes.filtered"),
+        NumberMergeFailures("rocksdb.number.merge.failures"),
+        /// Number of times bloom was checked before creating iterator on a
+        /// file, and the number of times the check was useful in avoiding
+        /// iterator creation (and thus likely IOPs).
+        BloomFilterPrefixChecked("rocksdb.bloom.filter.prefix.checked"),
+        BloomFilterPrefixUseful("rocksdb.bloom.filter.prefix.useful"),
+        /// Number of times we had to reseek inside an iteration to skip
+        /// over large number of keys with same userkey.
+        NumberOfReseeksInIteration("rocksdb.number.reseeks.iteration"),
+        /// Record the number of calls to GetUpdatesSince. Useful to keep track of
+        /// transaction log iterator refreshes
+        GetUpdatesSinceCalls("rocksdb.getupdatessince.calls"),
+        /// Miss in the compressed block cache
+        BlockCacheCompressedMiss("rocksdb.block.cachecompressed.miss"),
+        /// Hit in the compressed block cache
+        BlockCacheCompressedHit("rocksdb.block.cachecompressed.hit"),
+        /// Number of blocks added to compressed block cache
+        BlockCacheCompressedAdd("rocksdb.block.cachecompressed.add"),
+        /// Number of failures when adding blocks to compressed block cache
+        BlockCacheCompressedAddFailures("rocksdb.block.cachecompressed.add.failures"),
+        /// Number of times WAL sync is done
+        WalFileSynced("rocksdb.wal.synced"),
+        /// Number of bytes written to WAL
+        WalFileBytes("rocksdb.wal.bytes"),
+        /// Writes can be processed by requesting thread or by the thread at the
+        /// head of the writers queue.
+        WriteDoneBySelf("rocksdb.write.self"),
+        /// Equivalent to writes done for others
+        WriteDoneByOther("rocksdb.write.other"),
+        /// Number of writes ending up with timed-out.
+        WriteTimedout("rocksdb.write.timeout"),
+        /// Number of Write calls that request WAL
+        WriteWithWal("rocksdb.write.wal"),
+        /// Bytes read during compaction
+        CompactReadBytes("rocksdb.compact.read.bytes"),
+        /// Bytes written during compaction
+        CompactWriteBytes("rocksdb.compact.write.bytes"),
+        /// Bytes written during flush
+        FlushWriteBytes("rocksdb.flush.write.bytes"),
+        /// Compaction read and write statistics broken down by CompactionReason
+        CompactReadBytesMarked("rocksdb.compact.read.marked.bytes"),
+        CompactReadBytesPeriodic("rocksdb.compact.read.periodic.bytes"),
+        CompactReadBytesTtl("rocksdb.compact.read.ttl.bytes"),
+        CompactWriteBytesMarked("rocksdb.compact.write.marked.bytes"),
+        CompactWriteBytesPeriodic("rocksdb.compact.write.periodic.bytes"),
+        CompactWriteBytesTtl("rocksdb.compact.write.ttl.bytes"),
+        /// Number of table's properties loaded directly from file, without creating
+        /// table reader object.
+        NumberDirectLoadTableProperties("rocksdb.number.direct.load.table.properties"),
+        NumberSuperversionAcquires("rocksdb.number.superversion_acquires"),
+        NumberSuperversionReleases("rocksdb.number.superversion_releases"),
+        NumberSuperversionCleanups("rocksdb.number.superversion_cleanups"),
+        /// Number of compressions/decompressions executed
+        NumberBlockCompressed("rocksdb.number.block.compressed"),
+        NumberBlockDecompressed("rocksdb.number.block.decompressed"),
+        NumberBlockNotCompressed("rocksdb.number.block.not_compressed"),
+        MergeOperationTotalTime("rocksdb.merge.operation.time.nanos"),
+        FilterOperationTotalTime("rocksdb.filter.operation.time.nanos"),
+        /// Row cache.
+        RowCacheHit("rocksdb.row.cache.hit"),
+        RowCacheMiss("rocksdb.row.cache.miss"),
+        /// Estimate of total bytes actually used.
+        ///
+        /// Read amplification can be calculated as
+        /// `ReadAmpTotalReadBytes / ReadAmpEstimateUsefulBytes`.
+        /// Requires `read_amp_bytes_per_bit` to be set on the block based
+        /// table options.
+        ReadAmpEstimateUsefulBytes("rocksdb.read.amp.estimate.useful.bytes"),
+        /// Total size of loaded data blocks.
+        ReadAmpTotalReadBytes("rocksdb.read.amp.total.read.bytes"),
+        /// Number of refill intervals where rate limiter's bytes are fully consumed.
+        NumberRateLimiterDrains("rocksdb.number.rate_limiter.drains"),
+        /// Number of internal keys skipped by Iterator
+        NumberIterSkip("rocksdb.number.iter.skip"),
+        /// Number of Put/PutTTL/PutUntil to BlobDB. Only applicable to legacy BlobDB.
+        BlobDbNumPut("rocksdb.blobdb.num.put"),
+        /// Number of Write to BlobDB. Only applicable to legacy BlobDB.
+        BlobDbNumWrite("rocksdb.blobdb.num.write"),
+        /// Number of Get to BlobDB. Only applicable to legacy BlobDB.
+        BlobDbNumGet("rocksdb.blobdb.num.get"),
+        /// Number of MultiGet to BlobDB. Only applicable to legacy BlobDB.
+        BlobDbNumMultiget("rocksdb.blobdb.num.multiget"),
+        /// Number of Seek/SeekToFirst/SeekToLast/SeekForPrev to BlobDB iterator. Only
+        /// applicable to legacy BlobDB.
+        BlobDbNumSeek("rocksdb.blobdb.num.seek"),
+        /// Number of Next to BlobDB iterator. Only applicable to legacy BlobDB.
+        BlobDbNumNext("rocksdb.blobdb.num.next"),
+        /// Number of Prev to BlobDB iterator. Only applicable to legacy BlobDB.
+        BlobDbNumPrev("rocksdb.blobdb.num.prev"),
+        /// Number of keys written to BlobDB. Only applicable to legacy BlobDB.
+        BlobDbNumKeysWritten("rocksdb.blobdb.num.keys.written"),
+        /// Number of keys read from BlobDB. Only applicable to legacy BlobDB.
+        BlobDbNumKeysRead("rocksdb.blobdb.num.keys.read"),
+        /// Number of bytes (key + value) written to BlobDB. Only applicable to legacy
+        /// BlobDB.
+        BlobDbBytesWritten("rocksdb.blobdb.bytes.written"),
+        /// Number of bytes (keys + value) read from BlobDB. Only applicable to legacy
+        /// BlobDB.
+        BlobDbBytesRead("rocksdb.blobdb.bytes.read"),
+        /// Number of keys written by BlobDB as non-TTL inlined value. Only applicable to
+        /// legacy BlobDB.
+        BlobDbWriteInlined("rocksdb.blobdb.write.inlined"),
+        /// Number of keys written by BlobDB as TTL inlined value. Only applicable to legacy
+        /// BlobDB.
+        BlobDbWriteInlinedTtl("rocksdb.blobdb.write.inlined.ttl"),
+        /// Number of keys written by BlobDB as non-TTL blob value. Only applicable to
+        /// legacy BlobDB.
+        BlobDbWriteBlob("rocksdb.blobdb.write.blob"),
+        /// Number of keys written by BlobDB as TTL blob value. Only applicable to legacy
+        /// BlobDB.
+        BlobDbWriteBlobTtl("rocksdb.blobdb.write.blob.ttl"),
+        /// Number of bytes written to blob file.
+        BlobDbBlobFileBytesWritten("rocksdb.blobdb.blob.file.bytes.written"),
+        /// Number of bytes read from blob file.
+        BlobDbBlobFileBytesRead("rocksdb.blobdb.blob.file.bytes.read"),
+        /// Number of times a blob files being synced.
+        BlobDbBlobFileSynced("rocksdb.blobdb.blob.file.synced"),
+        /// Number of blob index evicted from base DB by BlobDB compaction filter because
+        /// of expiration. Only applicable to legacy BlobDB.
+        BlobDbBlobIndexExpiredCount("rocksdb.blobdb.blob.index.expired.count"),
+        /// Size of blob index evicted from base DB by BlobDB compaction filter
+        /// because of expiration. Only applicable to legacy BlobDB.
+        BlobDbBlobIndexExpiredSize("rocksdb.blobdb.blob.index.expired.size"),
+        /// Number of blob index evicted from base DB by BlobDB compaction filter because
+        /// of corresponding file deleted. Only applicable to legacy BlobDB.
+        BlobDbBlobIndexEvictedCount("rocksdb.blobdb.blob.index.evicted.count"),
+        /// Size of blob index evicted from base DB by BlobDB compaction filter
+        /// because of corresponding file deleted. Only applicable to legacy BlobDB.
+        BlobDbBlobIndexEvictedSize("rocksdb.blobdb.blob.index.evicted.size"),
+        /// Number of blob files that were obsoleted by garbage collection. Only applicable
+        /// to legacy BlobDB.
+        BlobDbGcNumFiles("rocksdb.blobdb.gc.num.files"),
+        /// Number of blob files generated by garbage collection. Only applicable to legacy
+        /// BlobDB.
+        BlobDbGcNumNewFiles("rocksdb.blobdb.gc.num.new.files"),
+        /// Number of BlobDB garbage collection failures. Only applicable to legacy BlobDB.
+        BlobDbGcFailures("rocksdb.blobdb.gc.failures"),
+        /// Number of keys dropped by BlobDB garbage collection because they had been
+        /// overwritten. DEPRECATED.
+        BlobDbGcNumKeysOverwritten("rocksdb.blobdb.gc.num.keys.overwritten"),
+        /// Number of keys dropped by BlobDB garbage collection because of expiration.
+        /// DEPRECATED.
+        BlobDbGcNumKeysExpired("rocksdb.blobdb.gc.num.keys.expired"),
+        /// Number of keys relocated to new blob file by garbage collection.
+        BlobDbGcNumKeysRelocated("rocksdb.blobdb.gc.num.keys.relocated"),
+        /// Number of bytes dropped by BlobDB garbage collection because they had been
+        /// overwritten. DEPRECATED.
+        BlobDbGcBytesOverwritten("rocksdb.blobdb.gc.bytes.overwritten"),
+        /// Number of bytes dropped by BlobDB garbage collection because of expiration.
+        /// DEPRECATED.
+        BlobDbGcBytesExpired("rocksdb.blobdb.gc.bytes.expired"),
+        /// Number of bytes relocated to new blob file by garbage collection.
+        BlobDbGcBytesRelocated("rocksdb.blobdb.gc.bytes.relocated"),
+        /// Number of blob files evicted because of BlobDB is full. Only applicable to
+        /// legacy BlobDB.
+        BlobDbFifoNumFilesEvicted("rocksdb.blobdb.fifo.num.files.evicted"),
+        /// Number of keys in the blob files evicted because of BlobDB is full. Only
+        /// applicable to legacy BlobDB.
+        BlobDbFifoNumKeysEvicted("rocksdb.blobdb.fifo.num.keys.evicted"),
+        /// Number of bytes in the blob files evicted because of BlobDB is full. Only
+        /// applicable to legacy BlobDB.
+        BlobDbFifoBytesEvicted("rocksdb.blobdb.fifo.bytes.evicted"),
+        /// Number of times prepare_mutex_ is acquired in the fast path.
+        TxnPrepareMutexOverhead("rocksdb.txn.overhead.mutex.prepare"),
+        /// Number of times old_commit_map_mutex_ is acquired in the fast path.
+        TxnOldCommitMapMutexOverhead("rocksdb.txn.overhead.mutex.old.commit.map"),
+        /// Number of times we checked a batch for duplicate keys.
+        TxnDuplicateKeyOverhead("rocksdb.txn.overhead.duplicate.key"),
+        /// Number of times snapshot_mutex_ is acquired in the fast path.
+        TxnSnapshotMutexOverhead("rocksdb.txn.overhead.mutex.snapshot"),
+        /// Number of times ::Get returned TryAgain due to expired snapshot seq
+        TxnGetTryAgain("rocksdb.txn.get.tryagain"),
+        /// Number of keys actually found in MultiGet calls (vs number requested by
+        /// caller)
+        /// `NumberMultigetKeysRead` gives the number requested by caller
+        NumberMultigetKeysFound("rocksdb.number.multiget.keys.found"),
+        /// Number of iterators created
+        NoIteratorCreated("rocksdb.num.iterator.created"),
+        /// Number of iterators deleted
+        NoIteratorDeleted("rocksdb.num.iterator.deleted"),
+        BlockCacheCompressionDictMiss("rocksdb.block.cache.compression.dict.miss"),
+        BlockCacheCompressionDictHit("rocksdb.block.cache.compression.dict.hit"),
+        BlockCacheCompressionDictAdd("rocksdb.block.cache.compression.dict.add"),
+        BlockCacheCompressionDictBytesInsert("rocksdb.block.cache.compression.dict.bytes.insert"),
+        BlockCacheCompressionDictBytesEvict("rocksdb.block.cache.compression.dict.bytes.evict"),
+        /// Number of blocks redundantly inserted into block cache.
+        BlockCacheAddRedundant("rocksdb.block.cache.add.redundant"),
+        /// Number of index blocks redundantly inserted into block cache.
+        BlockCacheIndexAddRedundant("rocksdb.block.cache.index.add.redundant"),
+        /// Number of filter blocks redundantly inserted into block cache.
+        BlockCacheFilterAddRedundant("rocksdb.block.cache.filter.add.redundant"),
+        /// Number of data blocks redundantly inserted into block cache.
+        BlockCacheDataAddRedundant("rocksdb.block.cache.data.add.redundant"),
+        /// Number of dict blocks redundantly inserted into block cache.
+        BlockCacheCompressionDictAddRedundant("rocksdb.block.cache.compression.dict.add.redundant"),
+        /// Number of files marked as trash by sst file manager and will be deleted
+        /// later by background thread.
+        FilesMarkedTrash("rocksdb.files.marked.trash"),
+        /// Number of files deleted immediately by sst file manger through delete scheduler.
+        FilesDeletedImmediately("rocksdb.files.deleted.immediately"),
+        /// The counters for error handler, not that, bg_io_error is the subset of
+        /// bg_error and bg_retryable_io_error is the subset of bg_io_error
+        ErrorHandlerBgErrorCount("rocksdb.error.handler.bg.errro.count"),
+        ErrorHandlerBgIoErrorCount("rocksdb.error.handler.bg.io.errro.count"),
+        ErrorHandlerBgRetryableIoErrorCount("rocksdb.error.handler.bg.retryable.io.errro.count"),
+        ErrorHandlerAutoresumeCount("rocksdb.error.handler.autoresume.count"),
+        ErrorHandlerAutoresumeRetryTotalCount("rocksdb.error.handler.autoresume.retry.total.count"),
+        ErrorHandlerAutoresumeSuccessCount("rocksdb.error.handler.autoresume.success.count"),
+        /// Raw bytes of data (payload) present on memtable at flush time.
+        MemtablePayloadBytesAtFlush("rocksdb.memtable.payload.bytes.at.flush"),
+        /// Outdated bytes of data present on memtable at flush time.
+        MemtableGarbageBytesAtFlush("rocksdb.memtable.garbage.bytes.at.flush"),
+        /// Secondary cache statistics
+        SecondaryCacheHits("rocksdb.secondary.cache.hits"),
+        /// Bytes read by `VerifyChecksum()` and `VerifyFileChecksums()` APIs.
+        VerifyChecksumReadBytes("rocksdb.verify_checksum.read.bytes"),
+        /// Bytes read/written while creating backups
+        BackupReadBytes("rocksdb.backup.read.bytes"),
+        BackupWriteBytes("rocksdb.backup.write.bytes"),
+        /// Remote compaction read/write statistics
+        RemoteCompactReadBytes("rocksdb.remote.compact.read.bytes"),
+        RemoteCompactWriteBytes("rocksdb.remote.compact.write.bytes"),
+        /// Tiered storage related statistics
+        HotFileReadBytes("rocksdb.hot.file.read.bytes"),
+        WarmFileReadBytes("rocksdb.warm.file.read.bytes"),
+        ColdFileReadBytes("rocksdb.cold.file.read.bytes"),
+        HotFileReadCount("rocksdb.hot.file.read.count"),
+        WarmFileReadCount("rocksdb.warm.file.read.count"),
+        ColdFileReadCount("rocksdb.cold.file.read.count"),
+        /// Last level and non-last level read statistics
+        LastLevelReadBytes("rocksdb.last.level.read.bytes"),
+        LastLevelReadCount("rocksdb.last.level.read.count"),
+        NonLastLevelReadBytes("rocksdb.non.last.level.read.bytes"),
+        NonLastLevelReadCount("rocksdb.non.last.level.read.count"),
+        BlockChecksumComputeCount("rocksdb.block.checksum.compute.count"),
+        MultigetCoroutineCount("rocksdb.multiget.coroutine.count"),
+    }
+}
+
+iterable_named_enum! {
+    /// A distribution of values collected by RocksDB.
+    pub enum Histogram {
+        DbGet("rocksdb.db.get.micros"),
+        DbWrite("rocksdb.db.write.micros"),
+        CompactionTime("rocksdb.compaction.times.micros"),
+        CompactionCpuTime("rocksdb.compaction.times.cpu_micros"),
+        SubcompactionSetupTime("rocksdb.subcompaction.setup.times.micros"),
+        TableSyncMicros("rocksdb.table.sync.micros"),
+        CompactionOutfileSyncMicros("rocksdb.compaction.outfile.sync.micros"),
+        WalFileSyncMicros("rocksdb.wal.file.sync.micros"),
+        ManifestFileSyncMicros("rocksdb.manifest.file.sync.micros"),
+        /// Time spent in IO during table open
+        TableOpenIoMicros("rocksdb.table.open.io.micros"),
+        DbMultiget("rocksdb.db.multiget.micros"),
+        ReadBlockCompactionMicros("rocksdb.read.block.compaction.micros"),
+        ReadBlockGetMicros("rocksdb.read.block.get.micros"),
+        WriteRawBlockMicros("rocksdb.write.raw.block.micros"),
+        StallL0SlowdownCount("rocksdb.l0.slowdown.count"),
+        StallMemtableCompactionCount("rocksdb.memtable.compaction.count"),
+        StallL0NumFilesCount("rocksdb.num.files.stall.count"),
+        HardRateLimitDelayCount("rocksdb.hard.rate.limit.delay.count"),
+        SoftRateLimitDelayCount("rocksdb.soft.rate.limit.delay.count"),
+        NumFilesInSingleCompaction("rocksdb.numfiles.in.singlecompaction"),
+        DbSeek("rocksdb.db.seek.micros"),
+        WriteStall("rocksdb.db.write.stall"),
+        SstReadMicros("rocksdb.sst.read.micros"),
+        /// The number of subcompactions actually scheduled during a compaction
+        NumSubcompactionsScheduled("rocksdb.num.subcompactions.scheduled"),
+        /// Value size distribution in each operation
+        BytesPerRead("rocksdb.bytes.per.read"),
+        BytesPerWrite("rocksdb.bytes.per.write"),
+        BytesPerMultiget("rocksdb.bytes.per.multiget"),
+        /// Number of bytes compressed/decompressed
+        /// number of bytes is when uncompressed; i.e. before/after respectively
+        BytesCompressed("rocksdb.bytes.compressed"),
+        BytesDecompressed("rocksdb.bytes.decompressed"),
+        CompressionTimesNanos("rocksdb.compression.times.nanos"),
+        DecompressionTimesNanos("rocksdb.decompression.times.nanos"),
+        /// Number of merge operands passed to the merge operator in user read
+        /// requests.
+        ReadNumMergeOperands("rocksdb.read.num.merge_operands"),
+        /// Size of keys written to BlobDB. Only applicable to legacy BlobDB.
+        BlobDbKeySize("rocksdb.blobdb.key.size"),
+        /// Size of values written to BlobDB. Only applicable to legacy BlobDB.
+        BlobDbValueSize("rocksdb.blobdb.value.size"),
+        /// BlobDB Put/PutWithTTL/PutUntil/Write latency. Only applicable to legacy
+        /// BlobDB.
+        BlobDbWriteMicros("rocksdb.blobdb.write.micros"),
+        /// BlobDB Get latency. Only applicable to legacy BlobDB.
+        BlobDbGetMicros("rocksdb.blobdb.get.micros"),
+        /// BlobDB MultiGet latency. Only applicable to legacy BlobDB.
+        BlobDbMultigetMicros("rocksdb.blobdb.multiget.micros"),
+        /// BlobDB Seek/SeekToFirst/SeekToLast/SeekForPrev latency. Only applicable to
+        /// legacy BlobDB.
+        BlobDbSeekMicros("rocksdb.blobdb.seek.micros"),
+        /// BlobDB Next latency. Only applicable to legacy BlobDB.
+        BlobDbNextMicros("rocksdb.blobdb.next.micros"),
+        /// BlobDB Prev latency. Only applicable to legacy BlobDB.
+        BlobDbPrevMicros("rocksdb.blobdb.prev.micros"),
+        /// Blob file write latency.
+        BlobDbBlobFileWriteMicros("rocksdb.blobdb.blob.file.write.micros"),
+        /// Blob file read latency.
+        BlobDbBlobFileReadMicros("rocksdb.blobdb.blob.file.read.micros"),
+        /// Blob file sync latency.
+        BlobDbBlobFileSyncMicros("rocksdb.blobdb.blob.file.sync.micros"),
+        /// BlobDB garbage collection time. DEPRECATED.
+        BlobDbGcMicros("rocksdb.blobdb.gc.micros"),
+        /// BlobDB compression time.
+        BlobDbCompressionMicros("rocksdb.blobdb.compression.micros"),
+        /// BlobDB decompression time.
+        BlobDbDecompressionMicros("rocksdb.blobdb.decompression.micros"),
+        /// Time spent flushing memtable to disk
+        FlushTime("rocksdb.db.flush.micros"),
+        SstBatchSize("rocksdb.sst.batch.size"),
+        /// Num of index and filter blocks read from file system per level.
+        NumIndexAndFilterBlocksReadPerLevel("rocksdb.num.index.and.filter.blocks.read.per.level"),
+        /// Num of data blocks read from file system per level.
+        /// Obsolete
+        NumDataBlocksReadPerLevel("rocksdb.num.data.blocks.read.per.level"),
+        /// Num of sst files read from file system per level.
+        NumSstReadPerLevel("rocksdb.num.sst.read.per.level"),
+        /// Error handler statistics
+        ErrorHandlerAutoresumeRetryCount("rocksdb.error.handler.autoresume.retry.count"),
+        /// Stats related to asynchronous read requests.
+        AsyncReadBytes("rocksdb.async.read.bytes"),
+        PollWaitMicros("rocksdb.poll.wait.micros"),
+        /// Number of prefetched bytes discarded by RocksDB.
+        PrefetchedBytesDiscarded("rocksdb.prefetched.bytes.discarded"),
+        /// Number of IOs issued in parallel in a MultiGet batch
+        MultigetIoBatchSize("rocksdb.multiget.io.batch.size"),
+        /// Number of levels requiring IO for MultiGet
+        NumLevelReadPerMultiget("rocksdb.num.level.read.per.multiget"),
+    }
+}
+
+/// A summary of a [`Histogram`].
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct HistogramData {
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub average: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+    pub count: u64,
+    pub sum: u64,
+}
+
+/// Controls which statistics RocksDB collects.
+///
+/// Every level collects everything the previous ones do.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(i32)]
+pub enum StatsLevel {
+    /// Disable all metrics
+    DisableAll = 0,
+    /// Disable timer stats, and skip histogram stats
+    ExceptHistogramOrTimers,
+    /// Skip timer stats
+    ExceptTimers,
+    /// Collect all stats except time inside mutex lock AND time spent on
+    /// compression.
+    ExceptDetailedTimers,
+    /// Collect all stats except the counters requiring to get time inside the
+    /// mutex lock.
+    ExceptTimeForMutex,
+    /// Collect all stats, including measuring duration of mutex operations.
+    All,
+}
+
+/// A handle to the statistics of an [`Options`](crate::Options).
+///
+/// The handle shares the statistics with every database opened with the
+/// options it was obtained from, and stays valid after they are closed.
+pub struct Statistics {
+    inner: *mut ffi::rocksdb_ext_statistics_t,
+}
+
+// Statistics are updated concurrently by RocksDB and safe to share.
+unsafe impl Send for Statistics {}
+unsafe impl Sync for Statistics {}
+
+impl Statistics {
+    /// Returns the statistics of `opts`, if enabled.
+    pub(crate) fn from_options(opts: *const ffi::rocksdb_options_t) -> Option<Self> {
+        let inner = unsafe { ffi::rocksdb_ext_options_get_statistics(opts) };
+        if inner.is_null() {
+            None
+        } else {
+            Some(Self { inner })
+        }
+    }
+
+    /// Returns the current value of a counter.
+    pub fn ticker(&self, ticker: Ticker) -> u64 {
+        unsafe { ffi::rocksdb_ext_statistics_get_ticker_count(self.inner, ticker as u32) }
+    }
+
+    /// Returns a summary of a histogram.
+    pub fn histogram(&self, histogram: Histogram) -> HistogramData {
+        let mut data = ffi::rocksdb_ext_histogram_data_t {
+            median: 0.0,
+            percentile95: 0.0,
+            percentile99: 0.0,
+            average: 0.0,
+            standard_deviation: 0.0,
+            max: 0.0,
+            count: 0,
+            sum: 0,
+            min: 0.0,
+        };
+        unsafe {
+            ffi::rocksdb_ext_statistics_get_histogram_data(
+                self.inner,
+                histogram as u32,
+                ptr::addr_of_mut!(data),
+            );
+        }
+        HistogramData {
+            p50: data.median,
+            p95: data.percentile95,
+            p99: data.percentile99,
+            average: data.average,
+            std_dev: data.standard_deviation,
+            min: data.min,
+            max: data.max,
+            count: data.count,
+            sum: data.sum,
+        }
+    }
+
+    /// Resets all counters and histograms to zero.
+    pub fn reset(&self) -> Result<(), Error> {
+        unsafe {
+            ffi_try!(ffi::rocksdb_ext_statistics_reset(self.inner));
+        }
+        Ok(())
+    }
+
+    /// Sets which statistics are collected.
+    ///
+    /// Default: `StatsLevel::ExceptDetailedTimers`
+    pub fn set_stats_level(&self, level: StatsLevel) {
+        unsafe {
+            ffi::rocksdb_ext_statistics_set_stats_level(self.inner, level as i32);
+        }
+    }
+
+    /// Returns which statistics are collected.
+    pub fn stats_level(&self) -> StatsLevel {
+        match unsafe { ffi::rocksdb_ext_statistics_get_stats_level(self.inner) } {
+            0 => StatsLevel::DisableAll,
+            1 => StatsLevel::ExceptHistogramOrTimers,
+            2 => StatsLevel::ExceptTimers,
+            3 => StatsLevel::ExceptDetailedTimers,
+            4 => StatsLevel::ExceptTimeForMutex,
+            _ => StatsLevel::All,
+        }
+    }
+}
+
+impl Drop for Statistics {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::rocksdb_ext_statistics_destroy(self.inner);
+        }
+    }
+}
@@ -0,0 +1,283 @@
+// Copyright 2022 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tables of custom, read-only formats.
+//!
+//! A [`TableFactory`] lets a database serve immutable files in a format of
+//! your own through the normal `get` and iterator paths.  The files are
+//! added with [`DB::ingest_external_file_opts`], using
+//! [`IngestExternalFileOptions::set_write_global_seqno`] disabled so that
+//! RocksDB leaves their contents alone; ingesting them with it enabled
+//! fails with [`ErrorKind::InvalidArgument`].
+//!
+//! Tables written by the database itself, by flushes and compactions, keep
+//! the block-based format, so the database remains writable.  Compactions
+//! rewrite ingested files into that format.
+//!
+//! Files are opened by the factory unless they are block-based tables, which
+//! are recognized by their footer.
+//!
+//! [`DB::ingest_external_file_opts`]: crate::DBWithThreadMode::ingest_external_file_opts
+//! [`IngestExternalFileOptions::set_write_global_seqno`]: crate::IngestExternalFileOptions::set_write_global_seqno
+//! [`ErrorKind::InvalidArgument`]: crate::ErrorKind::InvalidArgument
+
+use std::ffi::{CStr, CString};
+use std::io;
+use std::path::PathBuf;
+use std::ptr::{self, NonNull};
+use std::slice;
+
+use libc::{c_char, c_uchar, c_void, size_t};
+
+use crate::{ffi, ffi_util::error_message, file_system::to_path_buf_lossy};
+
+/// Opens tables of a custom format.
+pub trait TableFactory: Send + Sync + 'static {
+    type Reader: TableReader;
+
+    /// The name of the table factory.
+    fn name(&self) -> &CStr;
+
+    /// Opens a table file, which may be read until the reader is dropped.
+    fn open(&self, file: TableFile) -> io::Result<Self::Reader>;
+}
+
+/// A table of a custom format, opened by a [`TableFactory`].
+///
+/// Readers are shared by all threads reading the table.  The table is
+/// scanned once after it is opened to collect the statistics RocksDB keeps
+/// about every table.
+pub trait TableReader: Send + Sync + 'static {
+    /// Returns a new, unpositioned iterator over the entries of the table.
+    fn new_iterator(&self) -> Box<dyn TableIterator + '_>;
+}
+
+/// An iterator over the entries of a table, sorted by key in the order of
+/// the comparator of the column family.
+///
+/// Keys must be unique.  Positioning the iterator past either end of the
+/// table makes it invalid.
+pub trait TableIterator: Send {
+    /// Returns `true` if the iterator is positioned at an entry.
+    fn valid(&self) -> bool;
+
+    /// Positions the iterator at the first entry.
+    fn seek_to_first(&mut self);
+
+    /// Positions the iterator at the last entry.
+    fn seek_to_last(&mut self);
+
+    /// Positions the iterator at the first entry with a key at or past `key`.
+    fn seek(&mut self, key: &[u8]);
+
+    /// Positions the iterator at the last entry with a key at or before
+    /// `key`.
+    fn seek_for_prev(&mut self, key: &[u8]);
+
+    /// Moves to the next entry.  Requires the iterator to be valid.
+    fn next(&mut self);
+
+    /// Moves to the previous entry.  Requires the iterator to be valid.
+    fn prev(&mut self);
+
+    /// Returns the key of the current entry.  Requires the iterator to be
+    /// valid.
+    fn key(&self) -> &[u8];
+
+    /// Returns the value of the current entry.  Requires the iterator to be
+    /// valid.
+    fn value(&self) -> &[u8];
+
+    /// Returns the error that made the iterator invalid, if any.
+    fn status(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A table file opened by RocksDB, read through the file system of the
+/// database.
+pub struct TableFile {
+    inner: NonNull<ffi::rocksdb_ext_table_file_t>,
+}
+
+unsafe impl Send for TableFile {}
+unsafe impl Sync for TableFile {}
+
+impl TableFile {
+    /// Returns the path of the file.
+    pub fn path(&self) -> PathBuf {
+        unsafe {
+            let mut len: size_t = 0;
+            let name =
+                ffi::rocksdb_ext_table_file_name(self.inner.as_ptr(), ptr::addr_of_mut!(len));
+            to_path_buf_lossy(name, len)
+        }
+    }
+
+    /// Returns the size of the file in bytes.
+    pub fn size(&self) -> u64 {
+        unsafe { ffi::rocksdb_ext_table_file_size(self.inner.as_ptr()) }
+    }
+
+    /// Reads up to `len` bytes at `offset`.  Fewer bytes are only returned at
+    /// the end of the file.
+    pub fn read(&self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let mut buf = vec![0; len];
+        let mut err: *mut c_char = ptr::null_mut();
+        let read = unsafe {
+            ffi::rocksdb_ext_table_file_read(
+                self.inner.as_ptr(),
+                offset,
+                len,
+                buf.as_mut_ptr() as *mut c_char,
+                ptr::addr_of_mut!(err),
+            )
+        };
+        if !err.is_null() {
+            return Err(io::Error::new(io::ErrorKind::Other, error_message(err)));
+        }
+        buf.truncate(read);
+        Ok(buf)
+    }
+}
+
+impl Drop for TableFile {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::rocksdb_ext_table_file_destroy(self.inner.as_ptr());
+        }
+    }
+}
+
+pub(crate) fn create_callbacks<F: TableFactory>() -> ffi::rocksdb_ext_table_factory_callbacks_t {
+    ffi::rocksdb_ext_table_factory_callbacks_t {
+        destructor: Some(destructor_callback::<F>),
+        open: Some(open_callback::<F>),
+        reader_destructor: Some(reader_destructor_callback::<F::Reader>),
+        reader_new_iterator: Some(reader_new_iterator_callback::<F::Reader>),
+        iterator_destructor: Some(iterator_destructor_callback),
+        iterator_valid: Some(iterator_valid_callback),
+        iterator_seek_to_first: Some(iterator_seek_to_first_callback),
+        iterator_seek_to_last: Some(iterator_seek_to_last_callback),
+        iterator_seek: Some(iterator_seek_callback),
+        iterator_seek_for_prev: Some(iterator_seek_for_prev_callback),
+        iterator_next: Some(iterator_next_callback),
+        iterator_prev: Some(iterator_prev_callback),
+        iterator_key: Some(iterator_key_callback),
+        iterator_value: Some(iterator_value_callback),
+        iterator_status: Some(iterator_status_callback),
+    }
+}
+
+/// The iterator state handed to RocksDB.  RocksDB drops iterators before
+/// the reader they were created from.
+type IteratorState = Box<dyn TableIterator + 'static>;
+
+unsafe fn set_error(errptr: *mut *mut c_char, e: &io::Error) {
+    let message = CString::new(e.to_string().replace('\0', "")).unwrap_or_default();
+    *errptr = libc::strdup(message.as_ptr());
+}
+
+unsafe extern "C" fn destructor_callback<F: TableFactory>(raw_cb: *mut c_void) {
+    drop(Box::from_raw(raw_cb as *mut F));
+}
+
+unsafe extern "C" fn open_callback<F: TableFactory>(
+    raw_cb: *mut c_void,
+    file: *mut ffi::rocksdb_ext_table_file_t,
+    errptr: *mut *mut c_char,
+) -> *mut c_void {
+    let cb = &*(raw_cb as *const F);
+    let file = TableFile {
+        inner: NonNull::new(file).unwrap(),
+    };
+    match cb.open(file) {
+        Ok(reader) => Box::into_raw(Box::new(reader)).cast::<c_void>(),
+        Err(e) => {
+            set_error(errptr, &e);
+            ptr::null_mut()
+        }
+    }
+}
+
+unsafe extern "C" fn reader_destructor_callback<R: TableReader>(reader: *mut c_void) {
+    drop(Box::from_raw(reader as *mut R));
+}
+
+unsafe extern "C" fn reader_new_iterator_callback<R: TableReader>(
+    reader: *mut c_void,
+) -> *mut c_void {
+    let iter = (*(reader as *const R)).new_iterator();
+    // SAFETY: see `IteratorState`.
+    let iter: IteratorState = std::mem::transmute(iter);
+    Box::into_raw(Box::new(iter)).cast::<c_void>()
+}
+
+unsafe fn iterator<'a>(iter: *mut c_void) -> &'a mut IteratorState {
+    &mut *(iter as *mut IteratorState)
+}
+
+unsafe extern "C" fn iterator_destructor_callback(iter: *mut c_void) {
+    drop(Box::from_raw(iter as *mut IteratorState));
+}
+
+unsafe extern "C" fn iterator_valid_callback(iter: *mut c_void) -> c_uchar {
+    c_uchar::from(iterator(iter).valid())
+}
+
+unsafe extern "C" fn iterator_seek_to_first_callback(iter: *mut c_void) {
+    iterator(iter).seek_to_first();
+}
+
+unsafe extern "C" fn iterator_seek_to_last_callback(iter: *mut c_void) {
+    iterator(iter).seek_to_last();
+}
+
+unsafe extern "C" fn iterator_seek_callback(iter: *mut c_void, key: *const c_char, len: size_t) {
+    iterator(iter).seek(slice::from_raw_parts(key as *const u8, len));
+}
+
+unsafe extern "C" fn iterator_seek_for_prev_callback(
+    iter: *mut c_void,
+    key: *const c_char,
+    len: size_t,
+) {
+    iterator(iter).seek_for_prev(slice::from_raw_parts(key as *const u8, len));
+}
+
+unsafe extern "C" fn iterator_next_callback(iter: *mut c_void) {
+    iterator(iter).next();
+}
+
+unsafe extern "C" fn iterator_prev_callback(iter: *mut c_void) {
+    iterator(iter).prev();
+}
+
+unsafe extern "C" fn iterator_key_callback(iter: *mut c_void, len: *mut size_t) -> *const c_char {
+    let key = iterator(iter).key();
+    *len = key.len();
+    key.as_ptr() as *const c_char
+}
+
+unsafe extern "C" fn iterator_value_callback(iter: *mut c_void, len: *mut size_t) -> *const c_char {
+    let value = iterator(iter).value();
+    *len = value.len();
+    value.as_ptr() as *const c_char
+}
+
+unsafe extern "C" fn iterator_status_callback(iter: *mut c_void, errptr: *mut *mut c_char) {
+    if let Err(e) = iterator(iter).status() {
+        set_error(errptr, &e);
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright 2022 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Properties RocksDB records about every table.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::ptr::{self, NonNull};
+use std::slice;
+
+use libc::{c_char, c_void, size_t};
+
+use crate::ffi;
+
+/// The properties of a set of tables, by the path of their files.
+pub type TablePropertiesCollection = HashMap<String, TableProperties>;
+
+/// The properties of a table, recorded when the table was written.
+pub struct TableProperties {
+    inner: NonNull<ffi::rocksdb_ext_table_properties_t>,
+}
+
+// The properties are immutable once the table is written.
+unsafe impl Send for TableProperties {}
+unsafe impl Sync for TableProperties {}
+
+macro_rules! uint64_properties {
+    ($($(#[$attr:meta])* $name:ident => $getter:ident,)*) => {
+        $(
+            $(#[$attr])*
+            pub fn $name(&self) -> u64 {
+                unsafe { ffi::$getter(self.inner.as_ptr()) }
+            }
+        )*
+    };
+}
+
+macro_rules! string_properties {
+    ($($(#[$attr:meta])* $name:ident => $getter:ident,)*) => {
+        $(
+            $(#[$attr])*
+            pub fn $name(&self) -> String {
+                let bytes = unsafe {
+                    let mut len: size_t = 0;
+                    let s = ffi::$getter(self.inner.as_ptr(), ptr::addr_of_mut!(len));
+                    slice::from_raw_parts(s as *const u8, len)
+                };
+                String::from_utf8_lossy(bytes).into_owned()
+            }
+        )*
+    };
+}
+
+impl TableProperties {
+    pub(crate) fn from_raw(inner: *mut ffi::rocksdb_ext_table_properties_t) -> Self {
+        Self {
+            inner: NonNull::new(inner).unwrap(),
+        }
+    }
+
+    uint64_properties! {
+        /// The total size of all data blocks.
+        data_size => rocksdb_ext_table_properties_get_data_size,
+        /// The size of the index block.
+        index_size => rocksdb_ext_table_properties_get_index_size,
+        /// The size of the filter block.
+        filter_size => rocksdb_ext_table_properties_get_filter_size,
+        /// The total size of all keys before compression.
+        raw_key_size => rocksdb_ext_table_properties_get_raw_key_size,
+        /// The total size of all values before compression.
+        raw_value_size => rocksdb_ext_table_properties_get_raw_value_size,
+        /// The number of data blocks.
+        num_data_blocks => rocksdb_ext_table_properties_get_num_data_blocks,
+        /// The number of entries, including deletions and merge operands.
+        num_entries => rocksdb_ext_table_properties_get_num_entries,
+        /// The number of deletions.
+        num_deletions => rocksdb_ext_table_properties_get_num_deletions,
+        /// The number of merge operands.
+        num_merge_operands => rocksdb_ext_table_properties_get_num_merge_operands,
+        /// The number of range deletions.
+        num_range_deletions => rocksdb_ext_table_properties_get_num_range_deletions,
+        /// The format version of the table.
+        format_version => rocksdb_ext_table_properties_get_format_version,
+        /// The length of every key if all keys have the same length, or 0.
+        fixed_key_len => rocksdb_ext_table_properties_get_fixed_key_len,
+        /// The ID of the column family the table belongs to.
+        column_family_id => rocksdb_ext_table_properties_get_column_family_id,
+        /// The time, in seconds since the epoch, the oldest data in the table
+        /// was written, or 0 if unknown.
+        creation_time => rocksdb_ext_table_properties_get_creation_time,
+        /// The time, in seconds since the epoch, the oldest key in the table
+        /// was written, or 0 if unknown.
+        oldest_key_time => rocksdb_ext_table_properties_get_oldest_key_time,
+        /// The time, in seconds since the epoch, the table was created, or 0
+        /// if unknown.
+        file_creation_time => rocksdb_ext_table_properties_get_file_creation_time,
+    }
+
+    string_properties! {
+        /// The name of the column family the table belongs to.
+        column_family_name => rocksdb_ext_table_properties_get_column_family_name,
+        /// The name of the comparator the table is sorted by.
+        comparator_name => rocksdb_ext_table_properties_get_comparator_name,
+        /// The name of the merge operator, or `"nullptr"` if none was set.
+        merge_operator_name => rocksdb_ext_table_properties_get_merge_operator_name,
+        /// The name of the prefix extractor, or `"nullptr"` if none was set.
+        prefix_extractor_name => rocksdb_ext_table_properties_get_prefix_extractor_name,
+        /// The name of the filter policy, or empty if none was set.
+        filter_policy_name => rocksdb_ext_table_properties_get_filter_policy_name,
+        /// The name of the compression algorithm of the data blocks.
+        compression_name => rocksdb_ext_table_properties_get_compression_name,
+        /// The compression options of the data blocks.
+        compression_options => rocksdb_ext_table_properties_get_compression_options,
+    }
+
+    /// Returns the properties added by the table properties collectors of
+    /// the column family.
+    pub fn user_collected_properties(&self) -> HashMap<String, Vec<u8>> {
+        let mut props = HashMap::new();
+        unsafe {
+            ffi::rocksdb_ext_table_properties_get_user_collected_properties(
+                self.inner.as_ptr(),
+                ptr::addr_of_mut!(props).cast::<c_void>(),
+                Some(add_user_collected_property),
+            );
+        }
+        props
+    }
+}
+
+unsafe extern "C" fn add_user_collected_property(
+    state: *mut c_void,
+    key: *const c_char,
+    key_len: size_t,
+    value: *const c_char,
+    value_len: size_t,
+) {
+    let props = &mut *(state as *mut HashMap<String, Vec<u8>>);
+    let key = slice::from_raw_parts(key as *const u8, key_len);
+    let value = slice::from_raw_parts(value as *const u8, value_len);
+    props.insert(String::from_utf8_lossy(key).into_owned(), value.to_vec());
+}
+
+/// Takes ownership of a collection returned by RocksDB.
+pub(crate) unsafe fn collection_from_raw(
+    coll: *mut ffi::rocksdb_ext_table_properties_collection_t,
+) -> TablePropertiesCollection {
+    let n = ffi::rocksdb_ext_table_properties_collection_count(coll);
+    let mut props = HashMap::with_capacity(n);
+    for i in 0..n {
+        let mut len: size_t = 0;
+        let name =
+            ffi::rocksdb_ext_table_properties_collection_name(coll, i, ptr::addr_of_mut!(len));
+        let name = slice::from_raw_parts(name as *const u8, len);
+        props.insert(
+            String::from_utf8_lossy(name).into_owned(),
+            TableProperties::from_raw(ffi::rocksdb_ext_table_properties_collection_get(coll, i)),
+        );
+    }
+    ffi::rocksdb_ext_table_properties_collection_destroy(coll);
+    props
+}
+
+impl fmt::Debug for TableProperties {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TableProperties")
+            .field("num_entries", &self.num_entries())
+            .field("data_size", &self.data_size())
+            .field("column_family_name", &self.column_family_name())
+            .field("compression_name", &self.compression_name())
+            .field("creation_time", &self.creation_time())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for TableProperties {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::rocksdb_ext_table_properties_destroy(self.inner.as_ptr());
+        }
+    }
+}
@@ -0,0 +1,259 @@
+// Copyright 2022 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Custom properties collected for every table a database writes.
+//!
+//! The properties are stored in the table and returned by
+//! [`TableProperties::user_collected_properties`].
+//!
+//! ```
+//! use std::collections::HashMap;
+//! use std::ffi::CStr;
+//! use rocksdb::table_properties_collector::{
+//!     EntryType, TablePropertiesCollector, TablePropertiesCollectorContext,
+//!     TablePropertiesCollectorFactory,
+//! };
+//! use rocksdb::Options;
+//!
+//! /// Asks for tables in which most entries are deletions to be compacted.
+//! #[derive(Default)]
+//! struct DeletionCounter {
+//!     entries: u64,
+//!     deletions: u64,
+//! }
+//!
+//! impl TablePropertiesCollector for DeletionCounter {
+//!     fn add_user_key(&mut self, _: &[u8], _: &[u8], entry_type: EntryType, _: u64, _: u64) {
+//!         self.entries += 1;
+//!         if entry_type == EntryType::Delete {
+//!             self.deletions += 1;
+//!         }
+//!     }
+//!
+//!     fn finish(&mut self) -> HashMap<String, Vec<u8>> {
+//!         let mut props = HashMap::new();
+//!         props.insert("deletions".to_owned(), self.deletions.to_be_bytes().to_vec());
+//!         props
+//!     }
+//!
+//!     fn need_compact(&self) -> bool {
+//!         self.deletions * 2 > self.entries
+//!     }
+//!
+//!     fn name(&self) -> &CStr {
+//!         CStr::from_bytes_with_nul(b"DeletionCounter\0").unwrap()
+//!     }
+//! }
+//!
+//! struct DeletionCounterFactory;
+//!
+//! impl TablePropertiesCollectorFactory for DeletionCounterFactory {
+//!     type Collector = DeletionCounter;
+//!
+//!     fn create(&self, _: TablePropertiesCollectorContext) -> DeletionCounter {
+//!         DeletionCounter::default()
+//!     }
+//!
+//!     fn name(&self) -> &CStr {
+//!         CStr::from_bytes_with_nul(b"DeletionCounterFactory\0").unwrap()
+//!     }
+//! }
+//!
+//! let mut opts = Options::default();
+//! opts.add_table_properties_collector_factory(DeletionCounterFactory);
+//! ```
+//!
+//! [`TableProperties::user_collected_properties`]: crate::TableProperties::user_collected_properties
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::ffi::CStr;
+use std::slice;
+
+use libc::{c_char, c_int, c_uchar, c_void, size_t};
+
+use crate::ffi;
+
+/// The type of an entry added to a table.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum EntryType {
+    Put,
+    Delete,
+    SingleDelete,
+    Merge,
+    RangeDeletion,
+    BlobIndex,
+    DeleteWithTimestamp,
+    Other,
+}
+
+impl EntryType {
+    fn from_raw(value: c_int) -> Self {
+        match value {
+            0 => EntryType::Put,
+            1 => EntryType::Delete,
+            2 => EntryType::SingleDelete,
+            3 => EntryType::Merge,
+            4 => EntryType::RangeDeletion,
+            5 => EntryType::BlobIndex,
+            6 => EntryType::DeleteWithTimestamp,
+            _ => EntryType::Other,
+        }
+    }
+}
+
+/// Collects the properties of a single table while it is written.
+pub trait TablePropertiesCollector: Send + 'static {
+    /// Called for every entry added to the table, in order.  `file_size` is
+    /// the size of the table written so far.
+    fn add_user_key(
+        &mut self,
+        key: &[u8],
+        value: &[u8],
+        entry_type: EntryType,
+        seq: u64,
+        file_size: u64,
+    );
+
+    /// Called once all entries were added.  Returns the properties to store
+    /// in the table.
+    fn finish(&mut self) -> HashMap<String, Vec<u8>>;
+
+    /// Returns `true` if the table should be compacted once it is written.
+    fn need_compact(&self) -> bool {
+        false
+    }
+
+    /// Returns a name that identifies this collector.
+    fn name(&self) -> &CStr;
+}
+
+/// Creates a [`TablePropertiesCollector`] for every table a database writes.
+///
+/// See [`Options::add_table_properties_collector_factory`].
+///
+/// [`Options::add_table_properties_collector_factory`]: crate::Options::add_table_properties_collector_factory
+pub trait TablePropertiesCollectorFactory: Send + Sync + 'static {
+    type Collector: TablePropertiesCollector;
+
+    /// Returns a collector for a new table.  Called concurrently by tables
+    /// written in parallel.
+    fn create(&self, context: TablePropertiesCollectorContext) -> Self::Collector;
+
+    /// Returns a name that identifies this collector factory.
+    fn name(&self) -> &CStr;
+}
+
+/// Context information of a table a collector is created for.
+#[derive(Debug, Copy, Clone)]
+pub struct TablePropertiesCollectorContext {
+    /// The ID of the column family the table belongs to.
+    pub column_family_id: u32,
+    /// The level the table is written to, if known.
+    pub level_at_creation: Option<u32>,
+}
+
+pub(crate) fn create_callbacks<F: TablePropertiesCollectorFactory>(
+) -> ffi::rocksdb_ext_table_properties_collector_factory_callbacks_t {
+    ffi::rocksdb_ext_table_properties_collector_factory_callbacks_t {
+        destructor: Some(destructor_callback::<F>),
+        name: Some(name_callback::<F>),
+        create_collector: Some(create_collector_callback::<F>),
+        collector_destructor: Some(collector_destructor_callback::<F::Collector>),
+        collector_name: Some(collector_name_callback::<F::Collector>),
+        collector_add_user_key: Some(collector_add_user_key_callback::<F::Collector>),
+        collector_finish: Some(collector_finish_callback::<F::Collector>),
+        collector_need_compact: Some(collector_need_compact_callback::<F::Collector>),
+    }
+}
+
+unsafe extern "C" fn destructor_callback<F: TablePropertiesCollectorFactory>(raw_cb: *mut c_void) {
+    drop(Box::from_raw(raw_cb as *mut F));
+}
+
+unsafe extern "C" fn name_callback<F: TablePropertiesCollectorFactory>(
+    raw_cb: *mut c_void,
+) -> *const c_char {
+    let cb = &*(raw_cb as *const F);
+    cb.name().as_ptr()
+}
+
+unsafe extern "C" fn create_collector_callback<F: TablePropertiesCollectorFactory>(
+    raw_cb: *mut c_void,
+    column_family_id: u32,
+    level_at_creation: c_int,
+) -> *mut c_void {
+    let cb = &*(raw_cb as *const F);
+    let collector = cb.create(TablePropertiesCollectorContext {
+        column_family_id,
+        level_at_creation: u32::try_from(level_at_creation).ok(),
+    });
+    Box::into_raw(Box::new(collector)).cast::<c_void>()
+}
+
+unsafe extern "C" fn collector_destructor_callback<C: TablePropertiesCollector>(
+    collector: *mut c_void,
+) {
+    drop(Box::from_raw(collector as *mut C));
+}
+
+unsafe extern "C" fn collector_name_callback<C: TablePropertiesCollector>(
+    collector: *mut c_void,
+) -> *const c_char {
+    let collector = &*(collector as *const C);
+    collector.name().as_ptr()
+}
+
+unsafe extern "C" fn collector_add_user_key_callback<C: TablePropertiesCollector>(
+    collector: *mut c_void,
+    key: *const c_char,
+    key_len: size_t,
+    value: *const c_char,
+    value_len: size_t,
+    entry_type: c_int,
+    seq: u64,
+    file_size: u64,
+) {
+    let collector = &mut *(collector as *mut C);
+    collector.add_user_key(
+        slice::from_raw_parts(key as *const u8, key_len),
+        slice::from_raw_parts(value as *const u8, value_len),
+        EntryType::from_raw(entry_type),
+        seq,
+        file_size,
+    );
+}
+
+unsafe extern "C" fn collector_finish_callback<C: TablePropertiesCollector>(
+    collector: *mut c_void,
+    props: *mut ffi::rocksdb_ext_user_collected_properties_t,
+) {
+    let collector = &mut *(collector as *mut C);
+    for (key, value) in collector.finish() {
+        ffi::rocksdb_ext_user_collected_properties_add(
+            props,
+            key.as_ptr() as *const c_char,
+            key.len(),
+            value.as_ptr() as *const c_char,
+            value.len(),
+        );
+    }
+}
+
+unsafe extern "C" fn collector_need_compact_callback<C: TablePropertiesCollector>(
+    collector: *mut c_void,
+) -> c_uchar {
+    let collector = &*(collector as *const C);
+    c_uchar::from(collector.need_compact())
+}
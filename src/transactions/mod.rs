@@ -19,6 +19,10 @@ mod transaction;
 mod transaction_db;
 
 pub use optimistic_transaction_db::OptimisticTransactionDB;
-pub use options::{OptimisticTransactionOptions, TransactionDBOptions, TransactionOptions};
+#[cfg(not(windows))]
+pub use options::RangeLockManager;
+pub use options::{
+    OptimisticTransactionOptions, TransactionDBOptions, TransactionOptions, TxnDBWritePolicy,
+};
 pub use transaction::Transaction;
-pub use transaction_db::TransactionDB;
+pub use transaction_db::{DeadlockInfo, DeadlockPath, KeyLockInfo, TransactionDB, WaitingTxns};
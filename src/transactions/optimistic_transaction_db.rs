@@ -132,11 +132,13 @@ impl<T: ThreadMode> OptimisticTransactionDB<T> {
 
         let cpath = to_cpath(&path)?;
 
-        if let Err(e) = fs::create_dir_all(&path) {
-            return Err(Error::new(format!(
-                "Failed to create RocksDB directory: `{:?}`.",
-                e
-            )));
+        if opts.uses_local_file_system() {
+            if let Err(e) = fs::create_dir_all(&path) {
+                return Err(Error::new(format!(
+                    "Failed to create RocksDB directory: `{:?}`.",
+                    e
+                )));
+            }
         }
 
         let db: *mut ffi::rocksdb_optimistictransactiondb_t;
@@ -263,6 +265,7 @@ impl<T: ThreadMode> OptimisticTransactionDB<T> {
                     std::ptr::null_mut(),
                 )
             },
+            active: None,
             _marker: PhantomData::default(),
         }
     }
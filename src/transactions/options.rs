@@ -13,7 +13,29 @@
 // limitations under the License.
 //
 
-use crate::ffi;
+use libc::c_int;
+
+use crate::{ffi, Error};
+
+/// When the writes of a transaction are written to the database.
+///
+/// Transactions buffer their writes until they are written to the
+/// database, where other transactions see them once they are committed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TxnDBWritePolicy {
+    /// Writes are written to the database on commit.  Transactions must fit
+    /// in memory, and commit takes as long as writing all of them.
+    WriteCommitted = ffi::rocksdb_ext_txndb_write_committed as isize,
+    /// Writes are written to the database on
+    /// [`prepare`](crate::Transaction::prepare), so that commit only writes
+    /// a commit marker.
+    WritePrepared = ffi::rocksdb_ext_txndb_write_prepared as isize,
+    /// Writes are also written to the database before prepare, once they
+    /// exceed the write batch flush threshold, so that transactions need not
+    /// fit in memory.  See
+    /// [`TransactionDBOptions::set_default_write_batch_flush_threshold`].
+    WriteUnprepared = ffi::rocksdb_ext_txndb_write_unprepared as isize,
+}
 
 pub struct TransactionOptions {
     pub(crate) inner: *mut ffi::rocksdb_transaction_options_t,
@@ -126,6 +148,20 @@ impl TransactionOptions {
             ffi::rocksdb_transaction_options_set_max_write_batch_size(self.inner, size);
         }
     }
+
+    /// Specifies the size in bytes above which a transaction of a
+    /// [`TxnDBWritePolicy::WriteUnprepared`] database writes its buffered
+    /// writes to the database before it is prepared.  0 means never, and a
+    /// negative value uses the threshold of [`TransactionDBOptions`].
+    ///
+    /// Default: -1.
+    pub fn set_write_batch_flush_threshold(&mut self, threshold: i64) {
+        unsafe {
+            ffi::rocksdb_ext_transaction_options_set_write_batch_flush_threshold(
+                self.inner, threshold,
+            );
+        }
+    }
 }
 
 impl Drop for TransactionOptions {
@@ -226,6 +262,46 @@ impl TransactionDBOptions {
             ffi::rocksdb_transactiondb_options_set_num_stripes(self.inner, num_stripes);
         }
     }
+
+    /// Specifies when the writes of a transaction are written to the
+    /// database.  See [`TxnDBWritePolicy`].
+    ///
+    /// Switching the policy of an existing database requires that it has no
+    /// prepared transactions.
+    ///
+    /// Default: [`TxnDBWritePolicy::WriteCommitted`].
+    pub fn set_write_policy(&mut self, policy: TxnDBWritePolicy) {
+        unsafe {
+            ffi::rocksdb_ext_transactiondb_options_set_write_policy(self.inner, policy as c_int);
+        }
+    }
+
+    /// Specifies the size in bytes above which a transaction of a
+    /// [`TxnDBWritePolicy::WriteUnprepared`] database writes its buffered
+    /// writes to the database before it is prepared, unless the
+    /// [`TransactionOptions`] of the transaction specify otherwise.  0 means
+    /// never.
+    ///
+    /// Default: 0.
+    pub fn set_default_write_batch_flush_threshold(&mut self, threshold: i64) {
+        unsafe {
+            ffi::rocksdb_ext_transactiondb_options_set_default_write_batch_flush_threshold(
+                self.inner, threshold,
+            );
+        }
+    }
+
+    /// Makes transactions lock ranges of keys instead of single keys, see
+    /// [`RangeLockManager`].  The manager can be dropped once the database
+    /// is opened.
+    ///
+    /// Default: a lock manager locking single keys.
+    #[cfg(not(windows))]
+    pub fn set_lock_manager(&mut self, manager: &RangeLockManager) {
+        unsafe {
+            ffi::rocksdb_ext_transactiondb_options_set_lock_manager(self.inner, manager.inner);
+        }
+    }
 }
 
 impl Drop for TransactionDBOptions {
@@ -236,6 +312,67 @@ impl Drop for TransactionDBOptions {
     }
 }
 
+/// A lock manager that lets transactions lock ranges of keys, to prevent
+/// phantom reads without locking every key.  Not available on Windows.
+///
+/// See [`TransactionDBOptions::set_lock_manager`] and
+/// [`Transaction::get_range_lock_cf`](crate::Transaction::get_range_lock_cf).
+#[cfg(not(windows))]
+pub struct RangeLockManager {
+    pub(crate) inner: *mut ffi::rocksdb_ext_rangelockmanager_t,
+}
+
+#[cfg(not(windows))]
+unsafe impl Send for RangeLockManager {}
+#[cfg(not(windows))]
+unsafe impl Sync for RangeLockManager {}
+
+#[cfg(not(windows))]
+impl Default for RangeLockManager {
+    fn default() -> Self {
+        let mgr = unsafe { ffi::rocksdb_ext_rangelockmanager_create() };
+        assert!(
+            !mgr.is_null(),
+            "Could not create RocksDB range lock manager"
+        );
+        Self { inner: mgr }
+    }
+}
+
+#[cfg(not(windows))]
+impl RangeLockManager {
+    pub fn new() -> RangeLockManager {
+        RangeLockManager::default()
+    }
+
+    /// Specifies the maximum number of bytes used by locks.  Once exceeded,
+    /// the locks of a transaction are merged into fewer, wider ranges.
+    ///
+    /// Fails if the locks already use more memory.
+    pub fn set_max_lock_memory(&self, max_lock_memory: usize) -> Result<(), Error> {
+        let code = unsafe {
+            ffi::rocksdb_ext_rangelockmanager_set_max_lock_memory(self.inner, max_lock_memory)
+        };
+        if code == 0 {
+            Ok(())
+        } else {
+            Err(Error::new(format!(
+                "Could not set max lock memory to {}: error {}",
+                max_lock_memory, code
+            )))
+        }
+    }
+}
+
+#[cfg(not(windows))]
+impl Drop for RangeLockManager {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::rocksdb_ext_rangelockmanager_destroy(self.inner);
+        }
+    }
+}
+
 pub struct OptimisticTransactionOptions {
     pub(crate) inner: *mut ffi::rocksdb_optimistictransaction_options_t,
 }
@@ -22,6 +22,8 @@ use crate::{
 };
 use libc::{c_char, c_void, size_t};
 
+use super::transaction_db::ActiveTransactions;
+
 /// RocksDB Transaction.
 ///
 /// To use transactions, you must first create a [`TransactionDB`] or [`OptimisticTransactionDB`].
@@ -30,6 +32,8 @@ use libc::{c_char, c_void, size_t};
 /// [`OptimisticTransactionDB`]: crate::OptimisticTransactionDB
 pub struct Transaction<'db, DB> {
     pub(crate) inner: *mut ffi::rocksdb_transaction_t,
+    // the transactions of a TransactionDB, which this one leaves when dropped.
+    pub(crate) active: Option<&'db ActiveTransactions>,
     pub(crate) _marker: PhantomData<&'db DB>,
 }
 
@@ -172,6 +176,17 @@ impl<'db, DB> Transaction<'db, DB> {
         }
     }
 
+    /// Prepares the transaction for a two-phase commit, persisting its writes
+    /// so that it can be committed or rolled back after a crash.  The
+    /// transaction must have a name, see [`set_name`](Self::set_name).
+    ///
+    /// With [`TxnDBWritePolicy::WritePrepared`] and
+    /// [`TxnDBWritePolicy::WriteUnprepared`], the writes are also written to
+    /// the database, so that [`commit`](Self::commit) only writes a commit
+    /// marker.
+    ///
+    /// [`TxnDBWritePolicy::WritePrepared`]: crate::TxnDBWritePolicy::WritePrepared
+    /// [`TxnDBWritePolicy::WriteUnprepared`]: crate::TxnDBWritePolicy::WriteUnprepared
     pub fn prepare(&self) -> Result<(), Error> {
         unsafe {
             ffi_try!(ffi::rocksdb_transaction_prepare(self.inner));
@@ -179,6 +194,20 @@ impl<'db, DB> Transaction<'db, DB> {
         Ok(())
     }
 
+    /// Returns the ID of this transaction, which identifies it in
+    /// [`TransactionDB::lock_status_data`] and
+    /// [`TransactionDB::deadlock_info_buffer`], and which
+    /// [`TransactionDB::waiting_txns`] takes.  Transactions of an
+    /// [`OptimisticTransactionDB`] have no ID and return 0.
+    ///
+    /// [`TransactionDB::lock_status_data`]: crate::TransactionDB::lock_status_data
+    /// [`TransactionDB::waiting_txns`]: crate::TransactionDB::waiting_txns
+    /// [`TransactionDB::deadlock_info_buffer`]: crate::TransactionDB::deadlock_info_buffer
+    /// [`OptimisticTransactionDB`]: crate::OptimisticTransactionDB
+    pub fn id(&self) -> u64 {
+        unsafe { ffi::rocksdb_ext_transaction_get_id(self.inner) }
+    }
+
     /// Returns snapshot associated with transaction if snapshot was enabled in [`TransactionOptions`].
     /// Otherwise, returns a snapshot with `nullptr` inside which doesn't effect read operations.
     ///
@@ -729,6 +758,57 @@ impl<'db, DB> Transaction<'db, DB> {
         Ok(())
     }
 
+    /// Locks the keys from `start` to `end`, both included, in the default
+    /// column family.  See [`get_range_lock_cf`](Self::get_range_lock_cf).
+    #[cfg(not(windows))]
+    pub fn get_range_lock<K: AsRef<[u8]>>(&self, start: K, end: K) -> Result<(), Error> {
+        self.get_range_lock_inner(ptr::null_mut(), start.as_ref(), end.as_ref())
+    }
+
+    /// Locks the keys from `start` to `end`, both included, in `cf`, so
+    /// that other transactions can neither write nor lock keys in the range
+    /// until this transaction ends.
+    ///
+    /// Requires a [`TransactionDB`] opened with a [`RangeLockManager`], and
+    /// otherwise fails with [`NotSupported`].  Like
+    /// [`get_for_update`](Self::get_for_update), may fail with [`Busy`] or
+    /// [`TimedOut`] if another transaction holds a lock in the range.
+    ///
+    /// [`TransactionDB`]: crate::TransactionDB
+    /// [`RangeLockManager`]: crate::RangeLockManager
+    /// [`NotSupported`]: crate::ErrorKind::NotSupported
+    /// [`Busy`]: crate::ErrorKind::Busy
+    /// [`TimedOut`]: crate::ErrorKind::TimedOut
+    #[cfg(not(windows))]
+    pub fn get_range_lock_cf<K: AsRef<[u8]>>(
+        &self,
+        cf: &impl AsColumnFamilyRef,
+        start: K,
+        end: K,
+    ) -> Result<(), Error> {
+        self.get_range_lock_inner(cf.inner(), start.as_ref(), end.as_ref())
+    }
+
+    #[cfg(not(windows))]
+    fn get_range_lock_inner(
+        &self,
+        cf: *mut ffi::rocksdb_column_family_handle_t,
+        start: &[u8],
+        end: &[u8],
+    ) -> Result<(), Error> {
+        unsafe {
+            ffi_try!(ffi::rocksdb_ext_transaction_get_range_lock(
+                self.inner,
+                cf,
+                start.as_ptr() as *const c_char,
+                start.len() as size_t,
+                end.as_ptr() as *const c_char,
+                end.len() as size_t
+            ));
+        }
+        Ok(())
+    }
+
     pub fn iterator<'a: 'b, 'b>(
         &'a self,
         mode: IteratorMode,
@@ -847,6 +927,11 @@ impl<'db, DB> Transaction<'db, DB> {
         DBRawIteratorWithThreadMode::new_cf(self, cf_handle.inner(), readopts)
     }
 
+    /// Returns the writes buffered by this transaction.  With
+    /// [`TxnDBWritePolicy::WriteUnprepared`], writes already written to the
+    /// database are not included.
+    ///
+    /// [`TxnDBWritePolicy::WriteUnprepared`]: crate::TxnDBWritePolicy::WriteUnprepared
     pub fn get_writebatch(&self) -> WriteBatchWithTransaction<true> {
         unsafe {
             let wi = ffi::rocksdb_transaction_get_writebatch_wi(self.inner);
@@ -874,6 +959,9 @@ impl<'db, DB> Transaction<'db, DB> {
 
 impl<'db, DB> Drop for Transaction<'db, DB> {
     fn drop(&mut self) {
+        if let Some(active) = self.active {
+            active.lock().unwrap().remove(&self.id());
+        }
         unsafe {
             ffi::rocksdb_transaction_destroy(self.inner);
         }
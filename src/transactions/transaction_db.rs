@@ -14,12 +14,12 @@
 //
 
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap},
     ffi::CString,
     fs, iter,
     marker::PhantomData,
     path::{Path, PathBuf},
-    ptr,
+    ptr, slice,
     sync::{Arc, Mutex},
 };
 
@@ -36,7 +36,7 @@ use crate::{
     WriteOptions, DB, DEFAULT_COLUMN_FAMILY_NAME,
 };
 use ffi::rocksdb_transaction_t;
-use libc::{c_char, c_int, c_void, size_t};
+use libc::{c_char, c_int, c_uchar, c_void, size_t};
 
 #[cfg(not(feature = "multi-threaded-cf"))]
 type DefaultThreadMode = crate::SingleThreaded;
@@ -74,9 +74,13 @@ pub struct TransactionDB<T: ThreadMode = DefaultThreadMode> {
     path: PathBuf,
     // prepared 2pc transactions.
     prepared: Mutex<Vec<*mut rocksdb_transaction_t>>,
+    active: ActiveTransactions,
     _outlive: Vec<OptionsMustOutliveDB>,
 }
 
+/// The transactions of a [`TransactionDB`] that have not been dropped, by ID.
+pub(crate) type ActiveTransactions = Mutex<HashMap<u64, *mut rocksdb_transaction_t>>;
+
 unsafe impl<T: ThreadMode> Send for TransactionDB<T> {}
 unsafe impl<T: ThreadMode> Sync for TransactionDB<T> {}
 
@@ -232,11 +236,13 @@ impl<T: ThreadMode> TransactionDB<T> {
 
         let cpath = to_cpath(&path)?;
 
-        if let Err(e) = fs::create_dir_all(&path) {
-            return Err(Error::new(format!(
-                "Failed to create RocksDB directory: `{:?}`.",
-                e
-            )));
+        if opts.uses_local_file_system() {
+            if let Err(e) = fs::create_dir_all(&path) {
+                return Err(Error::new(format!(
+                    "Failed to create RocksDB directory: `{:?}`.",
+                    e
+                )));
+            }
         }
 
         let db: *mut ffi::rocksdb_transactiondb_t;
@@ -313,6 +319,7 @@ impl<T: ThreadMode> TransactionDB<T> {
             cfs: T::new_cf_map_internal(cf_map),
             path: path.as_ref().to_path_buf(),
             prepared: Mutex::new(prepared),
+            active: Mutex::default(),
             _outlive: outlive,
         })
     }
@@ -403,15 +410,24 @@ impl<T: ThreadMode> TransactionDB<T> {
         write_opts: &WriteOptions,
         txn_opts: &TransactionOptions,
     ) -> Transaction<'a, Self> {
+        self.track(unsafe {
+            ffi::rocksdb_transaction_begin(
+                self.inner,
+                write_opts.inner,
+                txn_opts.inner,
+                std::ptr::null_mut(),
+            )
+        })
+    }
+
+    /// Wraps a transaction of this database, so that `waiting_txns` can find
+    /// it until it is dropped.
+    fn track(&self, inner: *mut rocksdb_transaction_t) -> Transaction<Self> {
+        let id = unsafe { ffi::rocksdb_ext_transaction_get_id(inner) };
+        self.active.lock().unwrap().insert(id, inner);
         Transaction {
-            inner: unsafe {
-                ffi::rocksdb_transaction_begin(
-                    self.inner,
-                    write_opts.inner,
-                    txn_opts.inner,
-                    std::ptr::null_mut(),
-                )
-            },
+            inner,
+            active: Some(&self.active),
             _marker: PhantomData::default(),
         }
     }
@@ -425,13 +441,73 @@ impl<T: ThreadMode> TransactionDB<T> {
             .lock()
             .unwrap()
             .drain(0..)
-            .map(|inner| Transaction {
-                inner,
-                _marker: PhantomData::default(),
-            })
+            .map(|inner| self.track(inner))
             .collect()
     }
 
+    /// Returns the keys currently locked by transactions, for diagnosing
+    /// lock contention.
+    pub fn lock_status_data(&self) -> Vec<KeyLockInfo> {
+        let mut locks: Vec<KeyLockInfo> = Vec::new();
+        unsafe {
+            ffi::rocksdb_ext_transactiondb_get_lock_status_data(
+                self.inner,
+                ptr::addr_of_mut!(locks).cast::<c_void>(),
+                Some(add_key_lock_info),
+            );
+        }
+        locks
+    }
+
+    /// Returns the most recent deadlocks detected by transactions with
+    /// deadlock detection enabled, newest first.
+    ///
+    /// See [`TransactionOptions::set_deadlock_detect`].
+    pub fn deadlock_info_buffer(&self) -> Vec<DeadlockPath> {
+        let mut paths: Vec<DeadlockPath> = Vec::new();
+        unsafe {
+            ffi::rocksdb_ext_transactiondb_get_deadlock_info_buffer(
+                self.inner,
+                ptr::addr_of_mut!(paths).cast::<c_void>(),
+                Some(add_deadlock_path),
+                Some(add_deadlock_info),
+            );
+        }
+        paths
+    }
+
+    /// Returns the key the transaction with the given [`Transaction::id`] is
+    /// waiting to lock and the transactions holding the lock, or `None` if it
+    /// is not waiting or no longer exists.
+    ///
+    /// Unlike the transaction itself, the database can be shared with other
+    /// threads, which lets them watch a blocked transaction.
+    pub fn waiting_txns(&self, txn_id: u64) -> Option<WaitingTxns> {
+        let mut waiting: Option<WaitingTxns> = None;
+        // Holding the lock keeps the transaction from being destroyed.
+        let active = self.active.lock().unwrap();
+        if let Some(&txn) = active.get(&txn_id) {
+            unsafe {
+                ffi::rocksdb_ext_transaction_get_waiting_txns(
+                    txn,
+                    ptr::addr_of_mut!(waiting).cast::<c_void>(),
+                    Some(set_waiting_txns),
+                );
+            }
+        }
+        waiting
+    }
+
+    /// Sets the number of deadlocks kept by
+    /// [`deadlock_info_buffer`](Self::deadlock_info_buffer).
+    ///
+    /// Default: 5.
+    pub fn set_deadlock_info_buffer_size(&self, size: u32) {
+        unsafe {
+            ffi::rocksdb_ext_transactiondb_set_deadlock_info_buffer_size(self.inner, size);
+        }
+    }
+
     /// Returns the bytes associated with a key value.
     pub fn get<K: AsRef<[u8]>>(&self, key: K) -> Result<Option<Vec<u8>>, Error> {
         self.get_pinned(key).map(|x| x.map(|v| v.as_ref().to_vec()))
@@ -972,6 +1048,120 @@ impl TransactionDB<MultiThreaded> {
     }
 }
 
+/// A key locked by transactions, see [`TransactionDB::lock_status_data`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyLockInfo {
+    /// The ID of the column family of the key
+    pub cf_id: u32,
+    pub key: Vec<u8>,
+    /// The IDs of the transactions holding the lock, see [`Transaction::id`]
+    pub txn_ids: Vec<u64>,
+    /// Whether the lock is exclusive, or shared by `txn_ids`
+    pub exclusive: bool,
+}
+
+/// A transaction waiting for a lock as part of a deadlock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadlockInfo {
+    pub txn_id: u64,
+    /// The ID of the column family of `waiting_key`
+    pub cf_id: u32,
+    /// Whether the transaction waits for an exclusive lock
+    pub exclusive: bool,
+    pub waiting_key: Vec<u8>,
+}
+
+/// A deadlock, see [`TransactionDB::deadlock_info_buffer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadlockPath {
+    /// The transactions of the cycle, each waiting for a lock held by the
+    /// next one
+    pub path: Vec<DeadlockInfo>,
+    /// Whether the detection gave up because the cycle was longer than the
+    /// deadlock detection depth, in which case `path` is incomplete
+    pub limit_exceeded: bool,
+    /// When the deadlock was detected, in seconds since the Unix epoch
+    pub deadlock_time: i64,
+}
+
+/// The lock a transaction is waiting for, see [`TransactionDB::waiting_txns`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WaitingTxns {
+    /// The ID of the column family of `key`
+    pub cf_id: u32,
+    pub key: Vec<u8>,
+    /// The IDs of the transactions holding the lock
+    pub txn_ids: Vec<u64>,
+}
+
+unsafe extern "C" fn set_waiting_txns(
+    state: *mut c_void,
+    cf_id: u32,
+    key: *const c_char,
+    key_len: size_t,
+    txn_ids: *const u64,
+    num_txn_ids: size_t,
+) {
+    if num_txn_ids == 0 {
+        return;
+    }
+    let waiting = &mut *(state as *mut Option<WaitingTxns>);
+    *waiting = Some(WaitingTxns {
+        cf_id,
+        key: slice::from_raw_parts(key as *const u8, key_len).to_vec(),
+        txn_ids: slice::from_raw_parts(txn_ids, num_txn_ids).to_vec(),
+    });
+}
+
+unsafe extern "C" fn add_key_lock_info(
+    state: *mut c_void,
+    cf_id: u32,
+    key: *const c_char,
+    key_len: size_t,
+    txn_ids: *const u64,
+    num_txn_ids: size_t,
+    exclusive: c_uchar,
+) {
+    let locks = &mut *(state as *mut Vec<KeyLockInfo>);
+    locks.push(KeyLockInfo {
+        cf_id,
+        key: slice::from_raw_parts(key as *const u8, key_len).to_vec(),
+        txn_ids: slice::from_raw_parts(txn_ids, num_txn_ids).to_vec(),
+        exclusive: exclusive != 0,
+    });
+}
+
+unsafe extern "C" fn add_deadlock_path(
+    state: *mut c_void,
+    limit_exceeded: c_uchar,
+    deadlock_time: i64,
+) {
+    let paths = &mut *(state as *mut Vec<DeadlockPath>);
+    paths.push(DeadlockPath {
+        path: Vec::new(),
+        limit_exceeded: limit_exceeded != 0,
+        deadlock_time,
+    });
+}
+
+unsafe extern "C" fn add_deadlock_info(
+    state: *mut c_void,
+    txn_id: u64,
+    cf_id: u32,
+    exclusive: c_uchar,
+    waiting_key: *const c_char,
+    waiting_key_len: size_t,
+) {
+    let paths = &mut *(state as *mut Vec<DeadlockPath>);
+    let path = paths.last_mut().expect("deadlock info without a path");
+    path.path.push(DeadlockInfo {
+        txn_id,
+        cf_id,
+        exclusive: exclusive != 0,
+        waiting_key: slice::from_raw_parts(waiting_key as *const u8, waiting_key_len).to_vec(),
+    });
+}
+
 impl<T: ThreadMode> Drop for TransactionDB<T> {
     fn drop(&mut self) {
         unsafe {
@@ -0,0 +1,304 @@
+// Copyright 2020 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Column families with typed keys and values.
+//!
+//! A [`TypedColumnFamily`] encodes keys with a [`KeyCodec`] and values with
+//! a [`ValueCodec`].  A codec implementing both for different types can be
+//! assembled from a pair, such as `(OrderedCodec, BincodeCodec)`.
+//!
+//! Iterators return keys in the order of their encoding, so a key codec must
+//! encode keys such that the comparator of the column family orders them as
+//! wanted.  [`OrderedCodec`] encodes keys such that the default byte-wise
+//! comparator orders them like [`Ord`] does.
+//!
+//! ```
+//! use rocksdb::{
+//!     typed_column_family::{OrderedCodec, RawCodec},
+//!     Options, DB,
+//! };
+//!
+//! let path = "_path_for_rocksdb_storage_typed";
+//! {
+//!     let mut opts = Options::default();
+//!     opts.create_if_missing(true);
+//!     opts.create_missing_column_families(true);
+//!     let db = DB::open_cf(&opts, path, ["scores"]).unwrap();
+//!     let scores = db
+//!         .typed_cf::<(String, u32), Vec<u8>, (OrderedCodec, RawCodec)>("scores")
+//!         .unwrap();
+//!
+//!     scores.put(&("alice".to_owned(), 2), &b"b".to_vec()).unwrap();
+//!     scores.put(&("alice".to_owned(), 10), &b"c".to_vec()).unwrap();
+//!     scores.put(&("al".to_owned(), 1), &b"a".to_vec()).unwrap();
+//!
+//!     let keys: Vec<_> = scores.iterator().map(|item| item.unwrap().0).collect();
+//!     assert_eq!(
+//!         keys,
+//!         [("al".to_owned(), 1), ("alice".to_owned(), 2), ("alice".to_owned(), 10)]
+//!     );
+//! }
+//! let _ = DB::destroy(&Options::default(), path);
+//! ```
+
+use std::{marker::PhantomData, sync::Arc};
+
+use crate::keycodec;
+pub use crate::keycodec::OrderedKey;
+use crate::{
+    AsColumnFamilyRef, BoundColumnFamily, ColumnFamily, DBIteratorWithThreadMode, DBWithThreadMode,
+    Direction, Error, IteratorMode, MultiThreaded, SingleThreaded, ThreadMode,
+};
+
+/// Encodes and decodes the keys of a [`TypedColumnFamily`].
+///
+/// The comparator of the column family orders keys by their encoding, so
+/// the encoding decides the order of iteration.
+pub trait KeyCodec<K> {
+    fn encode_key(key: &K) -> Result<Vec<u8>, Error>;
+    fn decode_key(bytes: &[u8]) -> Result<K, Error>;
+}
+
+/// Encodes and decodes the values and merge operands of a
+/// [`TypedColumnFamily`].
+pub trait ValueCodec<V> {
+    fn encode_value(value: &V) -> Result<Vec<u8>, Error>;
+    fn decode_value(bytes: &[u8]) -> Result<V, Error>;
+}
+
+/// Uses the first codec for keys and the second one for values.
+impl<K, KC: KeyCodec<K>, VC> KeyCodec<K> for (KC, VC) {
+    fn encode_key(key: &K) -> Result<Vec<u8>, Error> {
+        KC::encode_key(key)
+    }
+
+    fn decode_key(bytes: &[u8]) -> Result<K, Error> {
+        KC::decode_key(bytes)
+    }
+}
+
+impl<V, KC, VC: ValueCodec<V>> ValueCodec<V> for (KC, VC) {
+    fn encode_value(value: &V) -> Result<Vec<u8>, Error> {
+        VC::encode_value(value)
+    }
+
+    fn decode_value(bytes: &[u8]) -> Result<V, Error> {
+        VC::decode_value(bytes)
+    }
+}
+
+/// Stores `Vec<u8>` keys and values as they are.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct RawCodec;
+
+impl KeyCodec<Vec<u8>> for RawCodec {
+    fn encode_key(key: &Vec<u8>) -> Result<Vec<u8>, Error> {
+        Ok(key.clone())
+    }
+
+    fn decode_key(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        Ok(bytes.to_vec())
+    }
+}
+
+impl ValueCodec<Vec<u8>> for RawCodec {
+    fn encode_value(value: &Vec<u8>) -> Result<Vec<u8>, Error> {
+        Ok(value.clone())
+    }
+
+    fn decode_value(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        Ok(bytes.to_vec())
+    }
+}
+
+/// Encodes [`OrderedKey`] types with [`keycodec`], such that the default
+/// byte-wise comparator orders them like [`Ord`] does.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct OrderedCodec;
+
+impl<K: OrderedKey> KeyCodec<K> for OrderedCodec {
+    fn encode_key(key: &K) -> Result<Vec<u8>, Error> {
+        Ok(keycodec::encode(key))
+    }
+
+    fn decode_key(bytes: &[u8]) -> Result<K, Error> {
+        keycodec::decode(bytes)
+    }
+}
+
+impl<V: OrderedKey> ValueCodec<V> for OrderedCodec {
+    fn encode_value(value: &V) -> Result<Vec<u8>, Error> {
+        Self::encode_key(value)
+    }
+
+    fn decode_value(bytes: &[u8]) -> Result<V, Error> {
+        Self::decode_key(bytes)
+    }
+}
+
+/// Encodes values with [`bincode`] and its default options.  The encoding
+/// does not preserve order, so it is not a key codec.
+///
+/// Requires the `bincode` feature.
+#[cfg(feature = "bincode")]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct BincodeCodec;
+
+#[cfg(feature = "bincode")]
+impl<V: serde::Serialize + serde::de::DeserializeOwned> ValueCodec<V> for BincodeCodec {
+    fn encode_value(value: &V) -> Result<Vec<u8>, Error> {
+        bincode::serialize(value)
+            .map_err(|e| Error::new(format!("Invalid argument: could not encode value: {}", e)))
+    }
+
+    fn decode_value(bytes: &[u8]) -> Result<V, Error> {
+        bincode::deserialize(bytes)
+            .map_err(|e| Error::new(format!("Corruption: could not decode value: {}", e)))
+    }
+}
+
+/// A column family with keys of type `K` and values of type `V`, encoded by
+/// the codec `C`.
+///
+/// See [`DBWithThreadMode::typed_cf`] and the [module](self) documentation.
+pub struct TypedColumnFamily<'db, K, V, C, T: ThreadMode, CF> {
+    db: &'db DBWithThreadMode<T>,
+    cf: CF,
+    _marker: PhantomData<fn() -> (K, V, C)>,
+}
+
+impl<'db, K, V, C, T, CF> TypedColumnFamily<'db, K, V, C, T, CF>
+where
+    C: KeyCodec<K> + ValueCodec<V>,
+    T: ThreadMode,
+    CF: AsColumnFamilyRef,
+{
+    pub fn new(db: &'db DBWithThreadMode<T>, cf: CF) -> Self {
+        Self {
+            db,
+            cf,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the untyped column family.
+    pub fn cf(&self) -> &CF {
+        &self.cf
+    }
+
+    pub fn get(&self, key: &K) -> Result<Option<V>, Error> {
+        let key = C::encode_key(key)?;
+        match self.db.get_pinned_cf(&self.cf, key)? {
+            Some(value) => C::decode_value(&value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn put(&self, key: &K, value: &V) -> Result<(), Error> {
+        self.db
+            .put_cf(&self.cf, C::encode_key(key)?, C::encode_value(value)?)
+    }
+
+    /// Merges `operand` into the value of `key`, using the merge operator of
+    /// the column family.  The operand may have another type than values,
+    /// as long as the codec encodes it.
+    pub fn merge<O>(&self, key: &K, operand: &O) -> Result<(), Error>
+    where
+        C: ValueCodec<O>,
+    {
+        self.db.merge_cf(
+            &self.cf,
+            C::encode_key(key)?,
+            <C as ValueCodec<O>>::encode_value(operand)?,
+        )
+    }
+
+    pub fn delete(&self, key: &K) -> Result<(), Error> {
+        self.db.delete_cf(&self.cf, C::encode_key(key)?)
+    }
+
+    /// Iterates all entries in the order of their encoded keys.
+    pub fn iterator(&self) -> TypedIterator<'db, K, V, C, T> {
+        TypedIterator::new(self.db.iterator_cf(&self.cf, IteratorMode::Start))
+    }
+
+    /// Iterates the entries from `key` in `direction`.
+    pub fn iterator_from(
+        &self,
+        key: &K,
+        direction: Direction,
+    ) -> Result<TypedIterator<'db, K, V, C, T>, Error> {
+        let key = C::encode_key(key)?;
+        Ok(TypedIterator::new(self.db.iterator_cf(
+            &self.cf,
+            IteratorMode::From(&key, direction),
+        )))
+    }
+}
+
+impl DBWithThreadMode<SingleThreaded> {
+    /// Returns the column family `name` with keys of type `K` and values of
+    /// type `V`, encoded by the codec `C`.
+    pub fn typed_cf<K, V, C>(
+        &self,
+        name: &str,
+    ) -> Option<TypedColumnFamily<'_, K, V, C, SingleThreaded, &ColumnFamily>>
+    where
+        C: KeyCodec<K> + ValueCodec<V>,
+    {
+        Some(TypedColumnFamily::new(self, self.cf_handle(name)?))
+    }
+}
+
+impl DBWithThreadMode<MultiThreaded> {
+    /// Returns the column family `name` with keys of type `K` and values of
+    /// type `V`, encoded by the codec `C`.
+    pub fn typed_cf<K, V, C>(
+        &self,
+        name: &str,
+    ) -> Option<TypedColumnFamily<'_, K, V, C, MultiThreaded, Arc<BoundColumnFamily<'_>>>>
+    where
+        C: KeyCodec<K> + ValueCodec<V>,
+    {
+        Some(TypedColumnFamily::new(self, self.cf_handle(name)?))
+    }
+}
+
+/// Iterates the decoded entries of a [`TypedColumnFamily`].
+pub struct TypedIterator<'db, K, V, C, T: ThreadMode> {
+    inner: DBIteratorWithThreadMode<'db, DBWithThreadMode<T>>,
+    _marker: PhantomData<fn() -> (K, V, C)>,
+}
+
+impl<'db, K, V, C, T: ThreadMode> TypedIterator<'db, K, V, C, T> {
+    fn new(inner: DBIteratorWithThreadMode<'db, DBWithThreadMode<T>>) -> Self {
+        Self {
+            inner,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'db, K, V, C, T> Iterator for TypedIterator<'db, K, V, C, T>
+where
+    C: KeyCodec<K> + ValueCodec<V>,
+    T: ThreadMode,
+{
+    type Item = Result<(K, V), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.inner.next()?;
+        Some(item.and_then(|(key, value)| Ok((C::decode_key(&key)?, C::decode_value(&value)?))))
+    }
+}
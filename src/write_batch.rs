@@ -12,9 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{ffi, AsColumnFamilyRef};
-use libc::{c_char, c_void, size_t};
-use std::slice;
+use crate::{ffi, AsColumnFamilyRef, Error};
+use libc::{c_char, c_uchar, c_void, size_t};
+use std::{ptr, slice};
 
 /// A type alias to keep compatibility. See [`WriteBatchWithTransaction`] for details
 pub type WriteBatch = WriteBatchWithTransaction<false>;
@@ -54,6 +54,9 @@ pub struct WriteBatchWithTransaction<const TRANSACTION: bool> {
 ///
 /// The application must provide an implementation of this trait when
 /// iterating the operations within a `WriteBatch`
+///
+/// Keys written with a timestamp are passed with the timestamp appended,
+/// see [`split_timestamp`](crate::split_timestamp).
 pub trait WriteBatchIterator {
     /// Called with a key and value that were `put` into the batch.
     fn put(&mut self, key: Box<[u8]>, value: Box<[u8]>);
@@ -89,6 +92,154 @@ unsafe extern "C" fn writebatch_delete_callback(state: *mut c_void, k: *const c_
     leaked_cb.delete(key.to_vec().into_boxed_slice());
 }
 
+/// Receives every record of a write batch.
+///
+/// Unlike [`WriteBatchIterator`], it is told the column family of every
+/// operation, as well as merges, range deletions, log data and the markers
+/// of two-phase commit found in the WAL of a [`TransactionDB`].  Column
+/// families are identified by [`AsColumnFamilyRef::id`].  The slices passed
+/// to the methods are only valid during the call.
+///
+/// Keys written with a timestamp are passed with the timestamp appended,
+/// see [`split_timestamp`](crate::split_timestamp).
+///
+/// [`TransactionDB`]: crate::TransactionDB
+pub trait WriteBatchIteratorCf {
+    fn put_cf(&mut self, cf_id: u32, key: &[u8], value: &[u8]);
+    fn delete_cf(&mut self, cf_id: u32, key: &[u8]);
+    fn single_delete_cf(&mut self, cf_id: u32, key: &[u8]);
+    fn merge_cf(&mut self, cf_id: u32, key: &[u8], value: &[u8]);
+    /// Called with a range deletion from `begin_key` included to `end_key`
+    /// excluded.
+    fn delete_range_cf(&mut self, cf_id: u32, begin_key: &[u8], end_key: &[u8]);
+
+    /// Called with a blob added by
+    /// [`put_log_data`](WriteBatchWithTransaction::put_log_data).
+    fn log_data(&mut self, _blob: &[u8]) {}
+
+    /// Called before the operations of a prepared transaction.
+    /// `unprepared` is true if the transaction writes its operations before
+    /// being prepared, as with [`TxnDBWritePolicy::WriteUnprepared`].
+    ///
+    /// [`TxnDBWritePolicy::WriteUnprepared`]: crate::TxnDBWritePolicy::WriteUnprepared
+    fn mark_begin_prepare(&mut self, _unprepared: bool) {}
+
+    /// Called after the operations of the prepared transaction `xid`.
+    fn mark_end_prepare(&mut self, _xid: &[u8]) {}
+
+    fn mark_commit(&mut self, _xid: &[u8]) {}
+
+    fn mark_rollback(&mut self, _xid: &[u8]) {}
+
+    /// Called in place of the markers of a transaction without operations,
+    /// or of an empty batch if `empty_batch` is true.
+    fn mark_noop(&mut self, _empty_batch: bool) {}
+}
+
+/// Casts the state of the `rocksdb_ext_writebatch_handler_t` callbacks back
+/// into the handler.
+unsafe fn handler_cf<'a>(state: *mut c_void) -> &'a mut dyn WriteBatchIteratorCf {
+    *(state as *mut &mut dyn WriteBatchIteratorCf)
+}
+
+unsafe fn bytes<'a>(data: *const c_char, len: size_t) -> &'a [u8] {
+    slice::from_raw_parts(data as *const u8, len as usize)
+}
+
+unsafe extern "C" fn writebatch_put_cf_callback(
+    state: *mut c_void,
+    cf_id: u32,
+    k: *const c_char,
+    klen: size_t,
+    v: *const c_char,
+    vlen: size_t,
+) {
+    handler_cf(state).put_cf(cf_id, bytes(k, klen), bytes(v, vlen));
+}
+
+unsafe extern "C" fn writebatch_delete_cf_callback(
+    state: *mut c_void,
+    cf_id: u32,
+    k: *const c_char,
+    klen: size_t,
+) {
+    handler_cf(state).delete_cf(cf_id, bytes(k, klen));
+}
+
+unsafe extern "C" fn writebatch_single_delete_cf_callback(
+    state: *mut c_void,
+    cf_id: u32,
+    k: *const c_char,
+    klen: size_t,
+) {
+    handler_cf(state).single_delete_cf(cf_id, bytes(k, klen));
+}
+
+unsafe extern "C" fn writebatch_delete_range_cf_callback(
+    state: *mut c_void,
+    cf_id: u32,
+    begin: *const c_char,
+    begin_len: size_t,
+    end: *const c_char,
+    end_len: size_t,
+) {
+    handler_cf(state).delete_range_cf(cf_id, bytes(begin, begin_len), bytes(end, end_len));
+}
+
+unsafe extern "C" fn writebatch_merge_cf_callback(
+    state: *mut c_void,
+    cf_id: u32,
+    k: *const c_char,
+    klen: size_t,
+    v: *const c_char,
+    vlen: size_t,
+) {
+    handler_cf(state).merge_cf(cf_id, bytes(k, klen), bytes(v, vlen));
+}
+
+unsafe extern "C" fn writebatch_log_data_callback(
+    state: *mut c_void,
+    blob: *const c_char,
+    blob_len: size_t,
+) {
+    handler_cf(state).log_data(bytes(blob, blob_len));
+}
+
+unsafe extern "C" fn writebatch_mark_begin_prepare_callback(
+    state: *mut c_void,
+    unprepared: c_uchar,
+) {
+    handler_cf(state).mark_begin_prepare(unprepared != 0);
+}
+
+unsafe extern "C" fn writebatch_mark_end_prepare_callback(
+    state: *mut c_void,
+    xid: *const c_char,
+    xid_len: size_t,
+) {
+    handler_cf(state).mark_end_prepare(bytes(xid, xid_len));
+}
+
+unsafe extern "C" fn writebatch_mark_commit_callback(
+    state: *mut c_void,
+    xid: *const c_char,
+    xid_len: size_t,
+) {
+    handler_cf(state).mark_commit(bytes(xid, xid_len));
+}
+
+unsafe extern "C" fn writebatch_mark_rollback_callback(
+    state: *mut c_void,
+    xid: *const c_char,
+    xid_len: size_t,
+) {
+    handler_cf(state).mark_rollback(bytes(xid, xid_len));
+}
+
+unsafe extern "C" fn writebatch_mark_noop_callback(state: *mut c_void, empty_batch: c_uchar) {
+    handler_cf(state).mark_noop(empty_batch != 0);
+}
+
 impl<const TRANSACTION: bool> WriteBatchWithTransaction<TRANSACTION> {
     /// Construct with a reference to a byte array serialized by [`WriteBatch`].
     pub fn from_data(data: &[u8]) -> Self {
@@ -149,6 +300,48 @@ impl<const TRANSACTION: bool> WriteBatchWithTransaction<TRANSACTION> {
         }
     }
 
+    /// Passes every record of this write batch to `handler`, in order.
+    /// Fails if the batch is corrupted.
+    pub fn iterate_cf(&self, handler: &mut dyn WriteBatchIteratorCf) -> Result<(), Error> {
+        let callbacks = ffi::rocksdb_ext_writebatch_handler_t {
+            put: Some(writebatch_put_cf_callback),
+            delete_: Some(writebatch_delete_cf_callback),
+            single_delete: Some(writebatch_single_delete_cf_callback),
+            delete_range: Some(writebatch_delete_range_cf_callback),
+            merge: Some(writebatch_merge_cf_callback),
+            log_data: Some(writebatch_log_data_callback),
+            mark_begin_prepare: Some(writebatch_mark_begin_prepare_callback),
+            mark_end_prepare: Some(writebatch_mark_end_prepare_callback),
+            mark_commit: Some(writebatch_mark_commit_callback),
+            mark_rollback: Some(writebatch_mark_rollback_callback),
+            mark_noop: Some(writebatch_mark_noop_callback),
+        };
+        let mut handler = handler;
+        unsafe {
+            ffi_try!(ffi::rocksdb_ext_writebatch_iterate_cf(
+                self.inner,
+                ptr::addr_of_mut!(handler).cast::<c_void>(),
+                ptr::addr_of!(callbacks),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Appends a blob which is written to the WAL, but not to the database.
+    /// It is only seen by [`WriteBatchIteratorCf::log_data`], when
+    /// iterating the batch or the WAL.
+    pub fn put_log_data<B: AsRef<[u8]>>(&mut self, blob: B) {
+        let blob = blob.as_ref();
+
+        unsafe {
+            ffi::rocksdb_writebatch_put_log_data(
+                self.inner,
+                blob.as_ptr() as *const c_char,
+                blob.len() as size_t,
+            );
+        }
+    }
+
     /// Insert a value into the database under the given key.
     pub fn put<K, V>(&mut self, key: K, value: V)
     where
@@ -189,6 +382,63 @@ impl<const TRANSACTION: bool> WriteBatchWithTransaction<TRANSACTION> {
         }
     }
 
+    /// Inserts a value for `key` at timestamp `ts`, in the default column
+    /// family, which must have user-defined timestamps.
+    ///
+    /// Iterating the batch passes the key with the timestamp appended, see
+    /// [`split_timestamp`](crate::split_timestamp).
+    pub fn put_with_ts<K, T, V>(&mut self, key: K, ts: T, value: V)
+    where
+        K: AsRef<[u8]>,
+        T: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let key = key.as_ref();
+        let ts = ts.as_ref();
+        let value = value.as_ref();
+
+        unsafe {
+            ffi::rocksdb_ext_writebatch_put_with_ts(
+                self.inner,
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                ts.as_ptr() as *const c_char,
+                ts.len() as size_t,
+                value.as_ptr() as *const c_char,
+                value.len() as size_t,
+            );
+        }
+    }
+
+    /// Inserts a value for `key` at timestamp `ts`, in a column family with
+    /// user-defined timestamps.
+    ///
+    /// Iterating the batch passes the key with the timestamp appended, see
+    /// [`split_timestamp`](crate::split_timestamp).
+    pub fn put_cf_with_ts<K, T, V>(&mut self, cf: &impl AsColumnFamilyRef, key: K, ts: T, value: V)
+    where
+        K: AsRef<[u8]>,
+        T: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let key = key.as_ref();
+        let ts = ts.as_ref();
+        let value = value.as_ref();
+
+        unsafe {
+            ffi::rocksdb_writebatch_put_cf_with_ts(
+                self.inner,
+                cf.inner(),
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                ts.as_ptr() as *const c_char,
+                ts.len() as size_t,
+                value.as_ptr() as *const c_char,
+                value.len() as size_t,
+            );
+        }
+    }
+
     pub fn merge<K, V>(&mut self, key: K, value: V)
     where
         K: AsRef<[u8]>,
@@ -254,6 +504,52 @@ impl<const TRANSACTION: bool> WriteBatchWithTransaction<TRANSACTION> {
         }
     }
 
+    /// Deletes `key` at timestamp `ts`, in the default column family, which
+    /// must have user-defined timestamps.
+    ///
+    /// Iterating the batch passes the key with the timestamp appended, see
+    /// [`split_timestamp`](crate::split_timestamp).
+    pub fn delete_with_ts<K: AsRef<[u8]>, T: AsRef<[u8]>>(&mut self, key: K, ts: T) {
+        let key = key.as_ref();
+        let ts = ts.as_ref();
+
+        unsafe {
+            ffi::rocksdb_ext_writebatch_delete_with_ts(
+                self.inner,
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                ts.as_ptr() as *const c_char,
+                ts.len() as size_t,
+            );
+        }
+    }
+
+    /// Deletes `key` at timestamp `ts`, in a column family with user-defined
+    /// timestamps.
+    ///
+    /// Iterating the batch passes the key with the timestamp appended, see
+    /// [`split_timestamp`](crate::split_timestamp).
+    pub fn delete_cf_with_ts<K: AsRef<[u8]>, T: AsRef<[u8]>>(
+        &mut self,
+        cf: &impl AsColumnFamilyRef,
+        key: K,
+        ts: T,
+    ) {
+        let key = key.as_ref();
+        let ts = ts.as_ref();
+
+        unsafe {
+            ffi::rocksdb_writebatch_delete_cf_with_ts(
+                self.inner,
+                cf.inner(),
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                ts.as_ptr() as *const c_char,
+                ts.len() as size_t,
+            );
+        }
+    }
+
     /// Clear all updates buffered in this batch.
     pub fn clear(&mut self) {
         unsafe {
@@ -0,0 +1,358 @@
+// Copyright 2020 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use libc::{c_char, c_uchar, c_void, size_t};
+
+use crate::{
+    db::{DBCommon, DBInner},
+    ffi,
+    ffi_util::raw_data,
+    AsColumnFamilyRef, DBRawIteratorWithThreadMode, Error, Options, ReadOptions, ThreadMode,
+};
+
+/// A batch of write operations that can be read back before it is written.
+///
+/// Reads can see the batch alone, or the batch on top of a database, as a
+/// transaction sees its own writes.
+///
+/// ```
+/// use rocksdb::{Options, ReadOptions, WriteBatchWithIndex, DB};
+///
+/// let path = "_path_for_rocksdb_storage_wbwi";
+/// {
+///     let db = DB::open_default(path).unwrap();
+///     db.put(b"k1", b"v1").unwrap();
+///
+///     let mut batch = WriteBatchWithIndex::new(0, true);
+///     batch.put(b"k2", b"v2");
+///     batch.delete(b"k1");
+///     assert_eq!(batch.get_from_batch(&Options::default(), b"k2").unwrap().unwrap(), b"v2");
+///     assert!(batch
+///         .get_from_batch_and_db(&db, &ReadOptions::default(), b"k1")
+///         .unwrap()
+///         .is_none());
+///
+///     db.write_with_index(&batch).unwrap();
+///     assert_eq!(db.get(b"k2").unwrap().unwrap(), b"v2");
+/// }
+/// let _ = DB::destroy(&Options::default(), path);
+/// ```
+pub struct WriteBatchWithIndex {
+    pub(crate) inner: *mut ffi::rocksdb_writebatch_wi_t,
+}
+
+unsafe impl Send for WriteBatchWithIndex {}
+
+impl WriteBatchWithIndex {
+    /// Creates a batch with `reserved_bytes` of capacity.
+    ///
+    /// If `overwrite_key` is true, the index keeps only the latest write of
+    /// each key, which is required to iterate the batch over a database.
+    /// Otherwise reads of a key written more than once may fail with
+    /// `MergeInProgress`.
+    pub fn new(reserved_bytes: usize, overwrite_key: bool) -> Self {
+        Self {
+            inner: unsafe {
+                ffi::rocksdb_writebatch_wi_create(reserved_bytes, c_uchar::from(overwrite_key))
+            },
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        unsafe { ffi::rocksdb_writebatch_wi_count(self.inner) as usize }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return a reference to a byte array which represents a serialized version of the batch.
+    pub fn data(&self) -> &[u8] {
+        unsafe {
+            let mut batch_size: size_t = 0;
+            let batch_data = ffi::rocksdb_writebatch_wi_data(self.inner, &mut batch_size);
+            std::slice::from_raw_parts(batch_data as _, batch_size as usize)
+        }
+    }
+
+    /// Insert a value into the database under the given key.
+    pub fn put<K, V>(&mut self, key: K, value: V)
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let key = key.as_ref();
+        let value = value.as_ref();
+
+        unsafe {
+            ffi::rocksdb_writebatch_wi_put(
+                self.inner,
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                value.as_ptr() as *const c_char,
+                value.len() as size_t,
+            );
+        }
+    }
+
+    pub fn put_cf<K, V>(&mut self, cf: &impl AsColumnFamilyRef, key: K, value: V)
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let key = key.as_ref();
+        let value = value.as_ref();
+
+        unsafe {
+            ffi::rocksdb_writebatch_wi_put_cf(
+                self.inner,
+                cf.inner(),
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                value.as_ptr() as *const c_char,
+                value.len() as size_t,
+            );
+        }
+    }
+
+    pub fn merge<K, V>(&mut self, key: K, value: V)
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let key = key.as_ref();
+        let value = value.as_ref();
+
+        unsafe {
+            ffi::rocksdb_writebatch_wi_merge(
+                self.inner,
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                value.as_ptr() as *const c_char,
+                value.len() as size_t,
+            );
+        }
+    }
+
+    pub fn merge_cf<K, V>(&mut self, cf: &impl AsColumnFamilyRef, key: K, value: V)
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let key = key.as_ref();
+        let value = value.as_ref();
+
+        unsafe {
+            ffi::rocksdb_writebatch_wi_merge_cf(
+                self.inner,
+                cf.inner(),
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                value.as_ptr() as *const c_char,
+                value.len() as size_t,
+            );
+        }
+    }
+
+    /// Removes the database entry for key. Does nothing if the key was not found.
+    pub fn delete<K: AsRef<[u8]>>(&mut self, key: K) {
+        let key = key.as_ref();
+
+        unsafe {
+            ffi::rocksdb_writebatch_wi_delete(
+                self.inner,
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+            );
+        }
+    }
+
+    pub fn delete_cf<K: AsRef<[u8]>>(&mut self, cf: &impl AsColumnFamilyRef, key: K) {
+        let key = key.as_ref();
+
+        unsafe {
+            ffi::rocksdb_writebatch_wi_delete_cf(
+                self.inner,
+                cf.inner(),
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+            );
+        }
+    }
+
+    /// Clear all updates buffered in this batch.
+    pub fn clear(&mut self) {
+        unsafe {
+            ffi::rocksdb_writebatch_wi_clear(self.inner);
+        }
+    }
+
+    /// Records the state of the batch for
+    /// [`rollback_to_save_point`](Self::rollback_to_save_point).
+    pub fn set_save_point(&mut self) {
+        unsafe {
+            ffi::rocksdb_writebatch_wi_set_save_point(self.inner);
+        }
+    }
+
+    /// Removes the writes made since the most recent save point, and the
+    /// save point itself.  Fails if there is no save point.
+    pub fn rollback_to_save_point(&mut self) -> Result<(), Error> {
+        unsafe {
+            ffi_try!(ffi::rocksdb_writebatch_wi_rollback_to_save_point(
+                self.inner
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the value of `key` in the batch, or `None` if the batch does
+    /// not write it or deletes it.  `opts` provide the merge operator used
+    /// if the batch merges into the key, which fails with `MergeInProgress`
+    /// unless the batch also puts or deletes the key.
+    pub fn get_from_batch<K: AsRef<[u8]>>(
+        &self,
+        opts: &Options,
+        key: K,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let key = key.as_ref();
+        unsafe {
+            let mut val_len: size_t = 0;
+            let val = ffi_try!(ffi::rocksdb_writebatch_wi_get_from_batch(
+                self.inner,
+                opts.inner,
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                &mut val_len,
+            ));
+            Ok(take_value(val, val_len))
+        }
+    }
+
+    /// Returns the value of `key` in `cf` in the batch.  See
+    /// [`get_from_batch`](Self::get_from_batch).
+    pub fn get_from_batch_cf<K: AsRef<[u8]>>(
+        &self,
+        opts: &Options,
+        cf: &impl AsColumnFamilyRef,
+        key: K,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let key = key.as_ref();
+        unsafe {
+            let mut val_len: size_t = 0;
+            let val = ffi_try!(ffi::rocksdb_writebatch_wi_get_from_batch_cf(
+                self.inner,
+                opts.inner,
+                cf.inner(),
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                &mut val_len,
+            ));
+            Ok(take_value(val, val_len))
+        }
+    }
+
+    /// Returns the value of `key` as if the batch was written to `db`.
+    pub fn get_from_batch_and_db<T: ThreadMode, I: DBInner, K: AsRef<[u8]>>(
+        &self,
+        db: &DBCommon<T, I>,
+        readopts: &ReadOptions,
+        key: K,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let key = key.as_ref();
+        unsafe {
+            let mut val_len: size_t = 0;
+            let val = ffi_try!(ffi::rocksdb_writebatch_wi_get_from_batch_and_db(
+                self.inner,
+                db.inner.inner(),
+                readopts.inner,
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                &mut val_len,
+            ));
+            Ok(take_value(val, val_len))
+        }
+    }
+
+    /// Returns the value of `key` in `cf` as if the batch was written to
+    /// `db`.
+    pub fn get_from_batch_and_db_cf<T: ThreadMode, I: DBInner, K: AsRef<[u8]>>(
+        &self,
+        db: &DBCommon<T, I>,
+        readopts: &ReadOptions,
+        cf: &impl AsColumnFamilyRef,
+        key: K,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let key = key.as_ref();
+        unsafe {
+            let mut val_len: size_t = 0;
+            let val = ffi_try!(ffi::rocksdb_writebatch_wi_get_from_batch_and_db_cf(
+                self.inner,
+                db.inner.inner(),
+                readopts.inner,
+                cf.inner(),
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                &mut val_len,
+            ));
+            Ok(take_value(val, val_len))
+        }
+    }
+
+    /// Returns an iterator over `base` with the writes of the batch applied,
+    /// as if the batch was written to the database `base` iterates.  The
+    /// batch must not be changed while the iterator is in use.
+    ///
+    /// The batch must have been created with `overwrite_key`, and merges in
+    /// the batch are not supported.
+    pub fn iterator_with_base<'a, D>(
+        &'a self,
+        base: DBRawIteratorWithThreadMode<'a, D>,
+    ) -> DBRawIteratorWithThreadMode<'a, D> {
+        let (base, readopts) = base.into_raw_parts();
+        let inner =
+            unsafe { ffi::rocksdb_writebatch_wi_create_iterator_with_base(self.inner, base) };
+        DBRawIteratorWithThreadMode::from_inner(inner, readopts)
+    }
+
+    /// Returns an iterator over `base`, an iterator of `cf`, with the writes
+    /// of the batch to `cf` applied.  See
+    /// [`iterator_with_base`](Self::iterator_with_base).
+    pub fn iterator_with_base_cf<'a, D>(
+        &'a self,
+        base: DBRawIteratorWithThreadMode<'a, D>,
+        cf: &impl AsColumnFamilyRef,
+    ) -> DBRawIteratorWithThreadMode<'a, D> {
+        let (base, readopts) = base.into_raw_parts();
+        let inner = unsafe {
+            ffi::rocksdb_writebatch_wi_create_iterator_with_base_cf(self.inner, base, cf.inner())
+        };
+        DBRawIteratorWithThreadMode::from_inner(inner, readopts)
+    }
+}
+
+/// Takes ownership of a value returned by RocksDB.
+unsafe fn take_value(val: *mut c_char, val_len: size_t) -> Option<Vec<u8>> {
+    let value = raw_data(val, val_len);
+    ffi::rocksdb_free(val as *mut c_void);
+    value
+}
+
+impl Drop for WriteBatchWithIndex {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::rocksdb_writebatch_wi_destroy(self.inner);
+        }
+    }
+}
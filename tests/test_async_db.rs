@@ -0,0 +1,165 @@
+// Copyright 2022 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "async")]
+
+mod util;
+
+use futures::executor::block_on;
+use futures::future::join_all;
+use futures::StreamExt;
+use rocksdb::{AsyncDB, Direction, IteratorMode, Options, WriteBatch, DB};
+use util::DBPath;
+
+fn open(path: &DBPath) -> AsyncDB {
+    AsyncDB::with_threads(DB::open_default(path).unwrap(), 2)
+}
+
+#[test]
+fn test_async_get_put_delete() {
+    let path = DBPath::new("_rust_rocksdb_async_get_put_delete");
+    let db = open(&path);
+    block_on(async {
+        db.put(b"k1", b"v1").await.unwrap();
+        db.put(b"k2", b"v2").await.unwrap();
+        assert_eq!(db.get(b"k1").await.unwrap().unwrap(), b"v1");
+
+        db.delete(b"k1").await.unwrap();
+        assert!(db.get(b"k1").await.unwrap().is_none());
+
+        let mut batch = WriteBatch::default();
+        batch.put(b"k3", b"v3");
+        batch.delete(b"k2");
+        db.write(batch).await.unwrap();
+
+        let values = db.multi_get([b"k1", b"k2", b"k3"]).await;
+        let values: Vec<_> = values.into_iter().map(Result::unwrap).collect();
+        assert_eq!(values, vec![None, None, Some(b"v3".to_vec())]);
+
+        db.flush().await.unwrap();
+        db.compact_range::<&[u8], &[u8]>(None, None).await;
+        assert_eq!(db.get(b"k3").await.unwrap().unwrap(), b"v3");
+    });
+    // Calls that don't block are made on the database directly.
+    assert_eq!(db.db().get(b"k3").unwrap().unwrap(), b"v3");
+}
+
+#[test]
+fn test_async_column_families() {
+    let path = DBPath::new("_rust_rocksdb_async_column_families");
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.create_missing_column_families(true);
+    let db = AsyncDB::new(DB::open_cf(&opts, &path, ["cf1"]).unwrap());
+    block_on(async {
+        db.put_cf("cf1", b"k1", b"v1").await.unwrap();
+        assert_eq!(db.get_cf("cf1", b"k1").await.unwrap().unwrap(), b"v1");
+        assert!(db.get(b"k1").await.unwrap().is_none());
+
+        let entries: Vec<_> = db.iterator_cf("cf1", IteratorMode::Start).collect().await;
+        assert_eq!(entries.len(), 1);
+
+        db.flush_cf("cf1").await.unwrap();
+        db.compact_range_cf::<&[u8], &[u8]>("cf1", None, None)
+            .await
+            .unwrap();
+        db.delete_cf("cf1", b"k1").await.unwrap();
+        assert!(db.get_cf("cf1", b"k1").await.unwrap().is_none());
+
+        assert!(db.get_cf("missing", b"k1").await.is_err());
+        let entries: Vec<_> = db
+            .iterator_cf("missing", IteratorMode::Start)
+            .collect()
+            .await;
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].is_err());
+    });
+}
+
+#[test]
+fn test_async_iterator_stream() {
+    let path = DBPath::new("_rust_rocksdb_async_iterator_stream");
+    let db = open(&path);
+    // Spans several batches.
+    let keys: Vec<Vec<u8>> = (0..1000u32).map(|i| i.to_be_bytes().to_vec()).collect();
+    block_on(async {
+        let mut batch = WriteBatch::default();
+        for key in &keys {
+            batch.put(key, b"value");
+        }
+        db.write(batch).await.unwrap();
+
+        let forward: Vec<_> = db
+            .iterator(IteratorMode::Start)
+            .map(|item| item.unwrap().0.to_vec())
+            .collect()
+            .await;
+        assert_eq!(forward, keys);
+
+        let reverse: Vec<_> = db
+            .iterator(IteratorMode::End)
+            .map(|item| item.unwrap().0.to_vec())
+            .collect()
+            .await;
+        let mut expected = keys.clone();
+        expected.reverse();
+        assert_eq!(reverse, expected);
+
+        let from: Vec<_> = db
+            .iterator(IteratorMode::From(&keys[500], Direction::Forward))
+            .map(|item| item.unwrap().0.to_vec())
+            .collect()
+            .await;
+        assert_eq!(from, &keys[500..]);
+
+        // Dropping a stream early releases its iterator.
+        let first: Vec<_> = db.iterator(IteratorMode::Start).take(3).collect().await;
+        assert_eq!(first.len(), 3);
+    });
+}
+
+#[test]
+fn test_async_stream_outlives_handle() {
+    let path = DBPath::new("_rust_rocksdb_async_stream_outlives_handle");
+    let db = open(&path);
+    block_on(db.put(b"k1", b"v1")).unwrap();
+    let stream = db.iterator(IteratorMode::Start);
+    drop(db);
+    let entries: Vec<_> = block_on(stream.collect());
+    assert_eq!(entries.len(), 1);
+}
+
+#[test]
+fn test_async_concurrent_tasks() {
+    let path = DBPath::new("_rust_rocksdb_async_concurrent_tasks");
+    let db = open(&path);
+    block_on(async {
+        let puts = (0..100).map(|i| db.put(format!("key{}", i), format!("value{}", i)));
+        for result in join_all(puts).await {
+            result.unwrap();
+        }
+        let gets = (0..100).map(|i| db.get(format!("key{}", i)));
+        for (i, value) in join_all(gets).await.into_iter().enumerate() {
+            assert_eq!(value.unwrap().unwrap(), format!("value{}", i).as_bytes());
+        }
+    });
+}
+
+#[test]
+#[should_panic(expected = "boom")]
+fn test_async_panic_is_resumed() {
+    let path = DBPath::new("_rust_rocksdb_async_panic_is_resumed");
+    let db = open(&path);
+    block_on(db.run(|_| panic!("boom")))
+}
@@ -0,0 +1,286 @@
+// Copyright 2020 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod util;
+
+use pretty_assertions::assert_eq;
+
+use rocksdb::{
+    AsColumnFamilyRef, Change, ChangeOp, ErrorKind, Options, TransactionDB, TransactionDBOptions,
+    WriteBatch, DB, DEFAULT_COLUMN_FAMILY_NAME,
+};
+use util::DBPath;
+
+fn open(path: &DBPath, opts: &mut Options) -> DB {
+    opts.create_if_missing(true);
+    opts.create_missing_column_families(true);
+    opts.set_merge_operator_associative("concat", |_key, existing, operands| {
+        let mut result = existing.unwrap_or_default().to_vec();
+        for operand in operands {
+            result.extend_from_slice(operand);
+        }
+        Some(result)
+    });
+    DB::open_cf(opts, path, ["cf1"]).unwrap()
+}
+
+#[test]
+fn change_stream_decodes_batches() {
+    let path = DBPath::new("_rust_rocksdb_change_stream_decodes_batches");
+    let db = open(&path, &mut Options::default());
+    let cf1 = db.cf_handle("cf1").unwrap();
+    let default_id = db.cf_handle(DEFAULT_COLUMN_FAMILY_NAME).unwrap().id();
+    let cf1_id = cf1.id();
+    assert_ne!(default_id, cf1_id);
+
+    let start = db.latest_sequence_number();
+    db.put(b"a", b"1").unwrap();
+    let mut batch = WriteBatch::default();
+    batch.put_cf(&cf1, b"b", b"2");
+    batch.merge_cf(&cf1, b"b", b"3");
+    batch.delete(b"a");
+    batch.delete_range_cf(&cf1, b"c", b"d");
+    db.write(batch).unwrap();
+    db.delete(b"e").unwrap();
+
+    let changes: Vec<Change> = db
+        .change_stream(start)
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    let expected = vec![
+        (
+            default_id,
+            ChangeOp::Put {
+                key: b"a".to_vec(),
+                value: b"1".to_vec(),
+            },
+        ),
+        (
+            cf1_id,
+            ChangeOp::Put {
+                key: b"b".to_vec(),
+                value: b"2".to_vec(),
+            },
+        ),
+        (
+            cf1_id,
+            ChangeOp::Merge {
+                key: b"b".to_vec(),
+                operand: b"3".to_vec(),
+            },
+        ),
+        (default_id, ChangeOp::Delete { key: b"a".to_vec() }),
+        (
+            cf1_id,
+            ChangeOp::DeleteRange {
+                begin_key: b"c".to_vec(),
+                end_key: b"d".to_vec(),
+            },
+        ),
+        (default_id, ChangeOp::Delete { key: b"e".to_vec() }),
+    ];
+    assert_eq!(
+        changes,
+        expected
+            .into_iter()
+            .enumerate()
+            .map(|(i, (cf_id, op))| Change {
+                sequence: start + 1 + i as u64,
+                cf_id,
+                op,
+            })
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn change_stream_resume_and_filter() {
+    let path = DBPath::new("_rust_rocksdb_change_stream_resume_and_filter");
+    let db = open(&path, &mut Options::default());
+    let cf1 = db.cf_handle("cf1").unwrap();
+
+    let start = db.latest_sequence_number();
+    let mut batch = WriteBatch::default();
+    for key in [b"k1", b"k2", b"k3"] {
+        batch.put(key, b"");
+        batch.put_cf(&cf1, key, b"");
+    }
+    db.write(batch).unwrap();
+    db.put(b"k4", b"").unwrap();
+
+    // Stop in the middle of the batch, and resume from there.
+    let mut stream = db.change_stream(start).unwrap();
+    let first: Vec<u64> = stream
+        .by_ref()
+        .take(3)
+        .map(|change| change.unwrap().sequence)
+        .collect();
+    assert_eq!(first, [start + 1, start + 2, start + 3]);
+    let resume_after = stream.sequence();
+    assert_eq!(resume_after, start + 3);
+    drop(stream);
+    let rest: Vec<u64> = db
+        .change_stream(resume_after)
+        .unwrap()
+        .map(|change| change.unwrap().sequence)
+        .collect();
+    assert_eq!(rest, [start + 4, start + 5, start + 6, start + 7]);
+
+    let mut stream = db.change_stream(start).unwrap().filter_cf(&cf1);
+    let keys: Vec<ChangeOp> = stream.by_ref().map(|change| change.unwrap().op).collect();
+    assert_eq!(
+        keys,
+        [b"k1", b"k2", b"k3"]
+            .iter()
+            .map(|key| ChangeOp::Put {
+                key: key.to_vec(),
+                value: vec![],
+            })
+            .collect::<Vec<_>>()
+    );
+    // Skipped changes count as read.
+    assert_eq!(stream.sequence(), db.latest_sequence_number());
+    assert!(db
+        .change_stream(stream.sequence())
+        .unwrap()
+        .next()
+        .is_none());
+}
+
+#[test]
+fn change_stream_wal_gone() {
+    let path = DBPath::new("_rust_rocksdb_change_stream_wal_gone");
+    {
+        let db = open(&path, &mut Options::default());
+        db.put(b"k1", b"v1").unwrap();
+    }
+    // Reopening flushes the WAL, which is then deleted.
+    let db = open(&path, &mut Options::default());
+    db.put(b"k2", b"v2").unwrap();
+    let err = db.change_stream(0).unwrap().next().unwrap().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+#[test]
+fn change_stream_wal_ttl() {
+    let path = DBPath::new("_rust_rocksdb_change_stream_wal_ttl");
+    let mut opts = Options::default();
+    opts.set_wal_ttl_seconds(3600);
+    {
+        let db = open(&path, &mut opts);
+        db.put(b"k1", b"v1").unwrap();
+    }
+    // The flushed WAL is archived instead of deleted.
+    let db = open(&path, &mut opts);
+    db.put(b"k2", b"v2").unwrap();
+    let keys: Vec<ChangeOp> = db
+        .change_stream(0)
+        .unwrap()
+        .map(|change| change.unwrap().op)
+        .collect();
+    assert_eq!(
+        keys,
+        [
+            ChangeOp::Put {
+                key: b"k1".to_vec(),
+                value: b"v1".to_vec()
+            },
+            ChangeOp::Put {
+                key: b"k2".to_vec(),
+                value: b"v2".to_vec()
+            },
+        ]
+    );
+}
+
+#[test]
+fn change_stream_without_wal() {
+    let path = DBPath::new("_rust_rocksdb_change_stream_without_wal");
+    let db = open(&path, &mut Options::default());
+    db.put(b"k1", b"v1").unwrap();
+    let mut batch = WriteBatch::default();
+    batch.put(b"k2", b"v2");
+    db.write_without_wal(batch).unwrap();
+    let resume_after = db.latest_sequence_number();
+    let mut batch = WriteBatch::default();
+    batch.put(b"k3", b"v3");
+    db.write_without_wal(batch).unwrap();
+    db.put(b"k4", b"v4").unwrap();
+
+    // The changes are still in the WAL, but not those written without it.
+    let err = db
+        .change_stream(resume_after)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Incomplete);
+
+    let mut stream = db.change_stream(0).unwrap();
+    assert_eq!(
+        stream.next().unwrap().unwrap().op,
+        ChangeOp::Put {
+            key: b"k1".to_vec(),
+            value: b"v1".to_vec(),
+        }
+    );
+    let err = stream.next().unwrap().unwrap_err();
+    assert!(
+        matches!(err.kind(), ErrorKind::Incomplete | ErrorKind::Corruption),
+        "{}",
+        err
+    );
+
+    // Resuming after them skips them.
+    let changes: Vec<ChangeOp> = db
+        .change_stream(resume_after + 1)
+        .unwrap()
+        .map(|change| change.unwrap().op)
+        .collect();
+    assert_eq!(
+        changes,
+        [ChangeOp::Put {
+            key: b"k4".to_vec(),
+            value: b"v4".to_vec(),
+        }]
+    );
+}
+
+#[test]
+fn change_stream_two_phase_commit() {
+    let path = DBPath::new("_rust_rocksdb_change_stream_two_phase_commit");
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.set_wal_ttl_seconds(3600);
+    {
+        let db: TransactionDB =
+            TransactionDB::open(&opts, &TransactionDBOptions::default(), &path).unwrap();
+        let txn = db.transaction();
+        txn.set_name(b"txn1").unwrap();
+        txn.put(b"k1", b"v1").unwrap();
+        txn.prepare().unwrap();
+        txn.commit().unwrap();
+    }
+    // Reopening flushes the transaction, so a DB can open the database
+    // without recovering it, and archives the WAL holding it.
+    {
+        let _db: TransactionDB =
+            TransactionDB::open(&opts, &TransactionDBOptions::default(), &path).unwrap();
+    }
+    let db = DB::open(&opts, &path).unwrap();
+    assert_eq!(db.get(b"k1").unwrap().unwrap(), b"v1");
+    let err = db.change_stream(0).unwrap().next().unwrap().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotSupported);
+}
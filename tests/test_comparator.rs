@@ -0,0 +1,184 @@
+// Copyright 2022 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod util;
+
+use std::cmp::Ordering;
+use std::ffi::CStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::Arc;
+
+use pretty_assertions::assert_eq;
+
+use rocksdb::{
+    BlockBasedOptions, BytewiseComparatorWithU64Ts, Comparator, Options, ReverseBytewiseComparator,
+    DB,
+};
+use util::DBPath;
+
+/// Orders keys by a collation table, counting how often RocksDB asks for
+/// shorter index keys.
+struct CollatingComparator {
+    weights: [u8; 256],
+    separators: Arc<AtomicUsize>,
+    dropped: Arc<AtomicBool>,
+}
+
+impl CollatingComparator {
+    fn case_insensitive() -> Self {
+        let mut weights = [0; 256];
+        for (byte, weight) in weights.iter_mut().enumerate() {
+            *weight = (byte as u8).to_ascii_lowercase();
+        }
+        Self {
+            weights,
+            separators: Arc::default(),
+            dropped: Arc::default(),
+        }
+    }
+
+    fn weigh<'a>(&'a self, key: &'a [u8]) -> impl Iterator<Item = u8> + 'a {
+        key.iter().map(move |&byte| self.weights[byte as usize])
+    }
+}
+
+impl Comparator for CollatingComparator {
+    fn name(&self) -> &CStr {
+        CStr::from_bytes_with_nul(b"CaseInsensitive\0").unwrap()
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        self.weigh(a).cmp(self.weigh(b)).then_with(|| a.cmp(b))
+    }
+
+    fn find_shortest_separator(&self, _start: &[u8], _limit: &[u8]) -> Option<Vec<u8>> {
+        self.separators.fetch_add(1, AtomicOrdering::SeqCst);
+        None
+    }
+}
+
+impl Drop for CollatingComparator {
+    fn drop(&mut self) {
+        self.dropped.store(true, AtomicOrdering::SeqCst);
+    }
+}
+
+#[test]
+fn comparator_with_state() {
+    let path = DBPath::new("_rust_rocksdb_comparator_with_state");
+    let comparator = CollatingComparator::case_insensitive();
+    let separators = comparator.separators.clone();
+    let dropped = comparator.dropped.clone();
+
+    let mut block_opts = BlockBasedOptions::default();
+    block_opts.set_block_size(64);
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.set_block_based_table_factory(&block_opts);
+    opts.set_comparator_impl(Box::new(comparator));
+    let db = DB::open(&opts, &path).unwrap();
+    drop(opts);
+
+    for key in ["b", "A", "c", "a", "B"] {
+        db.put(key, [0; 100]).unwrap();
+    }
+    db.flush().unwrap();
+
+    let mut iter = db.raw_iterator();
+    iter.seek_to_first();
+    let mut keys = Vec::new();
+    while let Some(key) = iter.key() {
+        keys.push(String::from_utf8(key.to_vec()).unwrap());
+        iter.next();
+    }
+    iter.status().unwrap();
+    drop(iter);
+    assert_eq!(keys, ["A", "a", "B", "b", "c"]);
+    assert!(separators.load(AtomicOrdering::SeqCst) > 0);
+
+    // The comparator lives as long as the database using it.
+    assert!(!dropped.load(AtomicOrdering::SeqCst));
+    drop(db);
+    assert!(dropped.load(AtomicOrdering::SeqCst));
+}
+
+#[test]
+fn comparator_name_mismatch() {
+    let path = DBPath::new("_rust_rocksdb_comparator_name_mismatch");
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_comparator_impl(Box::new(ReverseBytewiseComparator));
+        let db = DB::open(&opts, &path).unwrap();
+        db.put(b"a", b"1").unwrap();
+    }
+    let mut opts = Options::default();
+    opts.set_comparator_impl(Box::new(CollatingComparator::case_insensitive()));
+    assert!(DB::open(&opts, &path).is_err());
+}
+
+#[test]
+fn reverse_bytewise_comparator() {
+    let cmp = ReverseBytewiseComparator;
+    assert_eq!(cmp.compare(b"a", b"b"), Ordering::Greater);
+    assert_eq!(cmp.compare(b"ab", b"a"), Ordering::Less);
+
+    assert_eq!(
+        cmp.find_shortest_separator(b"AA3AA", b"AA1BB"),
+        Some(b"AA3".to_vec())
+    );
+    // Already as short as it can get.
+    assert_eq!(cmp.find_shortest_separator(b"AA3", b"AA1BB"), None);
+    // One key is a prefix of the other.
+    assert_eq!(cmp.find_shortest_separator(b"AA2XY", b"AA2"), None);
+    assert_eq!(cmp.find_short_successor(b"AA"), None);
+
+    let path = DBPath::new("_rust_rocksdb_reverse_bytewise_comparator");
+    let mut block_opts = BlockBasedOptions::default();
+    block_opts.set_block_size(64);
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.set_block_based_table_factory(&block_opts);
+    opts.set_comparator_impl(Box::new(ReverseBytewiseComparator));
+    let db = DB::open(&opts, &path).unwrap();
+    for i in 0..100u32 {
+        db.put(format!("key{:03}", i), [0; 32]).unwrap();
+    }
+    db.flush().unwrap();
+    let mut iter = db.raw_iterator();
+    iter.seek(b"key050");
+    assert_eq!(iter.key(), Some(&b"key050"[..]));
+    iter.next();
+    assert_eq!(iter.key(), Some(&b"key049"[..]));
+}
+
+#[test]
+fn bytewise_comparator_with_u64_ts() {
+    let cmp = BytewiseComparatorWithU64Ts;
+    let key = |key: &[u8], ts: u64| [key, &ts.to_le_bytes()].concat();
+    assert_eq!(cmp.timestamp_size(), 8);
+    assert_eq!(cmp.compare(&key(b"a", 1), &key(b"b", 0)), Ordering::Less);
+    // Newer versions first.
+    assert_eq!(cmp.compare(&key(b"a", 2), &key(b"a", 1)), Ordering::Less);
+    assert_eq!(
+        cmp.compare_timestamp(&2u64.to_le_bytes(), &256u64.to_le_bytes()),
+        Ordering::Less
+    );
+    assert_eq!(cmp.compare_without_timestamp(b"a", b"b"), Ordering::Less);
+
+    // Malformed keys and timestamps are compared byte-wise.
+    assert_eq!(cmp.compare(b"a", &key(b"a", 1)), Ordering::Less);
+    assert_eq!(cmp.compare(b"b", b"a"), Ordering::Greater);
+    assert_eq!(cmp.compare_timestamp(b"\x02", b"\x01"), Ordering::Greater);
+}
@@ -14,9 +14,18 @@
 
 mod util;
 
+use std::ffi::{CStr, CString};
+use std::sync::{Arc, Mutex};
+
 use pretty_assertions::assert_eq;
 
-use rocksdb::{CompactionDecision, Options, DB};
+use rocksdb::compaction_filter::{CompactionFilter, ValueType};
+use rocksdb::compaction_filter_factory::{CompactionFilterContext, CompactionFilterFactory};
+use rocksdb::event_listener::TableFileCreationReason;
+use rocksdb::{
+    AsColumnFamilyRef, ColumnFamilyDescriptor, CompactionDecision, CompactionDecisionV2,
+    MergeOperands, Options, DB,
+};
 use util::DBPath;
 
 #[cfg(test)]
@@ -47,3 +56,177 @@ fn compaction_filter_test() {
         assert_eq!(&*db.get(b"%k").unwrap().unwrap(), b"secret");
     }
 }
+
+fn test_filter_v2(
+    _level: u32,
+    key: &[u8],
+    value_type: ValueType,
+    value: &[u8],
+) -> CompactionDecisionV2 {
+    match (key, value_type) {
+        (b"k2", _) => CompactionDecisionV2::RemoveAndSkipUntil(b"k4".to_vec()),
+        (_, ValueType::MergeOperand) => CompactionDecisionV2::Change(value.to_ascii_uppercase()),
+        (b"k5", _) => CompactionDecisionV2::Change([value, b"!"].concat()),
+        _ => CompactionDecisionV2::Keep,
+    }
+}
+
+fn concat_merge(
+    _key: &[u8],
+    existing_val: Option<&[u8]>,
+    operands: &MergeOperands,
+) -> Option<Vec<u8>> {
+    let mut result = existing_val.map(<[u8]>::to_vec).unwrap_or_default();
+    for operand in operands {
+        result.extend_from_slice(operand);
+    }
+    Some(result)
+}
+
+#[test]
+fn compaction_filter_v2_test() {
+    let path = DBPath::new("_rust_rocksdb_filter_v2_test");
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.set_merge_operator_associative("concat", concat_merge);
+    opts.set_compaction_filter_v2("test_v2", test_filter_v2);
+    let db = DB::open(&opts, &path).unwrap();
+    for key in [b"k1", b"k2", b"k3", b"k4", b"k5"] {
+        db.put(key, b"value").unwrap();
+    }
+    // Merge operands without a base value reach the filter one by one.
+    db.merge(b"m", b"a").unwrap();
+    db.flush().unwrap();
+    db.merge(b"m", b"b").unwrap();
+    db.compact_range(None::<&[u8]>, None::<&[u8]>);
+
+    assert_eq!(&*db.get(b"k1").unwrap().unwrap(), b"value");
+    assert!(db.get(b"k2").unwrap().is_none());
+    assert!(db.get(b"k3").unwrap().is_none());
+    assert_eq!(&*db.get(b"k4").unwrap().unwrap(), b"value");
+    assert_eq!(&*db.get(b"k5").unwrap().unwrap(), b"value!");
+    assert_eq!(&*db.get(b"m").unwrap().unwrap(), b"AB");
+}
+
+type SeenEntries = Arc<Mutex<Vec<(Vec<u8>, ValueType)>>>;
+
+struct RecordingFilter {
+    seen: SeenEntries,
+    name: CString,
+}
+
+impl CompactionFilter for RecordingFilter {
+    fn filter_v2(
+        &mut self,
+        _level: u32,
+        key: &[u8],
+        value_type: ValueType,
+        value: &[u8],
+    ) -> CompactionDecisionV2 {
+        self.seen.lock().unwrap().push((key.to_vec(), value_type));
+        if value == b"drop" {
+            CompactionDecisionV2::Remove
+        } else {
+            CompactionDecisionV2::Change(value.repeat(2))
+        }
+    }
+
+    fn name(&self) -> &CStr {
+        &self.name
+    }
+}
+
+struct RecordingFactory {
+    seen: SeenEntries,
+    contexts: Arc<Mutex<Vec<CompactionFilterContext>>>,
+    name: CString,
+}
+
+impl CompactionFilterFactory for RecordingFactory {
+    type Filter = RecordingFilter;
+
+    fn create(&mut self, context: CompactionFilterContext) -> RecordingFilter {
+        self.contexts.lock().unwrap().push(context);
+        RecordingFilter {
+            seen: self.seen.clone(),
+            name: CString::new("RecordingFilter").unwrap(),
+        }
+    }
+
+    fn name(&self) -> &CStr {
+        &self.name
+    }
+}
+
+#[test]
+fn compaction_filter_factory_v2_test() {
+    let path = DBPath::new("_rust_rocksdb_filter_factory_v2_test");
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let contexts = Arc::new(Mutex::new(Vec::new()));
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.set_merge_operator_associative("concat", concat_merge);
+    opts.set_compaction_filter_factory(RecordingFactory {
+        seen: seen.clone(),
+        contexts: contexts.clone(),
+        name: CString::new("RecordingFactory").unwrap(),
+    });
+    let db = DB::open(&opts, &path).unwrap();
+    db.put(b"a", b"x").unwrap();
+    db.put(b"b", b"drop").unwrap();
+    db.merge(b"c", b"y").unwrap();
+    db.compact_range(None::<&[u8]>, None::<&[u8]>);
+
+    assert_eq!(&*db.get(b"a").unwrap().unwrap(), b"xx");
+    assert!(db.get(b"b").unwrap().is_none());
+    assert_eq!(&*db.get(b"c").unwrap().unwrap(), b"yy");
+    assert_eq!(
+        *seen.lock().unwrap(),
+        vec![
+            (b"a".to_vec(), ValueType::Value),
+            (b"b".to_vec(), ValueType::Value),
+            (b"c".to_vec(), ValueType::MergeOperand),
+        ]
+    );
+    let contexts = contexts.lock().unwrap();
+    assert_eq!(contexts.len(), 1);
+    assert!(contexts[0].is_manual_compaction);
+    assert_eq!(contexts[0].reason, TableFileCreationReason::Compaction);
+}
+
+#[test]
+fn compaction_filter_factory_cf_test() {
+    let path = DBPath::new("_rust_rocksdb_filter_factory_cf_test");
+    let contexts = Arc::new(Mutex::new(Vec::new()));
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.create_missing_column_families(true);
+    let mut cf_opts = Options::default();
+    cf_opts.set_compaction_filter_factory(RecordingFactory {
+        seen: Arc::default(),
+        contexts: contexts.clone(),
+        name: CString::new("RecordingFactory").unwrap(),
+    });
+    let db = DB::open_cf_descriptors(
+        &opts,
+        &path,
+        vec![ColumnFamilyDescriptor::new("cf1", cf_opts)],
+    )
+    .unwrap();
+    let cf1 = db.cf_handle("cf1").unwrap();
+    db.put_cf(cf1, b"a", b"x").unwrap();
+    db.put_cf(cf1, b"b", b"drop").unwrap();
+    // Flushes are not filtered.
+    db.flush_cf(cf1).unwrap();
+    assert!(contexts.lock().unwrap().is_empty());
+    assert_eq!(&*db.get_cf(cf1, b"b").unwrap().unwrap(), b"drop");
+
+    db.compact_range_cf(cf1, None::<&[u8]>, None::<&[u8]>);
+    assert_eq!(&*db.get_cf(cf1, b"a").unwrap().unwrap(), b"xx");
+    assert!(db.get_cf(cf1, b"b").unwrap().is_none());
+    let contexts = contexts.lock().unwrap();
+    assert_eq!(contexts.len(), 1);
+    assert_eq!(contexts[0].column_family_id, cf1.id());
+    assert!(contexts[0].is_manual_compaction);
+    assert_eq!(contexts[0].reason, TableFileCreationReason::Compaction);
+}
@@ -0,0 +1,198 @@
+// Copyright 2022 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod util;
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rocksdb::event_listener::{
+    BackgroundErrorInfo, BackgroundErrorReason, CompactionJobInfo, CompactionReason, EventListener,
+    FlushJobInfo, FlushReason, TableFileCreationInfo, TableFileCreationReason, WriteStallCondition,
+    WriteStallInfo,
+};
+use rocksdb::fault_injection::FaultInjectionEnv;
+use rocksdb::{BottommostLevelCompaction, CompactOptions, Options, DB};
+use util::DBPath;
+
+#[derive(Default)]
+struct Recorder {
+    flushes: Mutex<Vec<FlushJobInfo>>,
+    compactions: Mutex<Vec<CompactionJobInfo>>,
+    stalls: Mutex<Vec<WriteStallInfo>>,
+    errors: Mutex<Vec<BackgroundErrorInfo>>,
+    files: Mutex<Vec<TableFileCreationInfo>>,
+}
+
+impl EventListener for Recorder {
+    fn on_flush_completed(&self, info: &FlushJobInfo) {
+        self.flushes.lock().unwrap().push(info.clone());
+    }
+
+    fn on_compaction_completed(&self, info: &CompactionJobInfo) {
+        self.compactions.lock().unwrap().push(info.clone());
+    }
+
+    fn on_stall_conditions_changed(&self, info: &WriteStallInfo) {
+        self.stalls.lock().unwrap().push(info.clone());
+    }
+
+    fn on_background_error(&self, info: &BackgroundErrorInfo) {
+        self.errors.lock().unwrap().push(info.clone());
+    }
+
+    fn on_table_file_created(&self, info: &TableFileCreationInfo) {
+        self.files.lock().unwrap().push(info.clone());
+    }
+}
+
+/// Waits until `events` holds at least `n` events.  Listeners are notified
+/// from background threads after the foreground call returned.
+fn wait_for<T: Clone>(events: &Mutex<Vec<T>>, n: usize) -> Vec<T> {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let events = events.lock().unwrap().clone();
+        if events.len() >= n || Instant::now() > deadline {
+            return events;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn options(recorder: &Arc<Recorder>) -> Options {
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.add_event_listener(recorder.clone());
+    opts
+}
+
+#[test]
+fn test_flush_events() {
+    let path = DBPath::new("_rust_rocksdb_event_listener_flush");
+    let recorder = Arc::new(Recorder::default());
+    let db = DB::open(&options(&recorder), &path).unwrap();
+
+    db.put(b"k1", b"v1").unwrap();
+    db.put(b"k2", b"v2").unwrap();
+    db.flush().unwrap();
+
+    let flushes = wait_for(&recorder.flushes, 1);
+    assert_eq!(flushes.len(), 1);
+    let flush = &flushes[0];
+    assert_eq!(flush.column_family_name, "default");
+    assert_eq!(flush.reason, FlushReason::ManualFlush);
+    assert_eq!(flush.num_entries, 2);
+    assert_eq!(flush.raw_key_size, 2 * (2 + 8));
+    assert_eq!(flush.raw_value_size, 4);
+    assert!(flush.file_size > 0);
+    assert!(flush.file_path.starts_with(&path));
+
+    let files = wait_for(&recorder.files, 1);
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].reason, TableFileCreationReason::Flush);
+    assert_eq!(files[0].file_path, flush.file_path);
+    assert_eq!(files[0].file_size, flush.file_size);
+    assert_eq!(files[0].num_entries, 2);
+    assert!(files[0].status.is_ok());
+}
+
+#[test]
+fn test_compaction_events() {
+    let path = DBPath::new("_rust_rocksdb_event_listener_compaction");
+    let recorder = Arc::new(Recorder::default());
+    let mut opts = options(&recorder);
+    opts.set_disable_auto_compactions(true);
+    let db = DB::open(&opts, &path).unwrap();
+
+    for i in 0..3 {
+        db.put(b"key", format!("value{}", i)).unwrap();
+        db.flush().unwrap();
+    }
+    // Without forcing it, the files are trivially moved to level 1.
+    let mut compact_opts = CompactOptions::default();
+    compact_opts.set_bottommost_level_compaction(BottommostLevelCompaction::Force);
+    db.compact_range_opt::<&[u8], &[u8]>(None, None, &compact_opts);
+
+    let compactions = wait_for(&recorder.compactions, 1);
+    assert_eq!(compactions.len(), 1);
+    let compaction = &compactions[0];
+    assert_eq!(compaction.column_family_name, "default");
+    assert!(compaction.status.is_ok());
+    assert_eq!(compaction.reason, CompactionReason::ManualCompaction);
+    assert_eq!(compaction.base_input_level, 0);
+    assert_eq!(compaction.input_files.len(), 3);
+    assert_eq!(compaction.output_files.len(), 1);
+    assert_eq!(compaction.num_input_records, 3);
+    assert_eq!(compaction.num_output_records, 1);
+    assert!(compaction.total_input_bytes > compaction.total_output_bytes);
+
+    let flushed: Vec<_> = wait_for(&recorder.flushes, 3)
+        .into_iter()
+        .map(|f| f.file_path)
+        .collect();
+    for input in &compaction.input_files {
+        assert!(flushed.contains(input), "{:?}", input);
+    }
+}
+
+#[test]
+fn test_stall_events() {
+    let path = DBPath::new("_rust_rocksdb_event_listener_stall");
+    let recorder = Arc::new(Recorder::default());
+    let mut opts = options(&recorder);
+    opts.set_level_zero_file_num_compaction_trigger(2);
+    opts.set_level_zero_slowdown_writes_trigger(2);
+    let db = DB::open(&opts, &path).unwrap();
+
+    for i in 0..2 {
+        db.put(format!("key{}", i), b"value").unwrap();
+        db.flush().unwrap();
+    }
+
+    // Writes are delayed once the second file is flushed, until the
+    // compaction it triggered finishes.  Notifications may be delivered out
+    // of order.
+    let stalls = wait_for(&recorder.stalls, 2);
+    assert!(stalls.iter().all(|s| s.column_family_name == "default"));
+    assert!(stalls
+        .iter()
+        .any(|s| s.previous == WriteStallCondition::Normal
+            && s.current == WriteStallCondition::Delayed));
+}
+
+#[test]
+fn test_background_error_events() {
+    let path = DBPath::new("_rust_rocksdb_event_listener_error");
+    let recorder = Arc::new(Recorder::default());
+    let env = FaultInjectionEnv::new().unwrap();
+    let mut opts = options(&recorder);
+    opts.set_env(env.env());
+    let db = DB::open(&opts, &path).unwrap();
+
+    db.put(b"k1", b"v1").unwrap();
+    env.fail_paths(|p| p.extension() == Some("sst".as_ref()));
+    assert!(db.flush().is_err());
+
+    let errors = wait_for(&recorder.errors, 1);
+    assert!(!errors.is_empty());
+    assert!(matches!(
+        errors[0].reason,
+        BackgroundErrorReason::Flush | BackgroundErrorReason::FlushNoWal
+    ));
+
+    let files = wait_for(&recorder.files, 1);
+    assert!(files[0].status.is_err());
+    assert!(recorder.flushes.lock().unwrap().is_empty());
+}
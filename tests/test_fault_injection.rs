@@ -0,0 +1,148 @@
+// Copyright 2022 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod util;
+
+use rocksdb::{fault_injection::FaultInjectionEnv, DBRecoveryMode, Options, WriteOptions, DB};
+use util::DBPath;
+
+const RECOVERY_MODES: [DBRecoveryMode; 4] = [
+    DBRecoveryMode::TolerateCorruptedTailRecords,
+    DBRecoveryMode::AbsoluteConsistency,
+    DBRecoveryMode::PointInTime,
+    DBRecoveryMode::SkipAnyCorruptedRecord,
+];
+
+fn sync_write() -> WriteOptions {
+    let mut opts = WriteOptions::default();
+    opts.set_sync(true);
+    opts
+}
+
+fn options(env: &FaultInjectionEnv, mode: DBRecoveryMode) -> Options {
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.set_env(env.env());
+    opts.set_wal_recovery_mode(mode);
+    opts
+}
+
+/// Writes a synced key followed by unsynced keys, then loses power keeping
+/// `keep` bytes of the unsynced data.
+fn crash_after_unsynced_writes(path: &DBPath, env: &FaultInjectionEnv, keep: u64) {
+    let db = DB::open(&options(env, DBRecoveryMode::PointInTime), path).unwrap();
+    db.put_opt(b"synced", b"value", &sync_write()).unwrap();
+    db.put(b"unsynced1", vec![1; 100]).unwrap();
+    db.put(b"unsynced2", vec![2; 100]).unwrap();
+
+    env.set_active(false);
+    env.truncate_unsynced_writes(keep).unwrap();
+    drop(db);
+    env.set_active(true);
+}
+
+#[test]
+fn test_unsynced_writes_are_lost() {
+    for mode in RECOVERY_MODES {
+        let path = DBPath::new("_rust_rocksdb_fault_injection_unsynced");
+        let env = FaultInjectionEnv::new().unwrap();
+        crash_after_unsynced_writes(&path, &env, 0);
+
+        let db = DB::open(&options(&env, mode), &path).unwrap();
+        assert_eq!(db.get(b"synced").unwrap().unwrap(), b"value", "{:?}", mode);
+        assert!(db.get(b"unsynced1").unwrap().is_none(), "{:?}", mode);
+        assert!(db.get(b"unsynced2").unwrap().is_none(), "{:?}", mode);
+    }
+}
+
+#[test]
+fn test_torn_write_recovery() {
+    for mode in RECOVERY_MODES {
+        let path = DBPath::new("_rust_rocksdb_fault_injection_torn");
+        let env = FaultInjectionEnv::new().unwrap();
+        // Keep the first unsynced record and a part of the second one.
+        crash_after_unsynced_writes(&path, &env, 150);
+
+        let result = DB::open(&options(&env, mode), &path);
+        if mode == DBRecoveryMode::AbsoluteConsistency {
+            assert!(result.is_err());
+            continue;
+        }
+        let db = result.unwrap();
+        assert_eq!(db.get(b"synced").unwrap().unwrap(), b"value", "{:?}", mode);
+        assert_eq!(
+            db.get(b"unsynced1").unwrap().unwrap(),
+            vec![1; 100],
+            "{:?}",
+            mode
+        );
+        assert!(db.get(b"unsynced2").unwrap().is_none(), "{:?}", mode);
+    }
+}
+
+#[test]
+fn test_flushed_data_survives_power_loss() {
+    let path = DBPath::new("_rust_rocksdb_fault_injection_flushed");
+    let env = FaultInjectionEnv::new().unwrap();
+    let opts = options(&env, DBRecoveryMode::PointInTime);
+    {
+        let db = DB::open(&opts, &path).unwrap();
+        db.put(b"flushed", b"value").unwrap();
+        db.flush().unwrap();
+        db.put(b"unsynced", b"value").unwrap();
+        env.simulate_power_loss().unwrap();
+    }
+    env.set_active(true);
+
+    let db = DB::open(&opts, &path).unwrap();
+    assert_eq!(db.get(b"flushed").unwrap().unwrap(), b"value");
+    assert!(db.get(b"unsynced").unwrap().is_none());
+}
+
+#[test]
+fn test_fail_nth_sync() {
+    let path = DBPath::new("_rust_rocksdb_fault_injection_sync");
+    let env = FaultInjectionEnv::new().unwrap();
+    let db = DB::open(&options(&env, DBRecoveryMode::PointInTime), &path).unwrap();
+
+    db.put_opt(b"k1", b"v1", &sync_write()).unwrap();
+    env.fail_nth_sync(2);
+    db.put_opt(b"k2", b"v2", &sync_write()).unwrap();
+    let err = db.put_opt(b"k3", b"v3", &sync_write()).unwrap_err();
+    assert!(err.to_string().contains("injected sync error"), "{}", err);
+}
+
+#[test]
+fn test_fail_paths() {
+    let path = DBPath::new("_rust_rocksdb_fault_injection_paths");
+    let env = FaultInjectionEnv::new().unwrap();
+    let db = DB::open(&options(&env, DBRecoveryMode::PointInTime), &path).unwrap();
+    db.put(b"k1", b"v1").unwrap();
+
+    env.fail_paths(|p| p.extension() == Some("sst".as_ref()));
+    assert!(db.flush().is_err());
+    // The data is still served from the memtable.
+    assert_eq!(db.get(b"k1").unwrap().unwrap(), b"v1");
+}
+
+#[test]
+fn test_inactive_file_system_rejects_writes() {
+    let path = DBPath::new("_rust_rocksdb_fault_injection_inactive");
+    let env = FaultInjectionEnv::new().unwrap();
+    let db = DB::open(&options(&env, DBRecoveryMode::PointInTime), &path).unwrap();
+
+    env.set_active(false);
+    assert!(!env.is_active());
+    assert!(db.put_opt(b"k1", b"v1", &sync_write()).is_err());
+}
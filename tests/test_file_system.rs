@@ -0,0 +1,349 @@
+// Copyright 2022 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod util;
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::{CStr, OsString};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+use rocksdb::file_system::{
+    Clock, Directory, FileSystem, RandomAccessFile, SequentialFile, StdFileSystem, SystemClock,
+    WritableFile,
+};
+use rocksdb::{Env, Options, DB};
+use util::DBPath;
+
+type Data = Arc<RwLock<Vec<u8>>>;
+
+/// A file system keeping all files in memory.
+#[derive(Clone, Default)]
+struct MemFileSystem {
+    state: Arc<Mutex<MemState>>,
+}
+
+#[derive(Default)]
+struct MemState {
+    files: HashMap<PathBuf, Data>,
+    dirs: HashSet<PathBuf>,
+    locks: HashSet<PathBuf>,
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, path.display().to_string())
+}
+
+impl MemFileSystem {
+    fn file(&self, path: &Path) -> io::Result<Data> {
+        let state = self.state.lock().unwrap();
+        state
+            .files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn file_names(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        state
+            .files
+            .keys()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+}
+
+impl FileSystem for MemFileSystem {
+    fn name(&self) -> &CStr {
+        CStr::from_bytes_with_nul(b"MemFileSystem\0").unwrap()
+    }
+
+    fn new_sequential_file(&self, path: &Path) -> io::Result<Box<dyn SequentialFile>> {
+        let data = self.file(path)?;
+        Ok(Box::new(MemSequentialFile { data, pos: 0 }))
+    }
+
+    fn new_random_access_file(&self, path: &Path) -> io::Result<Box<dyn RandomAccessFile>> {
+        Ok(Box::new(MemRandomAccessFile(self.file(path)?)))
+    }
+
+    fn new_writable_file(&self, path: &Path) -> io::Result<Box<dyn WritableFile>> {
+        let data = Data::default();
+        let mut state = self.state.lock().unwrap();
+        state.files.insert(path.to_path_buf(), data.clone());
+        Ok(Box::new(MemWritableFile(data)))
+    }
+
+    fn new_directory(&self, path: &Path) -> io::Result<Box<dyn Directory>> {
+        if self.state.lock().unwrap().dirs.contains(path) {
+            Ok(Box::new(MemDirectory))
+        } else {
+            Err(not_found(path))
+        }
+    }
+
+    fn file_exists(&self, path: &Path) -> io::Result<bool> {
+        let state = self.state.lock().unwrap();
+        Ok(state.files.contains_key(path) || state.dirs.contains(path))
+    }
+
+    fn get_children(&self, dir: &Path) -> io::Result<Vec<OsString>> {
+        let state = self.state.lock().unwrap();
+        if !state.dirs.contains(dir) {
+            return Err(not_found(dir));
+        }
+        Ok(state
+            .files
+            .keys()
+            .chain(state.dirs.iter())
+            .filter(|p| p.parent() == Some(dir))
+            .map(|p| p.file_name().unwrap().to_owned())
+            .collect())
+    }
+
+    fn delete_file(&self, path: &Path) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state
+            .files
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.dirs.insert(path.to_path_buf()) {
+            Ok(())
+        } else {
+            Err(io::Error::new(io::ErrorKind::AlreadyExists, "exists"))
+        }
+    }
+
+    fn create_dir_if_missing(&self, path: &Path) -> io::Result<()> {
+        self.state.lock().unwrap().dirs.insert(path.to_path_buf());
+        Ok(())
+    }
+
+    fn delete_dir(&self, path: &Path) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.dirs.remove(path) {
+            Ok(())
+        } else {
+            Err(not_found(path))
+        }
+    }
+
+    fn get_file_size(&self, path: &Path) -> io::Result<u64> {
+        Ok(self.file(path)?.read().unwrap().len() as u64)
+    }
+
+    fn get_file_modification_time(&self, path: &Path) -> io::Result<u64> {
+        self.file(path).map(|_| 0)
+    }
+
+    fn rename_file(&self, src: &Path, target: &Path) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let data = state.files.remove(src).ok_or_else(|| not_found(src))?;
+        state.files.insert(target.to_path_buf(), data);
+        Ok(())
+    }
+
+    fn lock_file(&self, path: &Path) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if !state.locks.insert(path.to_path_buf()) {
+            return Err(io::Error::new(io::ErrorKind::Other, "already locked"));
+        }
+        state.files.entry(path.to_path_buf()).or_default();
+        Ok(())
+    }
+
+    fn unlock_file(&self, path: &Path) -> io::Result<()> {
+        self.state.lock().unwrap().locks.remove(path);
+        Ok(())
+    }
+
+    fn is_directory(&self, path: &Path) -> io::Result<bool> {
+        let state = self.state.lock().unwrap();
+        if state.dirs.contains(path) {
+            Ok(true)
+        } else if state.files.contains_key(path) {
+            Ok(false)
+        } else {
+            Err(not_found(path))
+        }
+    }
+}
+
+struct MemSequentialFile {
+    data: Data,
+    pos: usize,
+}
+
+impl SequentialFile for MemSequentialFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let data = self.data.read().unwrap();
+        let start = self.pos.min(data.len());
+        let n = buf.len().min(data.len() - start);
+        buf[..n].copy_from_slice(&data[start..start + n]);
+        self.pos += n;
+        Ok(n)
+    }
+
+    fn skip(&mut self, n: u64) -> io::Result<()> {
+        self.pos += n as usize;
+        Ok(())
+    }
+}
+
+struct MemRandomAccessFile(Data);
+
+impl RandomAccessFile for MemRandomAccessFile {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        let data = self.0.read().unwrap();
+        let start = (offset as usize).min(data.len());
+        let n = buf.len().min(data.len() - start);
+        buf[..n].copy_from_slice(&data[start..start + n]);
+        Ok(n)
+    }
+}
+
+struct MemWritableFile(Data);
+
+impl WritableFile for MemWritableFile {
+    fn append(&mut self, data: &[u8]) -> io::Result<()> {
+        self.0.write().unwrap().extend_from_slice(data);
+        Ok(())
+    }
+
+    fn truncate(&mut self, size: u64) -> io::Result<()> {
+        self.0.write().unwrap().truncate(size as usize);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn file_size(&self) -> u64 {
+        self.0.read().unwrap().len() as u64
+    }
+}
+
+struct MemDirectory;
+
+impl Directory for MemDirectory {
+    fn fsync(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_in_memory_file_system() {
+    // A path that only exists in memory.
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db");
+    let fs = MemFileSystem::default();
+    let env = Env::from_file_system(fs.clone()).unwrap();
+
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.set_env(&env);
+    {
+        let db = DB::open(&opts, &path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        db.put(b"k2", b"v2").unwrap();
+        db.flush().unwrap();
+        db.put(b"k3", b"v3").unwrap();
+    }
+
+    let names = fs.file_names();
+    assert!(names.iter().any(|n| n == "CURRENT"));
+    assert!(names.iter().any(|n| n.ends_with(".sst")));
+    assert!(names.iter().any(|n| n.ends_with(".log")));
+    assert!(!path.exists());
+
+    let db = DB::open(&opts, &path).unwrap();
+    assert_eq!(db.get(b"k1").unwrap().unwrap(), b"v1");
+    assert_eq!(db.get(b"k2").unwrap().unwrap(), b"v2");
+    assert_eq!(db.get(b"k3").unwrap().unwrap(), b"v3");
+    assert!(db.get(b"k4").unwrap().is_none());
+}
+
+#[test]
+fn test_missing_database_is_reported() {
+    let env = Env::from_file_system(MemFileSystem::default()).unwrap();
+    let mut opts = Options::default();
+    opts.set_env(&env);
+    let dir = tempfile::tempdir().unwrap();
+    assert!(DB::open(&opts, dir.path().join("missing")).is_err());
+}
+
+#[test]
+fn test_std_file_system() {
+    let path = DBPath::new("_rust_rocksdb_std_file_system");
+    let env = Env::from_file_system(StdFileSystem::default()).unwrap();
+
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.set_env(&env);
+    {
+        let db = DB::open(&opts, &path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        db.flush().unwrap();
+
+        // The database is locked through the file system.
+        assert!(DB::open(&opts, &path).is_err());
+    }
+
+    // Files are readable with the default env.
+    let db = DB::open(&Options::default(), &path).unwrap();
+    assert_eq!(db.get(b"k1").unwrap().unwrap(), b"v1");
+}
+
+#[derive(Clone, Default)]
+struct CountingClock(Arc<AtomicUsize>);
+
+impl Clock for CountingClock {
+    fn now_micros(&self) -> u64 {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        SystemClock.now_micros()
+    }
+}
+
+#[test]
+fn test_custom_clock() {
+    let path = DBPath::new("_rust_rocksdb_custom_clock");
+    let clock = CountingClock::default();
+    let env = Env::from_file_system_and_clock(StdFileSystem::default(), clock.clone()).unwrap();
+
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.set_env(&env);
+    let db = DB::open(&opts, &path).unwrap();
+    db.put(b"k1", b"v1").unwrap();
+    db.flush().unwrap();
+
+    assert!(clock.0.load(Ordering::SeqCst) > 0);
+}
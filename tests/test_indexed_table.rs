@@ -0,0 +1,240 @@
+// Copyright 2020 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod util;
+
+use std::sync::mpsc::{self, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+
+use pretty_assertions::assert_eq;
+
+use rocksdb::{
+    indexed_table::{IndexEntry, IndexedTable},
+    keycodec, ColumnFamily, ErrorKind, Options, SingleThreaded, TransactionDB,
+    TransactionDBOptions,
+};
+use util::DBPath;
+
+fn open(path: &DBPath) -> TransactionDB {
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.create_missing_column_families(true);
+    TransactionDB::open_cf(
+        &opts,
+        &TransactionDBOptions::default(),
+        path,
+        ["docs", "docs_by_tag", "docs_by_len"],
+    )
+    .unwrap()
+}
+
+/// Values are space-separated tags.
+fn tags(_key: &[u8], value: &[u8]) -> Vec<Vec<u8>> {
+    value
+        .split(|&b| b == b' ')
+        .filter(|tag| !tag.is_empty())
+        .map(<[u8]>::to_vec)
+        .collect()
+}
+
+fn len(_key: &[u8], value: &[u8]) -> Vec<Vec<u8>> {
+    vec![(value.len() as u32).to_be_bytes().to_vec()]
+}
+
+fn docs_by_tag(db: &TransactionDB) -> IndexedTable<SingleThreaded, &ColumnFamily> {
+    IndexedTable::new(db, db.cf_handle("docs").unwrap()).with_index(
+        "by_tag",
+        db.cf_handle("docs_by_tag").unwrap(),
+        tags,
+    )
+}
+
+fn keys(keys: &[&str]) -> Vec<Vec<u8>> {
+    keys.iter().map(|key| key.as_bytes().to_vec()).collect()
+}
+
+#[test]
+fn indexed_table_put_delete() {
+    let path = DBPath::new("_rust_rocksdb_indexed_table_put_delete");
+    let db = open(&path);
+    let table = IndexedTable::new(&db, db.cf_handle("docs").unwrap())
+        .with_index("by_tag", db.cf_handle("docs_by_tag").unwrap(), tags)
+        .with_index("by_len", db.cf_handle("docs_by_len").unwrap(), len);
+
+    table.put(b"d1", b"red blue").unwrap();
+    table.put(b"d2", b"blue").unwrap();
+    table.put(b"d3", b"green red").unwrap();
+    assert_eq!(table.get(b"d1").unwrap().unwrap(), b"red blue");
+    assert_eq!(
+        table.lookup_by_index("by_tag", b"red").unwrap(),
+        keys(&["d1", "d3"])
+    );
+    assert_eq!(
+        table.lookup_by_index("by_tag", b"blue").unwrap(),
+        keys(&["d1", "d2"])
+    );
+    assert_eq!(
+        table.lookup_by_index("by_len", 4u32.to_be_bytes()).unwrap(),
+        keys(&["d2"])
+    );
+
+    table.put(b"d1", b"blue yellow").unwrap();
+    assert_eq!(
+        table.lookup_by_index("by_tag", b"red").unwrap(),
+        keys(&["d3"])
+    );
+    assert_eq!(
+        table.lookup_by_index("by_tag", b"yellow").unwrap(),
+        keys(&["d1"])
+    );
+
+    table.delete(b"d2").unwrap();
+    table.delete(b"missing").unwrap();
+    assert!(table.get(b"d2").unwrap().is_none());
+    assert_eq!(
+        table.lookup_by_index("by_tag", b"blue").unwrap(),
+        keys(&["d1"])
+    );
+    assert!(table
+        .lookup_by_index("by_len", 4u32.to_be_bytes())
+        .unwrap()
+        .is_empty());
+
+    assert!(table.verify_index("by_tag").unwrap().is_consistent());
+    assert!(table.verify_index("by_len").unwrap().is_consistent());
+    assert_eq!(
+        table
+            .lookup_by_index("by_color", b"red")
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidArgument
+    );
+}
+
+#[test]
+fn indexed_table_transaction() {
+    let path = DBPath::new("_rust_rocksdb_indexed_table_transaction");
+    let db = open(&path);
+    let table = IndexedTable::new(&db, db.cf_handle("docs").unwrap()).with_index(
+        "by_tag",
+        db.cf_handle("docs_by_tag").unwrap(),
+        tags,
+    );
+
+    {
+        let txn = db.transaction();
+        table.put_in(&txn, b"d1", b"red").unwrap();
+        table.put_in(&txn, b"d2", b"red").unwrap();
+        txn.rollback().unwrap();
+    }
+    assert!(table.lookup_by_index("by_tag", b"red").unwrap().is_empty());
+
+    let txn = db.transaction();
+    table.put_in(&txn, b"d1", b"red").unwrap();
+    table.put_in(&txn, b"d1", b"blue").unwrap();
+    table.put_in(&txn, b"d2", b"red").unwrap();
+    table.delete_in(&txn, b"d2").unwrap();
+    txn.commit().unwrap();
+    assert!(table.lookup_by_index("by_tag", b"red").unwrap().is_empty());
+    assert_eq!(
+        table.lookup_by_index("by_tag", b"blue").unwrap(),
+        keys(&["d1"])
+    );
+}
+
+#[test]
+fn indexed_table_verify_and_rebuild() {
+    let path = DBPath::new("_rust_rocksdb_indexed_table_verify_and_rebuild");
+    let db = open(&path);
+    let docs = db.cf_handle("docs").unwrap();
+    let by_tag = db.cf_handle("docs_by_tag").unwrap();
+
+    // Entries written before the index was added.
+    db.put_cf(&docs, b"d1", b"red").unwrap();
+    db.put_cf(&docs, b"d2", b"red blue").unwrap();
+    // An entry of a deleted document.
+    db.put_cf(
+        &by_tag,
+        keycodec::encode(&(b"red".to_vec(), b"d0".to_vec())),
+        b"",
+    )
+    .unwrap();
+
+    let table = IndexedTable::new(&db, db.cf_handle("docs").unwrap()).with_index(
+        "by_tag",
+        db.cf_handle("docs_by_tag").unwrap(),
+        tags,
+    );
+    let entry = |index_value: &[u8], primary_key: &[u8]| IndexEntry {
+        index_value: index_value.to_vec(),
+        primary_key: primary_key.to_vec(),
+    };
+
+    let report = table.verify_index("by_tag").unwrap();
+    assert!(!report.is_consistent());
+    assert_eq!(
+        report.missing,
+        vec![
+            entry(b"blue", b"d2"),
+            entry(b"red", b"d1"),
+            entry(b"red", b"d2")
+        ]
+    );
+    assert_eq!(report.dangling, vec![entry(b"red", b"d0")]);
+
+    assert_eq!(table.rebuild_index("by_tag").unwrap(), report);
+    assert!(table.verify_index("by_tag").unwrap().is_consistent());
+    assert_eq!(
+        table.lookup_by_index("by_tag", b"red").unwrap(),
+        keys(&["d1", "d2"])
+    );
+    assert!(table.rebuild_index("by_tag").unwrap().is_consistent());
+}
+
+#[test]
+fn indexed_table_rebuild_concurrent_writes() {
+    let path = DBPath::new("_rust_rocksdb_indexed_table_rebuild_concurrent_writes");
+    let db = Arc::new(open(&path));
+
+    // The writer drops its end of the channel when it is done.
+    let (writing, writer_done) = mpsc::channel::<()>();
+    let writer = {
+        let db = db.clone();
+        thread::spawn(move || {
+            let _writing = writing;
+            let table = docs_by_tag(&db);
+            for i in 0..500 {
+                let tag = if i % 3 == 0 { "red" } else { "blue" };
+                table.put(format!("d{}", i % 10), tag).unwrap();
+            }
+        })
+    };
+    // A rebuild either repairs nothing, since the index is kept consistent
+    // by the writer, or conflicts with a write.  It never removes entries
+    // written after it started.
+    let table = docs_by_tag(&db);
+    while let Err(TryRecvError::Empty) = writer_done.try_recv() {
+        match table.rebuild_index("by_tag") {
+            Ok(report) => assert!(report.is_consistent(), "{:?}", report),
+            Err(e) => assert!(
+                matches!(e.kind(), ErrorKind::Busy | ErrorKind::TimedOut),
+                "{}",
+                e
+            ),
+        }
+    }
+    writer.join().unwrap();
+    assert!(table.verify_index("by_tag").unwrap().is_consistent());
+}
@@ -0,0 +1,108 @@
+// Copyright 2020 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod util;
+
+use pretty_assertions::assert_eq;
+
+use rocksdb::{
+    keycodec::{self, Encoded},
+    ErrorKind, IteratorMode, PrefixRange, ReadOptions, DB,
+};
+use util::DBPath;
+
+type Key = (u64, i64, String, Vec<u8>, bool, Option<i64>);
+
+fn key(a: u64, b: i64, c: &str, d: &[u8], e: bool, f: Option<i64>) -> Key {
+    (a, b, c.to_owned(), d.to_vec(), e, f)
+}
+
+fn keys() -> Vec<Key> {
+    let mut keys = Vec::new();
+    for &a in &[0, 1, 255, 256, u64::MAX] {
+        for &b in &[i64::MIN, -256, -1, 0, 1, i64::MAX] {
+            for &c in &["", "\0", "\0\0", "a", "a\0", "a\0b", "ab", "\u{ff}"] {
+                for &d in &[&b""[..], b"\0", b"\xff", b"\xff\0", b"\xff\xff"] {
+                    for &e in &[false, true] {
+                        for &f in &[None, Some(-1), Some(0)] {
+                            keys.push(key(a, b, c, d, e, f));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    keys
+}
+
+#[test]
+fn test_keycodec_order_and_round_trip() {
+    let keys = keys();
+    let mut encoded: Vec<(Vec<u8>, &Key)> = keys.iter().map(|k| (keycodec::encode(k), k)).collect();
+    encoded.sort();
+    let mut sorted: Vec<&Key> = keys.iter().collect();
+    sorted.sort();
+    assert_eq!(encoded.iter().map(|(_, k)| *k).collect::<Vec<_>>(), sorted);
+
+    for (bytes, k) in &encoded {
+        assert_eq!(&keycodec::decode::<Key>(bytes).unwrap(), *k);
+    }
+}
+
+#[test]
+fn test_keycodec_decode_errors() {
+    let bytes = keycodec::encode(&(1u64, "a".to_owned()));
+    let err = keycodec::decode::<(u64, String)>(&bytes[..bytes.len() - 1]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Corruption);
+    assert!(keycodec::decode::<u64>(&bytes).is_err());
+    assert!(keycodec::decode::<bool>(&[2]).is_err());
+    assert!(keycodec::decode::<Option<u8>>(&[2, 0]).is_err());
+    assert!(keycodec::decode::<String>(&[0xff, 0, 1]).is_err());
+}
+
+#[test]
+fn test_keycodec_iterate_range() {
+    let path = DBPath::new("_rust_rocksdb_keycodec_iterate_range");
+    let db = DB::open_default(&path).unwrap();
+    let all = [
+        (1u64, "a".to_owned(), -1i64),
+        (1, "a".to_owned(), 3),
+        (1, "a\0".to_owned(), 0),
+        (1, "ab".to_owned(), 0),
+        (2, "".to_owned(), 0),
+    ];
+    for k in &all {
+        db.put(keycodec::encode(k), b"").unwrap();
+    }
+
+    let scan = |readopts: ReadOptions| -> Vec<(u64, String, i64)> {
+        db.iterator_opt(IteratorMode::Start, readopts)
+            .map(|item| keycodec::decode(&item.unwrap().0).unwrap())
+            .collect()
+    };
+
+    let mut readopts = ReadOptions::default();
+    readopts.set_iterate_range(PrefixRange(Encoded((1u64, "a".to_owned()))));
+    assert_eq!(scan(readopts), all[..2].to_vec());
+
+    let mut readopts = ReadOptions::default();
+    readopts.set_iterate_range(PrefixRange(Encoded((1u64,))));
+    assert_eq!(scan(readopts), all[..4].to_vec());
+
+    let mut readopts = ReadOptions::default();
+    readopts.set_iterate_range(
+        Encoded((1u64, "a".to_owned(), 0i64))..Encoded((2u64, String::new(), i64::MIN)),
+    );
+    assert_eq!(scan(readopts), all[1..4].to_vec());
+}
@@ -15,8 +15,16 @@
 mod util;
 
 use pretty_assertions::assert_eq;
-use rocksdb::{merge_operator::MergeFn, DBCompactionStyle, MergeOperands, Options, DB};
+use rocksdb::{
+    merge_operator::{MergeFn, MergeOperator},
+    ColumnFamilyDescriptor, DBCompactionStyle, MergeOperands, Options, DB,
+};
 use serde::{Deserialize, Serialize};
+use std::ffi::{CStr, CString};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
 use util::DBPath;
 
 fn test_provided_merge(
@@ -340,3 +348,163 @@ fn test_merge_state() {
     }
     assert!(DB::destroy(&opts, path).is_ok());
 }
+
+/// Concatenates operands.  An operand starting with `=` replaces the value
+/// so far, which lets reads stop at it.
+struct ConcatOperator {
+    name: CString,
+    partial_merges: Arc<AtomicUsize>,
+    should_merges: Arc<AtomicUsize>,
+}
+
+impl MergeOperator for ConcatOperator {
+    fn name(&self) -> &CStr {
+        &self.name
+    }
+
+    fn full_merge(
+        &self,
+        _key: &[u8],
+        existing_value: Option<&[u8]>,
+        operands: &MergeOperands,
+    ) -> Option<Vec<u8>> {
+        let mut result = existing_value.map(<[u8]>::to_vec).unwrap_or_default();
+        for op in operands {
+            match op.strip_prefix(b"=") {
+                Some(value) => result = value.to_vec(),
+                None => result.extend_from_slice(op),
+            }
+        }
+        Some(result)
+    }
+
+    fn partial_merge_multi(&self, _key: &[u8], operands: &MergeOperands) -> Option<Vec<u8>> {
+        self.partial_merges.fetch_add(1, Ordering::SeqCst);
+        if operands.iter().any(|op| op.starts_with(b"=")) {
+            return None;
+        }
+        Some(operands.iter().flatten().copied().collect())
+    }
+
+    fn should_merge(&self, operands: &MergeOperands) -> bool {
+        self.should_merges.fetch_add(1, Ordering::SeqCst);
+        operands.iter().any(|op| op.starts_with(b"="))
+    }
+}
+
+fn concat_operator() -> (ConcatOperator, Arc<AtomicUsize>, Arc<AtomicUsize>) {
+    let partial_merges = Arc::new(AtomicUsize::new(0));
+    let should_merges = Arc::new(AtomicUsize::new(0));
+    let operator = ConcatOperator {
+        name: CString::new("concat").unwrap(),
+        partial_merges: partial_merges.clone(),
+        should_merges: should_merges.clone(),
+    };
+    (operator, partial_merges, should_merges)
+}
+
+#[test]
+fn test_merge_operator_impl_should_merge() {
+    let db_path = DBPath::new("_rust_rocksdb_merge_operator_impl_should_merge");
+    let (operator, _, should_merges) = concat_operator();
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.set_merge_operator_impl(operator);
+
+    let db = DB::open(&opts, &db_path).unwrap();
+    db.put(b"k1", b"a").unwrap();
+    db.merge(b"k1", b"b").unwrap();
+    db.merge(b"k1", b"=c").unwrap();
+    db.merge(b"k1", b"d").unwrap();
+    assert_eq!(db.get(b"k1").unwrap().unwrap(), b"cd");
+    assert!(should_merges.load(Ordering::SeqCst) > 0);
+
+    db.merge(b"k2", b"a").unwrap();
+    db.merge(b"k2", b"b").unwrap();
+    assert_eq!(db.get(b"k2").unwrap().unwrap(), b"ab");
+}
+
+#[test]
+fn test_merge_operator_impl_partial_merge_multi() {
+    let db_path = DBPath::new("_rust_rocksdb_merge_operator_impl_partial_merge_multi");
+    let (operator, partial_merges, _) = concat_operator();
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.set_merge_operator_impl(operator);
+
+    let db = DB::open(&opts, &db_path).unwrap();
+    for op in [b"a", b"b", b"c"] {
+        db.merge(b"k1", op).unwrap();
+    }
+    db.flush().unwrap();
+    assert!(partial_merges.load(Ordering::SeqCst) > 0);
+    assert_eq!(db.get(b"k1").unwrap().unwrap(), b"abc");
+
+    db.put(b"k1", b"x").unwrap();
+    db.merge(b"k1", b"y").unwrap();
+    db.compact_range(None::<&[u8]>, None::<&[u8]>);
+    assert_eq!(db.get(b"k1").unwrap().unwrap(), b"xy");
+}
+
+fn merge_cf_descriptor(name: &str, merge_fn: impl MergeFn + Clone) -> ColumnFamilyDescriptor {
+    let mut opts = Options::default();
+    opts.set_merge_operator_associative(name, merge_fn);
+    ColumnFamilyDescriptor::new(name, opts)
+}
+
+#[test]
+fn test_builtin_merge_operators() {
+    use rocksdb::merge_operator::builtin;
+
+    let db_path = DBPath::new("_rust_rocksdb_builtin_merge_operators");
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.create_missing_column_families(true);
+    let cfs = vec![
+        merge_cf_descriptor("add", builtin::u64_add),
+        merge_cf_descriptor("max", builtin::u64_max),
+        merge_cf_descriptor("min", builtin::u64_min),
+        merge_cf_descriptor("union", builtin::set_union),
+        merge_cf_descriptor("append", builtin::string_append("::")),
+    ];
+    let db = DB::open_cf_descriptors(&opts, &db_path, cfs).unwrap();
+    let cf = |name| db.cf_handle(name).unwrap();
+
+    for value in [5, 1, 9] {
+        for name in ["add", "max", "min"] {
+            db.merge_cf(cf(name), b"k", builtin::encode_u64(value))
+                .unwrap();
+        }
+    }
+    db.merge_cf(cf("union"), b"k", builtin::encode_set(["b", "a"]))
+        .unwrap();
+    db.merge_cf(cf("union"), b"k", builtin::encode_set(["c", "a"]))
+        .unwrap();
+    db.put_cf(cf("append"), b"k", b"x").unwrap();
+    db.merge_cf(cf("append"), b"k", b"y").unwrap();
+    db.merge_cf(cf("append"), b"k", b"z").unwrap();
+    db.flush_cf(cf("union")).unwrap();
+    db.compact_range_cf(cf("union"), None::<&[u8]>, None::<&[u8]>);
+
+    let get_u64 = |name| builtin::decode_u64(&db.get_cf(cf(name), b"k").unwrap().unwrap());
+    assert_eq!(get_u64("add"), Some(15));
+    assert_eq!(get_u64("max"), Some(9));
+    assert_eq!(get_u64("min"), Some(1));
+
+    let union = db.get_cf(cf("union"), b"k").unwrap().unwrap();
+    assert_eq!(union, builtin::encode_set(["a", "b", "c"]));
+    assert_eq!(
+        builtin::decode_set(&union).unwrap(),
+        vec![&b"a"[..], &b"b"[..], &b"c"[..]]
+    );
+
+    let appended = db.get_cf(cf("append"), b"k").unwrap().unwrap();
+    assert_eq!(appended, b"x::y::z");
+    assert_eq!(
+        builtin::split_appended(&appended, b"::").collect::<Vec<_>>(),
+        vec![&b"x"[..], &b"y"[..], &b"z"[..]]
+    );
+
+    db.merge_cf(cf("add"), b"bad", b"not a u64").unwrap();
+    assert!(db.get_cf(cf("add"), b"bad").is_err());
+}
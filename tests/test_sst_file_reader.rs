@@ -0,0 +1,140 @@
+// Copyright 2022 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod util;
+
+use std::fs;
+use std::path::Path;
+
+use pretty_assertions::assert_eq;
+
+use rocksdb::{DBCompressionType, Options, ReadOptions, SstFileReader, SstFileWriter, DB};
+use util::DBPath;
+
+fn write_sst(opts: &Options, path: &Path) {
+    let mut writer = SstFileWriter::create(opts);
+    writer.open(path).unwrap();
+    writer.put(b"k1", b"v1").unwrap();
+    writer.put(b"k2", b"v2").unwrap();
+    writer.delete(b"k3").unwrap();
+    writer.put(b"k4", b"v4").unwrap();
+    writer.finish().unwrap();
+}
+
+#[test]
+fn sst_file_reader_iterates() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("reader.sst");
+    let opts = Options::default();
+    write_sst(&opts, &path);
+
+    let reader = SstFileReader::open(&opts, &path).unwrap();
+    let mut iter = reader.raw_iterator();
+    let mut entries = Vec::new();
+    iter.seek_to_first();
+    while let Some((key, value)) = iter.item() {
+        entries.push((key.to_vec(), value.to_vec()));
+        iter.next();
+    }
+    iter.status().unwrap();
+    assert_eq!(
+        entries,
+        vec![
+            (b"k1".to_vec(), b"v1".to_vec()),
+            (b"k2".to_vec(), b"v2".to_vec()),
+            (b"k4".to_vec(), b"v4".to_vec()),
+        ]
+    );
+
+    iter.seek_for_prev(b"k3");
+    assert_eq!(iter.key(), Some(&b"k2"[..]));
+    iter.seek(b"k3");
+    assert_eq!(iter.key(), Some(&b"k4"[..]));
+
+    let mut readopts = ReadOptions::default();
+    readopts.set_iterate_upper_bound(b"k2".to_vec());
+    let mut iter = reader.raw_iterator_opt(readopts);
+    iter.seek_to_first();
+    iter.next();
+    assert!(!iter.valid());
+
+    assert_eq!(reader.smallest_key().unwrap().unwrap(), b"k1");
+    assert_eq!(reader.largest_key().unwrap().unwrap(), b"k4");
+}
+
+#[test]
+fn sst_file_reader_properties() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("reader.sst");
+    let mut opts = Options::default();
+    opts.set_compression_type(DBCompressionType::None);
+    write_sst(&opts, &path);
+
+    let props = SstFileReader::open(&opts, &path)
+        .unwrap()
+        .table_properties();
+    assert_eq!(props.num_entries(), 4);
+    assert_eq!(props.num_deletions(), 1);
+    assert_eq!(props.raw_key_size(), 4 * (2 + 8));
+    assert_eq!(props.raw_value_size(), 3 * 2);
+    assert!(props.data_size() > 0);
+    assert_eq!(props.compression_name(), "NoCompression");
+    assert_eq!(props.comparator_name(), "leveldb.BytewiseComparator");
+    // Only tables written by a database record when they were created.
+    assert_eq!(props.file_creation_time(), 0);
+
+    let db_path = DBPath::new("_rust_rocksdb_sst_file_reader_properties");
+    let db = DB::open_default(&db_path).unwrap();
+    db.put(b"k1", b"v1").unwrap();
+    db.flush().unwrap();
+    let live_file = &db.live_files().unwrap()[0];
+    let path = (&db_path)
+        .as_ref()
+        .join(live_file.name.trim_start_matches('/'));
+    let props = SstFileReader::open(&opts, path).unwrap().table_properties();
+    assert_eq!(props.num_entries(), 1);
+    assert_eq!(props.column_family_name(), "default");
+    assert!(props.file_creation_time() > 0);
+}
+
+#[test]
+fn sst_file_reader_verifies_checksum() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("reader.sst");
+    let mut opts = Options::default();
+    opts.set_compression_type(DBCompressionType::None);
+    write_sst(&opts, &path);
+    SstFileReader::open(&opts, &path)
+        .unwrap()
+        .verify_checksum()
+        .unwrap();
+
+    // The first data block starts at the beginning of the file.
+    let mut data = fs::read(&path).unwrap();
+    data[0] ^= 0xff;
+    fs::write(&path, data).unwrap();
+    let reader = SstFileReader::open(&opts, &path).unwrap();
+    assert!(reader.verify_checksum().is_err());
+}
+
+#[test]
+fn sst_file_reader_open_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let opts = Options::default();
+    assert!(SstFileReader::open(&opts, dir.path().join("missing.sst")).is_err());
+
+    let path = dir.path().join("garbage.sst");
+    fs::write(&path, b"not an sst file").unwrap();
+    assert!(SstFileReader::open(&opts, &path).is_err());
+}
@@ -0,0 +1,112 @@
+// Copyright 2022 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod util;
+
+use rocksdb::statistics::{Histogram, StatsLevel, Ticker};
+use rocksdb::{Options, DB};
+use util::DBPath;
+
+fn options() -> Options {
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.enable_statistics();
+    opts
+}
+
+#[test]
+fn test_statistics_disabled() {
+    assert!(Options::default().statistics().is_none());
+}
+
+#[test]
+fn test_tickers_and_histograms() {
+    let path = DBPath::new("_rust_rocksdb_statistics_tickers");
+    let opts = options();
+    let stats = opts.statistics().unwrap();
+    let db = DB::open(&opts, &path).unwrap();
+
+    for i in 0..10u8 {
+        db.put([i], b"value").unwrap();
+    }
+    db.get([0]).unwrap();
+    db.get([20]).unwrap();
+
+    assert_eq!(stats.ticker(Ticker::NumberKeysWritten), 10);
+    assert_eq!(stats.ticker(Ticker::NumberKeysRead), 2);
+    assert_eq!(stats.ticker(Ticker::MemtableHit), 1);
+    assert_eq!(stats.ticker(Ticker::MemtableMiss), 1);
+
+    let writes = stats.histogram(Histogram::DbWrite);
+    assert_eq!(writes.count, 10);
+    assert!(writes.max >= writes.p50);
+    let gets = stats.histogram(Histogram::DbGet);
+    assert_eq!(gets.count, 2);
+    assert_eq!(stats.histogram(Histogram::DbSeek).count, 0);
+
+    stats.reset().unwrap();
+    assert_eq!(stats.ticker(Ticker::NumberKeysWritten), 0);
+    assert_eq!(stats.histogram(Histogram::DbWrite).count, 0);
+}
+
+#[test]
+fn test_statistics_shared_with_clones() {
+    let path = DBPath::new("_rust_rocksdb_statistics_clones");
+    let opts = options();
+    let stats = opts.statistics().unwrap();
+    {
+        let db = DB::open(&opts.clone(), &path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+    }
+    assert_eq!(stats.ticker(Ticker::NumberKeysWritten), 1);
+}
+
+#[test]
+fn test_stats_level() {
+    let path = DBPath::new("_rust_rocksdb_statistics_level");
+    let opts = options();
+    let stats = opts.statistics().unwrap();
+    assert_eq!(stats.stats_level(), StatsLevel::ExceptDetailedTimers);
+
+    stats.set_stats_level(StatsLevel::ExceptHistogramOrTimers);
+    assert_eq!(stats.stats_level(), StatsLevel::ExceptHistogramOrTimers);
+    let db = DB::open(&opts, &path).unwrap();
+    db.put(b"k1", b"v1").unwrap();
+    assert_eq!(stats.ticker(Ticker::NumberKeysWritten), 1);
+    assert_eq!(stats.histogram(Histogram::DbWrite).count, 0);
+
+    stats.set_stats_level(StatsLevel::DisableAll);
+    db.put(b"k2", b"v2").unwrap();
+    assert_eq!(stats.ticker(Ticker::NumberKeysWritten), 1);
+}
+
+#[test]
+fn test_names_match_statistics_string() {
+    let opts = options();
+    let report = opts.get_statistics().unwrap();
+    for ticker in Ticker::ALL {
+        assert!(
+            report.contains(&format!("{} COUNT", ticker)),
+            "{:?}",
+            ticker
+        );
+    }
+    for histogram in Histogram::ALL {
+        assert!(
+            report.contains(&format!("{} P50", histogram)),
+            "{:?}",
+            histogram
+        );
+    }
+}
@@ -0,0 +1,268 @@
+// Copyright 2022 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod util;
+
+use std::convert::TryInto;
+use std::ffi::CStr;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use rocksdb::table_factory::{TableFactory, TableFile, TableIterator, TableReader};
+use rocksdb::{
+    Direction, Error, ErrorKind, IngestExternalFileOptions, IteratorMode, Options, SstFileWriter,
+    DB,
+};
+use util::DBPath;
+
+const MAGIC: &[u8] = b"KVT1";
+
+/// Writes a table of the test format: a magic number followed by
+/// length-prefixed keys and values, sorted by key.
+fn write_table(path: &Path, entries: &[(&str, &str)]) {
+    let mut data = MAGIC.to_vec();
+    for (key, value) in entries {
+        for bytes in [key.as_bytes(), value.as_bytes()] {
+            data.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+            data.extend_from_slice(bytes);
+        }
+    }
+    fs::write(path, data).unwrap();
+}
+
+struct KvTableFactory;
+
+impl TableFactory for KvTableFactory {
+    type Reader = KvTable;
+
+    fn name(&self) -> &CStr {
+        CStr::from_bytes_with_nul(b"KvTable\0").unwrap()
+    }
+
+    fn open(&self, file: TableFile) -> io::Result<KvTable> {
+        let data = file.read(0, file.size() as usize)?;
+        if !data.starts_with(MAGIC) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{:?} is not a KvTable", file.path()),
+            ));
+        }
+        let mut entries = Vec::new();
+        let mut rest = &data[MAGIC.len()..];
+        while !rest.is_empty() {
+            let mut field = || {
+                let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+                let bytes = rest[4..4 + len].to_vec();
+                rest = &rest[4 + len..];
+                bytes
+            };
+            let key = field();
+            let value = field();
+            entries.push((key, value));
+        }
+        Ok(KvTable { entries })
+    }
+}
+
+struct KvTable {
+    entries: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl TableReader for KvTable {
+    fn new_iterator(&self) -> Box<dyn TableIterator + '_> {
+        Box::new(KvTableIterator {
+            entries: &self.entries,
+            pos: None,
+        })
+    }
+}
+
+struct KvTableIterator<'a> {
+    entries: &'a [(Vec<u8>, Vec<u8>)],
+    pos: Option<usize>,
+}
+
+impl KvTableIterator<'_> {
+    fn lower_bound(&self, key: &[u8]) -> usize {
+        self.entries.partition_point(|(k, _)| k.as_slice() < key)
+    }
+
+    fn set(&mut self, pos: usize) {
+        self.pos = Some(pos).filter(|&p| p < self.entries.len());
+    }
+}
+
+impl TableIterator for KvTableIterator<'_> {
+    fn valid(&self) -> bool {
+        self.pos.is_some()
+    }
+
+    fn seek_to_first(&mut self) {
+        self.set(0);
+    }
+
+    fn seek_to_last(&mut self) {
+        self.pos = self.entries.len().checked_sub(1);
+    }
+
+    fn seek(&mut self, key: &[u8]) {
+        self.set(self.lower_bound(key));
+    }
+
+    fn seek_for_prev(&mut self, key: &[u8]) {
+        let upper = self.entries.partition_point(|(k, _)| k.as_slice() <= key);
+        self.pos = upper.checked_sub(1);
+    }
+
+    fn next(&mut self) {
+        self.set(self.pos.unwrap() + 1);
+    }
+
+    fn prev(&mut self) {
+        self.pos = self.pos.unwrap().checked_sub(1);
+    }
+
+    fn key(&self) -> &[u8] {
+        &self.entries[self.pos.unwrap()].0
+    }
+
+    fn value(&self) -> &[u8] {
+        &self.entries[self.pos.unwrap()].1
+    }
+}
+
+fn options() -> Options {
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.set_table_factory(KvTableFactory);
+    opts
+}
+
+fn ingest(db: &DB, file: PathBuf) -> Result<(), Error> {
+    let mut opts = IngestExternalFileOptions::default();
+    opts.set_write_global_seqno(false);
+    db.ingest_external_file_opts(&opts, vec![file])
+}
+
+fn keys(db: &DB, mode: IteratorMode) -> Vec<String> {
+    db.iterator(mode)
+        .map(|item| String::from_utf8(item.unwrap().0.to_vec()).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_custom_table_reads() {
+    let path = DBPath::new("_rust_rocksdb_table_factory_reads");
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("table.kv");
+    write_table(&file, &[("a", "1"), ("c", "3"), ("e", "5")]);
+    {
+        let db = DB::open(&options(), &path).unwrap();
+        ingest(&db, file).unwrap();
+
+        assert_eq!(db.get(b"c").unwrap().unwrap(), b"3");
+        assert!(db.get(b"b").unwrap().is_none());
+        assert_eq!(keys(&db, IteratorMode::Start), ["a", "c", "e"]);
+        assert_eq!(keys(&db, IteratorMode::End), ["e", "c", "a"]);
+        assert_eq!(
+            keys(&db, IteratorMode::From(b"b", Direction::Forward)),
+            ["c", "e"]
+        );
+        assert_eq!(
+            keys(&db, IteratorMode::From(b"d", Direction::Reverse)),
+            ["c", "a"]
+        );
+
+        // The database still writes block-based tables.
+        db.put(b"b", b"2").unwrap();
+        db.flush().unwrap();
+    }
+
+    let db = DB::open(&options(), &path).unwrap();
+    assert_eq!(keys(&db, IteratorMode::Start), ["a", "b", "c", "e"]);
+    assert_eq!(db.get(b"e").unwrap().unwrap(), b"5");
+}
+
+#[test]
+fn test_custom_table_overrides_older_values() {
+    let path = DBPath::new("_rust_rocksdb_table_factory_overrides");
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("table.kv");
+    write_table(&file, &[("a", "new"), ("b", "new")]);
+    {
+        let db = DB::open(&options(), &path).unwrap();
+        db.put(b"a", b"old").unwrap();
+        db.put(b"c", b"old").unwrap();
+        let snapshot = db.snapshot();
+        ingest(&db, file).unwrap();
+
+        assert_eq!(db.get(b"a").unwrap().unwrap(), b"new");
+        assert_eq!(snapshot.get(b"a").unwrap().unwrap(), b"old");
+        assert!(snapshot.get(b"b").unwrap().is_none());
+        assert_eq!(keys(&db, IteratorMode::Start), ["a", "b", "c"]);
+    }
+
+    // Compactions rewrite the ingested table in the block-based format.
+    let db = DB::open(&options(), &path).unwrap();
+    assert_eq!(db.get(b"a").unwrap().unwrap(), b"new");
+    db.compact_range::<&[u8], &[u8]>(None, None);
+    for file in db.live_files().unwrap() {
+        let data = fs::read((&path).as_ref().join(file.name.trim_start_matches('/'))).unwrap();
+        assert!(!data.starts_with(MAGIC));
+    }
+    assert_eq!(db.get(b"a").unwrap().unwrap(), b"new");
+    assert_eq!(db.get(b"c").unwrap().unwrap(), b"old");
+}
+
+#[test]
+fn test_custom_table_open_error() {
+    let path = DBPath::new("_rust_rocksdb_table_factory_open_error");
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("table.kv");
+    fs::write(&file, b"not a table").unwrap();
+
+    let db = DB::open(&options(), &path).unwrap();
+    let err = ingest(&db, file).unwrap_err();
+    assert!(err.to_string().contains("is not a KvTable"), "{}", err);
+}
+
+#[test]
+fn test_custom_table_rejects_write_global_seqno() {
+    let path = DBPath::new("_rust_rocksdb_table_factory_write_global_seqno");
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("table.kv");
+    write_table(&file, &[("a", "1")]);
+    let contents = fs::read(&file).unwrap();
+
+    let db = DB::open(&options(), &path).unwrap();
+    let mut opts = IngestExternalFileOptions::default();
+    opts.set_write_global_seqno(true);
+    let err = db
+        .ingest_external_file_opts(&opts, vec![file.clone()])
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+    assert!(err.to_string().contains("write_global_seqno"), "{}", err);
+    assert_eq!(fs::read(&file).unwrap(), contents);
+    assert!(db.get(b"a").unwrap().is_none());
+
+    // Block-based tables may still have the sequence number written.
+    let sst = dir.path().join("table.sst");
+    let mut writer = SstFileWriter::create(&options());
+    writer.open(&sst).unwrap();
+    writer.put(b"b", b"2").unwrap();
+    writer.finish().unwrap();
+    db.ingest_external_file_opts(&opts, vec![sst]).unwrap();
+    assert_eq!(db.get(b"b").unwrap().unwrap(), b"2");
+}
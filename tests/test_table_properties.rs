@@ -0,0 +1,83 @@
+// Copyright 2022 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod util;
+
+use pretty_assertions::assert_eq;
+
+use rocksdb::{Options, DB};
+use util::DBPath;
+
+#[test]
+fn test_properties_of_all_tables() {
+    let path = DBPath::new("_rust_rocksdb_properties_of_all_tables");
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.create_missing_column_families(true);
+    let db = DB::open_cf(&opts, &path, ["cf1"]).unwrap();
+    let cf1 = db.cf_handle("cf1").unwrap();
+
+    assert!(db.get_properties_of_all_tables().unwrap().is_empty());
+
+    db.put_cf(cf1, b"k1", b"v1").unwrap();
+    db.put_cf(cf1, b"k2", b"v2").unwrap();
+    db.delete_cf(cf1, b"k3").unwrap();
+    db.delete_range_cf(cf1, b"k4", b"k5").unwrap();
+    db.flush_cf(cf1).unwrap();
+
+    let props = db.get_properties_of_all_tables_cf(cf1).unwrap();
+    assert_eq!(props.len(), 1);
+    let (name, props) = props.iter().next().unwrap();
+    assert!(name.ends_with(".sst"), "{}", name);
+    assert_eq!(props.column_family_name(), "cf1");
+    // Deletions and range deletions are entries too.
+    assert_eq!(props.num_entries(), 4);
+    assert_eq!(props.num_deletions(), 2);
+    assert_eq!(props.num_range_deletions(), 1);
+    assert_eq!(props.raw_key_size(), 4 * (2 + 8));
+    assert!(props
+        .user_collected_properties()
+        .contains_key("rocksdb.block.based.table.index.type"));
+
+    assert!(db.get_properties_of_all_tables().unwrap().is_empty());
+}
+
+#[test]
+fn test_properties_of_tables_in_range() {
+    let path = DBPath::new("_rust_rocksdb_properties_of_tables_in_range");
+    let db = DB::open_default(&path).unwrap();
+    for keys in [[b"a", b"b"], [b"c", b"d"], [b"e", b"f"]] {
+        for key in keys {
+            db.put(key, b"value").unwrap();
+        }
+        db.flush().unwrap();
+    }
+    assert_eq!(db.get_properties_of_all_tables().unwrap().len(), 3);
+
+    let props = db
+        .get_properties_of_tables_in_range(&[b"b".as_ref()..b"c".as_ref()])
+        .unwrap();
+    assert_eq!(props.len(), 2);
+
+    let props = db
+        .get_properties_of_tables_in_range(&[b"a".as_ref()..b"a0", b"e"..b"e0"])
+        .unwrap();
+    assert_eq!(props.len(), 2);
+    assert!(props.values().all(|props| props.num_entries() == 2));
+
+    let props = db
+        .get_properties_of_tables_in_range(&[b"x".as_ref()..b"z".as_ref()])
+        .unwrap();
+    assert!(props.is_empty());
+}
@@ -0,0 +1,184 @@
+// Copyright 2022 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod util;
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::ffi::CStr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use pretty_assertions::assert_eq;
+
+use rocksdb::properties;
+use rocksdb::table_properties_collector::{
+    EntryType, TablePropertiesCollector, TablePropertiesCollectorContext,
+    TablePropertiesCollectorFactory,
+};
+use rocksdb::{Options, SstFileReader, SstFileWriter, DB};
+use util::DBPath;
+
+#[derive(Default)]
+struct EntryCounter {
+    puts: u64,
+    deletions: u64,
+    max_seq: u64,
+}
+
+impl TablePropertiesCollector for EntryCounter {
+    fn add_user_key(
+        &mut self,
+        _key: &[u8],
+        _value: &[u8],
+        entry_type: EntryType,
+        seq: u64,
+        _file_size: u64,
+    ) {
+        match entry_type {
+            EntryType::Put => self.puts += 1,
+            EntryType::Delete => self.deletions += 1,
+            _ => {}
+        }
+        self.max_seq = self.max_seq.max(seq);
+    }
+
+    fn finish(&mut self) -> HashMap<String, Vec<u8>> {
+        let mut props = HashMap::new();
+        props.insert("test.puts".to_owned(), self.puts.to_be_bytes().to_vec());
+        props.insert(
+            "test.deletions".to_owned(),
+            self.deletions.to_be_bytes().to_vec(),
+        );
+        props.insert(
+            "test.max_seq".to_owned(),
+            self.max_seq.to_be_bytes().to_vec(),
+        );
+        props
+    }
+
+    fn need_compact(&self) -> bool {
+        self.deletions > self.puts
+    }
+
+    fn name(&self) -> &CStr {
+        CStr::from_bytes_with_nul(b"EntryCounter\0").unwrap()
+    }
+}
+
+#[derive(Default, Clone)]
+struct EntryCounterFactory {
+    contexts: Arc<Mutex<Vec<TablePropertiesCollectorContext>>>,
+}
+
+impl TablePropertiesCollectorFactory for EntryCounterFactory {
+    type Collector = EntryCounter;
+
+    fn create(&self, context: TablePropertiesCollectorContext) -> EntryCounter {
+        self.contexts.lock().unwrap().push(context);
+        EntryCounter::default()
+    }
+
+    fn name(&self) -> &CStr {
+        CStr::from_bytes_with_nul(b"EntryCounterFactory\0").unwrap()
+    }
+}
+
+fn counter(props: &HashMap<String, Vec<u8>>, name: &str) -> u64 {
+    u64::from_be_bytes(props[name].as_slice().try_into().unwrap())
+}
+
+#[test]
+fn test_collected_properties() {
+    let path = DBPath::new("_rust_rocksdb_collected_properties");
+    let factory = EntryCounterFactory::default();
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.add_table_properties_collector_factory(factory.clone());
+    let db = DB::open(&opts, &path).unwrap();
+
+    db.put(b"k1", b"v1").unwrap();
+    db.put(b"k2", b"v2").unwrap();
+    db.delete(b"k3").unwrap();
+    db.flush().unwrap();
+
+    let all = db.get_properties_of_all_tables().unwrap();
+    assert_eq!(all.len(), 1);
+    let (name, props) = all.iter().next().unwrap();
+    let collected = props.user_collected_properties();
+    assert_eq!(counter(&collected, "test.puts"), 2);
+    assert_eq!(counter(&collected, "test.deletions"), 1);
+    assert_eq!(counter(&collected, "test.max_seq"), 3);
+
+    let contexts = factory.contexts.lock().unwrap().clone();
+    assert_eq!(contexts.len(), 1);
+    assert_eq!(contexts[0].column_family_id, 0);
+    assert_eq!(contexts[0].level_at_creation, Some(0));
+
+    // The properties are stored in the file.
+    let reader = SstFileReader::open(&opts, name).unwrap();
+    let collected = reader.table_properties().user_collected_properties();
+    assert_eq!(counter(&collected, "test.puts"), 2);
+}
+
+#[test]
+fn test_collected_properties_of_sst_file_writer() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("collected.sst");
+    let mut opts = Options::default();
+    opts.add_table_properties_collector_factory(EntryCounterFactory::default());
+
+    let mut writer = SstFileWriter::create(&opts);
+    writer.open(&path).unwrap();
+    writer.put(b"k1", b"v1").unwrap();
+    writer.delete(b"k2").unwrap();
+    writer.delete(b"k3").unwrap();
+    writer.finish().unwrap();
+
+    let reader = SstFileReader::open(&opts, &path).unwrap();
+    let collected = reader.table_properties().user_collected_properties();
+    assert_eq!(counter(&collected, "test.puts"), 1);
+    assert_eq!(counter(&collected, "test.deletions"), 2);
+    assert_eq!(counter(&collected, "test.max_seq"), 0);
+}
+
+#[test]
+fn test_need_compact() {
+    let path = DBPath::new("_rust_rocksdb_collector_need_compact");
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.add_table_properties_collector_factory(EntryCounterFactory::default());
+    let db = DB::open(&opts, &path).unwrap();
+
+    db.put(b"k1", b"v1").unwrap();
+    db.put(b"k5", b"v5").unwrap();
+    db.flush().unwrap();
+    for key in [b"k2", b"k3", b"k4"] {
+        db.delete(key).unwrap();
+    }
+    db.flush().unwrap();
+
+    // The table of mostly deletions is marked for compaction, which moves
+    // it out of level 0 together with the older table it overlaps.
+    let level0 = properties::num_files_at_level(0);
+    for _ in 0..100 {
+        if db.property_int_value(&level0).unwrap() == Some(0) {
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    assert_eq!(db.property_int_value(&level0).unwrap(), Some(0));
+    assert_eq!(db.get(b"k1").unwrap().unwrap(), b"v1");
+}
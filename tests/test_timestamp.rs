@@ -0,0 +1,190 @@
+// Copyright 2022 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod util;
+
+use pretty_assertions::assert_eq;
+
+use rocksdb::{
+    split_timestamp, ColumnFamilyDescriptor, Options, ReadOptions, WriteBatch, WriteBatchIterator,
+    DB, DEFAULT_COLUMN_FAMILY_NAME,
+};
+use util::DBPath;
+
+fn ts(ts: u64) -> [u8; 8] {
+    ts.to_le_bytes()
+}
+
+fn read_at(ts: u64) -> ReadOptions {
+    let mut readopts = ReadOptions::default();
+    readopts.set_timestamp(ts.to_le_bytes());
+    readopts
+}
+
+fn open(path: &DBPath) -> DB {
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.set_comparator_with_u64_ts();
+    // Opening the default column family by name gives it a handle.
+    let default_cf = ColumnFamilyDescriptor::new(DEFAULT_COLUMN_FAMILY_NAME, opts.clone());
+    DB::open_cf_descriptors(&opts, path, vec![default_cf]).unwrap()
+}
+
+#[test]
+fn timestamp_reads_as_of() {
+    let path = DBPath::new("_rust_rocksdb_timestamp_reads_as_of");
+    let db = open(&path);
+    db.put_with_ts(b"k1", ts(1), b"v1").unwrap();
+    db.put_with_ts(b"k1", ts(2), b"v2").unwrap();
+    db.delete_with_ts(b"k1", ts(3)).unwrap();
+    db.put_with_ts(b"k2", ts(2), b"w2").unwrap();
+
+    assert!(db.get_opt(b"k1", &read_at(0)).unwrap().is_none());
+    assert_eq!(db.get_opt(b"k1", &read_at(1)).unwrap().unwrap(), b"v1");
+    assert_eq!(db.get_opt(b"k1", &read_at(2)).unwrap().unwrap(), b"v2");
+    assert!(db.get_opt(b"k1", &read_at(3)).unwrap().is_none());
+
+    // Reads need a timestamp and writes need one of the right size.
+    assert!(db.get(b"k1").is_err());
+    assert!(db.put(b"k3", b"v3").is_err());
+    assert!(db.put_with_ts(b"k3", [0u8; 4], b"v3").is_err());
+
+    // Versions survive a flush.
+    db.flush().unwrap();
+    assert_eq!(db.get_opt(b"k1", &read_at(1)).unwrap().unwrap(), b"v1");
+
+    let mut iter = db.raw_iterator_opt(read_at(2));
+    iter.seek_to_first();
+    let mut entries = Vec::new();
+    while let Some((key, value)) = iter.item() {
+        entries.push((
+            key.to_vec(),
+            iter.timestamp().unwrap().to_vec(),
+            value.to_vec(),
+        ));
+        iter.next();
+    }
+    iter.status().unwrap();
+    assert_eq!(
+        entries,
+        vec![
+            (b"k1".to_vec(), ts(2).to_vec(), b"v2".to_vec()),
+            (b"k2".to_vec(), ts(2).to_vec(), b"w2".to_vec()),
+        ]
+    );
+
+    let mut readopts = read_at(3);
+    readopts.set_iter_start_ts(ts(1));
+    let mut iter = db.raw_iterator_opt(readopts);
+    iter.seek_to_first();
+    let mut entries = Vec::new();
+    while let Some((key, value)) = iter.item() {
+        entries.push((
+            key.to_vec(),
+            iter.timestamp().unwrap().to_vec(),
+            value.to_vec(),
+        ));
+        iter.next();
+    }
+    iter.status().unwrap();
+    // Every version is returned, including deletions, keyed by the
+    // internal key: the user key, its timestamp and 8 bytes of sequence
+    // number and entry type.
+    let versions: Vec<_> = entries
+        .into_iter()
+        .map(|(key, ts, value)| {
+            let (user_key, suffix) = key.split_at(key.len() - ts.len() - 8);
+            assert_eq!(&suffix[..ts.len()], &ts[..]);
+            (user_key.to_vec(), ts, value)
+        })
+        .collect();
+    assert_eq!(
+        versions,
+        vec![
+            (b"k1".to_vec(), ts(3).to_vec(), Vec::new()),
+            (b"k1".to_vec(), ts(2).to_vec(), b"v2".to_vec()),
+            (b"k1".to_vec(), ts(1).to_vec(), b"v1".to_vec()),
+            (b"k2".to_vec(), ts(2).to_vec(), b"w2".to_vec()),
+        ]
+    );
+}
+
+#[test]
+fn timestamp_write_batch() {
+    struct Keys(Vec<(Vec<u8>, Vec<u8>)>);
+
+    impl Keys {
+        fn push(&mut self, key: &[u8]) {
+            let (key, ts) = split_timestamp(key, 8).unwrap();
+            self.0.push((key.to_vec(), ts.to_vec()));
+        }
+    }
+
+    impl WriteBatchIterator for Keys {
+        fn put(&mut self, key: Box<[u8]>, _value: Box<[u8]>) {
+            self.push(&key);
+        }
+
+        fn delete(&mut self, key: Box<[u8]>) {
+            self.push(&key);
+        }
+    }
+
+    let path = DBPath::new("_rust_rocksdb_timestamp_write_batch");
+    let db = open(&path);
+    let cf = db.cf_handle(DEFAULT_COLUMN_FAMILY_NAME).unwrap();
+    db.put_with_ts(b"k2", ts(1), b"v2").unwrap();
+    db.put_with_ts(b"k4", ts(1), b"v4").unwrap();
+
+    let mut batch = WriteBatch::default();
+    batch.put_cf_with_ts(cf, b"k1", ts(5), b"v1");
+    batch.delete_cf_with_ts(cf, b"k2", ts(5));
+    batch.put_with_ts(b"k3", ts(5), b"v3");
+    batch.delete_with_ts(b"k4", ts(5));
+    let mut keys = Keys(Vec::new());
+    batch.iterate(&mut keys);
+    assert_eq!(
+        keys.0,
+        vec![
+            (b"k1".to_vec(), ts(5).to_vec()),
+            (b"k2".to_vec(), ts(5).to_vec()),
+            (b"k3".to_vec(), ts(5).to_vec()),
+            (b"k4".to_vec(), ts(5).to_vec()),
+        ]
+    );
+    db.write(batch).unwrap();
+
+    assert_eq!(db.get_opt(b"k1", &read_at(5)).unwrap().unwrap(), b"v1");
+    assert!(db.get_opt(b"k1", &read_at(4)).unwrap().is_none());
+    assert!(db.get_opt(b"k2", &read_at(5)).unwrap().is_none());
+    assert_eq!(db.get_opt(b"k2", &read_at(4)).unwrap().unwrap(), b"v2");
+    assert_eq!(db.get_opt(b"k3", &read_at(5)).unwrap().unwrap(), b"v3");
+    assert!(db.get_opt(b"k3", &read_at(4)).unwrap().is_none());
+    assert!(db.get_opt(b"k4", &read_at(5)).unwrap().is_none());
+    assert_eq!(db.get_opt(b"k4", &read_at(4)).unwrap().unwrap(), b"v4");
+}
+
+#[test]
+fn timestamp_full_history_ts_low() {
+    let path = DBPath::new("_rust_rocksdb_timestamp_full_history_ts_low");
+    let db = open(&path);
+    let cf = db.cf_handle(DEFAULT_COLUMN_FAMILY_NAME).unwrap();
+    assert!(db.get_full_history_ts_low(cf).unwrap().is_empty());
+
+    db.increase_full_history_ts_low(cf, ts(5)).unwrap();
+    assert_eq!(db.get_full_history_ts_low(cf).unwrap(), ts(5));
+    db.increase_full_history_ts_low(cf, ts(7)).unwrap();
+    assert_eq!(db.get_full_history_ts_low(cf).unwrap(), ts(7));
+    assert!(db.increase_full_history_ts_low(cf, ts(6)).is_err());
+}
@@ -15,12 +15,18 @@
 
 mod util;
 
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
 use pretty_assertions::assert_eq;
 
+#[cfg(not(windows))]
+use rocksdb::RangeLockManager;
 use rocksdb::{
-    CuckooTableOptions, DBAccess, Direction, Error, ErrorKind, IteratorMode, Options, ReadOptions,
-    SliceTransform, TransactionDB, TransactionDBOptions, TransactionOptions,
-    WriteBatchWithTransaction, WriteOptions, DB,
+    CuckooTableOptions, DBAccess, Direction, Error, ErrorKind, IteratorMode, KeyLockInfo, Options,
+    ReadOptions, SliceTransform, Transaction, TransactionDB, TransactionDBOptions,
+    TransactionOptions, TxnDBWritePolicy, WaitingTxns, WriteBatchWithTransaction, WriteOptions, DB,
 };
 use util::DBPath;
 
@@ -676,6 +682,274 @@ fn two_phase_commit() {
     }
 }
 
+#[test]
+fn two_phase_commit_write_policies() {
+    for policy in [
+        TxnDBWritePolicy::WritePrepared,
+        TxnDBWritePolicy::WriteUnprepared,
+    ] {
+        let path = DBPath::new("_rust_rocksdb_transaction_db_2pc_write_policy");
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        let mut txn_db_opts = TransactionDBOptions::default();
+        txn_db_opts.set_write_policy(policy);
+        txn_db_opts.set_default_write_batch_flush_threshold(1024);
+
+        {
+            let db: TransactionDB = TransactionDB::open(&opts, &txn_db_opts, &path).unwrap();
+
+            let txn = db.transaction();
+            txn.set_name(b"big").unwrap();
+            for i in 0..100 {
+                txn.put(format!("k{}", i), [b'x'; 100]).unwrap();
+            }
+            assert_eq!(txn.get(b"k0").unwrap().unwrap(), [b'x'; 100]);
+            assert!(db.get(b"k0").unwrap().is_none());
+            // WriteUnprepared writes the batch to the database whenever it
+            // grows over the flush threshold, and starts a new one.
+            let buffered = txn.get_writebatch().len();
+            match policy {
+                TxnDBWritePolicy::WriteUnprepared => assert!(buffered < 100),
+                _ => assert_eq!(buffered, 100),
+            }
+            txn.prepare().unwrap();
+            assert!(db.get(b"k0").unwrap().is_none());
+            txn.commit().unwrap();
+            assert_eq!(db.get(b"k99").unwrap().unwrap(), [b'x'; 100]);
+
+            let txn = db.transaction();
+            txn.put(b"k0", b"v0").unwrap();
+            txn.set_name(b"t1").unwrap();
+            txn.prepare().unwrap();
+        }
+
+        {
+            let db: TransactionDB = TransactionDB::open(&opts, &txn_db_opts, &path).unwrap();
+            let txns = db.prepared_transactions();
+            assert_eq!(txns.len(), 1);
+            assert_eq!(db.get(b"k0").unwrap().unwrap(), [b'x'; 100]);
+            for txn in txns {
+                assert_eq!(txn.get_name().unwrap(), b"t1");
+                txn.commit().unwrap();
+            }
+            assert_eq!(db.get(b"k0").unwrap().unwrap(), b"v0");
+        }
+    }
+}
+
+fn deadlock_detecting_txn(db: &TransactionDB) -> Transaction<TransactionDB> {
+    let mut txn_opts = TransactionOptions::default();
+    txn_opts.set_deadlock_detect(true);
+    db.transaction_opt(&WriteOptions::default(), &txn_opts)
+}
+
+#[test]
+fn lock_status_and_deadlocks() {
+    let path = DBPath::new("_rust_rocksdb_transaction_db_lock_status");
+    let db: Arc<TransactionDB> = Arc::new(TransactionDB::open_default(&path).unwrap());
+
+    // txn1 runs on another thread, which owns a handle to the database.
+    let (txn1_locked, txn1_id) = mpsc::channel();
+    let (resume_txn1, txn1_resumed) = mpsc::channel();
+    let handle = {
+        let db = db.clone();
+        thread::spawn(move || {
+            let txn1 = deadlock_detecting_txn(&db);
+            txn1.get_for_update(b"a", true).unwrap();
+            txn1_locked.send(txn1.id()).unwrap();
+            txn1_resumed.recv().unwrap();
+            let result = txn1.get_for_update(b"b", true).map(drop);
+            drop(txn1);
+            result
+        })
+    };
+    let txn2 = deadlock_detecting_txn(&db);
+    txn2.get_for_update(b"b", true).unwrap();
+    let txn1_id = txn1_id.recv().unwrap();
+    assert_ne!(txn1_id, txn2.id());
+    assert!(db.waiting_txns(txn2.id()).is_none());
+
+    let mut locks = db.lock_status_data();
+    locks.sort_by(|a, b| a.key.cmp(&b.key));
+    assert_eq!(
+        locks,
+        vec![
+            KeyLockInfo {
+                cf_id: 0,
+                key: b"a".to_vec(),
+                txn_ids: vec![txn1_id],
+                exclusive: true,
+            },
+            KeyLockInfo {
+                cf_id: 0,
+                key: b"b".to_vec(),
+                txn_ids: vec![txn2.id()],
+                exclusive: true,
+            },
+        ]
+    );
+
+    // Each transaction waits for the lock held by the other, so one of
+    // them detects the deadlock and gives up.
+    resume_txn1.send(()).unwrap();
+    thread::sleep(Duration::from_millis(100));
+    let result2 = txn2.get_for_update(b"a", true).map(drop);
+    drop(txn2);
+    let result1 = handle.join().unwrap();
+    let err = result1.err().or_else(|| result2.err()).unwrap();
+    assert_eq!(err.kind(), ErrorKind::Busy);
+    assert!(db.lock_status_data().is_empty());
+
+    let deadlocks = db.deadlock_info_buffer();
+    assert_eq!(deadlocks.len(), 1);
+    assert!(!deadlocks[0].limit_exceeded);
+    let mut waiting_keys: Vec<_> = deadlocks[0]
+        .path
+        .iter()
+        .map(|info| info.waiting_key.clone())
+        .collect();
+    waiting_keys.sort();
+    assert_eq!(waiting_keys, vec![b"a".to_vec(), b"b".to_vec()]);
+}
+
+/// Makes two transactions lock `first` and `second` in opposite orders,
+/// and returns the error of the one that detects the deadlock.
+fn deadlock(db: &Arc<TransactionDB>, first: &'static [u8], second: &'static [u8]) -> Error {
+    let (txn1_locked, txn1_id) = mpsc::channel();
+    let (resume_txn1, txn1_resumed) = mpsc::channel();
+    let handle = {
+        let db = db.clone();
+        thread::spawn(move || {
+            let txn1 = deadlock_detecting_txn(&db);
+            txn1.get_for_update(first, true).unwrap();
+            txn1_locked.send(txn1.id()).unwrap();
+            txn1_resumed.recv().unwrap();
+            let result = txn1.get_for_update(second, true).map(drop);
+            drop(txn1);
+            result
+        })
+    };
+    let txn2 = deadlock_detecting_txn(db);
+    txn2.get_for_update(second, true).unwrap();
+    txn1_id.recv().unwrap();
+    resume_txn1.send(()).unwrap();
+    thread::sleep(Duration::from_millis(100));
+    let result2 = txn2.get_for_update(first, true).map(drop);
+    drop(txn2);
+    let result1 = handle.join().unwrap();
+    result1.err().or_else(|| result2.err()).unwrap()
+}
+
+#[test]
+fn deadlock_info_buffer_newest_first() {
+    let path = DBPath::new("_rust_rocksdb_transaction_db_deadlock_order");
+    let db: Arc<TransactionDB> = Arc::new(TransactionDB::open_default(&path).unwrap());
+    assert_eq!(deadlock(&db, b"a", b"b").kind(), ErrorKind::Busy);
+    assert_eq!(deadlock(&db, b"c", b"d").kind(), ErrorKind::Busy);
+
+    let waiting_keys: Vec<Vec<Vec<u8>>> = db
+        .deadlock_info_buffer()
+        .iter()
+        .map(|deadlock| {
+            let mut keys: Vec<_> = deadlock
+                .path
+                .iter()
+                .map(|info| info.waiting_key.clone())
+                .collect();
+            keys.sort();
+            keys
+        })
+        .collect();
+    assert_eq!(
+        waiting_keys,
+        vec![
+            vec![b"c".to_vec(), b"d".to_vec()],
+            vec![b"a".to_vec(), b"b".to_vec()],
+        ]
+    );
+}
+
+#[test]
+fn waiting_txns() {
+    let path = DBPath::new("_rust_rocksdb_transaction_db_waiting_txns");
+    let db: Arc<TransactionDB> = Arc::new(TransactionDB::open_default(&path).unwrap());
+    let mut txn_opts = TransactionOptions::default();
+    txn_opts.set_lock_timeout(10_000);
+    let txn1 = db.transaction_opt(&WriteOptions::default(), &txn_opts);
+    let txn1_id = txn1.id();
+    assert!(db.waiting_txns(txn1_id).is_none());
+
+    // txn2 holds the lock until another thread sees txn1 waiting for it.
+    let (txn2_locked, txn2_id) = mpsc::channel();
+    let handle = {
+        let db = db.clone();
+        thread::spawn(move || {
+            let txn2 = db.transaction();
+            txn2.get_for_update(b"a", true).unwrap();
+            txn2_locked.send(txn2.id()).unwrap();
+            loop {
+                if let Some(waiting) = db.waiting_txns(txn1_id) {
+                    return waiting;
+                }
+                thread::sleep(Duration::from_millis(1));
+            }
+        })
+    };
+    let txn2_id = txn2_id.recv().unwrap();
+    txn1.get_for_update(b"a", true).unwrap();
+    assert_eq!(
+        handle.join().unwrap(),
+        WaitingTxns {
+            cf_id: 0,
+            key: b"a".to_vec(),
+            txn_ids: vec![txn2_id],
+        }
+    );
+    assert!(db.waiting_txns(txn1_id).is_none());
+    drop(txn1);
+    assert!(db.waiting_txns(txn1_id).is_none());
+}
+
+#[test]
+#[cfg(not(windows))]
+fn range_lock() {
+    let path = DBPath::new("_rust_rocksdb_transaction_db_range_lock");
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    let mut txn_db_opts = TransactionDBOptions::default();
+    txn_db_opts.set_lock_manager(&RangeLockManager::new());
+    let db: TransactionDB = TransactionDB::open(&opts, &txn_db_opts, &path).unwrap();
+
+    let mut txn_opts = TransactionOptions::default();
+    txn_opts.set_lock_timeout(0);
+    let txn1 = db.transaction();
+    let txn2 = db.transaction_opt(&WriteOptions::default(), &txn_opts);
+    txn1.get_range_lock(b"a", b"m").unwrap();
+
+    let err = txn2.put(b"c", b"v2").unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::Busy | ErrorKind::TimedOut));
+    let err = txn2.get_range_lock(b"k", b"z").unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::Busy | ErrorKind::TimedOut));
+    txn2.put(b"x", b"v2").unwrap();
+
+    txn1.put(b"c", b"v1").unwrap();
+    txn1.commit().unwrap();
+    txn2.put(b"c", b"v2").unwrap();
+    txn2.commit().unwrap();
+    assert_eq!(db.get(b"c").unwrap().unwrap(), b"v2");
+    assert_eq!(db.get(b"x").unwrap().unwrap(), b"v2");
+}
+
+#[test]
+#[cfg(not(windows))]
+fn range_lock_needs_range_lock_manager() {
+    let path = DBPath::new("_rust_rocksdb_transaction_db_range_lock_unsupported");
+    let db: TransactionDB = TransactionDB::open_default(&path).unwrap();
+    let txn = db.transaction();
+    let err = txn.get_range_lock(b"a", b"m").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotSupported);
+}
+
 #[test]
 fn test_snapshot_outlive_transaction_db() {
     let t = trybuild::TestCases::new();
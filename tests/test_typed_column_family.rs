@@ -0,0 +1,164 @@
+// Copyright 2020 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod util;
+
+use pretty_assertions::assert_eq;
+
+use rocksdb::{
+    merge_operator::builtin,
+    typed_column_family::{KeyCodec, OrderedCodec, RawCodec, ValueCodec},
+    ColumnFamilyDescriptor, Direction, ErrorKind, Options, DB,
+};
+use util::DBPath;
+
+fn open_cf(path: &DBPath, name: &str, cf_opts: Options) -> DB {
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.create_missing_column_families(true);
+    DB::open_cf_descriptors(
+        &opts,
+        path,
+        vec![ColumnFamilyDescriptor::new(name, cf_opts)],
+    )
+    .unwrap()
+}
+
+#[test]
+fn typed_column_family_get_put_delete() {
+    let path = DBPath::new("_rust_rocksdb_typed_cf_get_put_delete");
+    let db = open_cf(&path, "users", Options::default());
+    let users = db.typed_cf::<u64, String, OrderedCodec>("users").unwrap();
+
+    assert_eq!(users.get(&1).unwrap(), None);
+    users.put(&1, &"alice".to_owned()).unwrap();
+    users.put(&2, &"bob".to_owned()).unwrap();
+    assert_eq!(users.get(&1).unwrap(), Some("alice".to_owned()));
+    users.delete(&1).unwrap();
+    assert_eq!(users.get(&1).unwrap(), None);
+    assert_eq!(users.get(&2).unwrap(), Some("bob".to_owned()));
+
+    assert!(db
+        .typed_cf::<u64, String, OrderedCodec>("missing")
+        .is_none());
+
+    // Values not written through the codec fail to decode.
+    db.put_cf(
+        &db.cf_handle("users").unwrap(),
+        OrderedCodec::encode_key(&3u64).unwrap(),
+        b"x",
+    )
+    .unwrap();
+    assert_eq!(users.get(&3).unwrap_err().kind(), ErrorKind::Corruption);
+}
+
+#[test]
+fn typed_column_family_ordered_iteration() {
+    let path = DBPath::new("_rust_rocksdb_typed_cf_ordered_iteration");
+    let db = open_cf(&path, "events", Options::default());
+    let events = db
+        .typed_cf::<(i64, Vec<u8>), Vec<u8>, (OrderedCodec, RawCodec)>("events")
+        .unwrap();
+
+    let mut keys = vec![
+        (-300, b"a".to_vec()),
+        (-1, vec![]),
+        (0, b"a\0b".to_vec()),
+        (0, b"a".to_vec()),
+        (0, b"a\0".to_vec()),
+        (0, b"ab".to_vec()),
+        (7, b"z".to_vec()),
+        (i64::MAX, vec![0xff]),
+        (i64::MIN, vec![]),
+    ];
+    for (i, key) in keys.iter().enumerate() {
+        events.put(key, &vec![i as u8]).unwrap();
+    }
+    keys.sort();
+
+    let iterated: Vec<_> = events.iterator().map(|item| item.unwrap().0).collect();
+    assert_eq!(iterated, keys);
+
+    let from: Vec<_> = events
+        .iterator_from(&(0, b"a\0".to_vec()), Direction::Reverse)
+        .unwrap()
+        .map(|item| item.unwrap().0)
+        .collect();
+    assert_eq!(
+        from,
+        vec![
+            (0, b"a\0".to_vec()),
+            (0, b"a".to_vec()),
+            (-1, vec![]),
+            (-300, b"a".to_vec()),
+            (i64::MIN, vec![]),
+        ]
+    );
+}
+
+#[test]
+fn typed_column_family_merge() {
+    struct U64Codec;
+
+    impl ValueCodec<u64> for U64Codec {
+        fn encode_value(value: &u64) -> Result<Vec<u8>, rocksdb::Error> {
+            Ok(builtin::encode_u64(*value).to_vec())
+        }
+
+        fn decode_value(bytes: &[u8]) -> Result<u64, rocksdb::Error> {
+            Ok(builtin::decode_u64(bytes).unwrap())
+        }
+    }
+
+    let path = DBPath::new("_rust_rocksdb_typed_cf_merge");
+    let mut cf_opts = Options::default();
+    cf_opts.set_merge_operator_associative("u64_add", builtin::u64_add);
+    let db = open_cf(&path, "counters", cf_opts);
+    let counters = db
+        .typed_cf::<String, u64, (OrderedCodec, U64Codec)>("counters")
+        .unwrap();
+
+    counters.merge(&"hits".to_owned(), &2u64).unwrap();
+    counters.merge(&"hits".to_owned(), &3u64).unwrap();
+    assert_eq!(counters.get(&"hits".to_owned()).unwrap(), Some(5));
+}
+
+#[cfg(feature = "bincode")]
+#[test]
+fn typed_column_family_bincode() {
+    use rocksdb::typed_column_family::BincodeCodec;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Account {
+        name: String,
+        balance: i64,
+    }
+
+    let path = DBPath::new("_rust_rocksdb_typed_cf_bincode");
+    let db = open_cf(&path, "accounts", Options::default());
+    let accounts = db
+        .typed_cf::<u32, Account, (OrderedCodec, BincodeCodec)>("accounts")
+        .unwrap();
+
+    let alice = Account {
+        name: "alice".to_owned(),
+        balance: -5,
+    };
+    accounts.put(&7, &alice).unwrap();
+    assert_eq!(accounts.get(&7).unwrap(), Some(alice));
+    let all: Vec<_> = accounts.iterator().map(Result::unwrap).collect();
+    assert_eq!(all.len(), 1);
+    assert_eq!(all[0].0, 7);
+}
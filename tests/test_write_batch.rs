@@ -12,11 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod util;
+
 use std::collections::HashMap;
 
 use pretty_assertions::assert_eq;
 
-use rocksdb::{WriteBatch, WriteBatchIterator};
+use rocksdb::{
+    AsColumnFamilyRef, Options, WriteBatch, WriteBatchIterator, WriteBatchIteratorCf, DB,
+    DEFAULT_COLUMN_FAMILY_NAME,
+};
+use util::DBPath;
 
 #[test]
 fn test_write_batch_clear() {
@@ -66,3 +72,89 @@ fn test_write_batch_with_serialized_data() {
     let mut it = Iterator { data: kvs };
     b2.iterate(&mut it);
 }
+
+#[test]
+fn test_write_batch_iterate_cf() {
+    #[derive(Debug, PartialEq)]
+    enum Record {
+        Put(u32, Vec<u8>, Vec<u8>),
+        Delete(u32, Vec<u8>),
+        Merge(u32, Vec<u8>, Vec<u8>),
+        DeleteRange(u32, Vec<u8>, Vec<u8>),
+        LogData(Vec<u8>),
+    }
+
+    struct Recorder(Vec<Record>);
+
+    impl WriteBatchIteratorCf for Recorder {
+        fn put_cf(&mut self, cf_id: u32, key: &[u8], value: &[u8]) {
+            self.0
+                .push(Record::Put(cf_id, key.to_vec(), value.to_vec()));
+        }
+
+        fn delete_cf(&mut self, cf_id: u32, key: &[u8]) {
+            self.0.push(Record::Delete(cf_id, key.to_vec()));
+        }
+
+        fn single_delete_cf(&mut self, _: u32, _: &[u8]) {
+            panic!("invalid single delete operation");
+        }
+
+        fn merge_cf(&mut self, cf_id: u32, key: &[u8], value: &[u8]) {
+            self.0
+                .push(Record::Merge(cf_id, key.to_vec(), value.to_vec()));
+        }
+
+        fn delete_range_cf(&mut self, cf_id: u32, begin_key: &[u8], end_key: &[u8]) {
+            self.0.push(Record::DeleteRange(
+                cf_id,
+                begin_key.to_vec(),
+                end_key.to_vec(),
+            ));
+        }
+
+        fn log_data(&mut self, blob: &[u8]) {
+            self.0.push(Record::LogData(blob.to_vec()));
+        }
+    }
+
+    let path = DBPath::new("_rust_rocksdb_write_batch_iterate_cf");
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.create_missing_column_families(true);
+    let db = DB::open_cf(&opts, &path, ["cf1"]).unwrap();
+    let cf1 = db.cf_handle("cf1").unwrap();
+    let default_id = db.cf_handle(DEFAULT_COLUMN_FAMILY_NAME).unwrap().id();
+    let cf1_id = cf1.id();
+
+    let mut batch = WriteBatch::default();
+    batch.put(b"k1", b"v1");
+    batch.put_log_data(b"blob");
+    batch.put_cf(&cf1, b"k2", b"v2");
+    batch.merge_cf(&cf1, b"k2", b"v3");
+    batch.delete(b"k1");
+    batch.delete_range_cf(&cf1, b"a", b"z");
+    // Log data is not an operation.
+    assert_eq!(batch.len(), 5);
+
+    let mut recorder = Recorder(Vec::new());
+    batch.iterate_cf(&mut recorder).unwrap();
+    assert_eq!(
+        recorder.0,
+        vec![
+            Record::Put(default_id, b"k1".to_vec(), b"v1".to_vec()),
+            Record::LogData(b"blob".to_vec()),
+            Record::Put(cf1_id, b"k2".to_vec(), b"v2".to_vec()),
+            Record::Merge(cf1_id, b"k2".to_vec(), b"v3".to_vec()),
+            Record::Delete(default_id, b"k1".to_vec()),
+            Record::DeleteRange(cf1_id, b"a".to_vec(), b"z".to_vec()),
+        ]
+    );
+
+    // The batch has the same records once read back from its data.
+    let mut recorder = Recorder(Vec::new());
+    WriteBatch::from_data(batch.data())
+        .iterate_cf(&mut recorder)
+        .unwrap();
+    assert_eq!(recorder.0.len(), 6);
+}
@@ -0,0 +1,92 @@
+// Copyright 2020 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod util;
+
+use pretty_assertions::assert_eq;
+
+use rocksdb::{Options, ReadOptions, WriteBatchWithIndex, DB};
+use util::DBPath;
+
+#[test]
+fn test_write_batch_with_index_get_from_batch() {
+    let opts = Options::default();
+    let mut batch = WriteBatchWithIndex::new(0, true);
+    assert!(batch.is_empty());
+    batch.put(b"k1", b"v1");
+    batch.put(b"k2", b"v2");
+    batch.delete(b"k2");
+    assert_eq!(batch.len(), 3);
+
+    assert_eq!(batch.get_from_batch(&opts, b"k1").unwrap().unwrap(), b"v1");
+    assert!(batch.get_from_batch(&opts, b"k2").unwrap().is_none());
+    assert!(batch.get_from_batch(&opts, b"k3").unwrap().is_none());
+
+    batch.set_save_point();
+    batch.put(b"k1", b"v1-new");
+    assert_eq!(
+        batch.get_from_batch(&opts, b"k1").unwrap().unwrap(),
+        b"v1-new"
+    );
+    batch.rollback_to_save_point().unwrap();
+    assert_eq!(batch.get_from_batch(&opts, b"k1").unwrap().unwrap(), b"v1");
+    assert!(batch.rollback_to_save_point().is_err());
+
+    batch.clear();
+    assert!(batch.is_empty());
+}
+
+#[test]
+fn test_write_batch_with_index_read_your_writes() {
+    let path = DBPath::new("_rust_rocksdb_write_batch_with_index");
+    let db = DB::open_default(&path).unwrap();
+    db.put(b"a", b"db").unwrap();
+    db.put(b"b", b"db").unwrap();
+    db.put(b"d", b"db").unwrap();
+
+    let mut batch = WriteBatchWithIndex::new(0, true);
+    batch.put(b"b", b"batch");
+    batch.put(b"c", b"batch");
+    batch.delete(b"d");
+
+    let readopts = ReadOptions::default();
+    let get = |key: &[u8]| batch.get_from_batch_and_db(&db, &readopts, key).unwrap();
+    assert_eq!(get(b"a").unwrap(), b"db");
+    assert_eq!(get(b"b").unwrap(), b"batch");
+    assert_eq!(get(b"c").unwrap(), b"batch");
+    assert!(get(b"d").is_none());
+
+    let mut iter = batch.iterator_with_base(db.raw_iterator());
+    iter.seek_to_first();
+    let mut items = Vec::new();
+    while iter.valid() {
+        items.push((iter.key().unwrap().to_vec(), iter.value().unwrap().to_vec()));
+        iter.next();
+    }
+    iter.status().unwrap();
+    drop(iter);
+    assert_eq!(
+        items,
+        vec![
+            (b"a".to_vec(), b"db".to_vec()),
+            (b"b".to_vec(), b"batch".to_vec()),
+            (b"c".to_vec(), b"batch".to_vec()),
+        ]
+    );
+
+    db.write_with_index(&batch).unwrap();
+    assert_eq!(db.get(b"b").unwrap().unwrap(), b"batch");
+    assert_eq!(db.get(b"c").unwrap().unwrap(), b"batch");
+    assert!(db.get(b"d").unwrap().is_none());
+}